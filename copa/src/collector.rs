@@ -0,0 +1,154 @@
+//! A [`Perform`] implementation collecting every action into a `Vec`.
+//!
+//! Meant for tests of code built on top of the parser, dispatched actions
+//! can be compared against an expected list instead of writing a
+//! dedicated [`Perform`] for each test.
+
+use crate::{OpaqueSequenceKind, Params, Perform};
+
+/// An action dispatched by the [`Parser`](crate::Parser).
+///
+/// Parameters and intermediates are copied out of the parser, so actions
+/// can be kept around after parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Action {
+    Print(char),
+    Execute(u8),
+    /// Parameters with their subparameters, intermediates, ignore and action.
+    Csi(Vec<Vec<u16>>, Vec<u8>, bool, char),
+    /// Parameters and whether the sequence was terminated by BEL.
+    Osc(Vec<Vec<u8>>, bool),
+    /// Intermediates, ignore and final byte.
+    Esc(Vec<u8>, bool, u8),
+    DcsHook(Vec<Vec<u16>>, Vec<u8>, bool, char),
+    DcsPut(u8),
+    DcsUnhook,
+    OpaqueStart(OpaqueSequenceKind),
+    OpaquePut(OpaqueSequenceKind, u8),
+    OpaqueEnd(OpaqueSequenceKind),
+}
+
+/// Records every dispatched [`Action`] in order.
+#[derive(Debug, Default, Clone)]
+pub struct Collector {
+    pub actions: Vec<Action>,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the collected actions, leaving the collector empty.
+    pub fn take(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.actions)
+    }
+}
+
+impl Perform for Collector {
+    fn print(&mut self, c: char) {
+        self.actions.push(Action::Print(c));
+    }
+
+    fn execute(&mut self, byte: u8) {
+        self.actions.push(Action::Execute(byte));
+    }
+
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, c: char) {
+        let params = params.iter().map(|subparam| subparam.to_vec()).collect();
+        self.actions
+            .push(Action::DcsHook(params, intermediates.to_vec(), ignore, c));
+    }
+
+    fn put(&mut self, byte: u8) {
+        self.actions.push(Action::DcsPut(byte));
+    }
+
+    fn unhook(&mut self) {
+        self.actions.push(Action::DcsUnhook);
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        let params = params.iter().map(|param| param.to_vec()).collect();
+        self.actions.push(Action::Osc(params, bell_terminated));
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        ignore: bool,
+        c: char,
+    ) {
+        let params = params.iter().map(|subparam| subparam.to_vec()).collect();
+        self.actions
+            .push(Action::Csi(params, intermediates.to_vec(), ignore, c));
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        self.actions
+            .push(Action::Esc(intermediates.to_vec(), ignore, byte));
+    }
+
+    fn opaque_start(&mut self, kind: OpaqueSequenceKind) {
+        self.actions.push(Action::OpaqueStart(kind));
+    }
+
+    fn opaque_put(&mut self, kind: OpaqueSequenceKind, byte: u8) {
+        self.actions.push(Action::OpaquePut(kind, byte));
+    }
+
+    fn opaque_end(&mut self, kind: OpaqueSequenceKind) {
+        self.actions.push(Action::OpaqueEnd(kind));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn collect(input: &[u8]) -> Vec<Action> {
+        let mut parser = Parser::new();
+        let mut collector = Collector::new();
+        for byte in input {
+            parser.advance(&mut collector, *byte);
+        }
+        collector.take()
+    }
+
+    #[test]
+    fn collects_mixed_input() {
+        let actions = collect(b"a\x1b[1;38:2:255m\r\x1b7\x1b]0;rio\x07\x1bP1$tx\x9cb");
+
+        assert_eq!(
+            actions,
+            [
+                Action::Print('a'),
+                Action::Csi(vec![vec![1], vec![38, 2, 255]], vec![], false, 'm'),
+                Action::Execute(b'\r'),
+                Action::Esc(vec![], false, b'7'),
+                Action::Osc(vec![b"0".to_vec(), b"rio".to_vec()], true),
+                Action::DcsHook(vec![vec![1]], vec![b'$'], false, 't'),
+                Action::DcsPut(b'x'),
+                Action::DcsUnhook,
+                Action::Print('b'),
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_like_internal_dispatcher() {
+        // Same input and expectations as `parse_csi_params_trailing_semicolon`
+        // and `esc_reset` in the parser tests.
+        assert_eq!(
+            collect(b"\x1b[4;m"),
+            [Action::Csi(vec![vec![4], vec![0]], vec![], false, 'm')]
+        );
+        assert_eq!(
+            collect(b"\x1b[3;1\x1b(A"),
+            [Action::Esc(vec![b'('], false, b'A')]
+        );
+    }
+}
@@ -22,6 +22,37 @@ pub enum State {
     Utf8 = 15,
 }
 
+impl State {
+    /// Whether the state is part of an escape, CSI or DCS sequence before
+    /// its final byte. The ignore states are left out, they only discard
+    /// bytes.
+    #[inline]
+    pub fn is_sequence(self) -> bool {
+        matches!(
+            self,
+            State::Escape
+                | State::EscapeIntermediate
+                | State::CsiEntry
+                | State::CsiParam
+                | State::CsiIntermediate
+                | State::DcsEntry
+                | State::DcsParam
+                | State::DcsIntermediate
+        )
+    }
+
+    /// State discarding the rest of an aborted sequence up to its end.
+    #[inline]
+    pub fn ignore_state(self) -> State {
+        match self {
+            State::DcsEntry | State::DcsParam | State::DcsIntermediate => {
+                State::DcsIgnore
+            }
+            _ => State::CsiIgnore,
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub enum Action {
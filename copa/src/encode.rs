@@ -0,0 +1,260 @@
+//! Encoders turning dispatched actions back into bytes.
+//!
+//! Each function produces the canonical 7-bit form of a sequence, feeding
+//! it to a [`Parser`](crate::Parser) dispatches the same action again.
+//! Meant for recording and replaying sessions, or for proxies normalizing
+//! the sequences they forward.
+//!
+//! Sequences dispatched with `ignore` set were truncated by the parser and
+//! can not be reproduced exactly.
+//!
+//! ```rust
+//! use copa::encode;
+//!
+//! assert_eq!(encode::encode_csi([&[1][..], &[38, 2, 255]], b"", 'm'), b"\x1b[1;38:2:255m");
+//! assert_eq!(encode::encode_osc([&b"0"[..], b"rio"], true), b"\x1b]0;rio\x07");
+//! assert_eq!(encode::encode_esc(b"(", b'B'), b"\x1b(B");
+//! ```
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// Encodes a CSI sequence.
+///
+/// `params` yields each parameter with its subparameters, as iterated from
+/// [`Params`](crate::Params). Private markers (`<`, `=`, `>` and `?`) are
+/// collected by the parser together with the intermediates, they are
+/// written before the parameters and the other intermediates after them.
+pub fn encode_csi<I>(params: I, intermediates: &[u8], action: char) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u16]>,
+{
+    let mut bytes = vec![ESC, b'['];
+    push_control_body(&mut bytes, params, intermediates, action);
+    bytes
+}
+
+/// Encodes an OSC sequence, terminated by BEL when `bell_terminated` is set
+/// and by `ESC \` otherwise.
+pub fn encode_osc<I>(params: I, bell_terminated: bool) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut bytes = vec![ESC, b']'];
+    for (i, param) in params.into_iter().enumerate() {
+        if i > 0 {
+            bytes.push(b';');
+        }
+        bytes.extend_from_slice(param.as_ref());
+    }
+
+    if bell_terminated {
+        bytes.push(BEL);
+    } else {
+        bytes.extend_from_slice(&[ESC, b'\\']);
+    }
+    bytes
+}
+
+/// Encodes an escape sequence.
+pub fn encode_esc(intermediates: &[u8], byte: u8) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(intermediates.len() + 2);
+    bytes.push(ESC);
+    bytes.extend_from_slice(intermediates);
+    bytes.push(byte);
+    bytes
+}
+
+/// Encodes a DCS sequence carrying `data`, terminated by `ESC \`.
+///
+/// Parameters and intermediates are laid out as in [`encode_csi`].
+pub fn encode_dcs<I>(
+    params: I,
+    intermediates: &[u8],
+    action: char,
+    data: &[u8],
+) -> Vec<u8>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u16]>,
+{
+    let mut bytes = vec![ESC, b'P'];
+    push_control_body(&mut bytes, params, intermediates, action);
+    bytes.extend_from_slice(data);
+    bytes.extend_from_slice(&[ESC, b'\\']);
+    bytes
+}
+
+fn push_control_body<I>(
+    bytes: &mut Vec<u8>,
+    params: I,
+    intermediates: &[u8],
+    action: char,
+) where
+    I: IntoIterator,
+    I::Item: AsRef<[u16]>,
+{
+    let is_private_marker = |byte: &u8| (0x3c..=0x3f).contains(byte);
+    bytes.extend(intermediates.iter().filter(|byte| is_private_marker(byte)));
+
+    for (i, param) in params.into_iter().enumerate() {
+        if i > 0 {
+            bytes.push(b';');
+        }
+        for (j, subparam) in param.as_ref().iter().enumerate() {
+            if j > 0 {
+                bytes.push(b':');
+            }
+            bytes.extend_from_slice(subparam.to_string().as_bytes());
+        }
+    }
+
+    bytes.extend(intermediates.iter().filter(|byte| !is_private_marker(byte)));
+
+    let mut buf = [0; 4];
+    bytes.extend_from_slice(action.encode_utf8(&mut buf).as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Params, Parser, Perform};
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Dispatch {
+        Csi(Vec<Vec<u16>>, Vec<u8>, bool, char),
+        Osc(Vec<Vec<u8>>, bool),
+        Esc(Vec<u8>, bool, u8),
+        Dcs(Vec<Vec<u16>>, Vec<u8>, char, Vec<u8>),
+    }
+
+    #[derive(Default)]
+    struct Dispatcher {
+        dispatched: Vec<Dispatch>,
+    }
+
+    impl Perform for Dispatcher {
+        fn csi_dispatch(
+            &mut self,
+            params: &Params,
+            intermediates: &[u8],
+            ignore: bool,
+            c: char,
+        ) {
+            let params = params.iter().map(|param| param.to_vec()).collect();
+            self.dispatched.push(Dispatch::Csi(
+                params,
+                intermediates.to_vec(),
+                ignore,
+                c,
+            ));
+        }
+
+        fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+            let params = params.iter().map(|param| param.to_vec()).collect();
+            self.dispatched.push(Dispatch::Osc(params, bell_terminated));
+        }
+
+        fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+            // The `\` of a String Terminator is dispatched on its own.
+            if byte == b'\\' {
+                return;
+            }
+            self.dispatched
+                .push(Dispatch::Esc(intermediates.to_vec(), ignore, byte));
+        }
+
+        fn hook(&mut self, params: &Params, intermediates: &[u8], _: bool, c: char) {
+            let params = params.iter().map(|param| param.to_vec()).collect();
+            self.dispatched.push(Dispatch::Dcs(
+                params,
+                intermediates.to_vec(),
+                c,
+                Vec::new(),
+            ));
+        }
+
+        fn put(&mut self, byte: u8) {
+            if let Some(Dispatch::Dcs(.., data)) = self.dispatched.last_mut() {
+                data.push(byte);
+            }
+        }
+    }
+
+    fn parse(bytes: &[u8]) -> Vec<Dispatch> {
+        let mut parser = Parser::new();
+        let mut dispatcher = Dispatcher::default();
+        for byte in bytes {
+            parser.advance(&mut dispatcher, *byte);
+        }
+        dispatcher.dispatched
+    }
+
+    /// Parses `input`, re-encodes the single dispatch and parses it again.
+    fn round_trip(input: &[u8]) -> (Vec<u8>, Vec<Dispatch>) {
+        let dispatched = parse(input);
+        assert_eq!(dispatched.len(), 1, "{dispatched:?}");
+        let encoded = match &dispatched[0] {
+            Dispatch::Csi(params, intermediates, _, c) => {
+                encode_csi(params, intermediates, *c)
+            }
+            Dispatch::Osc(params, bell_terminated) => {
+                encode_osc(params, *bell_terminated)
+            }
+            Dispatch::Esc(intermediates, _, byte) => encode_esc(intermediates, *byte),
+            Dispatch::Dcs(params, intermediates, c, data) => {
+                encode_dcs(params, intermediates, *c, data)
+            }
+        };
+        assert_eq!(parse(&encoded), dispatched);
+        (encoded, dispatched)
+    }
+
+    #[test]
+    fn csi_sgr_round_trip() {
+        let (encoded, _) = round_trip(b"\x1b[1;38:2:255:0:0;4m");
+        assert_eq!(encoded, b"\x1b[1;38:2:255:0:0;4m");
+
+        // Omitted parameters come back as explicit zeros.
+        let (encoded, _) = round_trip(b"\x1b[4;m");
+        assert_eq!(encoded, b"\x1b[4;0m");
+    }
+
+    #[test]
+    fn csi_private_marker_and_intermediates_round_trip() {
+        let (encoded, _) = round_trip(b"\x1b[?1049h");
+        assert_eq!(encoded, b"\x1b[?1049h");
+
+        let (encoded, _) = round_trip(b"\x1b[2 q");
+        assert_eq!(encoded, b"\x1b[2 q");
+    }
+
+    #[test]
+    fn osc_title_round_trip() {
+        let (encoded, _) = round_trip(b"\x1b]0;rio term\x07");
+        assert_eq!(encoded, b"\x1b]0;rio term\x07");
+
+        let (encoded, _) = round_trip(b"\x1b]2;rio\x1b\\");
+        assert_eq!(encoded, b"\x1b]2;rio\x1b\\");
+    }
+
+    #[test]
+    fn esc_and_dcs_round_trip() {
+        let (encoded, _) = round_trip(b"\x1b(B");
+        assert_eq!(encoded, b"\x1b(B");
+
+        let (encoded, dispatched) = round_trip(b"\x1bP1$tdata\x1b\\");
+        assert_eq!(encoded, b"\x1bP1$tdata\x1b\\");
+        assert_eq!(
+            dispatched,
+            [Dispatch::Dcs(
+                vec![vec![1]],
+                b"$".to_vec(),
+                't',
+                b"data".to_vec()
+            )]
+        );
+    }
+}
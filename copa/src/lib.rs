@@ -33,23 +33,38 @@
 #![deny(clippy::if_not_else, clippy::enum_glob_use)]
 #![cfg_attr(feature = "no_std", no_std)]
 
+use core::fmt;
+#[cfg(not(feature = "safe_osc"))]
 use core::mem::MaybeUninit;
 
 #[cfg(feature = "no_std")]
 use arrayvec::ArrayVec;
 
+#[cfg(all(feature = "testing", not(feature = "no_std")))]
+pub mod collector;
 mod definitions;
+#[cfg(all(feature = "encode", not(feature = "no_std")))]
+pub mod encode;
 mod params;
+#[cfg(all(feature = "recorder", not(feature = "no_std")))]
+mod recorder;
 mod table;
 mod utf8;
+#[cfg(feature = "util")]
+pub mod util;
 
+#[cfg(all(feature = "testing", not(feature = "no_std")))]
+pub use collector::Collector;
 pub use params::{Params, ParamsIter};
+#[cfg(all(feature = "recorder", not(feature = "no_std")))]
+pub use recorder::RecordingPerform;
 
 use definitions::{unpack, Action, State};
 
 const MAX_INTERMEDIATES: usize = 2;
 const MAX_OSC_PARAMS: usize = 16;
 const MAX_OSC_RAW: usize = 1024;
+const MAX_SEQUENCE_LEN: usize = 4096;
 
 struct VtUtf8Receiver<'a, P: Perform>(&'a mut P, &'a mut State);
 
@@ -65,13 +80,59 @@ impl<P: Perform> utf8::Receiver for VtUtf8Receiver<'_, P> {
     }
 }
 
+/// Kind of a string whose content is not interpreted by the parser
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OpaqueSequenceKind {
+    /// Start of String, `ESC X`.
+    Sos,
+    /// Privacy Message, `ESC ^`.
+    Pm,
+    /// Application Program Command, `ESC _`.
+    #[default]
+    Apc,
+}
+
+/// Outcome of [`Parser::advance_until_terminated`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Advanced {
+    /// Number of bytes consumed from the input.
+    pub consumed: usize,
+    /// Whether parsing stopped because [`Perform::terminated`] returned
+    /// `true`, rather than because every byte was consumed.
+    pub terminated: bool,
+}
+
 /// Parser for raw _VTE_ protocol which delegates actions to a [`Perform`]
 ///
 /// [`Perform`]: trait.Perform.html
 ///
 /// Generic over the value for the size of the raw Operating System Command
 /// buffer. Only used when the `no_std` feature is enabled.
-#[derive(Default)]
+///
+/// # Memory usage
+///
+/// Apart from the Operating System Command buffer every piece of state has
+/// a fixed size. With `std` the buffer grows with the length of the
+/// command unless a limit is set with [`Parser::with_max_osc_len`], bytes
+/// past the limit are dropped the same way they are with `no_std` once the
+/// buffer is full. Embedders parsing untrusted input should set one, an
+/// unterminated command would otherwise grow the buffer without bound.
+///
+/// Escape, CSI and DCS sequences longer than [`Parser::max_sequence_len`]
+/// bytes, counted up to their final byte, are dropped. The rest of the
+/// sequence is discarded up to its final byte, or its String Terminator
+/// for DCS, without collecting parameters.
+///
+/// # Reentrancy
+///
+/// [`Perform`] callbacks are invoked while the parser is mutably borrowed,
+/// so they can not feed bytes back into the same parser. Callbacks only
+/// observe borrowed slices of the parser state, which stay valid until the
+/// callback returns.
+///
+/// Parsers can be cloned to continue parsing from the same state, for
+/// example to try input speculatively.
+#[derive(Clone, Default)]
 pub struct Parser<const OSC_RAW_BUF_SIZE: usize = MAX_OSC_RAW> {
     state: State,
     intermediates: [u8; MAX_INTERMEDIATES],
@@ -84,10 +145,34 @@ pub struct Parser<const OSC_RAW_BUF_SIZE: usize = MAX_OSC_RAW> {
     osc_raw: Vec<u8>,
     osc_params: [(usize, usize); MAX_OSC_PARAMS],
     osc_num_params: usize,
+    max_osc_len: Option<usize>,
+    max_sequence_len: Option<usize>,
+    sequence_len: usize,
+    opaque_kind: OpaqueSequenceKind,
     ignoring: bool,
     utf8_parser: utf8::Parser,
 }
 
+impl<const OSC_RAW_BUF_SIZE: usize> fmt::Debug for Parser<OSC_RAW_BUF_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The OSC buffer can be large and hold arbitrary data, only its
+        // length is shown.
+        f.debug_struct("Parser")
+            .field("state", &self.state)
+            .field("intermediates", &self.intermediates())
+            .field("params", &self.params)
+            .field("param", &self.param)
+            .field("osc_len", &self.osc_raw.len())
+            .field("osc_num_params", &self.osc_num_params)
+            .field("max_osc_len", &self.max_osc_len)
+            .field("max_sequence_len", &self.max_sequence_len())
+            .field("sequence_len", &self.sequence_len)
+            .field("opaque_kind", &self.opaque_kind)
+            .field("ignoring", &self.ignoring)
+            .finish()
+    }
+}
+
 impl Parser {
     /// Create a new Parser
     pub fn new() -> Parser {
@@ -108,6 +193,58 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         Default::default()
     }
 
+    /// Limit the Operating System Command buffer to `max_osc_len` bytes,
+    /// bytes past it are dropped until the command ends.
+    ///
+    /// ```rust
+    /// let mut p = copa::Parser::new().with_max_osc_len(4096);
+    /// ```
+    #[must_use]
+    pub const fn with_max_osc_len(mut self, max_osc_len: usize) -> Self {
+        self.max_osc_len = Some(max_osc_len);
+        self
+    }
+
+    /// Maximum number of bytes kept for an Operating System Command.
+    #[inline]
+    pub const fn max_osc_len(&self) -> Option<usize> {
+        #[cfg(feature = "no_std")]
+        {
+            match self.max_osc_len {
+                Some(max_osc_len) if max_osc_len < OSC_RAW_BUF_SIZE => Some(max_osc_len),
+                _ => Some(OSC_RAW_BUF_SIZE),
+            }
+        }
+
+        #[cfg(not(feature = "no_std"))]
+        {
+            self.max_osc_len
+        }
+    }
+
+    /// Limit escape, CSI and DCS sequences to `max_sequence_len` bytes,
+    /// longer ones are aborted and reported with
+    /// [`Perform::sequence_aborted`]. The default of 4096 bytes is far
+    /// above any valid sequence.
+    ///
+    /// ```rust
+    /// let mut p = copa::Parser::new().with_max_sequence_len(256);
+    /// ```
+    #[must_use]
+    pub const fn with_max_sequence_len(mut self, max_sequence_len: usize) -> Self {
+        self.max_sequence_len = Some(max_sequence_len);
+        self
+    }
+
+    /// Maximum number of bytes of an escape, CSI or DCS sequence.
+    #[inline]
+    pub const fn max_sequence_len(&self) -> usize {
+        match self.max_sequence_len {
+            Some(max_sequence_len) => max_sequence_len,
+            None => MAX_SEQUENCE_LEN,
+        }
+    }
+
     #[inline]
     fn params(&self) -> &Params {
         &self.params
@@ -131,6 +268,34 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
             return;
         }
 
+        if let State::SosPmApcString = self.state {
+            if is_opaque_byte(byte) {
+                performer.opaque_put(self.opaque_kind, byte);
+                return;
+            }
+        }
+
+        // The 8-bit String Terminator ends the OSC string, unless it is the
+        // continuation byte of an UTF-8 character inside the string.
+        if byte == 0x9c
+            && matches!(self.state, State::OscString)
+            && !self.osc_in_utf8_sequence()
+        {
+            self.perform_state_change(performer, State::Ground, Action::None, byte);
+            return;
+        }
+
+        // Strings are not counted, their payload is either bounded by
+        // `max_osc_len` or streamed to the performer.
+        if self.state.is_sequence() {
+            self.sequence_len += 1;
+            if self.sequence_len > self.max_sequence_len() {
+                self.state = self.state.ignore_state();
+                self.sequence_len = 0;
+                performer.sequence_aborted();
+            }
+        }
+
         // Handle state changes in the anywhere state before evaluating changes
         // for current state.
         let mut change = table::STATE_CHANGES[State::Anywhere as usize][byte as usize];
@@ -145,6 +310,175 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         self.perform_state_change(performer, state, action, byte);
     }
 
+    /// Whether the parser is between sequences
+    ///
+    /// This is `true` in the ground state with no partial UTF-8 character
+    /// buffered, so the bytes fed to [`advance`] so far can be forwarded
+    /// without splitting an escape sequence or a character.
+    ///
+    /// [`advance`]: struct.Parser.html#method.advance
+    #[inline]
+    pub fn at_boundary(&self) -> bool {
+        matches!(self.state, State::Ground)
+    }
+
+    /// Advance the parser over a whole chunk of bytes
+    ///
+    /// Returns [`at_boundary`] after the last byte, `false` means the chunk
+    /// ended in the middle of a sequence which the next chunk completes.
+    ///
+    /// [`at_boundary`]: struct.Parser.html#method.at_boundary
+    pub fn advance_chunked<P: Perform>(
+        &mut self,
+        performer: &mut P,
+        bytes: &[u8],
+    ) -> bool {
+        let mut index = 0;
+        while index < bytes.len() {
+            index += self.advance_step(performer, &bytes[index..]);
+        }
+
+        self.at_boundary()
+    }
+
+    /// Advance the parser until the [`Perform`] asks to stop
+    ///
+    /// [`Perform::terminated`] is checked after every byte, or after every
+    /// run of bytes passed to the bulk methods of SOS, PM and APC strings,
+    /// the returned
+    /// [`Advanced`] tells how many bytes were consumed and whether parsing
+    /// stopped because of it or because the input was exhausted.
+    pub fn advance_until_terminated<P: Perform>(
+        &mut self,
+        performer: &mut P,
+        bytes: &[u8],
+    ) -> Advanced {
+        let mut index = 0;
+        while index < bytes.len() {
+            index += self.advance_step(performer, &bytes[index..]);
+
+            if performer.terminated() {
+                return Advanced {
+                    consumed: index,
+                    terminated: true,
+                };
+            }
+        }
+
+        Advanced {
+            consumed: bytes.len(),
+            terminated: false,
+        }
+    }
+
+    /// Advance the parser until a single complete sequence was dispatched
+    ///
+    /// A sequence is either a run of printable characters, a single executed
+    /// control, or a complete ESC, CSI, OSC, DCS, SOS, PM or APC sequence. The
+    /// `ESC \` closing a string is consumed along with it. Returns the number of
+    /// bytes consumed from `bytes`, an incomplete sequence consumes every byte
+    /// and is completed by following calls.
+    pub fn parse_one<P: Perform>(&mut self, performer: &mut P, bytes: &[u8]) -> usize {
+        let mut one = OneDispatch {
+            performer,
+            dispatched: None,
+            swallow_st: false,
+        };
+
+        let mut consumed = 0;
+        let mut terminated_by_esc = false;
+        for &byte in bytes {
+            match one.dispatched {
+                None => (),
+                // A run of printable characters ends before the first byte
+                // that would not be printed.
+                Some(Dispatched::Print)
+                    if matches!(self.state, State::Ground)
+                        && !matches!(Self::ground_action(byte), Action::Print) =>
+                {
+                    break
+                }
+                Some(Dispatched::Print) => (),
+                Some(_) => {
+                    if terminated_by_esc && byte == b'\\' {
+                        one.swallow_st = true;
+                        self.advance(&mut one, byte);
+                        consumed += 1;
+                    }
+                    break;
+                }
+            }
+
+            self.advance(&mut one, byte);
+            consumed += 1;
+            terminated_by_esc = byte == 0x1b
+                && matches!(
+                    one.dispatched,
+                    Some(Dispatched::Osc | Dispatched::Unhook | Dispatched::Opaque)
+                );
+        }
+
+        consumed
+    }
+
+    /// Advance over the start of `bytes`, which must not be empty, returning
+    /// the number of bytes consumed.
+    ///
+    /// Inside a SOS, PM or APC string the whole run of content bytes is
+    /// consumed at once, otherwise a single byte is.
+    #[inline]
+    fn advance_step<P: Perform>(&mut self, performer: &mut P, bytes: &[u8]) -> usize {
+        if let State::SosPmApcString = self.state {
+            let consumed = self.advance_opaque_string(performer, bytes);
+            if consumed > 0 {
+                return consumed;
+            }
+        }
+
+        self.advance(performer, bytes[0]);
+        1
+    }
+
+    /// Pass the leading run of content bytes of a SOS, PM or APC string to
+    /// the matching bulk method of the [`Perform`] with a single call.
+    fn advance_opaque_string<P: Perform>(
+        &mut self,
+        performer: &mut P,
+        bytes: &[u8],
+    ) -> usize {
+        let len = bytes
+            .iter()
+            .position(|&byte| !is_opaque_byte(byte))
+            .unwrap_or(bytes.len());
+        if len == 0 {
+            return 0;
+        }
+
+        let run = &bytes[..len];
+        match self.opaque_kind {
+            OpaqueSequenceKind::Sos => performer.sos_put_bulk(run),
+            OpaqueSequenceKind::Pm => performer.pm_put_bulk(run),
+            OpaqueSequenceKind::Apc => performer.apc_put_bulk(run),
+        }
+
+        len
+    }
+
+    /// Action performed by `byte` in the ground state, multi-byte UTF-8
+    /// characters are reported as [`Action::Print`].
+    #[inline]
+    fn ground_action(byte: u8) -> Action {
+        let mut change = table::STATE_CHANGES[State::Anywhere as usize][byte as usize];
+        if change == 0 {
+            change = table::STATE_CHANGES[State::Ground as usize][byte as usize];
+        }
+
+        match unpack(change) {
+            (_, Action::BeginUtf8) => Action::Print,
+            (_, action) => action,
+        }
+    }
+
     #[inline]
     fn process_utf8<P>(&mut self, performer: &mut P, byte: u8)
     where
@@ -186,9 +520,13 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                     State::DcsPassthrough => {
                         self.perform_action(performer, Action::Unhook, byte);
                     }
+                    // CAN and SUB abort the OSC string, so it is discarded
+                    // instead of being dispatched like it would on ST or BEL.
+                    State::OscString if byte == 0x18 || byte == 0x1a => (),
                     State::OscString => {
                         self.perform_action(performer, Action::OscEnd, byte);
                     }
+                    State::SosPmApcString => performer.opaque_end(self.opaque_kind),
                     _ => (),
                 }
 
@@ -204,6 +542,14 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                     State::OscString => {
                         self.perform_action(performer, Action::OscStart, byte);
                     }
+                    State::SosPmApcString => {
+                        self.opaque_kind = match byte {
+                            b'X' => OpaqueSequenceKind::Sos,
+                            b'^' => OpaqueSequenceKind::Pm,
+                            _ => OpaqueSequenceKind::Apc,
+                        };
+                        performer.opaque_start(self.opaque_kind);
+                    }
                     _ => (),
                 }
 
@@ -213,25 +559,37 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
         }
     }
 
+    /// Whether the OSC string ends with an incomplete UTF-8 character.
+    #[inline]
+    fn osc_in_utf8_sequence(&self) -> bool {
+        let continuations = self
+            .osc_raw
+            .iter()
+            .rev()
+            .take(3)
+            .take_while(|byte| (0x80..=0xbf).contains(*byte))
+            .count();
+
+        let expected = match self.osc_raw.iter().rev().nth(continuations) {
+            Some(0xc0..=0xdf) => 1,
+            Some(0xe0..=0xef) => 2,
+            Some(0xf0..=0xf7) => 3,
+            _ => 0,
+        };
+
+        continuations < expected
+    }
+
     /// Separate method for osc_dispatch that borrows self as read-only
-    ///
-    /// The aliasing is needed here for multiple slices into self.osc_raw
     #[inline]
     fn osc_dispatch<P: Perform>(&self, performer: &mut P, byte: u8) {
-        let mut slices: [MaybeUninit<&[u8]>; MAX_OSC_PARAMS] =
-            unsafe { MaybeUninit::uninit().assume_init() };
+        let boundaries = &self.osc_params[..self.osc_num_params];
 
-        for (i, slice) in slices.iter_mut().enumerate().take(self.osc_num_params) {
-            let indices = self.osc_params[i];
-            *slice = MaybeUninit::new(&self.osc_raw[indices.0..indices.1]);
-        }
+        #[cfg(not(feature = "no_std"))]
+        performer.osc_dispatch_raw(&self.osc_raw, boundaries, byte == 0x07);
 
-        unsafe {
-            let num_params = self.osc_num_params;
-            let params = &slices[..num_params] as *const [MaybeUninit<&[u8]>]
-                as *const [&[u8]];
-            performer.osc_dispatch(&*params, byte == 0x07);
-        }
+        #[cfg(feature = "no_std")]
+        osc_dispatch_params(performer, &self.osc_raw, boundaries, byte == 0x07);
     }
 
     #[inline]
@@ -271,6 +629,10 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                     }
                 }
 
+                if matches!(self.max_osc_len, Some(max) if self.osc_raw.len() >= max) {
+                    return;
+                }
+
                 let idx = self.osc_raw.len();
 
                 // Param separator
@@ -362,8 +724,17 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                     self.param = 0;
                 } else {
                     // Continue collecting bytes into param
-                    self.param = self.param.saturating_mul(10);
-                    self.param = self.param.saturating_add((byte - b'0') as u16);
+                    match self
+                        .param
+                        .checked_mul(10)
+                        .and_then(|param| param.checked_add((byte - b'0') as u16))
+                    {
+                        Some(param) => self.param = param,
+                        None => {
+                            self.param = u16::MAX;
+                            self.params.set_overflowed();
+                        }
+                    }
                 }
             }
             Action::Clear => {
@@ -371,6 +742,7 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
                 self.intermediate_idx = 0;
                 self.ignoring = false;
                 self.param = 0;
+                self.sequence_len = 0;
 
                 self.params.clear();
             }
@@ -381,6 +753,182 @@ impl<const OSC_RAW_BUF_SIZE: usize> Parser<OSC_RAW_BUF_SIZE> {
     }
 }
 
+/// Kind of the first top-level dispatch seen by [`Parser::parse_one`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dispatched {
+    Print,
+    Execute,
+    Csi,
+    Esc,
+    Osc,
+    Unhook,
+    Opaque,
+}
+
+/// Forwards every action while recording the first top-level dispatch.
+struct OneDispatch<'a, P: Perform> {
+    performer: &'a mut P,
+    dispatched: Option<Dispatched>,
+    // Drops the `ESC \` dispatch closing an OSC, DCS, SOS, PM or APC string.
+    swallow_st: bool,
+}
+
+impl<P: Perform> OneDispatch<'_, P> {
+    #[inline]
+    fn dispatch(&mut self, dispatched: Dispatched) {
+        if self.dispatched.is_none() {
+            self.dispatched = Some(dispatched);
+        }
+    }
+}
+
+impl<P: Perform> Perform for OneDispatch<'_, P> {
+    fn print(&mut self, c: char) {
+        self.dispatch(Dispatched::Print);
+        self.performer.print(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        self.dispatch(Dispatched::Execute);
+        self.performer.execute(byte);
+    }
+
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, c: char) {
+        self.performer.hook(params, intermediates, ignore, c);
+    }
+
+    fn put(&mut self, byte: u8) {
+        self.performer.put(byte);
+    }
+
+    fn unhook(&mut self) {
+        self.dispatch(Dispatched::Unhook);
+        self.performer.unhook();
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        self.dispatch(Dispatched::Osc);
+        self.performer.osc_dispatch(params, bell_terminated);
+    }
+
+    #[cfg(not(feature = "no_std"))]
+    fn osc_dispatch_raw(
+        &mut self,
+        raw: &[u8],
+        boundaries: &[(usize, usize)],
+        bell_terminated: bool,
+    ) {
+        self.dispatch(Dispatched::Osc);
+        self.performer
+            .osc_dispatch_raw(raw, boundaries, bell_terminated);
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        ignore: bool,
+        c: char,
+    ) {
+        self.dispatch(Dispatched::Csi);
+        self.performer
+            .csi_dispatch(params, intermediates, ignore, c);
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        if self.swallow_st {
+            return;
+        }
+
+        self.dispatch(Dispatched::Esc);
+        self.performer.esc_dispatch(intermediates, ignore, byte);
+    }
+
+    fn sequence_aborted(&mut self) {
+        self.performer.sequence_aborted();
+    }
+
+    fn opaque_start(&mut self, kind: OpaqueSequenceKind) {
+        self.performer.opaque_start(kind);
+    }
+
+    fn opaque_put(&mut self, kind: OpaqueSequenceKind, byte: u8) {
+        self.performer.opaque_put(kind, byte);
+    }
+
+    fn opaque_end(&mut self, kind: OpaqueSequenceKind) {
+        self.dispatch(Dispatched::Opaque);
+        self.performer.opaque_end(kind);
+    }
+
+    fn sos_put_bulk(&mut self, bytes: &[u8]) {
+        self.performer.sos_put_bulk(bytes);
+    }
+
+    fn pm_put_bulk(&mut self, bytes: &[u8]) {
+        self.performer.pm_put_bulk(bytes);
+    }
+
+    fn apc_put_bulk(&mut self, bytes: &[u8]) {
+        self.performer.apc_put_bulk(bytes);
+    }
+}
+
+/// Whether `byte` is content of a SOS, PM or APC string, C0 controls are
+/// ignored and the 8-bit String Terminator ends the string.
+#[inline]
+fn is_opaque_byte(byte: u8) -> bool {
+    byte >= 0x20 && byte != 0x9c
+}
+
+/// Dispatches an OSC to [`Perform::osc_dispatch`], slicing each parameter out of `raw`.
+///
+/// The aliasing is needed here for multiple slices into `raw`.
+#[cfg(not(feature = "safe_osc"))]
+#[inline]
+fn osc_dispatch_params<P: Perform + ?Sized>(
+    performer: &mut P,
+    raw: &[u8],
+    boundaries: &[(usize, usize)],
+    bell_terminated: bool,
+) {
+    let mut slices: [MaybeUninit<&[u8]>; MAX_OSC_PARAMS] =
+        unsafe { MaybeUninit::uninit().assume_init() };
+
+    for (slice, indices) in slices.iter_mut().zip(boundaries) {
+        *slice = MaybeUninit::new(&raw[indices.0..indices.1]);
+    }
+
+    unsafe {
+        let num_params = boundaries.len();
+        let params =
+            &slices[..num_params] as *const [MaybeUninit<&[u8]>] as *const [&[u8]];
+        performer.osc_dispatch(&*params, bell_terminated);
+    }
+}
+
+/// Same as `osc_dispatch_params` without `unsafe`, the slices are written over an
+/// array of empty ones instead of an uninitialized array. Enabled by `safe_osc`.
+#[cfg(any(feature = "safe_osc", all(test, not(feature = "no_std"))))]
+#[inline]
+fn osc_dispatch_params_safe<P: Perform + ?Sized>(
+    performer: &mut P,
+    raw: &[u8],
+    boundaries: &[(usize, usize)],
+    bell_terminated: bool,
+) {
+    let mut slices: [&[u8]; MAX_OSC_PARAMS] = [&[]; MAX_OSC_PARAMS];
+
+    for (slice, indices) in slices.iter_mut().zip(boundaries) {
+        *slice = &raw[indices.0..indices.1];
+    }
+
+    performer.osc_dispatch(&slices[..boundaries.len()], bell_terminated);
+}
+
+#[cfg(feature = "safe_osc")]
+use osc_dispatch_params_safe as osc_dispatch_params;
+
 /// Performs actions requested by the Parser
 ///
 /// Actions in this case mean, for example, handling a CSI escape sequence describing cursor
@@ -427,8 +975,30 @@ pub trait Perform {
     fn unhook(&mut self) {}
 
     /// Dispatch an operating system command.
+    ///
+    /// Strings aborted by `CAN` (0x18) or `SUB` (0x1A) are discarded without being
+    /// dispatched, only the `execute` for the aborting byte is performed.
     fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {}
 
+    /// Dispatch an operating system command with its whole raw payload.
+    ///
+    /// `raw` holds every parameter back to back without the `;` separators, and each
+    /// entry of `boundaries` is the `(start, end)` range of a parameter inside `raw`.
+    /// This lets handlers of large payloads (e.g. OSC 52) decode them in place instead of
+    /// copying every parameter. The slice borrows the parser buffer, so it is only valid
+    /// until this call returns.
+    ///
+    /// By default, this slices the parameters and calls [`Perform::osc_dispatch`].
+    #[cfg(not(feature = "no_std"))]
+    fn osc_dispatch_raw(
+        &mut self,
+        raw: &[u8],
+        boundaries: &[(usize, usize)],
+        bell_terminated: bool,
+    ) {
+        osc_dispatch_params(self, raw, boundaries, bell_terminated);
+    }
+
     /// A final character has arrived for a CSI sequence
     ///
     /// The `ignore` flag indicates that either more than two intermediates arrived
@@ -448,6 +1018,58 @@ pub trait Perform {
     /// The `ignore` flag indicates that more than two intermediates arrived and
     /// subsequent characters were ignored.
     fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {}
+
+    /// Called when an escape, CSI or DCS sequence grew past
+    /// [`Parser::max_sequence_len`] bytes. Nothing is dispatched for it and
+    /// the rest of the sequence is discarded.
+    fn sequence_aborted(&mut self) {}
+
+    /// Invoked when a SOS, PM or APC string starts.
+    fn opaque_start(&mut self, _kind: OpaqueSequenceKind) {}
+
+    /// Pass a byte of the SOS, PM or APC string started by `opaque_start`.
+    /// C0 controls are not passed.
+    fn opaque_put(&mut self, _kind: OpaqueSequenceKind, _byte: u8) {}
+
+    /// Called when a SOS, PM or APC string is terminated or aborted.
+    fn opaque_end(&mut self, _kind: OpaqueSequenceKind) {}
+
+    /// Pass a run of bytes of a SOS string.
+    ///
+    /// Large payloads are passed with a single call when the input is fed with
+    /// [`Parser::advance_chunked`] or [`Parser::advance_until_terminated`].
+    /// By default, this calls [`Perform::opaque_put`] for every byte.
+    fn sos_put_bulk(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.opaque_put(OpaqueSequenceKind::Sos, byte);
+        }
+    }
+
+    /// Pass a run of bytes of a PM string, see [`Perform::sos_put_bulk`].
+    fn pm_put_bulk(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.opaque_put(OpaqueSequenceKind::Pm, byte);
+        }
+    }
+
+    /// Pass a run of bytes of an APC string, like the payload of a Kitty
+    /// graphics command, see [`Perform::sos_put_bulk`].
+    fn apc_put_bulk(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.opaque_put(OpaqueSequenceKind::Apc, byte);
+        }
+    }
+
+    /// Whether parsing should stop, checked after every byte or bulk run by
+    /// [`Parser::advance_until_terminated`].
+    ///
+    /// This allows a performer to stop at a given sequence, for example the
+    /// beginning of a synchronized update, and handle the rest of the input
+    /// differently.
+    #[inline]
+    fn terminated(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(all(test, feature = "no_std"))]
@@ -458,6 +1080,7 @@ extern crate std;
 mod tests {
     use super::*;
 
+    use std::string::String;
     use std::vec::Vec;
 
     static OSC_BYTES: &[u8] = &[
@@ -523,6 +1146,288 @@ mod tests {
         }
     }
 
+    #[test]
+    fn at_boundary() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+        assert!(parser.at_boundary());
+
+        assert!(!parser.advance_chunked(&mut dispatcher, b"\x1b[1;3"));
+        assert!(dispatcher.dispatched.is_empty());
+
+        assert!(parser.advance_chunked(&mut dispatcher, b"1m"));
+        assert_eq!(
+            dispatcher.dispatched,
+            [Sequence::Csi(vec![vec![1], vec![31]], vec![], false, 'm')]
+        );
+
+        // A split UTF-8 character is not a boundary either.
+        assert!(!parser.advance_chunked(&mut dispatcher, &"a€".as_bytes()[..2]));
+        assert!(parser.advance_chunked(&mut dispatcher, &"€".as_bytes()[1..]));
+
+        // Neither is an OSC waiting for its terminator.
+        assert!(!parser.advance_chunked(&mut dispatcher, b"\x1b]0;rio\x1b"));
+        assert!(parser.advance_chunked(&mut dispatcher, b"\\"));
+    }
+
+    #[test]
+    fn cloned_parser_advances_independently() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+        for byte in b"\x1b[?1;2" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        let mut fork = parser.clone();
+        let mut fork_dispatcher = Dispatcher::default();
+        for byte in b"3m" {
+            fork.advance(&mut fork_dispatcher, *byte);
+        }
+        for byte in b"4h" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(
+            fork_dispatcher.dispatched,
+            [Sequence::Csi(
+                vec![vec![1], vec![23]],
+                vec![b'?'],
+                false,
+                'm'
+            )]
+        );
+        assert_eq!(
+            dispatcher.dispatched,
+            [Sequence::Csi(
+                vec![vec![1], vec![24]],
+                vec![b'?'],
+                false,
+                'h'
+            )]
+        );
+    }
+
+    #[test]
+    fn debug_shows_state_without_osc_bytes() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+        for byte in b"\x1b[?1;2" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+        let debug = format!("{parser:?}");
+        assert!(debug.contains("state: CsiParam"), "{debug}");
+        assert!(debug.contains("intermediates: [63]"), "{debug}");
+        assert!(debug.contains("params: [1]"), "{debug}");
+        assert!(debug.contains("param: 2"), "{debug}");
+
+        for byte in b"m\x1b]2;secret" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+        let debug = format!("{parser:?}");
+        assert!(debug.contains("osc_len: 7"), "{debug}");
+        assert!(!debug.contains("115, 101, 99"), "{debug}");
+    }
+
+    #[test]
+    fn advance_until_terminated() {
+        #[derive(Default)]
+        struct SyncStart {
+            sync: bool,
+        }
+
+        impl Perform for SyncStart {
+            fn csi_dispatch(
+                &mut self,
+                params: &Params,
+                intermediates: &[u8],
+                _ignore: bool,
+                action: char,
+            ) {
+                let is_sync = params.iter().next() == Some(&[2026][..]);
+                self.sync |= intermediates == [b'?'] && action == 'h' && is_sync;
+            }
+
+            fn terminated(&self) -> bool {
+                self.sync
+            }
+        }
+
+        let mut parser = Parser::new();
+        let mut performer = SyncStart::default();
+        let input = b"ab\x1b[?2026hcd";
+        let advanced = parser.advance_until_terminated(&mut performer, input);
+        assert_eq!(
+            advanced,
+            Advanced {
+                consumed: input.len() - 2,
+                terminated: true
+            }
+        );
+
+        // Terminating on the last byte is told apart from running out of input.
+        let mut performer = SyncStart::default();
+        let advanced =
+            parser.advance_until_terminated(&mut performer, &input[..input.len() - 2]);
+        assert_eq!(advanced.consumed, input.len() - 2);
+        assert!(advanced.terminated);
+
+        let mut performer = SyncStart::default();
+        let advanced = parser.advance_until_terminated(&mut performer, b"ab\x1b[1m");
+        assert_eq!(
+            advanced,
+            Advanced {
+                consumed: 6,
+                terminated: false
+            }
+        );
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Opaque {
+        Start(OpaqueSequenceKind),
+        Put(OpaqueSequenceKind, u8),
+        Bulk(OpaqueSequenceKind, Vec<u8>),
+        End(OpaqueSequenceKind),
+        Esc(u8),
+        Print(char),
+    }
+
+    #[derive(Default)]
+    struct OpaqueDispatcher {
+        dispatched: Vec<Opaque>,
+    }
+
+    impl Perform for OpaqueDispatcher {
+        fn print(&mut self, c: char) {
+            self.dispatched.push(Opaque::Print(c));
+        }
+
+        fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, byte: u8) {
+            self.dispatched.push(Opaque::Esc(byte));
+        }
+
+        fn opaque_start(&mut self, kind: OpaqueSequenceKind) {
+            self.dispatched.push(Opaque::Start(kind));
+        }
+
+        fn opaque_put(&mut self, kind: OpaqueSequenceKind, byte: u8) {
+            self.dispatched.push(Opaque::Put(kind, byte));
+        }
+
+        fn opaque_end(&mut self, kind: OpaqueSequenceKind) {
+            self.dispatched.push(Opaque::End(kind));
+        }
+
+        fn apc_put_bulk(&mut self, bytes: &[u8]) {
+            self.dispatched
+                .push(Opaque::Bulk(OpaqueSequenceKind::Apc, bytes.to_vec()));
+        }
+
+        fn pm_put_bulk(&mut self, bytes: &[u8]) {
+            self.dispatched
+                .push(Opaque::Bulk(OpaqueSequenceKind::Pm, bytes.to_vec()));
+        }
+    }
+
+    #[test]
+    fn parse_large_apc_in_bulk() {
+        let mut payload = b"Gf=100;".to_vec();
+        payload.resize(64 * 1024, b'A');
+
+        let mut input = b"\x1b_".to_vec();
+        input.extend_from_slice(&payload);
+        input.extend_from_slice(b"\x1b\\x");
+
+        let mut dispatcher = OpaqueDispatcher::default();
+        let mut parser = Parser::new();
+        assert!(parser.advance_chunked(&mut dispatcher, &input));
+        assert_eq!(
+            dispatcher.dispatched,
+            [
+                Opaque::Start(OpaqueSequenceKind::Apc),
+                Opaque::Bulk(OpaqueSequenceKind::Apc, payload.clone()),
+                Opaque::End(OpaqueSequenceKind::Apc),
+                Opaque::Esc(b'\\'),
+                Opaque::Print('x'),
+            ]
+        );
+
+        // Feeding a byte at a time passes the same content through `opaque_put`.
+        let mut dispatcher = OpaqueDispatcher::default();
+        for byte in &input {
+            parser.advance(&mut dispatcher, *byte);
+        }
+        let put: Vec<u8> = dispatcher
+            .dispatched
+            .iter()
+            .filter_map(|opaque| match opaque {
+                Opaque::Put(OpaqueSequenceKind::Apc, byte) => Some(*byte),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(put, payload);
+        assert_eq!(dispatcher.dispatched.last(), Some(&Opaque::Print('x')));
+    }
+
+    #[test]
+    fn parse_one_opaque_strings() {
+        for (input, kind) in [
+            (&b"\x1b_Gabc\x1b\\x"[..], OpaqueSequenceKind::Apc),
+            (b"\x1b^ab\x1b\\x", OpaqueSequenceKind::Pm),
+            (b"\x1bXab\x1b\\x", OpaqueSequenceKind::Sos),
+        ] {
+            let mut dispatcher = OpaqueDispatcher::default();
+            let mut parser = Parser::new();
+
+            // The ESC \ closing the string is consumed without dispatching.
+            let consumed = parser.parse_one(&mut dispatcher, input);
+            assert_eq!(consumed, input.len() - 1);
+
+            let mut expected = vec![Opaque::Start(kind)];
+            expected.extend(
+                input[2..consumed - 2]
+                    .iter()
+                    .map(|byte| Opaque::Put(kind, *byte)),
+            );
+            expected.push(Opaque::End(kind));
+            assert_eq!(dispatcher.dispatched, expected);
+        }
+    }
+
+    #[test]
+    fn parse_opaque_strings_across_chunks() {
+        let mut dispatcher = OpaqueDispatcher::default();
+        let mut parser = Parser::new();
+
+        // C0 controls split the runs without being passed, the 8-bit ST ends
+        // the string.
+        assert!(!parser.advance_chunked(&mut dispatcher, b"\x1b^ab\ncd"));
+        assert!(parser.advance_chunked(&mut dispatcher, b"ef\x9c"));
+        assert_eq!(
+            dispatcher.dispatched,
+            [
+                Opaque::Start(OpaqueSequenceKind::Pm),
+                Opaque::Bulk(OpaqueSequenceKind::Pm, b"ab".to_vec()),
+                Opaque::Bulk(OpaqueSequenceKind::Pm, b"cd".to_vec()),
+                Opaque::Bulk(OpaqueSequenceKind::Pm, b"ef".to_vec()),
+                Opaque::End(OpaqueSequenceKind::Pm),
+            ]
+        );
+
+        // SOS uses the default bulk method, which puts every byte.
+        let mut dispatcher = OpaqueDispatcher::default();
+        assert!(parser.advance_chunked(&mut dispatcher, b"\x1bXhi\x18"));
+        assert_eq!(
+            dispatcher.dispatched,
+            [
+                Opaque::Start(OpaqueSequenceKind::Sos),
+                Opaque::Put(OpaqueSequenceKind::Sos, b'h'),
+                Opaque::Put(OpaqueSequenceKind::Sos, b'i'),
+                Opaque::End(OpaqueSequenceKind::Sos),
+            ]
+        );
+    }
+
     #[test]
     fn parse_osc() {
         let mut dispatcher = Dispatcher::default();
@@ -597,6 +1502,234 @@ mod tests {
         }
     }
 
+    #[cfg(not(feature = "no_std"))]
+    #[test]
+    fn osc_dispatch_raw_boundaries() {
+        #[derive(Default)]
+        struct RawOsc {
+            raw: Vec<u8>,
+            boundaries: Vec<(usize, usize)>,
+        }
+
+        impl Perform for RawOsc {
+            fn osc_dispatch_raw(
+                &mut self,
+                raw: &[u8],
+                boundaries: &[(usize, usize)],
+                _bell_terminated: bool,
+            ) {
+                self.raw = raw.to_vec();
+                self.boundaries = boundaries.to_vec();
+            }
+        }
+
+        let mut raw_osc = RawOsc::default();
+        let mut dispatcher = Dispatcher::default();
+        let mut raw_parser = Parser::new();
+        let mut parser = Parser::new();
+        for byte in b"\x1b]52;c;aGVsbG8=\x07" {
+            raw_parser.advance(&mut raw_osc, *byte);
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(raw_osc.raw, b"52caGVsbG8=");
+        let params: Vec<Vec<u8>> = raw_osc
+            .boundaries
+            .iter()
+            .map(|(start, end)| raw_osc.raw[*start..*end].to_vec())
+            .collect();
+        assert_eq!(params, [&b"52"[..], b"c", b"aGVsbG8="]);
+
+        match &dispatcher.dispatched[..] {
+            [Sequence::Osc(expected, true)] => assert_eq!(&params, expected),
+            _ => panic!("expected osc sequence"),
+        }
+    }
+
+    #[cfg(not(feature = "no_std"))]
+    #[test]
+    fn osc_dispatch_params_safe_matches() {
+        type RawOscCall = (Vec<u8>, Vec<(usize, usize)>, bool);
+
+        #[derive(Default)]
+        struct RawOsc(Vec<RawOscCall>);
+
+        impl Perform for RawOsc {
+            fn osc_dispatch_raw(
+                &mut self,
+                raw: &[u8],
+                boundaries: &[(usize, usize)],
+                bell_terminated: bool,
+            ) {
+                self.0
+                    .push((raw.to_vec(), boundaries.to_vec(), bell_terminated));
+            }
+        }
+
+        let max_params = format!("\x1b]{}\x07", ";".repeat(params::MAX_PARAMS + 1));
+        let vectors: [&[u8]; 6] = [
+            OSC_BYTES,
+            b"\x1b]\x07",
+            max_params.as_bytes(),
+            b"\x1b]11;ff/00/ff\x07",
+            b"\x1b]52;c;aGVsbG8=\x1b\\",
+            "\x1b]2;echo '¯\\_(ツ)_/¯' && sleep 1\x07".as_bytes(),
+        ];
+
+        for input in vectors {
+            let mut raw_osc = RawOsc::default();
+            let mut parser = Parser::new();
+            for byte in input {
+                parser.advance(&mut raw_osc, *byte);
+            }
+            assert_eq!(raw_osc.0.len(), 1);
+
+            let (raw, boundaries, bell_terminated) = &raw_osc.0[0];
+            let mut dispatcher = Dispatcher::default();
+            let mut safe_dispatcher = Dispatcher::default();
+            osc_dispatch_params(&mut dispatcher, raw, boundaries, *bell_terminated);
+            osc_dispatch_params_safe(
+                &mut safe_dispatcher,
+                raw,
+                boundaries,
+                *bell_terminated,
+            );
+            assert_eq!(dispatcher.dispatched, safe_dispatcher.dispatched);
+        }
+    }
+
+    #[test]
+    fn parse_osc_8bit_st() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        for byte in b"\x1b]2;x\x9c" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(
+            dispatcher.dispatched,
+            [Sequence::Osc(vec![b"2".to_vec(), b"x".to_vec()], false)]
+        );
+    }
+
+    #[test]
+    fn parse_osc_utf8_continuation_is_not_st() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        // U+011C is encoded as 0xc4 0x9c.
+        for byte in "\x1b]2;\u{11c}\x07".as_bytes() {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(
+            dispatcher.dispatched,
+            [Sequence::Osc(
+                vec![b"2".to_vec(), "\u{11c}".as_bytes().to_vec()],
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_one_csi() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        let consumed = parser.parse_one(&mut dispatcher, b"\x1b[1;2mabc");
+
+        assert_eq!(consumed, 6);
+        assert_eq!(
+            dispatcher.dispatched,
+            [Sequence::Csi(vec![vec![1], vec![2]], vec![], false, 'm')]
+        );
+    }
+
+    #[test]
+    fn parse_one_osc() {
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new();
+
+        let consumed = parser.parse_one(&mut dispatcher, b"\x1b]0;t\x07\x1b[m");
+        assert_eq!(consumed, 6);
+
+        // The ESC \ closing the string is consumed without dispatching.
+        let consumed = parser.parse_one(&mut dispatcher, b"\x1b]0;t\x1b\\x");
+        assert_eq!(consumed, 7);
+
+        assert_eq!(
+            dispatcher.dispatched,
+            [
+                Sequence::Osc(vec![b"0".to_vec(), b"t".to_vec()], true),
+                Sequence::Osc(vec![b"0".to_vec(), b"t".to_vec()], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_one_text_run() {
+        #[derive(Default)]
+        struct Text {
+            printed: String,
+            executed: Vec<u8>,
+        }
+
+        impl Perform for Text {
+            fn print(&mut self, c: char) {
+                self.printed.push(c);
+            }
+
+            fn execute(&mut self, byte: u8) {
+                self.executed.push(byte);
+            }
+        }
+
+        let mut performer = Text::default();
+        let mut parser = Parser::new();
+        let input = "héllo\r\n".as_bytes();
+
+        let consumed = parser.parse_one(&mut performer, input);
+        assert_eq!(consumed, "héllo".len());
+        assert_eq!(performer.printed, "héllo");
+        assert!(performer.executed.is_empty());
+
+        let consumed = parser.parse_one(&mut performer, &input["héllo".len()..]);
+        assert_eq!(consumed, 1);
+        assert_eq!(performer.executed, [b'\r']);
+    }
+
+    #[test]
+    fn osc_aborted_by_can_or_sub() {
+        #[derive(Default)]
+        struct OscAbort {
+            osc: usize,
+            executed: Vec<u8>,
+        }
+
+        impl Perform for OscAbort {
+            fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {
+                self.osc += 1;
+            }
+
+            fn execute(&mut self, byte: u8) {
+                self.executed.push(byte);
+            }
+        }
+
+        for input in [b"\x1b]2;title\x18", b"\x1b]2;title\x1a"] {
+            let mut performer = OscAbort::default();
+            let mut parser = Parser::new();
+
+            for byte in input {
+                parser.advance(&mut performer, *byte);
+            }
+
+            assert_eq!(performer.osc, 0);
+            assert_eq!(performer.executed, [input[input.len() - 1]]);
+        }
+    }
+
     #[test]
     fn osc_c0_st_terminated() {
         static INPUT: &[u8] = b"\x1b]11;ff/00/ff\x1b\\";
@@ -727,6 +1860,140 @@ mod tests {
         }
     }
 
+    #[cfg(not(feature = "no_std"))]
+    #[test]
+    fn max_osc_len_caps_buffer() {
+        static NUM_BYTES: usize = 10 * 1024 * 1024;
+        static MAX_OSC_LEN: usize = 4096;
+
+        let mut dispatcher = Dispatcher::default();
+        let mut parser = Parser::new().with_max_osc_len(MAX_OSC_LEN);
+
+        for byte in b"\x1b]52;" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+        for _ in 0..NUM_BYTES {
+            parser.advance(&mut dispatcher, b'a');
+        }
+        assert_eq!(parser.osc_raw.len(), MAX_OSC_LEN);
+
+        parser.advance(&mut dispatcher, 0x07);
+
+        assert_eq!(dispatcher.dispatched.len(), 1);
+        match &dispatcher.dispatched[0] {
+            Sequence::Osc(params, _) => {
+                assert_eq!(params.len(), 2);
+                assert_eq!(params[0], b"52");
+                assert_eq!(params[1].len(), MAX_OSC_LEN - params[0].len());
+            }
+            _ => panic!("expected osc sequence"),
+        }
+    }
+
+    #[test]
+    fn huge_csi_aborted_at_max_sequence_len() {
+        static NUM_BYTES: usize = 10 * 1024 * 1024;
+        static MAX_SEQUENCE_LEN: usize = 64;
+
+        #[derive(Default)]
+        struct Aborts {
+            csi: usize,
+            aborted: usize,
+            printed: usize,
+        }
+
+        impl Perform for Aborts {
+            fn print(&mut self, _c: char) {
+                self.printed += 1;
+            }
+
+            fn csi_dispatch(
+                &mut self,
+                _params: &Params,
+                _intermediates: &[u8],
+                _ignore: bool,
+                _action: char,
+            ) {
+                self.csi += 1;
+            }
+
+            fn sequence_aborted(&mut self) {
+                self.aborted += 1;
+            }
+        }
+
+        let mut performer = Aborts::default();
+        let mut parser = Parser::new().with_max_sequence_len(MAX_SEQUENCE_LEN);
+        assert_eq!(Parser::new().max_sequence_len(), 4096);
+
+        parser.advance(&mut performer, 0x1b);
+        parser.advance(&mut performer, b'[');
+        for _ in 0..NUM_BYTES / 2 {
+            parser.advance(&mut performer, b'1');
+            parser.advance(&mut performer, b';');
+        }
+        parser.advance(&mut performer, b'm');
+
+        // Parameters past the limit and the final `m` are discarded.
+        assert_eq!(performer.aborted, 1);
+        assert_eq!(performer.csi, 0);
+        assert_eq!(performer.printed, 0);
+        assert!(parser.at_boundary());
+
+        // Sequences under the limit are not affected.
+        for byte in b"\x1b[38;2;255;255;255m" {
+            parser.advance(&mut performer, *byte);
+        }
+        assert_eq!(performer.csi, 1);
+        assert_eq!(performer.aborted, 1);
+    }
+
+    #[test]
+    fn huge_dcs_aborted_until_string_terminator() {
+        #[derive(Default)]
+        struct Aborts {
+            hooks: usize,
+            puts: usize,
+            aborted: usize,
+            printed: usize,
+        }
+
+        impl Perform for Aborts {
+            fn print(&mut self, _c: char) {
+                self.printed += 1;
+            }
+
+            fn hook(&mut self, _: &Params, _: &[u8], _: bool, _: char) {
+                self.hooks += 1;
+            }
+
+            fn put(&mut self, _byte: u8) {
+                self.puts += 1;
+            }
+
+            fn sequence_aborted(&mut self) {
+                self.aborted += 1;
+            }
+        }
+
+        let mut performer = Aborts::default();
+        let mut parser = Parser::new().with_max_sequence_len(16);
+
+        let mut input = b"\x1bP".to_vec();
+        input.extend(b"1;".repeat(64));
+        input.extend(b"qdata\x1b\\after");
+        for byte in input {
+            parser.advance(&mut performer, byte);
+        }
+
+        // The data is discarded with the sequence, up to the terminator.
+        assert_eq!(performer.aborted, 1);
+        assert_eq!(performer.hooks, 0);
+        assert_eq!(performer.puts, 0);
+        assert_eq!(performer.printed, "after".len());
+        assert!(parser.at_boundary());
+    }
+
     #[test]
     fn parse_csi_max_params() {
         // This will build a list of repeating '1;'s
@@ -810,6 +2077,104 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    struct ParamDefaults {
+        next_or: Vec<u16>,
+        get_usize: Vec<usize>,
+    }
+
+    impl Perform for ParamDefaults {
+        fn csi_dispatch(&mut self, params: &Params, _: &[u8], _: bool, _: char) {
+            let mut params_iter = params.iter();
+            self.next_or = (0..3).map(|_| params_iter.next_or(7)).collect();
+            self.get_usize = (0..3).map(|i| params.get_usize(i, 7)).collect();
+        }
+    }
+
+    #[test]
+    fn params_default_on_trailing_semicolon() {
+        let mut dispatcher = ParamDefaults::default();
+        let mut parser = Parser::new();
+
+        for byte in b"\x1b[4;m" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(dispatcher.next_or, [4, 7, 7]);
+        assert_eq!(dispatcher.get_usize, [4, 7, 7]);
+    }
+
+    #[test]
+    fn params_default_on_leading_semicolon() {
+        let mut dispatcher = ParamDefaults::default();
+        let mut parser = Parser::new();
+
+        for byte in b"\x1b[;4m" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(dispatcher.next_or, [7, 4, 7]);
+        assert_eq!(dispatcher.get_usize, [7, 4, 7]);
+    }
+
+    #[test]
+    fn params_default_uses_first_subparameter() {
+        let mut dispatcher = ParamDefaults::default();
+        let mut parser = Parser::new();
+
+        for byte in b"\x1b[38:2:255;0;65535m" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(dispatcher.next_or, [38, 7, u16::MAX]);
+        assert_eq!(dispatcher.get_usize, [38, 7, u16::MAX as usize]);
+    }
+
+    #[test]
+    fn parse_csi_subparams_vs_params() {
+        for (input, expected) in [
+            (&b"\x1b[4:3m"[..], vec![vec![4, 3]]),
+            (&b"\x1b[4;3m"[..], vec![vec![4], vec![3]]),
+        ] {
+            let mut dispatcher = Dispatcher::default();
+            let mut parser = Parser::new();
+
+            for byte in input {
+                parser.advance(&mut dispatcher, *byte);
+            }
+
+            assert_eq!(dispatcher.dispatched.len(), 1);
+            match &dispatcher.dispatched[0] {
+                Sequence::Csi(params, ..) => assert_eq!(params, &expected),
+                _ => panic!("expected csi sequence"),
+            }
+        }
+    }
+
+    #[test]
+    fn params_subparam_boundary() {
+        #[derive(Default)]
+        struct Boundaries(Vec<bool>);
+
+        impl Perform for Boundaries {
+            fn csi_dispatch(&mut self, params: &Params, _: &[u8], _: bool, _: char) {
+                self.0 = (0..=params.len())
+                    .map(|i| params.is_subparam_boundary(i))
+                    .collect();
+            }
+        }
+
+        let mut dispatcher = Boundaries::default();
+        let mut parser = Parser::new();
+
+        // Parse a longer sequence first so no state leaks into the next one.
+        for byte in b"\x1b[1;2;3;4m\x1b[4:3;1m" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(dispatcher.0, [false, true, false, false]);
+    }
+
     #[test]
     fn parse_long_csi_param() {
         // The important part is the parameter, which is (i64::MAX + 1)
@@ -828,6 +2193,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_csi_param_overflow() {
+        #[derive(Default)]
+        struct Overflows(Vec<(Vec<u16>, bool)>);
+
+        impl Perform for Overflows {
+            fn csi_dispatch(&mut self, params: &Params, _: &[u8], _: bool, _: char) {
+                let values = params.iter().flatten().copied().collect();
+                self.0.push((values, params.overflowed()));
+            }
+        }
+
+        let mut dispatcher = Overflows::default();
+        let mut parser = Parser::new();
+        for byte in b"\x1b[65535;65536r\x1b[1;65535r\x1b[38:5:99999m\x1b[m" {
+            parser.advance(&mut dispatcher, *byte);
+        }
+
+        assert_eq!(
+            dispatcher.0,
+            [
+                (vec![65535, u16::MAX], true),
+                // The flag is reset by the next sequence.
+                (vec![1, 65535], false),
+                (vec![38, 5, u16::MAX], true),
+                (vec![0], false),
+            ]
+        );
+    }
+
     #[test]
     fn csi_reset() {
         static INPUT: &[u8] = b"\x1b[3;1\x1b[?1049h";
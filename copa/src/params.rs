@@ -4,7 +4,7 @@ use core::fmt::{self, Debug, Formatter};
 
 pub(crate) const MAX_PARAMS: usize = 32;
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Params {
     /// Number of subparameters for each parameter.
     ///
@@ -22,6 +22,9 @@ pub struct Params {
 
     /// Total number of parameters and subparameters.
     len: usize,
+
+    /// Whether a value exceeded `u16::MAX` and was saturated.
+    overflowed: bool,
 }
 
 impl Params {
@@ -38,11 +41,59 @@ impl Params {
     }
 
     /// Returns an iterator over all parameters and subparameters.
+    ///
+    /// Each yielded slice is one `;` separated parameter, holding its `:` separated
+    /// subparameters. So `4:3` yields `[4, 3]` while `4;3` yields `[4]` and `[3]`.
     #[inline]
     pub fn iter(&self) -> ParamsIter<'_> {
         ParamsIter::new(self)
     }
 
+    /// Returns the first value of the parameter at index `i` as `usize`.
+    ///
+    /// A missing parameter or a value of `0` yields `default`, following the
+    /// convention that an omitted or zero parameter selects the default value.
+    #[inline]
+    pub fn get_usize(&self, i: usize, default: usize) -> usize {
+        match self.iter().nth(i) {
+            Some(&[param, ..]) if param != 0 => param as usize,
+            _ => default,
+        }
+    }
+
+    /// Returns `true` if the value at `index` was separated from the previous one by
+    /// `:`, making it a subparameter instead of a new parameter.
+    ///
+    /// The `index` counts every parameter and subparameter, up to [`Params::len`].
+    #[inline]
+    pub fn is_subparam_boundary(&self, index: usize) -> bool {
+        let mut start = 0;
+        while start < self.len {
+            let end = start + self.subparams[start] as usize;
+            if index < end {
+                return index != start;
+            }
+            start = end;
+        }
+
+        false
+    }
+
+    /// Returns `true` if a parameter or subparameter was larger than `u16::MAX`.
+    ///
+    /// Such values are saturated to `u16::MAX`, consumers can check this to
+    /// reject the sequence instead of acting on the clamped value.
+    #[inline]
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// Mark a value of the current sequence as saturated.
+    #[inline]
+    pub(crate) fn set_overflowed(&mut self) {
+        self.overflowed = true;
+    }
+
     /// Returns `true` if there is no more space for additional parameters.
     #[inline]
     pub(crate) fn is_full(&self) -> bool {
@@ -54,6 +105,7 @@ impl Params {
     pub(crate) fn clear(&mut self) {
         self.current_subparams = 0;
         self.len = 0;
+        self.overflowed = false;
     }
 
     /// Add an additional parameter.
@@ -87,6 +139,8 @@ impl<'a> IntoIterator for &'a Params {
 }
 
 /// Immutable subparameter iterator.
+///
+/// Yields one slice per `;` separated parameter, see [`Params::iter`].
 pub struct ParamsIter<'a> {
     params: &'a Params,
     index: usize,
@@ -96,6 +150,18 @@ impl<'a> ParamsIter<'a> {
     fn new(params: &'a Params) -> Self {
         Self { params, index: 0 }
     }
+
+    /// Returns the first value of the next parameter.
+    ///
+    /// A missing parameter or a value of `0` yields `default`, following the
+    /// convention that an omitted or zero parameter selects the default value.
+    #[inline]
+    pub fn next_or(&mut self, default: u16) -> u16 {
+        match self.next() {
+            Some(&[param, ..]) if param != 0 => param,
+            _ => default,
+        }
+    }
 }
 
 impl<'a> Iterator for ParamsIter<'a> {
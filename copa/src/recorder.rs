@@ -0,0 +1,237 @@
+//! A [`Perform`] adapter that records every action to a writer.
+//!
+//! Useful to capture traces of what the parser dispatched, for example
+//! to reproduce issues reported by users. Each action is written as a
+//! single line, in the same format used by the `parselog` example.
+
+use std::io::Write;
+
+use crate::{OpaqueSequenceKind, Params, Perform};
+
+/// Forwards every action to the inner [`Perform`] while writing a line
+/// describing it to `W`.
+///
+/// Errors from the writer are ignored, recording never changes what is
+/// dispatched to the inner performer.
+pub struct RecordingPerform<W: Write, P: Perform> {
+    writer: W,
+    inner: P,
+}
+
+impl<W: Write, P: Perform> RecordingPerform<W, P> {
+    pub fn new(writer: W, inner: P) -> Self {
+        Self { writer, inner }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_parts(self) -> (W, P) {
+        (self.writer, self.inner)
+    }
+}
+
+impl<W: Write, P: Perform> Perform for RecordingPerform<W, P> {
+    fn print(&mut self, c: char) {
+        let _ = writeln!(self.writer, "[print] {:?}", c);
+        self.inner.print(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        let _ = writeln!(self.writer, "[execute] {:02x}", byte);
+        self.inner.execute(byte);
+    }
+
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, c: char) {
+        let _ = writeln!(
+            self.writer,
+            "[hook] params={:?}, intermediates={:?}, ignore={:?}, char={:?}",
+            params, intermediates, ignore, c
+        );
+        self.inner.hook(params, intermediates, ignore, c);
+    }
+
+    fn put(&mut self, byte: u8) {
+        let _ = writeln!(self.writer, "[put] {:02x}", byte);
+        self.inner.put(byte);
+    }
+
+    fn unhook(&mut self) {
+        let _ = writeln!(self.writer, "[unhook]");
+        self.inner.unhook();
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        let _ = writeln!(
+            self.writer,
+            "[osc_dispatch] params={:?}, bell_terminated={}",
+            params, bell_terminated
+        );
+        self.inner.osc_dispatch(params, bell_terminated);
+    }
+
+    fn osc_dispatch_raw(
+        &mut self,
+        raw: &[u8],
+        boundaries: &[(usize, usize)],
+        bell_terminated: bool,
+    ) {
+        let params: Vec<&[u8]> = boundaries
+            .iter()
+            .map(|(start, end)| &raw[*start..*end])
+            .collect();
+        let _ = writeln!(
+            self.writer,
+            "[osc_dispatch] params={:?}, bell_terminated={}",
+            params, bell_terminated
+        );
+        self.inner
+            .osc_dispatch_raw(raw, boundaries, bell_terminated);
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        ignore: bool,
+        c: char,
+    ) {
+        let _ = writeln!(
+            self.writer,
+            "[csi_dispatch] params={:?}, intermediates={:?}, ignore={:?}, char={:?}",
+            params, intermediates, ignore, c
+        );
+        self.inner.csi_dispatch(params, intermediates, ignore, c);
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        let _ = writeln!(
+            self.writer,
+            "[esc_dispatch] intermediates={:?}, ignore={:?}, byte={:02x}",
+            intermediates, ignore, byte
+        );
+        self.inner.esc_dispatch(intermediates, ignore, byte);
+    }
+
+    fn opaque_start(&mut self, kind: OpaqueSequenceKind) {
+        let _ = writeln!(self.writer, "[opaque_start] {:?}", kind);
+        self.inner.opaque_start(kind);
+    }
+
+    fn opaque_put(&mut self, kind: OpaqueSequenceKind, byte: u8) {
+        let _ = writeln!(self.writer, "[opaque_put] {:?} {:02x}", kind, byte);
+        self.inner.opaque_put(kind, byte);
+    }
+
+    fn opaque_end(&mut self, kind: OpaqueSequenceKind) {
+        let _ = writeln!(self.writer, "[opaque_end] {:?}", kind);
+        self.inner.opaque_end(kind);
+    }
+
+    fn sos_put_bulk(&mut self, bytes: &[u8]) {
+        let _ = writeln!(self.writer, "[sos_put_bulk] len={}", bytes.len());
+        self.inner.sos_put_bulk(bytes);
+    }
+
+    fn pm_put_bulk(&mut self, bytes: &[u8]) {
+        let _ = writeln!(self.writer, "[pm_put_bulk] len={}", bytes.len());
+        self.inner.pm_put_bulk(bytes);
+    }
+
+    fn apc_put_bulk(&mut self, bytes: &[u8]) {
+        let _ = writeln!(self.writer, "[apc_put_bulk] len={}", bytes.len());
+        self.inner.apc_put_bulk(bytes);
+    }
+
+    fn sequence_aborted(&mut self) {
+        let _ = writeln!(self.writer, "[sequence_aborted]");
+        self.inner.sequence_aborted();
+    }
+
+    fn terminated(&self) -> bool {
+        self.inner.terminated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[derive(Default)]
+    struct Counter {
+        print: usize,
+        execute: usize,
+        csi: usize,
+        esc: usize,
+        osc: usize,
+    }
+
+    impl Perform for Counter {
+        fn print(&mut self, _c: char) {
+            self.print += 1;
+        }
+
+        fn execute(&mut self, _byte: u8) {
+            self.execute += 1;
+        }
+
+        fn csi_dispatch(
+            &mut self,
+            _params: &Params,
+            _intermediates: &[u8],
+            _ignore: bool,
+            _c: char,
+        ) {
+            self.csi += 1;
+        }
+
+        fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {
+            self.esc += 1;
+        }
+
+        fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {
+            self.osc += 1;
+        }
+    }
+
+    #[test]
+    fn records_mixed_input() {
+        let input = b"a\x1b[1;38:2:255m\r\x1b7\x1b]0;rio\x07b";
+        let mut parser = Parser::new();
+        let mut recorder = RecordingPerform::new(Vec::new(), Counter::default());
+        for byte in input {
+            parser.advance(&mut recorder, *byte);
+        }
+
+        let (log, counter) = recorder.into_parts();
+        assert_eq!(counter.print, 2);
+        assert_eq!(counter.execute, 1);
+        assert_eq!(counter.csi, 1);
+        assert_eq!(counter.esc, 1);
+        assert_eq!(counter.osc, 1);
+
+        let log = String::from_utf8(log).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "[print] 'a'",
+                "[csi_dispatch] params=[1;38:2:255], intermediates=[], ignore=false, char='m'",
+                "[execute] 0d",
+                "[esc_dispatch] intermediates=[], ignore=false, byte=37",
+                "[osc_dispatch] params=[[48], [114, 105, 111]], bell_terminated=true",
+                "[print] 'b'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Helpers for consumers of OSC payloads.
+
+use core::fmt;
+
+/// Error returned by [`Base64Decoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base64Error {
+    /// Byte outside of the base64 alphabet.
+    InvalidByte(u8),
+    /// Padding before the last group or data after the padding.
+    InvalidPadding,
+    /// Payload ended with a single character in its last group.
+    Truncated,
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidByte(byte) => write!(f, "invalid base64 byte 0x{byte:02x}"),
+            Self::InvalidPadding => f.write_str("invalid base64 padding"),
+            Self::Truncated => f.write_str("truncated base64 payload"),
+        }
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for Base64Error {}
+
+/// Incremental decoder for standard base64, as carried by OSC 52 and
+/// image protocols.
+///
+/// Payload slices can be fed as they come out of the parser, decoded
+/// bytes are handed to a closure so the encoded string never needs to be
+/// buffered. Padding is optional, but when present it must end the
+/// payload.
+///
+/// ```rust
+/// use copa::util::Base64Decoder;
+///
+/// let mut decoded = Vec::new();
+/// let mut decoder = Base64Decoder::new();
+/// decoder.feed(b"cmlvIHRl", |byte| decoded.push(byte)).unwrap();
+/// decoder.feed(b"cm0=", |byte| decoded.push(byte)).unwrap();
+/// decoder.finish(|byte| decoded.push(byte)).unwrap();
+/// assert_eq!(decoded, b"rio term");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Base64Decoder {
+    /// Sextets of the current group, most significant first.
+    group: u32,
+    /// Number of sextets in the current group.
+    len: u8,
+    /// Number of `=` read, nothing else can follow them.
+    padding: u8,
+}
+
+impl Base64Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes `input`, calling `output` with every complete byte.
+    ///
+    /// On error the decoder is left as it was after the last valid byte.
+    pub fn feed(
+        &mut self,
+        input: &[u8],
+        mut output: impl FnMut(u8),
+    ) -> Result<(), Base64Error> {
+        for &byte in input {
+            if byte == b'=' {
+                // Padding completes a group of two or three sextets.
+                if self.len < 2 || self.len + self.padding >= 4 {
+                    return Err(Base64Error::InvalidPadding);
+                }
+                self.padding += 1;
+                continue;
+            }
+
+            if self.padding > 0 {
+                return Err(Base64Error::InvalidPadding);
+            }
+
+            let sextet = decode_byte(byte).ok_or(Base64Error::InvalidByte(byte))?;
+            self.group = (self.group << 6) | sextet as u32;
+            self.len += 1;
+            if self.len == 4 {
+                let [_, first, second, third] = self.group.to_be_bytes();
+                output(first);
+                output(second);
+                output(third);
+                self.group = 0;
+                self.len = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ends the payload, calling `output` with the bytes of an incomplete
+    /// last group.
+    pub fn finish(self, mut output: impl FnMut(u8)) -> Result<(), Base64Error> {
+        if self.padding > 0 && self.len + self.padding != 4 {
+            return Err(Base64Error::InvalidPadding);
+        }
+
+        match self.len {
+            0 => (),
+            1 => return Err(Base64Error::Truncated),
+            len => {
+                let group = self.group << (6 * (4 - len as u32));
+                let [_, first, second, _] = group.to_be_bytes();
+                output(first);
+                if len == 3 {
+                    output(second);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[inline]
+fn decode_byte(byte: u8) -> Option<u8> {
+    match byte {
+        b'A'..=b'Z' => Some(byte - b'A'),
+        b'a'..=b'z' => Some(byte - b'a' + 26),
+        b'0'..=b'9' => Some(byte - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, not(feature = "no_std")))]
+mod tests {
+    use super::*;
+
+    fn decode(chunks: &[&[u8]]) -> Result<Vec<u8>, Base64Error> {
+        let mut decoded = Vec::new();
+        let mut decoder = Base64Decoder::new();
+        for chunk in chunks {
+            decoder.feed(chunk, |byte| decoded.push(byte))?;
+        }
+        decoder.finish(|byte| decoded.push(byte))?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn decodes_payload_split_across_chunks() {
+        assert_eq!(decode(&[b"aGVsbG8gd2", b"9ybGQ="]).unwrap(), b"hello world");
+        assert_eq!(decode(&[b"cg", b"=", b"="]).unwrap(), b"r");
+        assert_eq!(decode(&[b"cmlv", b""]).unwrap(), b"rio");
+        // Unpadded payloads.
+        assert_eq!(decode(&[b"cmk"]).unwrap(), b"ri");
+        assert_eq!(decode(&[b"+/8"]).unwrap(), [0xfb, 0xff]);
+        assert_eq!(decode(&[]).unwrap(), b"");
+    }
+
+    #[test]
+    fn rejects_invalid_payloads() {
+        assert_eq!(
+            decode(&[b"aGVs", b"bG8*"]),
+            Err(Base64Error::InvalidByte(b'*'))
+        );
+        assert_eq!(decode(&[b"cm\n"]), Err(Base64Error::InvalidByte(b'\n')));
+        assert_eq!(decode(&[b"c==="]), Err(Base64Error::InvalidPadding));
+        assert_eq!(
+            decode(&[b"cmk=", b"cmlv"]),
+            Err(Base64Error::InvalidPadding)
+        );
+        assert_eq!(decode(&[b"cmlvc"]), Err(Base64Error::Truncated));
+        assert_eq!(decode(&[b"cm="]), Err(Base64Error::InvalidPadding));
+    }
+}
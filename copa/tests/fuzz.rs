@@ -0,0 +1,269 @@
+//! Randomized parsing of mutated escape sequences.
+//!
+//! Every input is parsed byte by byte and again split at random boundaries
+//! across the bulk methods, both must consume the whole input and dispatch
+//! the same actions. Runs a fixed number of iterations from a fixed seed,
+//! `COPA_FUZZ_ITERATIONS` and `COPA_FUZZ_SEED` can be set to explore more.
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use copa::{OpaqueSequenceKind, Params, Parser, Perform};
+
+static VTE_DEMO: &[u8] = include_bytes!("demo.vte");
+
+/// Test vectors from the parser tests.
+static CORPUS: &[&[u8]] = &[
+    b"\x1b]2;jwilm@jwilm-desk: ~/code/alacritty\x07",
+    b"\x1b]11;ff/00/ff\x1b\\",
+    b"\x1b]8;;https://example.com\x1b\\rio\x1b]8;;\x1b\\",
+    b"\x1b]52;c;cmlvIHRlcm0=\x07",
+    b"\x1b[1;38:2:255:0:0;4m",
+    b"\x1b[4;m",
+    b"\x1b[?1049h\x1b[2 q\x1b[>1u",
+    b"\x1b[3;1\x1b(A",
+    b"\x1b[38:2::255:0:0m\x1b[58:5:1m",
+    b"\x1b[;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;m",
+    b"\x1b[99999999999999999999999999999999m",
+    b"\x1bP1$tx\x9cb",
+    b"\x1bPq#0;2;0;0;0#1;2;100;100;0#1~~@@vv@@~~@@~~$-\x1b\\",
+    b"\x1bP=1s\x1b\\\x1bP=2s\x1b\\",
+    b"\x1bX sos \x1b\\\x1b^ pm \x1b\\\x1b_Gf=100;AAAA\x1b\\",
+    b"\x9b1;2H\x9d0;title\x9c\x90q\x9c",
+    // Partial UTF-8 interrupted by an escape.
+    b"\xe6\xb1\x1b[31m\xe6\xb1\x89",
+    b"\xf0\x9f\x1b]0;\xf0\x9f\x98\x80\x07",
+    b"a\x1b[1;38:2:255m\r\x1b7\x1b]0;rio\x07\x1bP1$tx\x9cb",
+    b"\x1b\x1b\x1b[\x1b]\x1bP\x18\x1a\x07\x7f",
+];
+
+/// Bytes which change the parser state, inserted more often than others.
+const INTERESTING: &[u8] = &[
+    0x00, 0x07, 0x18, 0x1a, 0x1b, 0x7f, 0x90, 0x98, 0x9b, 0x9c, 0x9d, 0x9e, 0x9f, b'[',
+    b']', b'P', b'X', b'^', b'_', b'\\', b';', b':', b'?', b' ', b'0', b'9', b'm', 0xc2,
+    0xe6, 0xf0, 0xff,
+];
+
+const DEFAULT_ITERATIONS: u64 = 2_000;
+const MAX_INPUT_LEN: usize = 512;
+
+/// xorshift64*, enough to explore inputs without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn byte(&mut self) -> u8 {
+        if self.below(2) == 0 {
+            INTERESTING[self.below(INTERESTING.len())]
+        } else {
+            self.next() as u8
+        }
+    }
+}
+
+#[derive(Default)]
+struct Dispatcher {
+    actions: Vec<String>,
+}
+
+impl Perform for Dispatcher {
+    fn print(&mut self, c: char) {
+        self.actions.push(format!("print {c:?}"));
+    }
+
+    fn execute(&mut self, byte: u8) {
+        self.actions.push(format!("execute {byte}"));
+    }
+
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, c: char) {
+        self.actions
+            .push(format!("hook {params:?} {intermediates:?} {ignore} {c:?}"));
+    }
+
+    fn put(&mut self, byte: u8) {
+        self.actions.push(format!("put {byte}"));
+    }
+
+    fn unhook(&mut self) {
+        self.actions.push(String::from("unhook"));
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        self.actions
+            .push(format!("osc {params:?} {bell_terminated}"));
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        intermediates: &[u8],
+        ignore: bool,
+        c: char,
+    ) {
+        self.actions
+            .push(format!("csi {params:?} {intermediates:?} {ignore} {c:?}"));
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        self.actions
+            .push(format!("esc {intermediates:?} {ignore} {byte}"));
+    }
+
+    fn sequence_aborted(&mut self) {
+        self.actions.push(String::from("aborted"));
+    }
+
+    fn opaque_start(&mut self, kind: OpaqueSequenceKind) {
+        self.actions.push(format!("opaque start {kind:?}"));
+    }
+
+    fn opaque_put(&mut self, kind: OpaqueSequenceKind, byte: u8) {
+        self.actions.push(format!("opaque put {kind:?} {byte}"));
+    }
+
+    fn opaque_end(&mut self, kind: OpaqueSequenceKind) {
+        self.actions.push(format!("opaque end {kind:?}"));
+    }
+}
+
+fn mutate(rng: &mut Rng, input: &mut Vec<u8>) {
+    for _ in 0..=rng.below(8) {
+        let index = rng.below(input.len() + 1);
+        match rng.below(5) {
+            0 if index < input.len() => input[index] = rng.byte(),
+            1 if index < input.len() => {
+                input.remove(index);
+            }
+            2 => {
+                // Splice part of another vector.
+                let other = CORPUS[rng.below(CORPUS.len())];
+                let start = rng.below(other.len());
+                let end = start + rng.below(other.len() - start + 1);
+                input.splice(index..index, other[start..end].iter().copied());
+            }
+            3 => {
+                // Repeat a byte, long runs hit the length limits.
+                let byte = rng.byte();
+                let count = rng.below(MAX_INPUT_LEN);
+                input.splice(index..index, vec![byte; count]);
+            }
+            _ => input.insert(index, rng.byte()),
+        }
+    }
+    input.truncate(MAX_INPUT_LEN * 4);
+}
+
+fn generate(rng: &mut Rng) -> Vec<u8> {
+    let mut input = match rng.below(4) {
+        0 => (0..rng.below(MAX_INPUT_LEN)).map(|_| rng.byte()).collect(),
+        1 => {
+            let start = rng.below(VTE_DEMO.len());
+            let end = (start + rng.below(MAX_INPUT_LEN)).min(VTE_DEMO.len());
+            VTE_DEMO[start..end].to_vec()
+        }
+        _ => CORPUS[rng.below(CORPUS.len())].to_vec(),
+    };
+    mutate(rng, &mut input);
+    input
+}
+
+fn parse_bytewise(input: &[u8]) -> Vec<String> {
+    let mut parser = Parser::new();
+    let mut dispatcher = Dispatcher::default();
+    for byte in input {
+        parser.advance(&mut dispatcher, *byte);
+    }
+    dispatcher.actions
+}
+
+/// Parses `input` split at random boundaries, alternating between the
+/// bulk methods, and checks every method makes progress.
+fn parse_split(rng: &mut Rng, input: &[u8]) -> Vec<String> {
+    let mut parser = Parser::new();
+    let mut dispatcher = Dispatcher::default();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let (chunk, next) = rest.split_at(1 + rng.below(rest.len()));
+        if rng.below(2) == 0 {
+            parser.advance_chunked(&mut dispatcher, chunk);
+        } else {
+            let advanced = parser.advance_until_terminated(&mut dispatcher, chunk);
+            assert_eq!(advanced.consumed, chunk.len());
+            assert!(!advanced.terminated);
+        }
+        rest = next;
+    }
+    dispatcher.actions
+}
+
+fn check_parse_one(input: &[u8]) {
+    let mut parser = Parser::new();
+    let mut dispatcher = Dispatcher::default();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let consumed = parser.parse_one(&mut dispatcher, rest);
+        assert!(consumed > 0, "no progress on {rest:?}");
+        assert!(consumed <= rest.len());
+        rest = &rest[consumed..];
+    }
+}
+
+fn check(rng: &mut Rng, input: &[u8]) {
+    let bytewise = parse_bytewise(input);
+    assert_eq!(parse_split(rng, input), bytewise, "input: {input:?}");
+    check_parse_one(input);
+}
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[test]
+fn parser_never_panics_or_stalls() {
+    let iterations = env_u64("COPA_FUZZ_ITERATIONS", DEFAULT_ITERATIONS);
+    let seed = env_u64("COPA_FUZZ_SEED", 0x5eed_c0fa).max(1);
+
+    // A parser stuck in a loop would hang the test, fail it instead.
+    let (sender, receiver) = mpsc::channel();
+    let fuzzer = thread::spawn(move || {
+        let mut rng = Rng(seed);
+        for input in CORPUS {
+            check(&mut rng, input);
+        }
+        check(&mut rng, VTE_DEMO);
+
+        for _ in 0..iterations {
+            let input = generate(&mut rng);
+            check(&mut rng, &input);
+        }
+        let _ = sender.send(());
+    });
+
+    let timeout = Duration::from_secs(60 + iterations / 100);
+    match receiver.recv_timeout(timeout) {
+        Ok(()) => fuzzer.join().unwrap(),
+        // The fuzzer panicked, report its message.
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            if let Err(panic) = fuzzer.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            panic!("parser did not finish {iterations} inputs from seed {seed:#x}")
+        }
+    }
+}
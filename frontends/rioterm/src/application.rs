@@ -1,13 +1,14 @@
 use crate::event::{ClickState, EventPayload, EventProxy, RioEvent, RioEventType};
 use crate::ime::Preedit;
-use crate::renderer::utils::update_colors_based_on_theme;
 use crate::router::{routes::RoutePath, Router};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 use crate::screen::touch::on_touch;
-use crate::watcher::configuration_file_updates;
+use crate::watcher::{configuration_file_updates, power_source_updates};
 use raw_window_handle::HasDisplayHandle;
 use rio_backend::clipboard::{Clipboard, ClipboardType};
+use rio_backend::config::bell::BellMode;
 use rio_backend::config::colors::ColorRgb;
+use rio_backend::config::renderer::Performance;
 use rio_window::application::ApplicationHandler;
 use rio_window::event::{
     ElementState, Hook, Ime, MouseButton, MouseScrollDelta, StartCause, TouchPhase,
@@ -21,7 +22,7 @@ use rio_window::platform::macos::ActiveEventLoopExtMacOS;
 #[cfg(target_os = "macos")]
 use rio_window::platform::macos::WindowExtMacOS;
 use rio_window::window::WindowId;
-use rio_window::window::{CursorIcon, Fullscreen};
+use rio_window::window::{CursorIcon, Fullscreen, UserAttentionType};
 use std::error::Error;
 use std::time::{Duration, Instant};
 
@@ -30,6 +31,9 @@ pub struct Application<'a> {
     event_proxy: EventProxy,
     router: Router<'a>,
     scheduler: Scheduler,
+    // Performance picked for `renderer.performance = "auto"`, once the
+    // power source is watched
+    auto_performance: Option<Performance>,
 }
 
 impl Application<'_> {
@@ -50,21 +54,39 @@ impl Application<'_> {
 
         let proxy = event_loop.create_proxy();
         let event_proxy = EventProxy::new(proxy.clone());
+        let config_file_path = rio_backend::config::config_file_path();
         let _ = configuration_file_updates(
-            rio_backend::config::config_dir_path(),
+            config_file_path
+                .parent()
+                .map(std::path::Path::to_path_buf)
+                .unwrap_or_else(rio_backend::config::config_dir_path),
             event_proxy.clone(),
         );
         let scheduler = Scheduler::new(proxy);
         event_loop.listen_device_events(DeviceEvents::Never);
 
         #[cfg(target_os = "macos")]
-        event_loop.set_confirm_before_quit(config.confirm_before_quit);
+        event_loop.set_confirm_before_quit(config.should_confirm_before_quit());
 
-        Application {
+        let mut application = Application {
             config,
             event_proxy,
             router,
             scheduler,
+            auto_performance: None,
+        };
+        application.performance();
+        application
+    }
+
+    /// Performance in use, starts watching the power source the first time
+    /// `renderer.performance` is `auto`.
+    fn performance(&mut self) -> Performance {
+        match self.config.renderer.performance {
+            Performance::Auto => *self
+                .auto_performance
+                .get_or_insert_with(|| power_source_updates(self.event_proxy.clone())),
+            performance => performance,
         }
     }
 
@@ -104,7 +126,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
         {
             // This is a hacky solution to force an update to the window on linux
             // Fix is only for windows with opacity that aren't being computed at all
-            if self.config.window.opacity < 1. || self.config.window.blur {
+            if self.config.window.is_transparent() || self.config.window.blur {
                 for (_id, route) in self.router.routes.iter_mut() {
                     route.update_config(&self.config, &self.router.font_library, false);
 
@@ -126,7 +148,7 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
             return;
         }
 
-        update_colors_based_on_theme(&mut self.config, event_loop.system_theme());
+        self.config.apply_system_theme(event_loop.system_theme());
 
         self.router.create_window(
             event_loop,
@@ -220,6 +242,23 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     }
                 }
             }
+            RioEventType::Rio(RioEvent::Resize(route_id, winsize)) => {
+                if let Some(route) = self.router.routes.get(&window_id) {
+                    route
+                        .window
+                        .screen
+                        .context_manager
+                        .resize_pty(route_id, winsize);
+                }
+            }
+            RioEventType::Rio(RioEvent::PerformanceChanged(performance)) => {
+                self.auto_performance = Some(performance);
+                if self.config.renderer.performance == Performance::Auto {
+                    for (_id, route) in self.router.routes.iter_mut() {
+                        route.window.set_performance(performance);
+                    }
+                }
+            }
             RioEventType::Rio(RioEvent::ReportToAssistant(error)) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
                     route.report_error(&error);
@@ -245,7 +284,10 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                 };
 
                 self.config = config;
+                self.config.apply_system_theme(event_loop.system_theme());
+                let performance = self.performance();
                 for (_id, route) in self.router.routes.iter_mut() {
+                    route.window.set_performance(performance);
                     if has_font_updates {
                         if let Some(ref err) = font_library_errors {
                             route
@@ -274,7 +316,10 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
             }
             RioEventType::Rio(RioEvent::Exit) => {
                 if let Some(route) = self.router.routes.get_mut(&window_id) {
-                    if cfg!(target_os = "macos") && self.config.confirm_before_quit {
+                    if cfg!(target_os = "macos")
+                        && self.config.should_confirm_before_quit()
+                        && route.window.screen.context_manager.has_running_processes()
+                    {
                         route.confirm_quit();
                         route.request_redraw();
                     } else {
@@ -316,6 +361,41 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                     }
                 }
             }
+            RioEventType::Rio(RioEvent::Bell) => {
+                if let Some(route) = self.router.routes.get_mut(&window_id) {
+                    match self.config.bell.mode {
+                        BellMode::Visual => {
+                            route.window.screen.renderer.trigger_visual_bell();
+                            route.request_redraw();
+
+                            // Render again once the flash is over to clear it.
+                            let timer_id = TimerId::new(
+                                Topic::VisualBell,
+                                route.window.screen.ctx().current_route(),
+                            );
+                            let event = EventPayload::new(
+                                RioEventType::Rio(RioEvent::Render),
+                                window_id,
+                            );
+                            self.scheduler.unschedule(timer_id);
+                            self.scheduler.schedule(
+                                event,
+                                Duration::from_millis(self.config.bell.duration),
+                                false,
+                                timer_id,
+                            );
+                        }
+                        BellMode::Audible => {
+                            if !crate::platform::audible_bell() {
+                                route.window.winit_window.request_user_attention(Some(
+                                    UserAttentionType::Informational,
+                                ));
+                            }
+                        }
+                        BellMode::None => {}
+                    }
+                }
+            }
             RioEventType::Rio(RioEvent::PrepareRender(millis)) => {
                 if let Some(route) = self.router.routes.get(&window_id) {
                     let timer_id = TimerId::new(
@@ -520,7 +600,9 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
             #[cfg(target_os = "macos")]
             RioEventType::Rio(RioEvent::CloseWindow) => {
                 self.router.routes.remove(&window_id);
-                if self.router.routes.is_empty() && !self.config.confirm_before_quit {
+                if self.router.routes.is_empty()
+                    && !self.config.should_confirm_before_quit()
+                {
                     event_loop.exit();
                 }
             }
@@ -640,18 +722,20 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
 
         match event {
             WindowEvent::CloseRequested => {
-                // MacOS doesn't exit the loop
-                if cfg!(target_os = "macos") && self.config.confirm_before_quit {
-                    self.router.routes.remove(&window_id);
+                let should_confirm = self.config.should_confirm_before_quit();
+                if should_confirm
+                    && route.window.screen.context_manager.has_running_processes()
+                {
+                    route.confirm_quit();
+                    route.request_redraw();
                     return;
                 }
 
-                if self.config.confirm_before_quit {
-                    route.confirm_quit();
-                    route.request_redraw();
+                self.router.routes.remove(&window_id);
+
+                // MacOS doesn't exit the loop
+                if cfg!(target_os = "macos") && should_confirm {
                     return;
-                } else {
-                    self.router.routes.remove(&window_id);
                 }
 
                 if self.router.routes.is_empty() {
@@ -1059,11 +1143,12 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                 let has_regained_focus = !route.window.is_focused && focused;
                 route.window.is_focused = focused;
 
-                if has_regained_focus {
+                route.window.screen.on_focus_change(focused);
+
+                // The window opacity may change with the focus.
+                if has_regained_focus || route.window.screen.needs_opacity_frame() {
                     route.request_redraw();
                 }
-
-                route.window.screen.on_focus_change(focused);
             }
 
             WindowEvent::Occluded(occluded) => {
@@ -1071,13 +1156,17 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
             }
 
             WindowEvent::ThemeChanged(new_theme) => {
-                update_colors_based_on_theme(&mut self.config, Some(new_theme));
-                route.window.screen.update_config(
-                    &self.config,
-                    &self.router.font_library,
-                    false,
-                );
-                route.window.configure_window(&self.config);
+                // Every window receives the event, only the first one
+                // changes the colors and updates all of them.
+                if !self.config.apply_system_theme(Some(new_theme)) {
+                    return;
+                }
+
+                for route in self.router.routes.values_mut() {
+                    route.update_config(&self.config, &self.router.font_library, false);
+                    route.window.configure_window(&self.config);
+                    route.request_redraw();
+                }
             }
 
             WindowEvent::DroppedFile(path) => {
@@ -1120,7 +1209,14 @@ impl ApplicationHandler<EventPayload> for Application<'_> {
                         route.window.screen.render_welcome();
                     }
                     RoutePath::Terminal => {
+                        // Frames are requested until the animations end,
+                        // rendering starts new ones when the cursor moves
+                        // or the terminal scrolls.
+                        let is_animating = route.window.animate();
                         route.window.screen.render();
+                        if is_animating || route.window.screen.is_animating() {
+                            route.request_frame(&mut self.scheduler);
+                        }
                     }
                     RoutePath::ConfirmQuit => {
                         route
@@ -10,8 +10,36 @@ use rio_window::keyboard::NamedKey;
 use rio_window::platform::modifier_supplement::KeyEventExtModifierSupplement;
 use std::borrow::Cow;
 
-#[inline(never)]
+/// Parts of a [`KeyEvent`] used to build its escape sequence.
+pub struct KeyInput<'a> {
+    pub logical_key: Key,
+    pub key_without_modifiers: Key,
+    pub text_with_all_modifiers: Option<&'a str>,
+    pub location: KeyLocation,
+    pub state: ElementState,
+    pub repeat: bool,
+}
+
+impl<'a> From<&'a KeyEvent> for KeyInput<'a> {
+    fn from(key: &'a KeyEvent) -> Self {
+        Self {
+            logical_key: key.logical_key.clone(),
+            key_without_modifiers: key.key_without_modifiers(),
+            text_with_all_modifiers: key.text_with_all_modifiers(),
+            location: key.location,
+            state: key.state,
+            repeat: key.repeat,
+        }
+    }
+}
+
+#[inline]
 pub fn build_key_sequence(key: &KeyEvent, mods: ModifiersState, mode: Mode) -> Vec<u8> {
+    encode_key(&KeyInput::from(key), mods, mode)
+}
+
+#[inline(never)]
+pub fn encode_key(key: &KeyInput, mods: ModifiersState, mode: Mode) -> Vec<u8> {
     let mut modifiers = mods.into();
 
     let kitty_seq = mode.intersects(
@@ -40,11 +68,11 @@ pub fn build_key_sequence(key: &KeyEvent, mods: ModifiersState, mode: Mode) -> V
     let text = if key.logical_key == Key::Named(NamedKey::Delete) {
         None
     } else {
-        key.text_with_all_modifiers()
+        key.text_with_all_modifiers
     };
 
     #[cfg(not(target_os = "macos"))]
-    let text = key.text_with_all_modifiers();
+    let text = key.text_with_all_modifiers;
 
     let associated_text = text.filter(|text| {
         mode.contains(Mode::REPORT_ASSOCIATED_TEXT)
@@ -101,7 +129,18 @@ pub fn build_key_sequence(key: &KeyEvent, mods: ModifiersState, mode: Mode) -> V
     payload.into_bytes()
 }
 
-/// Helper to build escape sequence payloads from [`KeyEvent`].
+/// Bytes written for `text` when the key is not encoded as an escape
+/// sequence, prefixed with `ESC` when alt sends escape.
+pub fn build_legacy_sequence(text: &str, alt_send_esc: bool) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len() + 1);
+    if alt_send_esc && text.len() == 1 {
+        bytes.push(b'\x1b');
+    }
+    bytes.extend_from_slice(text.as_bytes());
+    bytes
+}
+
+/// Helper to build escape sequence payloads from [`KeyInput`].
 pub struct SequenceBuilder {
     mode: Mode,
     /// The emitted sequence should follow the kitty keyboard protocol.
@@ -117,7 +156,7 @@ impl SequenceBuilder {
     /// Try building sequence from the event's emitting text.
     fn try_build_textual(
         &self,
-        key: &KeyEvent,
+        key: &KeyInput,
         associated_text: Option<&str>,
     ) -> Option<SequenceBase> {
         let character = match key.logical_key.as_ref() {
@@ -133,7 +172,7 @@ impl SequenceBuilder {
             let mut unicode_key_code = u32::from(base_character);
 
             // Try to get the base for keys which change based on modifier, like `1` for `!`.
-            match key.key_without_modifiers().as_ref() {
+            match key.key_without_modifiers.as_ref() {
                 Key::Character(unmodded) if alternate_key_code == unicode_key_code => {
                     unicode_key_code =
                         u32::from(unmodded.chars().next().unwrap_or(base_character));
@@ -164,7 +203,7 @@ impl SequenceBuilder {
     /// Try building from numpad key.
     ///
     /// `None` is returned when the key is neither known nor numpad.
-    fn try_build_numpad(&self, key: &KeyEvent) -> Option<SequenceBase> {
+    fn try_build_numpad(&self, key: &KeyInput) -> Option<SequenceBase> {
         if !self.kitty_seq || key.location != KeyLocation::Numpad {
             return None;
         }
@@ -208,7 +247,7 @@ impl SequenceBuilder {
 
     /// Try building from [`NamedKey`] using the kitty keyboard protocol encoding
     /// for functional keys.
-    fn try_build_named_kitty(&self, key: &KeyEvent) -> Option<SequenceBase> {
+    fn try_build_named_kitty(&self, key: &KeyInput) -> Option<SequenceBase> {
         let named = match key.logical_key {
             Key::Named(named) if self.kitty_seq => named,
             _ => return None,
@@ -265,7 +304,7 @@ impl SequenceBuilder {
     /// Try building from [`NamedKey`].
     fn try_build_named_normal(
         &self,
-        key: &KeyEvent,
+        key: &KeyInput,
         has_associated_text: bool,
     ) -> Option<SequenceBase> {
         let named = match key.logical_key {
@@ -322,7 +361,7 @@ impl SequenceBuilder {
     /// Try building escape from control characters (e.g. Enter) and modifiers.
     fn try_build_control_char_or_mod(
         &self,
-        key: &KeyEvent,
+        key: &KeyInput,
         mods: &mut SequenceModifiers,
     ) -> Option<SequenceBase> {
         if !self.kitty_encode_all && !self.kitty_seq {
@@ -459,3 +498,70 @@ fn is_control_character(text: &str) -> bool {
     let codepoint = text.bytes().next().unwrap();
     text.len() == 1 && (codepoint < 0x20 || (0x7f..=0x9f).contains(&codepoint))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_input(key: Key, text: Option<&str>, state: ElementState) -> KeyInput<'_> {
+        KeyInput {
+            logical_key: key.clone(),
+            key_without_modifiers: key,
+            text_with_all_modifiers: text,
+            location: KeyLocation::Standard,
+            state,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn test_release_reported_with_event_types() {
+        let mode = Mode::DISAMBIGUATE_ESC_CODES | Mode::REPORT_EVENT_TYPES;
+        let key = key_input(
+            Key::Character("a".into()),
+            Some("a"),
+            ElementState::Released,
+        );
+        assert_eq!(
+            encode_key(&key, ModifiersState::empty(), mode),
+            b"\x1b[97;1:3u"
+        );
+
+        let enter = key_input(
+            Key::Named(NamedKey::Enter),
+            Some("\r"),
+            ElementState::Released,
+        );
+        assert_eq!(
+            encode_key(&enter, ModifiersState::CONTROL, mode),
+            b"\x1b[13;5:3u"
+        );
+
+        // Without the flags there is nothing to report.
+        assert!(encode_key(&key, ModifiersState::empty(), Mode::empty()).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_keys_send_text() {
+        let key = key_input(Key::Character("a".into()), Some("a"), ElementState::Pressed);
+        assert!(encode_key(&key, ModifiersState::empty(), Mode::empty()).is_empty());
+        assert_eq!(build_legacy_sequence("a", false), b"a");
+        assert_eq!(build_legacy_sequence("a", true), b"\x1ba");
+        assert_eq!(build_legacy_sequence("\r", false), b"\r");
+
+        let escape = key_input(
+            Key::Named(NamedKey::Escape),
+            Some("\x1b"),
+            ElementState::Pressed,
+        );
+        assert!(encode_key(&escape, ModifiersState::empty(), Mode::empty()).is_empty());
+        assert_eq!(
+            encode_key(
+                &escape,
+                ModifiersState::empty(),
+                Mode::DISAMBIGUATE_ESC_CODES
+            ),
+            b"\x1b[27u"
+        );
+    }
+}
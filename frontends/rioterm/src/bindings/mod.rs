@@ -8,7 +8,7 @@ use crate::crosswords::vi_mode::ViMotion;
 use crate::crosswords::Mode;
 use bitflags::bitflags;
 use rio_backend::config::bindings::KeyBinding as ConfigKeyBinding;
-use rio_backend::config::keyboard::Keyboard as ConfigKeyboard;
+use rio_backend::config::keyboard::{Keyboard as ConfigKeyboard, KeyboardProtocol};
 use rio_window::event::MouseButton;
 use rio_window::keyboard::Key::*;
 use rio_window::keyboard::NamedKey::*;
@@ -676,7 +676,7 @@ pub fn default_key_bindings(
             ViMotion::Bracket;
     );
 
-    if !config_keyboard.use_kitty_keyboard_protocol {
+    if config_keyboard.protocol() == KeyboardProtocol::Legacy {
         bindings.extend(bindings!(
             KeyBinding;
             Key::Named(Home), ModifiersState::SHIFT, +BindingMode::ALT_SCREEN, ~BindingMode::VI; Action::Esc("\x1b[1;2H".into());
@@ -945,14 +945,31 @@ fn convert(config_key_binding: ConfigKeyBinding) -> Result<KeyBinding, String> {
         }
     }
 
-    let mut action: Action = config_key_binding.action.into();
+    let mut action: Action = if config_key_binding.action.eq_ignore_ascii_case("spawn") {
+        if config_key_binding.program.is_empty() {
+            return Err("'spawn' action requires a 'program'".to_string());
+        }
+
+        if config_key_binding.args.is_empty() {
+            Action::Run(Program::Just(config_key_binding.program))
+        } else {
+            Action::Run(Program::WithArgs {
+                program: config_key_binding.program,
+                args: config_key_binding.args,
+            })
+        }
+    } else {
+        config_key_binding.action.into()
+    };
+
     if !config_key_binding.text.is_empty() {
         action = Action::Esc(config_key_binding.text);
     }
 
     if !config_key_binding.bytes.is_empty() {
-        if let Ok(str_from_bytes) = std::str::from_utf8(&config_key_binding.bytes) {
-            action = Action::Esc(str_from_bytes.into());
+        match String::from_utf8(config_key_binding.bytes) {
+            Ok(str_from_bytes) => action = Action::Esc(str_from_bytes),
+            Err(_) => return Err("'bytes' must be valid UTF-8".to_string()),
         }
     }
 
@@ -1519,6 +1536,8 @@ mod tests {
             bytes: vec![],
             text: String::from(""),
             mode: String::from(""),
+            program: String::from(""),
+            args: vec![],
         }];
 
         let new_bindings = config_key_bindings(config_bindings, bindings);
@@ -1526,4 +1545,49 @@ mod tests {
         assert_eq!(new_bindings.len(), 2);
         assert_eq!(new_bindings[1].action, Action::ReceiveChar);
     }
+
+    #[test]
+    fn convert_spawn_and_bytes() {
+        let config_binding = ConfigKeyBinding {
+            key: String::from("e"),
+            action: String::from("Spawn"),
+            with: String::from("super"),
+            bytes: vec![],
+            text: String::from(""),
+            mode: String::from(""),
+            program: String::from("code"),
+            args: vec![String::from(".")],
+        };
+
+        let binding = convert(config_binding.clone()).unwrap();
+        assert_eq!(
+            binding.action,
+            Action::Run(Program::WithArgs {
+                program: String::from("code"),
+                args: vec![String::from(".")],
+            })
+        );
+
+        // Spawn without a program is rejected.
+        assert!(convert(ConfigKeyBinding {
+            program: String::from(""),
+            ..config_binding.clone()
+        })
+        .is_err());
+
+        let binding = convert(ConfigKeyBinding {
+            action: String::from(""),
+            bytes: b"\x1b[A".to_vec(),
+            ..config_binding.clone()
+        })
+        .unwrap();
+        assert_eq!(binding.action, Action::Esc(String::from("\x1b[A")));
+
+        assert!(convert(ConfigKeyBinding {
+            action: String::from(""),
+            bytes: vec![0xff],
+            ..config_binding
+        })
+        .is_err());
+    }
 }
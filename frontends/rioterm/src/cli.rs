@@ -9,6 +9,10 @@ use std::path::PathBuf;
 #[derive(Parser, Default, Debug)]
 #[clap(author, about, version)]
 pub struct Cli {
+    /// Load the config from a given file instead of the default location.
+    #[clap(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub config_file: Option<PathBuf>,
+
     /// Options which can be passed via IPC.
     #[clap(flatten)]
     pub window_options: WindowOptions,
@@ -55,6 +59,7 @@ impl TerminalOptions {
         Some(Shell {
             program: program.clone(),
             args: args.to_vec(),
+            environment: None,
         })
     }
 
@@ -85,7 +85,6 @@ impl<T: rio_backend::event::EventListener> ContextGridItem<T> {
 
 impl<T: rio_backend::event::EventListener> ContextGridItem<T> {
     #[inline]
-    #[allow(unused)]
     pub fn context(&self) -> &Context<T> {
         &self.val
     }
@@ -122,8 +121,7 @@ impl<T: rio_backend::event::EventListener> ContextGrid<T> {
     }
 
     #[inline]
-    #[allow(unused)]
-    pub fn contexts(&mut self) -> &Vec<ContextGridItem<T>> {
+    pub fn contexts(&self) -> &Vec<ContextGridItem<T>> {
         &self.inner
     }
 
@@ -383,10 +381,6 @@ impl<T: rio_backend::event::EventListener> ContextGrid<T> {
 
             let mut terminal = context.val.terminal.lock();
             terminal.resize::<ContextDimension>(context.val.dimension);
-            drop(terminal);
-            let winsize =
-                crate::renderer::utils::terminal_dimensions(&context.val.dimension);
-            let _ = context.val.messenger.send_resize(winsize);
         }
     }
 
@@ -432,10 +426,6 @@ impl<T: rio_backend::event::EventListener> ContextGrid<T> {
     fn request_resize(&mut self, index: usize) {
         let mut terminal = self.inner[index].val.terminal.lock();
         terminal.resize::<ContextDimension>(self.inner[index].val.dimension);
-        drop(terminal);
-        let winsize =
-            crate::renderer::utils::terminal_dimensions(&self.inner[index].val.dimension);
-        let _ = self.inner[index].val.messenger.send_resize(winsize);
     }
 
     pub fn remove_current(&mut self) {
@@ -1,7 +1,7 @@
 pub mod grid;
 pub mod renderable;
 
-use crate::ansi::CursorShape;
+use crate::ansi::{CursorShape, CursorStyle};
 use crate::context::grid::ContextDimension;
 use crate::context::grid::ContextGrid;
 use crate::context::grid::Delta;
@@ -9,9 +9,13 @@ use crate::event::sync::FairMutex;
 use crate::event::RioEvent;
 use crate::ime::Ime;
 use crate::messenger::Messenger;
+use crate::performer::recorder::SessionRecorder;
 use crate::performer::Machine;
 use renderable::Cursor;
 use renderable::RenderableContent;
+use rio_backend::config::colors::Colors;
+use rio_backend::config::profile::{self, Profile};
+use rio_backend::config::title::Title;
 use rio_backend::config::Shell;
 use rio_backend::crosswords::{Crosswords, MIN_COLUMNS, MIN_LINES};
 use rio_backend::error::{RioError, RioErrorLevel, RioErrorType};
@@ -22,13 +26,15 @@ use rio_backend::sugarloaf::{font::SugarloafFont, Object, SugarloafErrors};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::error::Error;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use teletypewriter::WinsizeBuilder;
 
 #[cfg(target_os = "windows")]
 use teletypewriter::create_pty;
 #[cfg(not(target_os = "windows"))]
-use teletypewriter::{create_pty_with_fork, create_pty_with_spawn};
+use teletypewriter::{create_pty_with_fork_env, create_pty_with_spawn_env};
 
 const DEFAULT_CONTEXT_CAPACITY: usize = 28;
 
@@ -44,6 +50,10 @@ pub struct Context<T: EventListener> {
     pub rich_text_id: usize,
     pub dimension: ContextDimension,
     pub ime: Ime,
+    /// Colors of the profile matched when the context was created.
+    pub colors: Option<Colors>,
+    /// Font size of the profile matched when the context was created.
+    pub font_size: Option<f32>,
 }
 
 impl<T: rio_backend::event::EventListener> Drop for Context<T> {
@@ -100,7 +110,7 @@ impl<T: EventListener> Context<T> {
             terminal.visible_rows(),
             terminal.display_offset(),
             terminal.cursor(),
-            terminal.blinking_cursor,
+            terminal.cursor_style().blinking,
         );
         drop(terminal);
 
@@ -123,12 +133,20 @@ pub struct ContextManagerConfig {
     pub shell: Shell,
     #[cfg(not(target_os = "windows"))]
     pub use_fork: bool,
+    #[cfg(not(target_os = "windows"))]
+    pub utf8: bool,
     pub working_dir: Option<String>,
     pub spawn_performer: bool,
     pub use_current_path: bool,
     pub is_native: bool,
     pub should_update_titles: bool,
     pub split_color: [f32; 4],
+    pub title: Title,
+    pub scroll_on_output: bool,
+    pub scroll_on_keystroke: bool,
+    pub colors: Colors,
+    pub profiles: Vec<Profile>,
+    pub record_session: Option<String>,
 }
 
 pub struct ContextManagerTitles {
@@ -181,6 +199,16 @@ pub struct ContextManager<T: EventListener> {
     pub titles: ContextManagerTitles,
 }
 
+/// Whether any `(shell_pid, foreground_pid)` pair has a program other than
+/// the shell in the foreground of its terminal.
+#[cfg(unix)]
+#[inline]
+fn has_running_children(processes: impl IntoIterator<Item = (u32, u32)>) -> bool {
+    processes
+        .into_iter()
+        .any(|(shell_pid, foreground_pid)| shell_pid != foreground_pid)
+}
+
 pub fn create_dead_context<T: rio_backend::event::EventListener>(
     event_proxy: T,
     window_id: WindowId,
@@ -210,6 +238,8 @@ pub fn create_dead_context<T: rio_backend::event::EventListener>(
         rich_text_id,
         dimension,
         ime: Ime::new(),
+        colors: None,
+        font_size: None,
     }
 }
 
@@ -226,16 +256,25 @@ pub fn create_mock_context<
     let config = ContextManagerConfig {
         #[cfg(not(target_os = "windows"))]
         use_fork: true,
+        #[cfg(not(target_os = "windows"))]
+        utf8: true,
         working_dir: None,
         shell: Shell {
             program: std::env::var("SHELL").unwrap_or("bash".to_string()),
             args: vec![],
+            environment: None,
         },
         spawn_performer: false,
         is_native: false,
         should_update_titles: false,
         use_current_path: false,
         split_color: [0., 0., 0., 0.],
+        title: Title::default(),
+        scroll_on_output: false,
+        scroll_on_keystroke: true,
+        colors: Colors::default(),
+        profiles: vec![],
+        record_session: None,
     };
     ContextManager::create_context(
         (&Cursor::default(), false),
@@ -270,18 +309,42 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             window_id,
             route_id,
         );
-        terminal.blinking_cursor = cursor_state.1;
+        terminal.set_default_cursor_style(CursorStyle {
+            shape: CursorShape::from_char(cursor_state.0.content),
+            blinking: cursor_state.1,
+        });
+        terminal.scroll_on_output = config.scroll_on_output;
+        terminal.scroll_on_keystroke = config.scroll_on_keystroke;
         let terminal: Arc<FairMutex<Crosswords<T>>> = Arc::new(FairMutex::new(terminal));
 
+        let working_dir = config
+            .working_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        let program = if config.shell.program.is_empty() {
+            std::env::var("SHELL").unwrap_or_default()
+        } else {
+            config.shell.program.clone()
+        };
+        let profile = profile::find(&config.profiles, &working_dir, &program);
+        #[cfg(not(target_os = "windows"))]
+        let env = profile
+            .map(|profile| profile.environment_variables())
+            .unwrap_or_default();
+
         let pty;
         #[cfg(not(target_os = "windows"))]
         {
             if config.use_fork {
                 tracing::info!("rio -> teletypewriter: create_pty_with_fork");
-                pty = match create_pty_with_fork(
+                pty = match create_pty_with_fork_env(
                     &Cow::Borrowed(&config.shell.program),
                     cols,
                     rows,
+                    config.utf8,
+                    &env,
                 ) {
                     Ok(created_pty) => created_pty,
                     Err(err) => {
@@ -291,12 +354,14 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
                 }
             } else {
                 tracing::info!("rio -> teletypewriter: create_pty_with_spawn");
-                pty = match create_pty_with_spawn(
+                pty = match create_pty_with_spawn_env(
                     &Cow::Borrowed(&config.shell.program),
                     config.shell.args.clone(),
                     &config.working_dir,
                     cols,
                     rows,
+                    config.utf8,
+                    &env,
                 ) {
                     Ok(created_pty) => created_pty,
                     Err(err) => {
@@ -329,13 +394,21 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             }
         }
 
-        let machine = Machine::new(
+        let mut machine = Machine::new(
             Arc::clone(&terminal),
             pty,
             event_proxy.clone(),
             window_id,
             route_id,
         )?;
+        if let Some(path) = &config.record_session {
+            // Each terminal records into its own file.
+            let path = format!("{path}.{route_id}");
+            match SessionRecorder::new(&path) {
+                Ok(recorder) => machine = machine.with_session_recorder(recorder),
+                Err(err) => tracing::error!("Failed to record session to {path}: {err}"),
+            }
+        }
         let channel = machine.channel();
         if config.spawn_performer {
             machine.spawn();
@@ -343,6 +416,9 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
 
         let messenger = Messenger::new(channel);
 
+        let colors = profile.and_then(|profile| profile.colors(&config.colors));
+        let font_size = profile.and_then(|profile| profile.fonts.size);
+
         Ok(Context {
             route_id,
             #[cfg(not(target_os = "windows"))]
@@ -355,6 +431,8 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             renderable_content: RenderableContent::new(cursor_state.0.clone()),
             dimension,
             ime: Ime::new(),
+            colors,
+            font_size,
         })
     }
 
@@ -452,16 +530,25 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         let config = ContextManagerConfig {
             #[cfg(not(target_os = "windows"))]
             use_fork: true,
+            #[cfg(not(target_os = "windows"))]
+            utf8: true,
             working_dir: None,
             shell: Shell {
                 program: std::env::var("SHELL").unwrap_or("bash".to_string()),
                 args: vec![],
+                environment: None,
             },
             spawn_performer: false,
             is_native: false,
             should_update_titles: false,
             use_current_path: false,
             split_color: [0., 0., 0., 0.],
+            title: Title::default(),
+            scroll_on_output: false,
+            scroll_on_keystroke: true,
+            colors: Colors::default(),
+            profiles: vec![],
+            record_session: None,
         };
         let initial_context = ContextManager::create_context(
             (&Cursor::default(), false),
@@ -713,10 +800,16 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
                         terminal.title.to_string()
                     };
 
-                    let window_title = if terminal_title.is_empty() {
-                        program.to_owned()
-                    } else {
-                        format!("{} ({})", terminal_title, program)
+                    let variables = rio_backend::config::title::TitleVariables {
+                        index: i + 1,
+                        cwd: &path,
+                        command: &program,
+                        title: &terminal_title,
+                    };
+                    let window_title = match self.config.title.compose(&variables) {
+                        Some(window_title) => window_title,
+                        None if terminal_title.is_empty() => program.to_owned(),
+                        None => format!("{} ({})", terminal_title, program),
                     };
 
                     if cfg!(target_os = "macos") {
@@ -758,6 +851,40 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         &mut self.contexts
     }
 
+    /// Whether a program other than the shell runs in the foreground of
+    /// any split of any tab, unknown on Windows so always assumed.
+    pub fn has_running_processes(&self) -> bool {
+        #[cfg(unix)]
+        {
+            has_running_children(self.contexts.iter().flat_map(|grid| {
+                grid.contexts().iter().map(|item| {
+                    let context = item.context();
+                    let foreground_pid = teletypewriter::foreground_process_id(
+                        *context.main_fd,
+                        context.shell_pid,
+                    );
+                    (context.shell_pid, foreground_pid)
+                })
+            }))
+        }
+
+        #[cfg(not(unix))]
+        true
+    }
+
+    /// Tells the PTY of the split with `route_id` its new window size.
+    pub fn resize_pty(&self, route_id: usize, winsize: WinsizeBuilder) {
+        let context = self
+            .contexts
+            .iter()
+            .flat_map(|grid| grid.contexts())
+            .map(|item| item.context())
+            .find(|context| context.route_id == route_id);
+        if let Some(context) = context {
+            let _ = context.messenger.send_resize(winsize);
+        }
+    }
+
     #[inline]
     pub fn current_grid_len(&self) -> usize {
         self.contexts[self.current_index].len()
@@ -901,28 +1028,29 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         self.select_tab(target_index);
     }
 
+    /// Working directory of the focused context, as reported by its shell
+    /// through OSC 7 or otherwise the one of its foreground process.
+    fn current_working_dir(&self) -> Option<String> {
+        let current = self.current();
+        if let Some(path) = current.terminal.lock().current_directory() {
+            return Some(path.to_string_lossy().to_string());
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            teletypewriter::foreground_process_path(*current.main_fd, current.shell_pid)
+                .ok()
+                .map(|path| path.to_string_lossy().to_string())
+        }
+
+        #[cfg(target_os = "windows")]
+        None
+    }
+
     pub fn split(&mut self, rich_text_id: usize, split_down: bool) {
         let mut working_dir = self.config.working_dir.clone();
         if self.config.use_current_path {
-            #[cfg(not(target_os = "windows"))]
-            {
-                let current_context = self.current();
-                if let Ok(path) = teletypewriter::foreground_process_path(
-                    *current_context.main_fd,
-                    current_context.shell_pid,
-                ) {
-                    working_dir = Some(path.to_string_lossy().to_string());
-                }
-            }
-
-            #[cfg(target_os = "windows")]
-            {
-                // if let Ok(path) = teletypewriter::foreground_process_path() {
-                //     working_dir =
-                //         Some(path.to_string_lossy().to_string());
-                // }
-                working_dir = None;
-            }
+            working_dir = self.current_working_dir();
         }
 
         let mut cloned_config = self.config.clone();
@@ -964,26 +1092,41 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
         split_down: bool,
         config: rio_backend::config::Config,
     ) {
+        let working_dir = if config.inherits_working_dir() {
+            self.current_working_dir()
+        } else {
+            config.working_dir.to_owned()
+        };
         let (shell, working_dir) = process_open_url(
             config.shell.to_owned(),
-            config.working_dir.to_owned(),
+            working_dir,
             config.editor.to_owned(),
             None,
         );
 
         let context_manager_config = ContextManagerConfig {
-            use_current_path: config.navigation.use_current_path,
+            use_current_path: config.navigation.use_current_path
+                || config.inherits_working_dir(),
             shell,
             working_dir,
             spawn_performer: true,
             #[cfg(not(target_os = "windows"))]
             use_fork: config.use_fork,
+            #[cfg(not(target_os = "windows"))]
+            utf8: config.utf8,
             is_native: config.navigation.is_native(),
             // When navigation is collapsed and does not contain any color rule
             // does not make sense fetch for foreground process names
             should_update_titles: !(config.navigation.is_collapsed_mode()
-                && config.navigation.color_automation.is_empty()),
+                && config.navigation.color_automation.is_empty()
+                && config.title.template.is_none()),
             split_color: config.colors.split,
+            title: config.title.clone(),
+            scroll_on_output: config.scroll.on_output,
+            scroll_on_keystroke: config.scroll.on_keystroke,
+            colors: config.colors,
+            profiles: config.profiles.clone(),
+            record_session: config.developer.record_session.clone(),
         };
 
         self.acc_current_route += 1;
@@ -1018,25 +1161,7 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
     pub fn add_context(&mut self, redirect: bool, rich_text_id: usize) {
         let mut working_dir = self.config.working_dir.clone();
         if self.config.use_current_path {
-            #[cfg(not(target_os = "windows"))]
-            {
-                let current_context = self.current();
-                if let Ok(path) = teletypewriter::foreground_process_path(
-                    *current_context.main_fd,
-                    current_context.shell_pid,
-                ) {
-                    working_dir = Some(path.to_string_lossy().to_string());
-                }
-            }
-
-            #[cfg(target_os = "windows")]
-            {
-                // if let Ok(path) = teletypewriter::foreground_process_path() {
-                //     working_dir =
-                //         Some(path.to_string_lossy().to_string());
-                // }
-                working_dir = None;
-            }
+            working_dir = self.current_working_dir();
         }
 
         if self.config.is_native {
@@ -1105,6 +1230,7 @@ pub fn process_open_url(
                     shell = Shell {
                         program: editor.program,
                         args,
+                        environment: None,
                     }
                 } else if path_buf.is_dir() {
                     working_dir = Some(path_buf.display().to_string());
@@ -1155,6 +1281,49 @@ pub mod test {
         assert_eq!(context_manager.current_index, 2);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_has_running_children() {
+        assert!(!has_running_children([]));
+        assert!(!has_running_children([(10, 10), (20, 20)]));
+        assert!(has_running_children([(10, 10), (20, 21)]));
+    }
+
+    #[test]
+    fn test_add_context_with_profile() {
+        let window_id: WindowId = WindowId::from(0);
+        let config = rio_backend::config::Config::from_toml_str(
+            r##"
+            [[profiles]]
+            match = "~/work/*"
+            colors = { background = "#2e0000" }
+            env = { RIO_PROFILE = "work" }
+            fonts = { size = 20 }
+        "##,
+        )
+        .unwrap();
+        let home = dirs::home_dir().unwrap();
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(5, VoidListener {}, window_id).unwrap();
+        context_manager.config.profiles = config.profiles;
+        assert!(context_manager.current().colors.is_none());
+
+        context_manager.config.working_dir =
+            Some(home.join("work/rio").display().to_string());
+        context_manager.add_context(true, 0);
+        let colors = context_manager.current().colors.unwrap();
+        assert_eq!(colors.background.0, [46. / 255., 0., 0., 1.]);
+        assert_eq!(colors.foreground, Colors::default().foreground);
+        assert_eq!(context_manager.current().font_size, Some(20.));
+
+        context_manager.config.working_dir =
+            Some(home.join("personal").display().to_string());
+        context_manager.add_context(true, 0);
+        assert!(context_manager.current().colors.is_none());
+        assert_eq!(context_manager.current().font_size, None);
+    }
+
     #[test]
     fn test_add_context_start_with_capacity_limit() {
         let window_id: WindowId = WindowId::from(0);
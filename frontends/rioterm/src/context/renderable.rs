@@ -32,7 +32,8 @@ pub struct RenderableContent {
     pub hyperlink_range: Option<SelectionRange>,
     pub has_pending_updates: bool,
     pub last_typing: Option<Instant>,
-    pub is_cursor_visible: bool,
+    // The cursor follows the blink phase, it stays visible while typing.
+    pub is_cursor_blinking: bool,
 }
 
 impl RenderableContent {
@@ -47,7 +48,7 @@ impl RenderableContent {
             hyperlink_range: None,
             has_pending_updates: false,
             last_typing: None,
-            is_cursor_visible: true,
+            is_cursor_blinking: false,
         }
     }
 
@@ -84,20 +85,12 @@ impl RenderableContent {
         self.cursor.state = cursor;
 
         let has_selection = self.selection_range.is_some();
-        if !has_selection && has_blinking_enabled {
-            let mut should_blink = true;
-            if let Some(last_typing_time) = self.last_typing {
-                if last_typing_time.elapsed() < Duration::from_secs(1) {
-                    should_blink = false;
-                }
-            }
-
-            if should_blink {
-                self.is_cursor_visible = !self.is_cursor_visible;
-                diff.insert(*self.cursor.state.pos.row as usize);
-            } else {
-                self.is_cursor_visible = true;
-            }
+        let is_typing = self.last_typing.is_some_and(|last_typing_time| {
+            last_typing_time.elapsed() < Duration::from_secs(1)
+        });
+        self.is_cursor_blinking = !has_selection && has_blinking_enabled && !is_typing;
+        if self.is_cursor_blinking {
+            diff.insert(*self.cursor.state.pos.row as usize);
         }
 
         self.strategy = RenderableContentStrategy::Full;
@@ -39,18 +39,31 @@ use windows_sys::Win32::System::Console::{
 
 const LOG_LEVEL_ENV: &str = "RIO_LOG_LEVEL";
 
-pub fn setup_environment_variables(config: &rio_backend::config::Config) {
-    #[cfg(unix)]
-    let terminfo = if teletypewriter::terminfo_exists("rio") {
-        "rio"
-    } else {
-        "xterm-256color"
-    };
+/// `TERM` for the shell, the configured `terminal.term` is used as is,
+/// otherwise `rio` when its terminfo is installed or `xterm-256color`.
+#[cfg(unix)]
+fn term_name(
+    config: &rio_backend::config::Config,
+    terminfo_exists: impl Fn(&str) -> bool,
+) -> String {
+    match config.terminal.term.as_deref() {
+        Some(term) if !term.is_empty() => {
+            if !terminfo_exists(term) {
+                tracing::warn!("terminfo for the configured term {term:?} was not found");
+            }
+            term.to_owned()
+        }
+        _ if terminfo_exists("rio") => String::from("rio"),
+        _ => String::from("xterm-256color"),
+    }
+}
 
+pub fn setup_environment_variables(config: &rio_backend::config::Config) {
     #[cfg(unix)]
     {
         let span = tracing::span!(tracing::Level::INFO, "setup_environment_variables");
         let _guard = span.enter();
+        let terminfo = term_name(config, teletypewriter::terminfo_exists);
         tracing::info!("terminfo: {terminfo}");
         std::env::set_var("TERM", terminfo);
     }
@@ -69,12 +82,8 @@ pub fn setup_environment_variables(config: &rio_backend::config::Config) {
     }
 
     // Set env vars from config.
-    for env_config in config.env_vars.iter() {
-        let env_vec: Vec<&str> = env_config.split('=').collect();
-
-        if env_vec.len() == 2 {
-            std::env::set_var(env_vec[0], env_vec[1]);
-        }
+    for (key, value) in config.environment_variables() {
+        std::env::set_var(key, value);
     }
 }
 
@@ -139,6 +148,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load command line options.
     let args = cli::Cli::parse();
 
+    // Resolved before the working directory changes, so reloads and the
+    // config file shortcuts use the same file. It is not passed on to the
+    // shells, nor to the instances of rio started from them.
+    let config_file = args
+        .config_file
+        .clone()
+        .map(PathBuf::into_os_string)
+        .or_else(|| std::env::var_os(rio_backend::config::CONFIG_FILE_ENV));
+    std::env::remove_var(rio_backend::config::CONFIG_FILE_ENV);
+    rio_backend::config::init_config_file_path(config_file);
+
     let write_config_path = args.window_options.terminal_options.write_config.clone();
     if let Some(config_path) = write_config_path {
         let _ = setup_logs_by_filter_level("TRACE", false);
@@ -151,19 +171,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(err) => (rio_backend::config::Config::default(), Some(err)),
     };
 
-    // Read platform property and overwrite values per OS
-    //
-    // [shell]
-    // # default (in this case will be used on MacOS/Linux)
-    // program = "/bin/fish"
-    // args = ["--login"]
-    //
-    // [platform]
-    // # Microsoft Windows overwrite
-    // windows.shell.program = "pwsh"
-    // windows.shell.args = ["-l"]
-    config.overwrite_based_on_platform();
-
     {
         let log_to_file = args.window_options.terminal_options.enable_log_file;
         if let Err(e) = setup_logs_by_filter_level(
@@ -173,7 +180,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             eprintln!("unable to configure the logger: {e:?}");
         }
 
-        if let Some(command) = args.window_options.terminal_options.command() {
+        if let Some(mut command) = args.window_options.terminal_options.command() {
+            command.environment = config.shell.environment.take();
             config.shell = command;
             config.use_fork = false;
         }
@@ -210,3 +218,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use rio_backend::config::Config;
+
+    #[test]
+    fn test_term_name_uses_configured_term() {
+        let mut config = Config::default();
+        config.terminal.term = Some(String::from("xterm-256color"));
+        assert_eq!(term_name(&config, |_| true), "xterm-256color");
+
+        // Used even when its terminfo is missing.
+        config.terminal.term = Some(String::from("custom-term"));
+        assert_eq!(term_name(&config, |_| false), "custom-term");
+    }
+
+    #[test]
+    fn test_term_name_falls_back_to_detection() {
+        let mut config = Config::default();
+        assert_eq!(term_name(&config, |term| term == "rio"), "rio");
+        assert_eq!(term_name(&config, |_| false), "xterm-256color");
+
+        config.terminal.term = Some(String::new());
+        assert_eq!(term_name(&config, |term| term == "rio"), "rio");
+        assert_eq!(term_name(&config, |_| false), "xterm-256color");
+    }
+}
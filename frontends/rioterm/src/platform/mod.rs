@@ -1,2 +1,139 @@
 #[cfg(target_os = "macos")]
 pub mod macos;
+
+use rio_backend::config::renderer::PowerSource;
+
+/// Plays the system alert sound, returns `false` if the platform has none.
+#[inline]
+pub fn audible_bell() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        #[link(name = "AppKit", kind = "framework")]
+        extern "C" {
+            fn NSBeep();
+        }
+
+        unsafe { NSBeep() };
+        true
+    }
+
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Diagnostics::Debug::MessageBeep;
+        use windows_sys::Win32::UI::WindowsAndMessaging::MB_OK;
+
+        unsafe { MessageBeep(MB_OK) != 0 }
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    false
+}
+
+/// Where the system is drawing power from, `None` when it can't be told,
+/// for example on desktops without a battery.
+pub fn power_source() -> Option<PowerSource> {
+    #[cfg(target_os = "macos")]
+    {
+        #[link(name = "IOKit", kind = "framework")]
+        extern "C" {
+            fn IOPSGetTimeRemainingEstimate() -> f64;
+        }
+
+        // kIOPSTimeRemainingUnlimited, the system is on AC power. Any
+        // other value, including an estimate still being computed, means
+        // it runs from a battery.
+        const TIME_REMAINING_UNLIMITED: f64 = -2.0;
+
+        let remaining = unsafe { IOPSGetTimeRemainingEstimate() };
+        if remaining == TIME_REMAINING_UNLIMITED {
+            Some(PowerSource::Ac)
+        } else {
+            Some(PowerSource::Battery)
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Power::{
+            GetSystemPowerStatus, SYSTEM_POWER_STATUS,
+        };
+
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+            return None;
+        }
+
+        match status.ACLineStatus {
+            0 => Some(PowerSource::Battery),
+            1 => Some(PowerSource::Ac),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        power_source_from_sysfs(std::path::Path::new("/sys/class/power_supply"))
+    }
+}
+
+/// Reads the power source from a `power_supply` sysfs class directory, an
+/// online mains supply wins over batteries.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn power_source_from_sysfs(path: &std::path::Path) -> Option<PowerSource> {
+    let read = |supply: &std::path::Path, attribute: &str| {
+        std::fs::read_to_string(supply.join(attribute))
+            .map(|value| value.trim().to_owned())
+            .unwrap_or_default()
+    };
+
+    let mut has_battery = false;
+    for entry in std::fs::read_dir(path).ok()?.flatten() {
+        let supply = entry.path();
+        match read(&supply, "type").as_str() {
+            "Mains" | "USB" if read(&supply, "online") == "1" => {
+                return Some(PowerSource::Ac)
+            }
+            "Battery" if read(&supply, "scope") != "Device" => has_battery = true,
+            _ => (),
+        }
+    }
+
+    has_battery.then_some(PowerSource::Battery)
+}
+
+#[cfg(all(test, not(any(target_os = "macos", windows))))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_source_from_sysfs() {
+        let dir = std::env::temp_dir().join("rio-test-power-supply");
+        let _ = std::fs::remove_dir_all(&dir);
+        let supply = |name: &str, attributes: &[(&str, &str)]| {
+            let supply = dir.join(name);
+            std::fs::create_dir_all(&supply).unwrap();
+            for (attribute, value) in attributes {
+                std::fs::write(supply.join(attribute), format!("{value}\n")).unwrap();
+            }
+        };
+
+        // Desktops have no battery.
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(power_source_from_sysfs(&dir), None);
+
+        supply("BAT0", &[("type", "Battery"), ("status", "Discharging")]);
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        // Batteries of peripherals, like a mouse, don't power the system.
+        supply(
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device")],
+        );
+        assert_eq!(power_source_from_sysfs(&dir), Some(PowerSource::Battery));
+
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert_eq!(power_source_from_sysfs(&dir), Some(PowerSource::Ac));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(power_source_from_sysfs(&dir), None);
+    }
+}
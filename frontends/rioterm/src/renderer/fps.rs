@@ -0,0 +1,104 @@
+use rio_backend::config::colors::Colors;
+use rio_backend::sugarloaf::font::FontLibrary;
+use rio_backend::sugarloaf::{Object, Text};
+use std::time::{Duration, Instant};
+
+const FPS_COUNTER_FONT_SIZE: f32 = 14.;
+
+/// Counts the frames prepared in the last second, shown with
+/// `developer.enable-fps-counter`.
+pub struct FpsCounter {
+    start: Instant,
+    frames: u32,
+    fps: u32,
+    // Advance of a character of the counter, to align it to the right.
+    char_width: f32,
+}
+
+impl FpsCounter {
+    pub fn new(now: Instant, font_library: &FontLibrary) -> FpsCounter {
+        FpsCounter {
+            start: now,
+            frames: 0,
+            fps: 0,
+            char_width: font_library
+                .inner
+                .lock()
+                .ui_char_width(FPS_COUNTER_FONT_SIZE),
+        }
+    }
+
+    /// Counts a frame, `fps` is updated once per second.
+    pub fn frame(&mut self, now: Instant) {
+        self.frames += 1;
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= Duration::from_secs(1) {
+            self.fps = (self.frames as f64 / elapsed.as_secs_f64()).round() as u32;
+            self.frames = 0;
+            self.start = now;
+        }
+    }
+
+    #[inline]
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+}
+
+/// Text of the counter, the GPU time is left out when the adapter does
+/// not support timestamp queries.
+pub fn fps_counter_text(fps: u32, gpu_time: Option<Duration>) -> String {
+    match gpu_time {
+        Some(gpu_time) => {
+            format!("{fps} fps | gpu {:.2}ms", gpu_time.as_secs_f64() * 1000.)
+        }
+        None => format!("{fps} fps"),
+    }
+}
+
+#[inline]
+pub fn draw_fps_counter(
+    objects: &mut Vec<Object>,
+    colors: &Colors,
+    dimensions: (f32, f32, f32),
+    fps_counter: &FpsCounter,
+    gpu_time: Option<Duration>,
+) {
+    let (width, height, scale) = dimensions;
+    let content = fps_counter_text(fps_counter.fps(), gpu_time);
+    let text_width = content.chars().count() as f32 * fps_counter.char_width;
+
+    objects.push(Object::Text(Text::single_line(
+        ((width / scale) - text_width - 10., (height / scale) - 20.),
+        content,
+        FPS_COUNTER_FONT_SIZE,
+        colors.foreground,
+    )));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fps_counter_updates_every_second() {
+        let start = Instant::now();
+        let mut counter = FpsCounter::new(start, &FontLibrary::default());
+        for frame in 1..60 {
+            counter.frame(start + Duration::from_millis(frame * 16));
+        }
+        assert_eq!(counter.fps(), 0);
+
+        counter.frame(start + Duration::from_secs(1));
+        assert_eq!(counter.fps(), 60);
+    }
+
+    #[test]
+    fn test_fps_counter_text() {
+        assert_eq!(fps_counter_text(60, None), "60 fps");
+        assert_eq!(
+            fps_counter_text(60, Some(Duration::from_micros(1500))),
+            "60 fps | gpu 1.50ms"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+mod fps;
 pub mod navigation;
 mod search;
 pub mod utils;
@@ -14,14 +15,17 @@ use rio_backend::config::colors::{
     term::{List, TermColors},
     AnsiColor, ColorArray, Colors, NamedColor,
 };
+use rio_backend::config::keyboard::KeyboardProtocol;
 use rio_backend::config::Config;
-use rio_backend::event::EventProxy;
+use rio_backend::event::{EventListener, EventProxy};
 use rio_backend::sugarloaf::{
-    Content, FragmentStyle, FragmentStyleDecoration, Graphic, Stretch, Style,
-    SugarCursor, Sugarloaf, UnderlineInfo, UnderlineShape, Weight,
+    drawable_character, Animations, BlinkPhase, BlinkRate, Content, FragmentStyle,
+    FragmentStyleDecoration, Graphic, Object, Rect, Stretch, Style, SugarCursor,
+    Sugarloaf, UnderlineInfo, UnderlineShape, Weight,
 };
 use std::collections::HashMap;
 use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
 
 use rustc_hash::FxHashMap;
 use unicode_width::UnicodeWidthChar;
@@ -35,6 +39,7 @@ pub struct Renderer {
     pub config_has_blinking_enabled: bool,
     pub config_blinking_interval: u64,
     ignore_selection_fg_color: bool,
+    use_drawable_chars: bool,
     #[allow(unused)]
     pub option_as_alt: String,
     #[allow(unused)]
@@ -44,10 +49,44 @@ pub struct Renderer {
     pub dynamic_background: ([f32; 4], wgpu::Color, bool),
     font_context: rio_backend::sugarloaf::font::FontLibrary,
     font_cache: FxHashMap<
-        (char, rio_backend::sugarloaf::font_introspector::Attributes),
+        (
+            char,
+            Option<char>,
+            rio_backend::sugarloaf::font_introspector::Attributes,
+        ),
         (usize, f32),
     >,
     active_search: Option<String>,
+    disable_unfocused_render: bool,
+    is_focused: bool,
+    // Content was not prepared while unfocused, the next frame must
+    // rebuild every line.
+    has_pending_full_render: bool,
+    bell_color: [f32; 4],
+    bell_duration: Duration,
+    visual_bell_start: Option<Instant>,
+    unfocused_split_opacity: f32,
+    // Alpha multiplier of the cell backgrounds and of the Sugarloaf
+    // background for the next renders, apart from the window opacity.
+    background_alpha: f32,
+    // Background opacity of the window while focused and unfocused,
+    // only applied when the window is transparent.
+    window_opacity: f32,
+    unfocused_window_opacity: f32,
+    is_transparent: bool,
+    // Cursor and blinking text derive their visibility from the phase
+    // set on Sugarloaf, counted from `blink_start`.
+    blink_start: Instant,
+    blink_phase: BlinkPhase,
+    rendered_blink_phase: BlinkPhase,
+    has_blinking_text: bool,
+    // Rich text of the focused split in the last frame, splits are
+    // fully rendered again when it changes so dimming follows the focus.
+    active_rich_text_id: Option<usize>,
+    // Shape and color of the cursor while `animations` move it toward its
+    // cell, it is drawn apart from the text until it gets there.
+    cursor_trail: Option<(CursorShape, [f32; 4])>,
+    fps_counter: Option<fps::FpsCounter>,
 }
 
 impl Renderer {
@@ -61,8 +100,8 @@ impl Renderer {
 
         let mut dynamic_background =
             (named_colors.background.0, named_colors.background.1, false);
-        if config.window.opacity < 1. {
-            dynamic_background.1.a = config.window.opacity as f64;
+        if config.window.is_transparent() {
+            dynamic_background.1.a = config.window.effective_opacity(true) as f64;
             dynamic_background.2 = true;
         } else if config.window.background_image.is_some() {
             dynamic_background.1 = wgpu::Color::TRANSPARENT;
@@ -83,19 +122,47 @@ impl Renderer {
             macos_use_unified_titlebar: config.window.macos_use_unified_titlebar,
             config_blinking_interval: config.cursor.blinking_interval.clamp(350, 1200),
             option_as_alt: config.option_as_alt.to_lowercase(),
-            is_kitty_keyboard_enabled: config.keyboard.use_kitty_keyboard_protocol,
+            is_kitty_keyboard_enabled: config.keyboard.protocol()
+                == KeyboardProtocol::Kitty,
             is_vi_mode_enabled: false,
             config_has_blinking_enabled: config.cursor.blinking,
             ignore_selection_fg_color: config.ignore_selection_fg_color,
+            use_drawable_chars: config.fonts.use_drawable_chars,
             colors,
             navigation: ScreenNavigation::new(
                 config.navigation.clone(),
                 color_automation,
+                config.title.clone(),
                 config.padding_y,
+                font_context,
             ),
             named_colors,
             dynamic_background,
             active_search: None,
+            disable_unfocused_render: config.renderer.disable_unfocused_render,
+            is_focused: true,
+            has_pending_full_render: false,
+            bell_color: config.bell.color,
+            bell_duration: Duration::from_millis(config.bell.duration),
+            visual_bell_start: None,
+            unfocused_split_opacity: config
+                .navigation
+                .unfocused_split_opacity
+                .clamp(0., 1.),
+            background_alpha: 1.0,
+            fps_counter: config
+                .developer
+                .enable_fps_counter
+                .then(|| fps::FpsCounter::new(Instant::now(), font_context)),
+            window_opacity: config.window.effective_opacity(true),
+            unfocused_window_opacity: config.window.effective_opacity(false),
+            is_transparent: config.window.is_transparent(),
+            active_rich_text_id: None,
+            cursor_trail: None,
+            blink_start: Instant::now(),
+            blink_phase: BlinkPhase::default(),
+            rendered_blink_phase: BlinkPhase::default(),
+            has_blinking_text: false,
             font_cache: FxHashMap::default(),
             font_context: font_context.clone(),
         }
@@ -113,12 +180,23 @@ impl Renderer {
         let mut foreground_color = self.compute_color(&square.fg, flags);
         let mut background_color = self.compute_bg_color(square);
 
-        let content = if square.c == '\t' || flags.contains(Flags::HIDDEN) {
-            ' '
-        } else {
-            square.c
+        let is_blink_hidden = flags.intersects(Flags::ALL_BLINKS) && {
+            self.has_blinking_text = true;
+            let rate = if flags.contains(Flags::BLINK_FAST) {
+                BlinkRate::Fast
+            } else {
+                BlinkRate::Slow
+            };
+            !self.blink_phase.is_visible(rate)
         };
 
+        let content =
+            if square.c == '\t' || flags.contains(Flags::HIDDEN) || is_blink_hidden {
+                ' '
+            } else {
+                square.c
+            };
+
         let font_attrs = match (
             flags.contains(Flags::ITALIC),
             flags.contains(Flags::BOLD_ITALIC),
@@ -260,21 +338,23 @@ impl Renderer {
                 style.decoration =
                     Some(FragmentStyleDecoration::Underline(UnderlineInfo {
                         offset: -1.0,
-                        size: -1.0,
+                        size: 1.0,
                         is_doubled: false,
                         shape: UnderlineShape::Regular,
                     }));
-            } else if selection_range.is_some()
-                && selection_range
-                    .unwrap()
-                    .contains(Pos::new(line, Column(column)))
-            {
+            } else if selection_range.is_some_and(|selection| {
+                // Wide chars are highlighted as a whole when only their
+                // spacer is selected.
+                selection.contains(Pos::new(line, Column(column)))
+                    || (square.flags.contains(Flags::WIDE_CHAR)
+                        && selection.contains(Pos::new(line, Column(column + 1))))
+            }) {
                 style.color = if self.ignore_selection_fg_color {
                     self.compute_color(&square.fg, square.flags)
                 } else {
-                    self.named_colors.selection_foreground
+                    self.named_colors.selection_foreground()
                 };
-                style.background_color = Some(self.named_colors.selection_background);
+                style.background_color = Some(self.named_colors.selection_background());
             } else if search_hints.is_some()
                 && search_hints.as_mut().map_or(false, |search| {
                     search.advance(Pos::new(line, Column(column)))
@@ -284,13 +364,13 @@ impl Renderer {
                     .as_ref()
                     .map_or(false, |fm| fm.contains(&Pos::new(line, Column(column))));
                 if is_focused {
-                    style.color = self.named_colors.search_focused_match_foreground;
+                    style.color = self.named_colors.search_focused_match_foreground();
                     style.background_color =
-                        Some(self.named_colors.search_focused_match_background);
+                        Some(self.named_colors.search_focused_match_background());
                 } else {
-                    style.color = self.named_colors.search_match_foreground;
+                    style.color = self.named_colors.search_match_foreground();
                     style.background_color =
-                        Some(self.named_colors.search_match_background);
+                        Some(self.named_colors.search_match_background());
                 }
             }
 
@@ -315,8 +395,35 @@ impl Renderer {
                 style.background_color = None;
             }
 
+            if self.use_drawable_chars {
+                style.drawable_char = drawable_character(square_content);
+            }
+
+            if self.background_alpha < 1. {
+                if let Some(background_color) = style.background_color.as_mut() {
+                    background_color[3] *= self.background_alpha;
+                }
+            }
+
+            if !is_active && self.unfocused_split_opacity < 1. {
+                dim_style(&mut style, self.unfocused_split_opacity);
+            }
+
+            // VS15 and VS16 select between text and emoji presentation.
+            let variation = if square_content == square.c {
+                square.zerowidth().and_then(|zerowidth| {
+                    zerowidth
+                        .iter()
+                        .copied()
+                        .find(|c| matches!(c, '\u{FE0E}' | '\u{FE0F}'))
+                })
+            } else {
+                None
+            };
+
             if let Some((font_id, width)) =
-                self.font_cache.get(&(square_content, style.font_attrs))
+                self.font_cache
+                    .get(&(square_content, variation, style.font_attrs))
             {
                 style.font_id = *font_id;
                 style.width = *width;
@@ -334,7 +441,7 @@ impl Renderer {
                 // single width. So, we completely rely on what font is
                 // being used and then set width 2 for it.
                 if let Some((font_id, is_emoji)) =
-                    font_ctx.find_best_font_match(square_content, &style)
+                    font_ctx.find_best_font_match(square_content, variation, &style)
                 {
                     style.font_id = font_id;
                     if is_emoji {
@@ -344,67 +451,38 @@ impl Renderer {
                 style.width = width;
 
                 self.font_cache.insert(
-                    (square_content, style.font_attrs),
+                    (square_content, variation, style.font_attrs),
                     (style.font_id, style.width),
                 );
             };
 
-            if square_content == ' ' {
-                if !last_char_was_space {
-                    if !content.is_empty() {
-                        if let Some(line) = line_opt {
-                            builder.add_text_on_line(line, &content, last_style);
-                        } else {
-                            builder.add_text(&content, last_style);
-                        }
-                        content.clear();
-                    }
-
-                    last_char_was_space = true;
-                    last_style = style;
-                }
-            } else {
-                if last_char_was_space && !content.is_empty() {
-                    if let Some(line) = line_opt {
-                        builder.add_text_on_line(line, &content, last_style);
-                    } else {
-                        builder.add_text(&content, last_style);
-                    }
-                    content.clear();
-                }
-
-                last_char_was_space = false;
-            }
-
-            if last_style != style {
-                if !content.is_empty() {
-                    if let Some(line) = line_opt {
-                        builder.add_text_on_line(line, &content, last_style);
-                    } else {
-                        builder.add_text(&content, last_style);
-                    }
-                    content.clear();
-                }
-
-                last_style = style;
+            // Squares are coalesced into a single fragment until the style
+            // changes, runs are also broken between words and spaces so the
+            // shaped words can be reused from the word cache.
+            let is_space = square_content == ' ';
+            if !content.is_empty()
+                && (last_style != style || is_space != last_char_was_space)
+            {
+                Self::add_fragment(builder, line_opt, &content, last_style);
+                content.clear();
             }
 
+            last_style = style;
+            last_char_was_space = is_space;
             content.push(square_content);
 
-            // Render last column and break row
-            if column == (columns - 1) {
-                if !content.is_empty() {
-                    if let Some(line) = line_opt {
-                        builder.add_text_on_line(line, &content, last_style);
-                    } else {
-                        builder.add_text(&content, last_style);
-                    }
+            // Combining characters are shaped together with their base.
+            if square_content == square.c {
+                if let Some(zerowidth) = square.zerowidth() {
+                    content.extend(zerowidth);
                 }
-
-                break;
             }
         }
 
+        if !content.is_empty() {
+            Self::add_fragment(builder, line_opt, &content, last_style);
+        }
+
         if let Some(line) = line_opt {
             builder.build_line(line);
         } else {
@@ -412,57 +490,38 @@ impl Renderer {
         }
     }
 
+    #[inline]
+    fn add_fragment(
+        builder: &mut Content,
+        line_opt: Option<usize>,
+        content: &str,
+        style: FragmentStyle,
+    ) {
+        if let Some(line) = line_opt {
+            builder.add_text_on_line(line, content, style);
+        } else {
+            builder.add_text(content, style);
+        }
+    }
+
+    /// Regular colors are brightened when the flags are exactly bold and
+    /// darkened when they are exactly dim.
+    #[inline]
+    fn compute_named_color(&self, name: NamedColor, flags: Flags) -> ColorArray {
+        let is_regular = name <= NamedColor::White;
+        let name = match flags {
+            Flags::DIM if is_regular => name.to_dim(),
+            Flags::BOLD if is_regular => name.to_light(),
+            _ => name,
+        };
+
+        self.named_colors.resolve(name)
+    }
+
     #[inline]
     fn compute_color(&self, color: &AnsiColor, flags: Flags) -> ColorArray {
         match color {
-            AnsiColor::Named(ansi_name) => match (ansi_name, flags) {
-                (NamedColor::Background, _) => self.named_colors.background.0,
-                (NamedColor::Cursor, _) => self.named_colors.cursor,
-
-                (NamedColor::Black, Flags::DIM) => self.named_colors.dim_black,
-                (NamedColor::Black, Flags::BOLD) => self.named_colors.light_black,
-                (NamedColor::Black, _) => self.named_colors.black,
-                (NamedColor::Blue, Flags::DIM) => self.named_colors.dim_blue,
-                (NamedColor::Blue, Flags::BOLD) => self.named_colors.light_blue,
-                (NamedColor::Blue, _) => self.named_colors.blue,
-                (NamedColor::Cyan, Flags::DIM) => self.named_colors.dim_cyan,
-                (NamedColor::Cyan, Flags::BOLD) => self.named_colors.light_cyan,
-                (NamedColor::Cyan, _) => self.named_colors.cyan,
-                (NamedColor::Foreground, _) => self.named_colors.foreground,
-                (NamedColor::Green, Flags::DIM) => self.named_colors.dim_green,
-                (NamedColor::Green, Flags::BOLD) => self.named_colors.light_green,
-                (NamedColor::Green, _) => self.named_colors.green,
-                (NamedColor::Magenta, Flags::DIM) => self.named_colors.dim_magenta,
-                (NamedColor::Magenta, Flags::BOLD) => self.named_colors.light_magenta,
-                (NamedColor::Magenta, _) => self.named_colors.magenta,
-                (NamedColor::Red, Flags::DIM) => self.named_colors.dim_red,
-                (NamedColor::Red, Flags::BOLD) => self.named_colors.light_red,
-                (NamedColor::Red, _) => self.named_colors.red,
-                (NamedColor::White, Flags::DIM) => self.named_colors.dim_white,
-                (NamedColor::White, Flags::BOLD) => self.named_colors.light_white,
-                (NamedColor::White, _) => self.named_colors.white,
-                (NamedColor::Yellow, Flags::DIM) => self.named_colors.dim_yellow,
-                (NamedColor::Yellow, Flags::BOLD) => self.named_colors.light_yellow,
-                (NamedColor::Yellow, _) => self.named_colors.yellow,
-                (NamedColor::LightBlack, _) => self.named_colors.light_black,
-                (NamedColor::LightBlue, _) => self.named_colors.light_blue,
-                (NamedColor::LightCyan, _) => self.named_colors.light_cyan,
-                (NamedColor::LightForeground, _) => self.named_colors.light_foreground,
-                (NamedColor::LightGreen, _) => self.named_colors.light_green,
-                (NamedColor::LightMagenta, _) => self.named_colors.light_magenta,
-                (NamedColor::LightRed, _) => self.named_colors.light_red,
-                (NamedColor::LightWhite, _) => self.named_colors.light_white,
-                (NamedColor::LightYellow, _) => self.named_colors.light_yellow,
-                (NamedColor::DimBlack, _) => self.named_colors.dim_black,
-                (NamedColor::DimBlue, _) => self.named_colors.dim_blue,
-                (NamedColor::DimCyan, _) => self.named_colors.dim_cyan,
-                (NamedColor::DimForeground, _) => self.named_colors.dim_foreground,
-                (NamedColor::DimGreen, _) => self.named_colors.dim_green,
-                (NamedColor::DimMagenta, _) => self.named_colors.dim_magenta,
-                (NamedColor::DimRed, _) => self.named_colors.dim_red,
-                (NamedColor::DimWhite, _) => self.named_colors.dim_white,
-                (NamedColor::DimYellow, _) => self.named_colors.dim_yellow,
-            },
+            AnsiColor::Named(ansi_name) => self.compute_named_color(*ansi_name, flags),
             AnsiColor::Spec(rgb) => {
                 if !flags.contains(Flags::DIM) {
                     rgb.to_arr()
@@ -487,54 +546,9 @@ impl Renderer {
     #[inline]
     fn compute_bg_color(&self, square: &Square) -> ColorArray {
         match square.bg {
-            AnsiColor::Named(ansi_name) => match (ansi_name, square.flags) {
-                (NamedColor::Background, _) => self.named_colors.background.0,
-                (NamedColor::Cursor, _) => self.named_colors.cursor,
-
-                (NamedColor::Black, Flags::DIM) => self.named_colors.dim_black,
-                (NamedColor::Black, Flags::BOLD) => self.named_colors.light_black,
-                (NamedColor::Black, _) => self.named_colors.black,
-                (NamedColor::Blue, Flags::DIM) => self.named_colors.dim_blue,
-                (NamedColor::Blue, Flags::BOLD) => self.named_colors.light_blue,
-                (NamedColor::Blue, _) => self.named_colors.blue,
-                (NamedColor::Cyan, Flags::DIM) => self.named_colors.dim_cyan,
-                (NamedColor::Cyan, Flags::BOLD) => self.named_colors.light_cyan,
-                (NamedColor::Cyan, _) => self.named_colors.cyan,
-                (NamedColor::Foreground, _) => self.named_colors.foreground,
-                (NamedColor::Green, Flags::DIM) => self.named_colors.dim_green,
-                (NamedColor::Green, Flags::BOLD) => self.named_colors.light_green,
-                (NamedColor::Green, _) => self.named_colors.green,
-                (NamedColor::Magenta, Flags::DIM) => self.named_colors.dim_magenta,
-                (NamedColor::Magenta, Flags::BOLD) => self.named_colors.light_magenta,
-                (NamedColor::Magenta, _) => self.named_colors.magenta,
-                (NamedColor::Red, Flags::DIM) => self.named_colors.dim_red,
-                (NamedColor::Red, Flags::BOLD) => self.named_colors.light_red,
-                (NamedColor::Red, _) => self.named_colors.red,
-                (NamedColor::White, Flags::DIM) => self.named_colors.dim_white,
-                (NamedColor::White, Flags::BOLD) => self.named_colors.light_white,
-                (NamedColor::White, _) => self.named_colors.white,
-                (NamedColor::Yellow, Flags::DIM) => self.named_colors.dim_yellow,
-                (NamedColor::Yellow, Flags::BOLD) => self.named_colors.light_yellow,
-                (NamedColor::Yellow, _) => self.named_colors.yellow,
-                (NamedColor::LightBlack, _) => self.named_colors.light_black,
-                (NamedColor::LightBlue, _) => self.named_colors.light_blue,
-                (NamedColor::LightCyan, _) => self.named_colors.light_cyan,
-                (NamedColor::LightForeground, _) => self.named_colors.light_foreground,
-                (NamedColor::LightGreen, _) => self.named_colors.light_green,
-                (NamedColor::LightMagenta, _) => self.named_colors.light_magenta,
-                (NamedColor::LightRed, _) => self.named_colors.light_red,
-                (NamedColor::LightWhite, _) => self.named_colors.light_white,
-                (NamedColor::LightYellow, _) => self.named_colors.light_yellow,
-                (NamedColor::DimBlack, _) => self.named_colors.dim_black,
-                (NamedColor::DimBlue, _) => self.named_colors.dim_blue,
-                (NamedColor::DimCyan, _) => self.named_colors.dim_cyan,
-                (NamedColor::DimForeground, _) => self.named_colors.dim_foreground,
-                (NamedColor::DimGreen, _) => self.named_colors.dim_green,
-                (NamedColor::DimMagenta, _) => self.named_colors.dim_magenta,
-                (NamedColor::DimRed, _) => self.named_colors.dim_red,
-                (NamedColor::DimWhite, _) => self.named_colors.dim_white,
-                (NamedColor::DimYellow, _) => self.named_colors.dim_yellow,
-            },
+            AnsiColor::Named(ansi_name) => {
+                self.compute_named_color(ansi_name, square.flags)
+            }
             AnsiColor::Spec(rgb) => rgb.to_arr(),
             AnsiColor::Indexed(idx) => self.colors[idx as usize],
         }
@@ -605,11 +619,7 @@ impl Renderer {
             ..FragmentStyle::default()
         };
 
-        let cursor_color = if !self.is_vi_mode_enabled {
-            self.named_colors.cursor
-        } else {
-            self.named_colors.vi_cursor
-        };
+        let cursor_color = self.cursor_color();
 
         let (decoration, decoration_color) = self.compute_decoration(square);
         style.decoration = decoration;
@@ -643,36 +653,211 @@ impl Renderer {
         (style, content)
     }
 
+    #[inline]
+    fn cursor_color(&self) -> [f32; 4] {
+        if !self.is_vi_mode_enabled {
+            self.named_colors.cursor
+        } else {
+            self.named_colors.vi_cursor
+        }
+    }
+
+    #[inline]
+    pub fn set_focused(&mut self, is_focused: bool) {
+        if is_focused && !self.is_focused {
+            self.has_pending_full_render = true;
+        }
+        self.is_focused = is_focused;
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+
+    /// Sets the alpha multiplier of the backgrounds for the next renders,
+    /// lines are rebuilt when it changes.
+    #[inline]
+    #[allow(unused)]
+    pub fn set_background_alpha(&mut self, alpha: f32) {
+        let alpha = alpha.clamp(0., 1.);
+        if alpha != self.background_alpha {
+            self.background_alpha = alpha;
+            self.has_pending_full_render = true;
+        }
+    }
+
+    #[inline]
+    pub fn background_alpha(&self) -> f32 {
+        self.background_alpha
+    }
+
+    /// Background opacity of the window for the current focus.
+    #[inline]
+    pub fn window_opacity(&self) -> f32 {
+        if self.is_focused {
+            self.window_opacity
+        } else {
+            self.unfocused_window_opacity
+        }
+    }
+
+    /// Background of the window drawn with `opacity`, `None` when the
+    /// window is not transparent.
+    #[inline]
+    pub fn window_background(&self, opacity: f32) -> Option<wgpu::Color> {
+        if !self.is_transparent {
+            return None;
+        }
+
+        let mut color = self.dynamic_background.1;
+        color.a = opacity as f64;
+        Some(color)
+    }
+
+    /// Returns `false` when the window is unfocused and
+    /// `renderer.disable-unfocused-render` is enabled.
+    #[inline]
+    pub fn should_render(&self) -> bool {
+        self.is_focused || !self.disable_unfocused_render
+    }
+
+    /// Flashes the window with `bell.color` for `bell.duration`.
+    #[inline]
+    pub fn trigger_visual_bell(&mut self) {
+        self.visual_bell_start = Some(Instant::now());
+    }
+
+    #[inline]
+    fn draw_visual_bell(&mut self, objects: &mut Vec<Object>, dimensions: (f32, f32)) {
+        let Some(start) = self.visual_bell_start else {
+            return;
+        };
+
+        if start.elapsed() >= self.bell_duration {
+            self.visual_bell_start = None;
+            return;
+        }
+
+        let (width, height) = dimensions;
+        objects.push(Object::Rect(Rect {
+            position: [0., 0.],
+            color: self.bell_color,
+            size: [width * 2., height * 2.],
+        }));
+    }
+
+    #[inline]
+    fn blink_interval(&self) -> Duration {
+        Duration::from_millis(self.config_blinking_interval)
+    }
+
+    /// Whether a blinking cursor is shown, it shares the slow cadence of
+    /// blinking text.
+    #[inline]
+    fn is_cursor_blink_visible(&self) -> bool {
+        self.blink_phase.is_visible(BlinkRate::Slow)
+    }
+
+    /// Milliseconds until the next frame where something blinks, `None`
+    /// when neither the cursor nor any text is blinking.
+    pub fn next_blink(&self, is_cursor_blinking: bool) -> Option<u64> {
+        let rate = if self.has_blinking_text {
+            BlinkRate::Fast
+        } else if is_cursor_blinking {
+            BlinkRate::Slow
+        } else {
+            return None;
+        };
+
+        let delay = BlinkPhase::next_toggle(
+            self.blink_start.elapsed(),
+            self.blink_interval(),
+            rate,
+        );
+        Some(delay.as_millis() as u64)
+    }
+
     #[inline]
     pub fn set_vi_mode(&mut self, is_vi_mode_enabled: bool) {
         self.is_vi_mode_enabled = is_vi_mode_enabled;
     }
 
-    #[inline]
-    pub fn prepare_term(
+    /// Queues the lines of every context of the current grid, returns
+    /// `false` without touching `content` if rendering is skipped.
+    fn prepare_content<T: EventListener + Clone + Send + 'static>(
         &mut self,
-        sugarloaf: &mut Sugarloaf,
-        context_manager: &mut ContextManager<EventProxy>,
+        content: &mut Content,
+        animations: &mut Animations,
+        context_manager: &mut ContextManager<T>,
         hints: &mut Option<HintMatches>,
         focused_match: &Option<RangeInclusive<Pos>>,
-    ) {
-        let content = sugarloaf.content();
+    ) -> bool {
+        if !self.should_render() {
+            return false;
+        }
+
         let grid = context_manager.current_grid_mut();
         let active_index = grid.current;
+        let active_rich_text_id = Some(grid.current().rich_text_id);
+        let has_active_changed = self.active_rich_text_id != active_rich_text_id;
+        let has_focus_changed = self.unfocused_split_opacity < 1. && has_active_changed;
+        self.active_rich_text_id = active_rich_text_id;
+
+        // Lines with blinking text are not damaged when the phase changes.
+        let has_blink_changed =
+            self.has_blinking_text && self.rendered_blink_phase != self.blink_phase;
+        if has_blink_changed {
+            self.has_blinking_text = false;
+        }
+        self.rendered_blink_phase = self.blink_phase;
 
+        // Contexts matching a profile are rendered with its colors.
+        let window_colors = self.named_colors;
         for (index, grid_context) in grid.contexts_mut().iter_mut().enumerate() {
             let is_active = active_index == index;
             let context = grid_context.context_mut();
+            self.named_colors = context.colors.unwrap_or(window_colors);
             let rich_text_id = context.rich_text_id;
             let renderable_content = context.renderable_content();
-            let mut is_cursor_visible = renderable_content.is_cursor_visible
+            let mut is_cursor_visible = (!renderable_content.is_cursor_blinking
+                || self.is_cursor_blink_visible())
                 && renderable_content.cursor.state.is_visible();
             if !is_active && renderable_content.cursor.state.is_visible() {
                 is_cursor_visible = true;
             }
 
             let display_offset = renderable_content.display_offset;
-            let strategy = if is_active && hints.is_some() {
+
+            // Animations follow the cursor and the scroll offset of the
+            // active split, they jump when another split gets active.
+            let mut has_trail_changed = false;
+            if is_active {
+                let pos = renderable_content.cursor.state.pos;
+                let cursor = [pos.col.0 as f32, pos.row.0 as f32];
+                if has_active_changed {
+                    animations.snap_cursor_and_scroll(cursor, display_offset as f32);
+                } else {
+                    animations.set_cursor_target(cursor);
+                    animations.set_scroll_target(display_offset as f32);
+                }
+
+                let cursor_trail = (is_cursor_visible
+                    && animations.is_cursor_animating())
+                .then(|| (renderable_content.cursor.state.content, self.cursor_color()));
+                has_trail_changed = cursor_trail.is_some() != self.cursor_trail.is_some();
+                self.cursor_trail = cursor_trail;
+                if self.cursor_trail.is_some() {
+                    is_cursor_visible = false;
+                }
+            }
+
+            let strategy = if self.has_pending_full_render
+                || has_focus_changed
+                || has_trail_changed
+                || has_blink_changed
+                || (is_active && hints.is_some())
+            {
                 &RenderableContentStrategy::Full
             } else {
                 &renderable_content.strategy
@@ -724,6 +909,38 @@ impl Renderer {
                 RenderableContentStrategy::Noop => {}
             }
         }
+        self.named_colors = window_colors;
+
+        self.has_pending_full_render = false;
+        true
+    }
+
+    #[inline]
+    pub fn prepare_term(
+        &mut self,
+        sugarloaf: &mut Sugarloaf,
+        context_manager: &mut ContextManager<EventProxy>,
+        hints: &mut Option<HintMatches>,
+        focused_match: &Option<RangeInclusive<Pos>>,
+    ) {
+        sugarloaf.set_blink_phase(BlinkPhase::from_elapsed(
+            self.blink_start.elapsed(),
+            self.blink_interval(),
+        ));
+        self.blink_phase = sugarloaf.blink_phase();
+
+        let mut animations = *sugarloaf.animations();
+        let is_prepared = self.prepare_content(
+            sugarloaf.content(),
+            &mut animations,
+            context_manager,
+            hints,
+            focused_match,
+        );
+        *sugarloaf.animations_mut() = animations;
+        if !is_prepared {
+            return;
+        }
 
         let window_size = sugarloaf.window_size();
         let scale_factor = sugarloaf.scale_factor();
@@ -747,10 +964,520 @@ impl Renderer {
             self.active_search = None;
         }
 
-        for rte in context_manager.grid_objects() {
-            objects.push(rte);
+        let context = context_manager.current();
+        let scale = context.dimension.dimension.scale;
+        let cell = [
+            context.dimension.dimension.width / scale,
+            context.dimension.dimension.height * sugarloaf.style().line_height / scale,
+        ];
+        for mut object in context_manager.grid_objects() {
+            if let Object::RichText(rich_text) = &mut object {
+                if rich_text.id == context.rich_text_id {
+                    // Lines are shifted by the part of the scroll that is
+                    // still animating.
+                    rich_text.position[1] += scroll_shift(
+                        context.renderable_content.display_offset,
+                        animations.scroll_offset(),
+                        cell[1],
+                    );
+                    if let Some((shape, color)) = self.cursor_trail {
+                        if let Some(trail) = cursor_trail(
+                            shape,
+                            color,
+                            rich_text.position,
+                            animations.cursor(),
+                            cell,
+                        ) {
+                            objects.push(Object::Rect(trail));
+                        }
+                    }
+                }
+            }
+            objects.push(object);
+        }
+
+        self.draw_visual_bell(&mut objects, (window_size.width, window_size.height));
+
+        if let Some(fps_counter) = self.fps_counter.as_mut() {
+            fps_counter.frame(Instant::now());
+            fps::draw_fps_counter(
+                &mut objects,
+                &self.named_colors,
+                (window_size.width, window_size.height, scale_factor),
+                fps_counter,
+                sugarloaf.last_gpu_time(),
+            );
         }
 
         sugarloaf.set_objects(objects);
     }
 }
+
+/// Vertical offset of the lines while the scroll animates from the
+/// previous display offset toward `display_offset`.
+#[inline]
+fn scroll_shift(display_offset: i32, scroll_offset: f32, cell_height: f32) -> f32 {
+    (scroll_offset - display_offset as f32) * cell_height
+}
+
+/// Rectangle of a cursor drawn at `cursor`, a position in cells that can
+/// fall between two of them, from the `origin` of the text.
+fn cursor_trail(
+    shape: CursorShape,
+    color: [f32; 4],
+    origin: [f32; 2],
+    cursor: [f32; 2],
+    cell: [f32; 2],
+) -> Option<Rect> {
+    let size = match shape {
+        CursorShape::Block => cell,
+        CursorShape::Beam => [3., cell[1]],
+        CursorShape::Underline => [cell[0], 3.],
+        CursorShape::Hidden => return None,
+    };
+    let x = origin[0] + cursor[0] * cell[0];
+    let y = origin[1] + cursor[1] * cell[1];
+    Some(Rect {
+        position: [x, y + cell[1] - size[1]],
+        color,
+        size,
+    })
+}
+
+/// Multiplies the alpha of the colors of `style` by `opacity`.
+#[inline]
+fn dim_style(style: &mut FragmentStyle, opacity: f32) {
+    style.color[3] *= opacity;
+    if let Some(background_color) = style.background_color.as_mut() {
+        background_color[3] *= opacity;
+    }
+    if let Some(decoration_color) = style.decoration_color.as_mut() {
+        decoration_color[3] *= opacity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::VoidListener;
+    use rio_backend::event::WindowId;
+    use rio_backend::selection::SelectionRange;
+    use rio_backend::sugarloaf::font::FontLibrary;
+    use rio_backend::sugarloaf::layout::RichTextLayout;
+
+    fn fragments_for_row(row: &Row<Square>) -> Vec<String> {
+        let font_library = FontLibrary::default();
+        let mut renderer = Renderer::new(&Config::default(), &font_library);
+        let renderable_content = RenderableContent::new(Cursor::default());
+
+        let mut content = Content::new(&font_library);
+        let id = content.create_state(&RichTextLayout::default());
+        content.sel(id).new_line();
+        renderer.create_line(
+            &mut content,
+            row,
+            false,
+            None,
+            Line(0),
+            &renderable_content,
+            &mut None,
+            &None,
+            true,
+        );
+
+        content.get_state(&id).unwrap().lines[0]
+            .fragments
+            .iter()
+            .map(|fragment| fragment.content.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_same_style_squares_are_coalesced() {
+        let mut row = Row::<Square>::new(10);
+        for square in row.inner.iter_mut() {
+            square.c = 'a';
+        }
+
+        assert_eq!(fragments_for_row(&row), ["aaaaaaaaaa"]);
+    }
+
+    #[test]
+    fn test_style_change_splits_fragment() {
+        let mut row = Row::<Square>::new(10);
+        for (column, square) in row.inner.iter_mut().enumerate() {
+            square.c = 'a';
+            if column >= 5 {
+                square.fg = AnsiColor::Named(NamedColor::Red);
+            }
+        }
+
+        assert_eq!(fragments_for_row(&row), ["aaaaa", "aaaaa"]);
+    }
+
+    #[test]
+    fn test_combining_characters_follow_their_base() {
+        let mut row = Row::<Square>::new(1);
+        row.inner[0].c = 'e';
+        row.inner[0].push_zerowidth('\u{301}');
+
+        let fragments = fragments_for_row(&row);
+        assert_eq!(fragments, ["e\u{301}"]);
+        assert_eq!(fragments[0].chars().count(), 2);
+    }
+
+    fn selected_fragments(
+        row: &Row<Square>,
+        selection: SelectionRange,
+    ) -> Vec<(String, FragmentStyle)> {
+        let font_library = FontLibrary::default();
+        let mut config = Config::default();
+        config.colors.selection_foreground = Some([1.0, 0.0, 0.0, 1.0]);
+        config.colors.selection_background = Some([0.0, 0.0, 1.0, 1.0]);
+        let mut renderer = Renderer::new(&config, &font_library);
+        let mut renderable_content = RenderableContent::new(Cursor::default());
+        renderable_content.selection_range = Some(selection);
+
+        let mut content = Content::new(&font_library);
+        let id = content.create_state(&RichTextLayout::default());
+        content.sel(id).new_line();
+        renderer.create_line(
+            &mut content,
+            row,
+            false,
+            None,
+            Line(0),
+            &renderable_content,
+            &mut None,
+            &None,
+            true,
+        );
+
+        content.get_state(&id).unwrap().lines[0]
+            .fragments
+            .iter()
+            .map(|fragment| (fragment.content.clone(), fragment.style))
+            .collect()
+    }
+
+    #[test]
+    fn test_selection_is_highlighted() {
+        let mut row = Row::<Square>::new(4);
+        for (square, c) in row.inner.iter_mut().zip("abcd".chars()) {
+            square.c = c;
+        }
+
+        let selection = SelectionRange::new(
+            Pos::new(Line(0), Column(1)),
+            Pos::new(Line(0), Column(2)),
+            false,
+        );
+        let fragments = selected_fragments(&row, selection);
+        let contents: Vec<&str> = fragments.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(contents, ["a", "bc", "d"]);
+        assert_eq!(fragments[1].1.color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(fragments[1].1.background_color, Some([0.0, 0.0, 1.0, 1.0]));
+        assert_ne!(fragments[0].1.background_color, Some([0.0, 0.0, 1.0, 1.0]));
+        assert_ne!(fragments[2].1.background_color, Some([0.0, 0.0, 1.0, 1.0]));
+
+        // A selection starting on the spacer of a wide char highlights it.
+        let mut row = Row::<Square>::new(4);
+        row.inner[0].c = '字';
+        row.inner[0].flags.insert(Flags::WIDE_CHAR);
+        row.inner[1].flags.insert(Flags::WIDE_CHAR_SPACER);
+        row.inner[2].c = 'a';
+        row.inner[3].c = 'b';
+        let selection = SelectionRange::new(
+            Pos::new(Line(0), Column(1)),
+            Pos::new(Line(0), Column(2)),
+            false,
+        );
+        let fragments = selected_fragments(&row, selection);
+        let contents: Vec<&str> = fragments.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(contents, ["字", "a", "b"]);
+        assert_eq!(fragments[0].1.background_color, Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(fragments[1].1.background_color, Some([0.0, 0.0, 1.0, 1.0]));
+        assert_ne!(fragments[2].1.background_color, Some([0.0, 0.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_window_opacity_follows_focus() {
+        let font_library = FontLibrary::default();
+        let mut config = Config::default();
+        config.window.opacity = 0.9;
+        config.window.unfocused_opacity = Some(0.6);
+        let mut renderer = Renderer::new(&config, &font_library);
+        assert_eq!(renderer.window_opacity(), 0.9);
+
+        renderer.set_focused(false);
+        assert_eq!(renderer.window_opacity(), 0.6);
+        let background = renderer.window_background(renderer.window_opacity());
+        assert_eq!(background.map(|color| color.a), Some(0.6f32 as f64));
+
+        renderer.set_focused(true);
+        assert_eq!(renderer.window_opacity(), 0.9);
+
+        // Opaque windows are left untouched.
+        let renderer = Renderer::new(&Config::default(), &font_library);
+        assert_eq!(renderer.window_background(0.5), None);
+    }
+
+    #[test]
+    fn test_unfocused_split_is_dimmed() {
+        let font_library = FontLibrary::default();
+        let mut config = Config::default();
+        config.navigation.unfocused_split_opacity = 0.5;
+        let mut renderer = Renderer::new(&config, &font_library);
+        let renderable_content = RenderableContent::new(Cursor::default());
+
+        let mut row = Row::<Square>::new(2);
+        row.inner[0].c = 'a';
+        row.inner[1].c = 'b';
+        row.inner[1].bg = AnsiColor::Named(NamedColor::Red);
+
+        let mut styles = |is_active: bool| -> Vec<FragmentStyle> {
+            let mut content = Content::new(&font_library);
+            let id = content.create_state(&RichTextLayout::default());
+            content.sel(id).new_line();
+            renderer.create_line(
+                &mut content,
+                &row,
+                false,
+                None,
+                Line(0),
+                &renderable_content,
+                &mut None,
+                &None,
+                is_active,
+            );
+            content.get_state(&id).unwrap().lines[0]
+                .fragments
+                .iter()
+                .map(|fragment| fragment.style)
+                .collect()
+        };
+
+        let active = styles(true);
+        let inactive = styles(false);
+        assert_eq!(active.len(), inactive.len());
+        for (active, inactive) in active.iter().zip(&inactive) {
+            assert_eq!(inactive.color[3], active.color[3] * 0.5);
+            assert_eq!(inactive.color[..3], active.color[..3]);
+        }
+        let background = |style: &FragmentStyle| style.background_color.unwrap()[3];
+        assert_eq!(background(&inactive[1]), background(&active[1]) * 0.5);
+
+        // Opacity of 1 leaves unfocused splits untouched.
+        let mut renderer = Renderer::new(&Config::default(), &font_library);
+        let mut content = Content::new(&font_library);
+        let id = content.create_state(&RichTextLayout::default());
+        content.sel(id).new_line();
+        renderer.create_line(
+            &mut content,
+            &row,
+            false,
+            None,
+            Line(0),
+            &renderable_content,
+            &mut None,
+            &None,
+            false,
+        );
+        let style = content.get_state(&id).unwrap().lines[0].fragments[0].style;
+        assert_eq!(style.color, active[0].color);
+    }
+
+    #[test]
+    fn test_background_alpha_applies_to_cell_backgrounds() {
+        let font_library = FontLibrary::default();
+        let mut renderer = Renderer::new(&Config::default(), &font_library);
+        let renderable_content = RenderableContent::new(Cursor::default());
+
+        let mut row = Row::<Square>::new(2);
+        row.inner[0].c = 'a';
+        row.inner[1].c = 'b';
+        row.inner[1].bg = AnsiColor::Named(NamedColor::Red);
+
+        let styles = |renderer: &mut Renderer| -> Vec<FragmentStyle> {
+            let mut content = Content::new(&font_library);
+            let id = content.create_state(&RichTextLayout::default());
+            content.sel(id).new_line();
+            renderer.create_line(
+                &mut content,
+                &row,
+                false,
+                None,
+                Line(0),
+                &renderable_content,
+                &mut None,
+                &None,
+                true,
+            );
+            content.get_state(&id).unwrap().lines[0]
+                .fragments
+                .iter()
+                .map(|fragment| fragment.style)
+                .collect()
+        };
+
+        let opaque = styles(&mut renderer);
+        renderer.set_background_alpha(0.5);
+        assert_eq!(renderer.background_alpha(), 0.5);
+        assert!(renderer.has_pending_full_render);
+        let translucent = styles(&mut renderer);
+
+        let background = |style: &FragmentStyle| style.background_color.unwrap()[3];
+        assert_eq!(background(&translucent[1]), background(&opaque[1]) * 0.5);
+        // Only the backgrounds change, not the text.
+        assert_eq!(translucent[1].color, opaque[1].color);
+    }
+
+    #[test]
+    fn test_cursor_and_blinking_text_share_phase() {
+        let font_library = FontLibrary::default();
+        let mut renderer = Renderer::new(&Config::default(), &font_library);
+
+        let mut slow = Square {
+            c: 'a',
+            ..Square::default()
+        };
+        slow.flags.insert(Flags::BLINK_SLOW);
+        let mut fast = slow.clone();
+        fast.flags = Flags::BLINK_FAST;
+
+        for phase in 0..8 {
+            renderer.blink_phase = BlinkPhase(phase);
+            let (_, content) = renderer.create_style(&slow);
+            assert_eq!(content == 'a', renderer.is_cursor_blink_visible());
+            let (_, content) = renderer.create_style(&fast);
+            assert_eq!(content == 'a', phase % 2 == 0);
+        }
+        assert!(renderer.has_blinking_text);
+
+        // Blinking text keeps the fast cadence scheduled.
+        assert!(
+            renderer.next_blink(false).unwrap() <= renderer.config_blinking_interval / 2
+        );
+        renderer.has_blinking_text = false;
+        assert_eq!(renderer.next_blink(false), None);
+        assert!(renderer.next_blink(true).unwrap() <= renderer.config_blinking_interval);
+    }
+
+    #[test]
+    fn test_unfocused_render_skips_content() {
+        let font_library = FontLibrary::default();
+        let mut config = Config::default();
+        config.renderer.disable_unfocused_render = true;
+        let mut renderer = Renderer::new(&config, &font_library);
+
+        let mut context_manager =
+            ContextManager::start_with_capacity(1, VoidListener {}, WindowId::from(0))
+                .unwrap();
+        let mut content = Content::new(&font_library);
+        let id = content.create_state(&RichTextLayout::default());
+        let rows = context_manager
+            .current()
+            .terminal
+            .lock()
+            .visible_rows()
+            .len();
+
+        renderer.set_focused(false);
+        assert!(!renderer.prepare_content(
+            &mut content,
+            &mut Animations::default(),
+            &mut context_manager,
+            &mut None,
+            &None
+        ));
+        assert!(content.get_state(&id).unwrap().lines.is_empty());
+
+        renderer.set_focused(true);
+        assert!(renderer.prepare_content(
+            &mut content,
+            &mut Animations::default(),
+            &mut context_manager,
+            &mut None,
+            &None
+        ));
+        let queued = content
+            .get_state(&id)
+            .unwrap()
+            .lines
+            .iter()
+            .filter(|line| !line.fragments.is_empty())
+            .count();
+        assert_eq!(queued, rows);
+    }
+
+    #[test]
+    fn test_cursor_trail_follows_cursor_moves() {
+        use rio_backend::performer::handler::Handler;
+
+        let font_library = FontLibrary::default();
+        let mut renderer = Renderer::new(&Config::default(), &font_library);
+        let mut animations = Animations::new(true, Duration::from_millis(100));
+        let mut context_manager =
+            ContextManager::start_with_capacity(1, VoidListener {}, WindowId::from(0))
+                .unwrap();
+        let mut content = Content::new(&font_library);
+        content.create_state(&RichTextLayout::default());
+        let mut prepare =
+            |renderer: &mut Renderer,
+             animations: &mut Animations,
+             context_manager: &mut ContextManager<VoidListener>| {
+                assert!(renderer.prepare_content(
+                    &mut content,
+                    animations,
+                    context_manager,
+                    &mut None,
+                    &None,
+                ));
+            };
+
+        // The first frame places the cursor without animating it.
+        prepare(&mut renderer, &mut animations, &mut context_manager);
+        assert_eq!(animations.cursor(), [0., 0.]);
+        assert!(renderer.cursor_trail.is_none());
+
+        context_manager.current().terminal.lock().input('a');
+        prepare(&mut renderer, &mut animations, &mut context_manager);
+        assert!(animations.is_cursor_animating());
+        assert_eq!(
+            renderer.cursor_trail,
+            Some((CursorShape::Block, renderer.named_colors.cursor))
+        );
+
+        animations.tick(Duration::from_millis(50));
+        assert_eq!(animations.cursor(), [0.5, 0.]);
+
+        animations.tick(Duration::from_millis(50));
+        prepare(&mut renderer, &mut animations, &mut context_manager);
+        assert_eq!(animations.cursor(), [1., 0.]);
+        assert!(renderer.cursor_trail.is_none());
+    }
+
+    #[test]
+    fn test_cursor_trail_rect() {
+        let color = [1., 1., 1., 1.];
+        let trail = |shape| {
+            cursor_trail(shape, color, [10., 20.], [2.5, 1.], [8., 16.])
+                .map(|rect| (rect.position, rect.size))
+        };
+
+        assert_eq!(trail(CursorShape::Block), Some(([30., 36.], [8., 16.])));
+        assert_eq!(trail(CursorShape::Beam), Some(([30., 36.], [3., 16.])));
+        assert_eq!(trail(CursorShape::Underline), Some(([30., 49.], [8., 3.])));
+        assert_eq!(trail(CursorShape::Hidden), None);
+    }
+
+    #[test]
+    fn test_scroll_shift() {
+        // Scrolled three lines up, the lines start three lines higher and
+        // move down as the animation reaches the new offset.
+        assert_eq!(scroll_shift(3, 0., 16.), -48.);
+        assert_eq!(scroll_shift(3, 1.5, 16.), -24.);
+        assert_eq!(scroll_shift(3, 3., 16.), 0.);
+        assert_eq!(scroll_shift(0, 3., 16.), 48.);
+    }
+}
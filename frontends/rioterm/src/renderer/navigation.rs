@@ -1,8 +1,25 @@
 use crate::constants::*;
 use rio_backend::config::colors::Colors;
 use rio_backend::config::navigation::{Navigation, NavigationMode};
+use rio_backend::config::title::{Title, TitleVariables};
+use rio_backend::sugarloaf::font::FontLibrary;
 use rio_backend::sugarloaf::{Object, Rect, Text};
 use std::collections::HashMap;
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+const TAB_TITLE_FONT_SIZE: f32 = 14.;
+const TAB_WIDTH: f32 = 130.;
+const TAB_TEXT_PADDING_X: f32 = 4.;
+
+/// Label and colors of a tab in the tab bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabIndicator {
+    pub label: String,
+    pub foreground: [f32; 4],
+    pub background: [f32; 4],
+    pub is_current: bool,
+}
 
 pub struct ScreenNavigation {
     pub navigation: Navigation,
@@ -15,25 +32,36 @@ pub struct ScreenNavigation {
     scale: f32,
     pub padding_y: [f32; 2],
     color_automation: HashMap<String, HashMap<String, [f32; 4]>>,
+    title: Title,
+    // Advance of a character of the tab titles, measured from the font
+    // text objects are drawn with.
+    title_char_width: f32,
 }
 
 impl ScreenNavigation {
     pub fn new(
         navigation: Navigation,
         color_automation: HashMap<String, HashMap<String, [f32; 4]>>,
+        title: Title,
         padding_y: [f32; 2],
+        font_library: &FontLibrary,
     ) -> ScreenNavigation {
+        let title_char_width =
+            font_library.inner.lock().ui_char_width(TAB_TITLE_FONT_SIZE);
+
         ScreenNavigation {
             navigation,
             objects: Vec::with_capacity(26),
             keys: String::from(""),
             color_automation,
+            title,
             current: 0,
             len: 0,
             padding_y,
             width: 0.0,
             height: 0.0,
             scale: 0.0,
+            title_char_width,
         }
     }
 
@@ -184,26 +212,17 @@ impl ScreenNavigation {
             return;
         }
 
-        let mut initial_position_x = 0.;
-
         let renderable = Rect {
-            position: [initial_position_x, position_y],
+            position: [0., position_y],
             color: colors.bar,
             size: [self.width * 2., PADDING_Y_BOTTOM_TABS],
         };
 
         self.objects.push(Object::Rect(renderable));
 
-        let iter = 0..len;
-        let mut tabs = Vec::from_iter(iter);
+        let tabs = visible_tabs(self.current, len, self.width / self.scale);
 
-        let max_tab_width = 140.;
-        let screen_limit = ((self.width / self.scale) / max_tab_width).floor() as usize;
-        if len > screen_limit && self.current > screen_limit {
-            tabs = Vec::from_iter(self.current - screen_limit..len);
-        }
-
-        let text_pos_mod = 11.;
+        let mut indicators = Vec::with_capacity(tabs.len());
         for i in tabs {
             let mut background_color = colors.bar;
             let mut foreground_color = colors.tabs_foreground;
@@ -216,7 +235,15 @@ impl ScreenNavigation {
 
             let mut name = String::from("tab");
             if let Some(title) = titles.get(&i) {
-                if title[1].is_empty() {
+                let variables = TitleVariables {
+                    index: i + 1,
+                    command: &title[0],
+                    title: &title[1],
+                    cwd: &title[2],
+                };
+                if let Some(composed) = self.title.compose(&variables) {
+                    name = composed;
+                } else if title[1].is_empty() {
                     name = title[0].to_string();
                 } else {
                     name = format!("{} ({})", title[0], title[1]);
@@ -230,48 +257,119 @@ impl ScreenNavigation {
                 }
             }
 
-            let name_modifier = 90.;
-            if name.len() >= 14 {
-                name = name[0..14].to_string();
-            }
+            // The indicator is kept and only the name is truncated.
+            let indicator = if is_current {
+                String::from("▲ ")
+            } else {
+                format!("{}.", i + 1)
+            };
+            let name_width = TAB_WIDTH
+                - TAB_TEXT_PADDING_X * 2.
+                - text_width(&indicator, self.title_char_width);
+            let name = truncate_to_width(&name, name_width, self.title_char_width);
+
+            indicators.push(TabIndicator {
+                label: format!("{indicator}{name}"),
+                foreground: foreground_color,
+                background: background_color,
+                is_current,
+            });
+        }
 
-            self.objects.push(Object::Rect(Rect {
-                position: [initial_position_x, position_y],
-                color: background_color,
-                size: [250., PADDING_Y_BOTTOM_TABS],
-            }));
+        self.objects.extend(tab_indicators_objects(
+            &indicators,
+            position_y,
+            colors.tabs_active_highlight,
+        ));
+    }
+}
 
-            if is_current {
-                // TopBar case should render on bottom
-                let position = if position_y == 0.0 {
-                    PADDING_Y_BOTTOM_TABS - (PADDING_Y_BOTTOM_TABS / 10.)
-                } else {
-                    position_y
-                };
+/// Range of the tabs fitting in a bar `width` wide, it always contains
+/// `current` and at least one tab.
+fn visible_tabs(current: usize, len: usize, width: f32) -> Range<usize> {
+    let screen_limit = ((width / TAB_WIDTH).floor() as usize).max(1);
+    if len <= screen_limit {
+        return 0..len;
+    }
 
-                self.objects.push(Object::Rect(Rect {
-                    position: [initial_position_x, position],
-                    color: colors.tabs_active_highlight,
-                    size: [250., PADDING_Y_BOTTOM_TABS / 10.],
-                }));
-            }
+    let start = (current + 1)
+        .saturating_sub(screen_limit)
+        .min(len - screen_limit);
+    start..start + screen_limit
+}
 
-            let text = if is_current {
-                format!("▲ {}", name)
+/// Renders `indicators` side by side, `TAB_WIDTH` apart.
+fn tab_indicators_objects(
+    indicators: &[TabIndicator],
+    position_y: f32,
+    highlight_color: [f32; 4],
+) -> Vec<Object> {
+    let text_pos_mod = 11.;
+    let mut objects = Vec::with_capacity(indicators.len() * 3);
+    let mut initial_position_x = 0.;
+    for indicator in indicators {
+        objects.push(Object::Rect(Rect {
+            position: [initial_position_x, position_y],
+            color: indicator.background,
+            size: [250., PADDING_Y_BOTTOM_TABS],
+        }));
+
+        if indicator.is_current {
+            // TopBar case should render on bottom
+            let position = if position_y == 0.0 {
+                PADDING_Y_BOTTOM_TABS - (PADDING_Y_BOTTOM_TABS / 10.)
             } else {
-                format!("{}.{}", i + 1, name)
+                position_y
             };
 
-            self.objects.push(Object::Text(Text::single_line(
-                (initial_position_x + 4., position_y + text_pos_mod),
-                text,
-                14.,
-                foreground_color,
-            )));
+            objects.push(Object::Rect(Rect {
+                position: [initial_position_x, position],
+                color: highlight_color,
+                size: [250., PADDING_Y_BOTTOM_TABS / 10.],
+            }));
+        }
+
+        objects.push(Object::Text(Text::single_line(
+            (
+                initial_position_x + TAB_TEXT_PADDING_X,
+                position_y + text_pos_mod,
+            ),
+            indicator.label.to_owned(),
+            TAB_TITLE_FONT_SIZE,
+            indicator.foreground,
+        )));
+
+        initial_position_x += TAB_WIDTH;
+    }
 
-            initial_position_x += name_modifier + 40.;
+    objects
+}
+
+#[inline]
+fn text_width(text: &str, char_width: f32) -> f32 {
+    text.chars()
+        .map(|c| c.width().unwrap_or(0) as f32 * char_width)
+        .sum()
+}
+
+/// Truncates `text` to fit in `max_width`, ending with `…` when it does not.
+fn truncate_to_width(text: &str, max_width: f32, char_width: f32) -> String {
+    if text_width(text, char_width) <= max_width {
+        return text.to_string();
+    }
+
+    let max_width = max_width - char_width;
+    let mut width = 0.;
+    let mut truncated = String::new();
+    for c in text.chars() {
+        width += c.width().unwrap_or(0) as f32 * char_width;
+        if width > max_width {
+            break;
         }
+        truncated.push(c);
     }
+    truncated.push('…');
+    truncated
 }
 
 #[inline]
@@ -290,7 +388,105 @@ fn get_color_overwrite<'a>(
 mod tests {
     use std::collections::HashMap;
 
-    use crate::renderer::navigation::get_color_overwrite;
+    use crate::renderer::navigation::{
+        get_color_overwrite, tab_indicators_objects, text_width, truncate_to_width,
+        visible_tabs, TabIndicator, TAB_TITLE_FONT_SIZE, TAB_WIDTH,
+    };
+    use rio_backend::sugarloaf::font::FontLibrary;
+    use rio_backend::sugarloaf::Object;
+
+    fn title_char_width() -> f32 {
+        FontLibrary::default()
+            .inner
+            .lock()
+            .ui_char_width(TAB_TITLE_FONT_SIZE)
+    }
+
+    fn labels(objects: &[Object]) -> Vec<&str> {
+        objects
+            .iter()
+            .filter_map(|object| match object {
+                Object::Text(text) => Some(text.content.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tab_indicators() {
+        let indicators: Vec<TabIndicator> = ["▲ vim", "2.zsh", "3.docker"]
+            .iter()
+            .enumerate()
+            .map(|(i, label)| TabIndicator {
+                label: label.to_string(),
+                foreground: [1.0; 4],
+                background: [0.0; 4],
+                is_current: i == 0,
+            })
+            .collect();
+
+        let objects = tab_indicators_objects(&indicators, 0., [0.5; 4]);
+        assert_eq!(labels(&objects), ["▲ vim", "2.zsh", "3.docker"]);
+
+        assert!(tab_indicators_objects(&[], 0., [0.5; 4]).is_empty());
+    }
+
+    #[test]
+    fn test_visible_tabs_keep_current() {
+        assert_eq!(visible_tabs(1, 3, 1000.), 0..3);
+
+        // Only 7 tabs fit, the current one is kept at the right.
+        let width = 1000.;
+        assert_eq!(visible_tabs(0, 10, width), 0..7);
+        assert_eq!(visible_tabs(6, 10, width), 0..7);
+        assert_eq!(visible_tabs(8, 10, width), 2..9);
+        assert_eq!(visible_tabs(9, 10, width), 3..10);
+        for current in 0..10 {
+            let tabs = visible_tabs(current, 10, width);
+            assert!(tabs.contains(&current));
+            assert!(tabs.len() as f32 * TAB_WIDTH <= width);
+        }
+
+        // Narrower than a single tab.
+        assert_eq!(visible_tabs(4, 10, TAB_WIDTH / 2.), 4..5);
+    }
+
+    #[test]
+    fn test_truncate_long_title() {
+        let max_width = 120.;
+        let char_width = title_char_width();
+        let title = truncate_to_width(
+            "a very long command running in this tab",
+            max_width,
+            char_width,
+        );
+
+        assert!(title.ends_with('…'));
+        assert!(title.starts_with("a very long"));
+        assert!(text_width(&title, char_width) <= max_width);
+
+        let wide = truncate_to_width("漢字漢字漢字漢字漢字", 50., 5.);
+        assert_eq!(wide, "漢字漢字…");
+    }
+
+    #[test]
+    fn test_truncate_short_title() {
+        assert_eq!(truncate_to_width("zsh", 100., title_char_width()), "zsh");
+        assert_eq!(truncate_to_width("12345", 25., 5.), "12345");
+    }
+
+    #[test]
+    fn test_title_char_width_follows_font_size() {
+        let font_library = FontLibrary::default();
+        let font_library = font_library.inner.lock();
+        let char_width = font_library.ui_char_width(TAB_TITLE_FONT_SIZE);
+        assert!(char_width > 0.);
+        assert!(char_width < TAB_TITLE_FONT_SIZE);
+        assert_eq!(
+            font_library.ui_char_width(TAB_TITLE_FONT_SIZE * 2.),
+            char_width * 2.
+        );
+    }
 
     #[test]
     fn test_get_color_overwrite() {
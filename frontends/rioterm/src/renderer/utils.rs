@@ -1,8 +1,6 @@
 use crate::constants;
 use crate::context::grid::ContextDimension;
 use rio_backend::config::navigation::{Navigation, NavigationMode};
-use rio_backend::config::Config;
-use rio_window::window::Theme;
 
 #[inline]
 pub fn padding_top_from_config(
@@ -74,22 +72,3 @@ pub fn terminal_dimensions(layout: &ContextDimension) -> teletypewriter::Winsize
         rows: layout.lines as u16,
     }
 }
-
-pub fn update_colors_based_on_theme(config: &mut Config, theme_opt: Option<Theme>) {
-    if let Some(theme) = theme_opt {
-        if let Some(adaptive_colors) = &config.adaptive_colors {
-            match theme {
-                Theme::Light => {
-                    if let Some(light_colors) = adaptive_colors.light {
-                        config.colors = light_colors;
-                    }
-                }
-                Theme::Dark => {
-                    if let Some(darkcolors) = adaptive_colors.dark {
-                        config.colors = darkcolors;
-                    }
-                }
-            }
-        }
-    }
-}
@@ -7,6 +7,7 @@ use crate::screen::{Screen, ScreenWindowProperties};
 use assistant::Assistant;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use rio_backend::clipboard::Clipboard;
+use rio_backend::config::renderer::Performance;
 use rio_backend::config::Config as RioConfig;
 use rio_backend::error::{RioError, RioErrorLevel, RioErrorType};
 use rio_backend::event::{EventPayload, RioEvent, RioEventType};
@@ -252,6 +253,7 @@ impl Router<'_> {
             shell: rio_backend::config::Shell {
                 program: editor.program,
                 args,
+                environment: None,
             },
             ..current_config
         };
@@ -285,6 +287,7 @@ impl Router<'_> {
             shell: rio_backend::config::Shell {
                 program: editor.program,
                 args,
+                environment: None,
             },
             ..current_config
         };
@@ -377,6 +380,7 @@ pub struct RouteWindow<'a> {
     pub is_focused: bool,
     pub is_occluded: bool,
     has_fps_target: bool,
+    performance: Performance,
     pub render_timestamp: Instant,
     pub vblank_interval: Duration,
     pub winit_window: Window,
@@ -385,6 +389,32 @@ pub struct RouteWindow<'a> {
     pub is_macos_deadzone: bool,
 }
 
+/// Frames per second presented at most with `Performance::Low`.
+const LOW_PERFORMANCE_FPS: u64 = 30;
+
+/// Interval between two presented frames, follows the monitor refresh
+/// rate (60Hz if unknown), capped with `Performance::Low`, unless a target
+/// fps is set. A target of `0` removes the cap.
+fn frame_interval(
+    target_fps: Option<u64>,
+    refresh_rate_millihertz: Option<u32>,
+    performance: Performance,
+) -> Duration {
+    match target_fps {
+        Some(0) => Duration::ZERO,
+        Some(target_fps) => Duration::from_millis(1000 / target_fps.min(1000)),
+        None => {
+            let refresh_rate = refresh_rate_millihertz.unwrap_or(60_000).max(1);
+            let interval = Duration::from_micros(1_000_000_000 / refresh_rate as u64);
+            if performance == Performance::Low {
+                interval.max(Duration::from_millis(1000 / LOW_PERFORMANCE_FPS))
+            } else {
+                interval
+            }
+        }
+    }
+}
+
 impl<'a> RouteWindow<'a> {
     pub fn configure_window(&mut self, config: &rio_backend::config::Config) {
         configure_window(&self.winit_window, config);
@@ -395,6 +425,11 @@ impl<'a> RouteWindow<'a> {
     }
 
     pub fn wait_until(&self) -> Option<Duration> {
+        // Frames are not capped, render right away.
+        if self.vblank_interval.is_zero() {
+            return None;
+        }
+
         let elapsed_time = Instant::now()
             .duration_since(self.render_timestamp)
             .as_millis() as u64;
@@ -409,19 +444,42 @@ impl<'a> RouteWindow<'a> {
 
     pub fn update_vblank_interval(&mut self) {
         if !self.has_fps_target {
-            // Get the display vblank interval.
-            let monitor_vblank_interval = 1_000_000.
-                / self
-                    .winit_window
+            self.vblank_interval = frame_interval(
+                None,
+                self.winit_window
                     .current_monitor()
-                    .and_then(|monitor| monitor.refresh_rate_millihertz())
-                    .unwrap_or(60_000) as f64;
+                    .and_then(|monitor| monitor.refresh_rate_millihertz()),
+                self.performance,
+            );
+        }
+    }
 
-            // Now convert it to micro seconds.
-            let monitor_vblank_interval =
-                Duration::from_micros((1000. * monitor_vblank_interval) as u64);
+    /// Advances the animations of the screen, returns `true` while they
+    /// need more frames.
+    pub fn animate(&mut self) -> bool {
+        let is_animating = self.screen.tick_animations();
 
-            self.vblank_interval = monitor_vblank_interval;
+        // The background of macOS windows is drawn by the window itself.
+        #[cfg(target_os = "macos")]
+        {
+            use rio_window::platform::macos::WindowExtMacOS;
+            if self.screen.has_opacity_changed() {
+                let opacity = self.screen.window_opacity();
+                if let Some(color) = self.screen.renderer.window_background(opacity) {
+                    self.winit_window
+                        .set_background_color(color.r, color.g, color.b, color.a);
+                }
+            }
+        }
+
+        is_animating
+    }
+
+    /// Applies the performance resolved for `renderer.performance`.
+    pub fn set_performance(&mut self, performance: Performance) {
+        if self.performance != performance {
+            self.performance = performance;
+            self.update_vblank_interval();
         }
     }
 
@@ -477,27 +535,22 @@ impl<'a> RouteWindow<'a> {
             winit_window.set_cloaked(false);
         }
 
-        // Get the display vblank interval.
-        let monitor_vblank_interval = 1_000_000.
-            / winit_window
+        let performance = config
+            .renderer
+            .performance
+            .resolve(crate::platform::power_source());
+        let vblank_interval = frame_interval(
+            config.renderer.target_fps,
+            winit_window
                 .current_monitor()
-                .and_then(|monitor| monitor.refresh_rate_millihertz())
-                .unwrap_or(60_000) as f64;
-
-        // Now convert it to micro seconds.
-        let mut monitor_vblank_interval =
-            Duration::from_micros((1000. * monitor_vblank_interval) as u64);
-        let mut has_fps_target = false;
-
-        if let Some(target_fps) = config.renderer.target_fps {
-            monitor_vblank_interval =
-                Duration::from_millis(1000 / target_fps.clamp(1, 1000));
-            has_fps_target = true;
-        }
+                .and_then(|monitor| monitor.refresh_rate_millihertz()),
+            performance,
+        );
 
         Self {
-            vblank_interval: monitor_vblank_interval,
-            has_fps_target,
+            vblank_interval,
+            has_fps_target: config.renderer.target_fps.is_some(),
+            performance,
             render_timestamp: Instant::now(),
             is_focused: true,
             is_occluded: false,
@@ -508,3 +561,45 @@ impl<'a> RouteWindow<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_interval() {
+        let high = Performance::High;
+        assert_eq!(
+            frame_interval(None, None, high),
+            Duration::from_micros(16_666)
+        );
+        assert_eq!(
+            frame_interval(None, Some(240_000), high),
+            Duration::from_micros(4_166)
+        );
+        assert_eq!(
+            frame_interval(Some(30), Some(240_000), high),
+            Duration::from_millis(33)
+        );
+        assert_eq!(frame_interval(Some(0), Some(240_000), high), Duration::ZERO);
+        assert_eq!(
+            frame_interval(Some(5000), None, high),
+            Duration::from_millis(1)
+        );
+
+        // Low performance caps the refresh rate, but not a target fps.
+        let low = Performance::Low;
+        assert_eq!(
+            frame_interval(None, Some(240_000), low),
+            Duration::from_millis(33)
+        );
+        assert_eq!(
+            frame_interval(None, Some(20_000), low),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            frame_interval(Some(60), Some(240_000), low),
+            Duration::from_millis(16)
+        );
+    }
+}
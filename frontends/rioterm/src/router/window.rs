@@ -36,11 +36,20 @@ pub fn create_window_builder(
         })
         .with_resizable(true)
         .with_decorations(true)
-        .with_transparent(config.window.opacity < 1.)
+        .with_transparent(config.window.is_transparent())
         .with_blur(config.window.blur)
         .with_window_icon(Some(icon));
 
-    match config.window.decorations {
+    let decorations = config.window.decorations.supported();
+    if decorations != config.window.decorations {
+        tracing::warn!(
+            "window decorations {:?} are not supported, using {:?}",
+            config.window.decorations,
+            decorations
+        );
+    }
+
+    match decorations {
         Decorations::Disabled => {
             window_builder = window_builder.with_decorations(false);
         }
@@ -118,6 +127,11 @@ pub fn create_window_builder(
         }
     };
 
+    if let Some([x, y]) = config.window.position {
+        window_builder =
+            window_builder.with_position(rio_window::dpi::LogicalPosition { x, y });
+    }
+
     window_builder
 }
 
@@ -152,7 +166,7 @@ pub fn configure_window(winit_window: &Window, config: &Config) {
         }
     }
 
-    let is_transparent = config.window.opacity < 1.;
+    let is_transparent = config.window.is_transparent();
     winit_window.set_transparent(is_transparent);
 
     #[cfg(target_os = "macos")]
@@ -163,7 +177,7 @@ pub fn configure_window(winit_window: &Window, config: &Config) {
             bg_color.r,
             bg_color.g,
             bg_color.b,
-            config.window.opacity as f64,
+            config.window.effective_opacity(true) as f64,
         );
         winit_window.set_has_shadow(!is_transparent);
     }
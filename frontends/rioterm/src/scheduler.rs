@@ -27,6 +27,7 @@ pub enum Topic {
     RenderRoute,
     UpdateConfig,
     CursorBlinking,
+    VisualBell,
 }
 
 /// Event scheduled to be emitted at a specific time.
@@ -2,13 +2,14 @@
 // Copyright 2022-present Raphael Amorim
 //
 // The functions (including comments) and logic of process_key_event, build_key_sequence, process_mouse_bindings, copy_selection, start_selection, update_selection_scrolling,
-// side_by_pos, on_left_click, paste, sgr_mouse_report, mouse_report, normal_mouse_report, scroll,
+// side_by_pos, on_left_click, paste, mouse_report, scroll,
 // were retired from https://github.com/alacritty/alacritty/blob/c39c3c97f1a1213418c3629cc59a1d46e34070e0/alacritty/src/input.rs
 // which is licensed under Apache 2.0 license.
 
 pub mod hint;
 pub mod touch;
 
+use crate::ansi::CursorStyle;
 use crate::bindings::{
     Action as Act, BindingKey, BindingMode, FontSizeAction, MouseBinding, SearchAction,
     ViAction,
@@ -44,8 +45,8 @@ use rio_backend::crosswords::pos::{Boundary, CursorState, Direction, Line};
 use rio_backend::crosswords::search::RegexSearch;
 use rio_backend::event::{ClickState, EventProxy, SearchState};
 use rio_backend::sugarloaf::{
-    layout::RootStyle, Sugarloaf, SugarloafErrors, SugarloafRenderer, SugarloafWindow,
-    SugarloafWindowSize,
+    layout::RootStyle, Animations, Sugarloaf, SugarloafErrors, SugarloafRenderer,
+    SugarloafWindow, SugarloafWindowSize,
 };
 use rio_window::event::ElementState;
 use rio_window::event::Modifiers;
@@ -60,6 +61,7 @@ use std::cmp::{max, min};
 use std::error::Error;
 use std::ffi::OsStr;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use touch::TouchPurpose;
 
 /// Minimum number of pixels at the bottom/top where selection scrolling is performed.
@@ -85,6 +87,10 @@ pub struct Screen<'screen> {
     pub sugarloaf: Sugarloaf<'screen>,
     pub context_manager: context::ContextManager<EventProxy>,
     pub clipboard: Rc<RefCell<Clipboard>>,
+    // Time the animations were last advanced to.
+    animation_tick: Instant,
+    // Window opacity of the background set on Sugarloaf.
+    rendered_opacity: f32,
 }
 
 pub struct ScreenWindowProperties {
@@ -134,9 +140,15 @@ impl Screen<'_> {
             },
         };
 
-        let power_preference = match config.renderer.performance {
-            RendererPerformance::High => wgpu::PowerPreference::HighPerformance,
+        let performance = config
+            .renderer
+            .performance
+            .resolve(crate::platform::power_source());
+        let power_preference = match performance {
             RendererPerformance::Low => wgpu::PowerPreference::LowPower,
+            RendererPerformance::High | RendererPerformance::Auto => {
+                wgpu::PowerPreference::HighPerformance
+            }
         };
 
         let backend = match config.renderer.backend {
@@ -174,6 +186,14 @@ impl Screen<'_> {
         };
 
         sugarloaf.update_filters(config.renderer.filters.as_slice());
+        // Applied before animations are enabled, so the first frame does
+        // not fade in.
+        let opacity = config.window.effective_opacity(true);
+        sugarloaf.animations_mut().set_opacity_target(opacity);
+        sugarloaf.set_animations(
+            config.animations,
+            Duration::from_millis(config.animation_duration),
+        );
 
         let renderer = Renderer::new(config, font_library);
 
@@ -187,26 +207,42 @@ impl Screen<'_> {
         let is_collapsed = config.navigation.is_collapsed_mode();
         let is_native = config.navigation.is_native();
 
+        // The first shell has no tab to inherit a working directory from.
+        let working_dir = if config.inherits_working_dir() {
+            None
+        } else {
+            config.working_dir.to_owned()
+        };
         let (shell, working_dir) = process_open_url(
             config.shell.to_owned(),
-            config.working_dir.to_owned(),
+            working_dir,
             config.editor.to_owned(),
             open_url.as_deref(),
         );
 
         let context_manager_config = context::ContextManagerConfig {
-            use_current_path: config.navigation.use_current_path,
+            use_current_path: config.navigation.use_current_path
+                || config.inherits_working_dir(),
             shell,
             working_dir,
             spawn_performer: true,
             #[cfg(not(target_os = "windows"))]
             use_fork: config.use_fork,
+            #[cfg(not(target_os = "windows"))]
+            utf8: config.utf8,
             is_native,
             // When navigation is collapsed and does not contain any color rule
             // does not make sense fetch for foreground process names
             should_update_titles: !(is_collapsed
-                && config.navigation.color_automation.is_empty()),
+                && config.navigation.color_automation.is_empty()
+                && config.title.template.is_none()),
             split_color: config.colors.split,
+            title: config.title.clone(),
+            scroll_on_output: config.scroll.on_output,
+            scroll_on_keystroke: config.scroll.on_keystroke,
+            colors: config.colors,
+            profiles: config.profiles.clone(),
+            record_session: config.developer.record_session.clone(),
         };
 
         let rich_text_id = sugarloaf.create_rich_text();
@@ -231,7 +267,7 @@ impl Screen<'_> {
             is_ime_enabled: false,
         };
 
-        let context_manager = context::ContextManager::start(
+        let mut context_manager = context::ContextManager::start(
             // config.cursor.blinking
             (&cursor, config.cursor.blinking),
             event_proxy,
@@ -243,6 +279,7 @@ impl Screen<'_> {
             margin,
             sugarloaf_errors,
         )?;
+        apply_profile_font_size(&mut sugarloaf, &mut context_manager, rich_text_id);
 
         if cfg!(target_os = "macos") {
             sugarloaf.set_background_color(None);
@@ -266,6 +303,8 @@ impl Screen<'_> {
             renderer,
             bindings,
             clipboard,
+            animation_tick: Instant::now(),
+            rendered_opacity: opacity,
         })
     }
 
@@ -371,7 +410,29 @@ impl Screen<'_> {
 
         self.sugarloaf
             .update_filters(config.renderer.filters.as_slice());
+        self.sugarloaf.set_animations(
+            config.animations,
+            Duration::from_millis(config.animation_duration),
+        );
+        let is_focused = self.renderer.is_focused();
         self.renderer = Renderer::new(config, font_library);
+        self.renderer.set_focused(is_focused);
+        // The background below is set with the focused opacity.
+        self.rendered_opacity = config.window.effective_opacity(true);
+        self.update_window_opacity();
+        self.context_manager.config.title = config.title.clone();
+        self.context_manager.config.scroll_on_output = config.scroll.on_output;
+        self.context_manager.config.scroll_on_keystroke = config.scroll.on_keystroke;
+        self.context_manager.config.colors = config.colors;
+        self.context_manager.config.profiles = config.profiles.clone();
+        self.context_manager.config.record_session =
+            config.developer.record_session.clone();
+        #[cfg(not(target_os = "windows"))]
+        let utf8_changed = self.context_manager.config.utf8 != config.utf8;
+        #[cfg(not(target_os = "windows"))]
+        {
+            self.context_manager.config.utf8 = config.utf8;
+        }
 
         for context_grid in self.context_manager.contexts_mut() {
             context_grid.update_margin((
@@ -387,11 +448,22 @@ impl Screen<'_> {
                 let mut terminal = current_context.terminal.lock();
                 current_context.renderable_content =
                     RenderableContent::from_cursor_config(&config.cursor);
-                let shape = config.cursor.shape;
-                terminal.cursor_shape = shape;
-                terminal.default_cursor_shape = shape;
-                terminal.blinking_cursor = config.cursor.blinking;
+                terminal.set_default_cursor_style(CursorStyle {
+                    shape: config.cursor.shape,
+                    blinking: config.cursor.blinking,
+                });
+                terminal.scroll_on_output = config.scroll.on_output;
+                terminal.scroll_on_keystroke = config.scroll.on_keystroke;
                 drop(terminal);
+
+                #[cfg(not(target_os = "windows"))]
+                if utf8_changed {
+                    if let Err(err) =
+                        teletypewriter::set_utf8(*current_context.main_fd, config.utf8)
+                    {
+                        tracing::warn!("failed to update utf8 mode: {err}");
+                    }
+                }
             }
         }
 
@@ -459,8 +531,8 @@ impl Screen<'_> {
         new_scale: f32,
         new_size: rio_window::dpi::PhysicalSize<u32>,
     ) -> &mut Self {
-        self.sugarloaf.rescale(new_scale);
-        self.sugarloaf.resize(new_size.width, new_size.height);
+        self.sugarloaf
+            .reconfigure(new_size.width, new_size.height, new_scale);
         self.render();
         self.resize_all_contexts();
         self.context_manager
@@ -476,7 +548,7 @@ impl Screen<'_> {
     #[inline]
     pub fn resize_all_contexts(&mut self) {
         // whenever a resize update happens: it will stored in
-        // the next layout, so once the messenger.send_resize triggers
+        // the next layout, so once the resize event reaches the pty
         // the wakeup from pty it will also trigger a sugarloaf.render()
         // and then eventually a render with the new layout computation.
         for context_grid in self.context_manager.contexts_mut() {
@@ -484,20 +556,17 @@ impl Screen<'_> {
                 let ctx = context.context_mut();
                 let mut terminal = ctx.terminal.lock();
                 terminal.resize::<ContextDimension>(ctx.dimension);
-                drop(terminal);
-                let winsize = crate::renderer::utils::terminal_dimensions(&ctx.dimension);
-                let _ = ctx.messenger.send_resize(winsize);
             }
         }
     }
 
     #[inline]
     pub fn scroll_bottom_when_cursor_not_visible(&mut self) {
-        let mut terminal = self.ctx_mut().current_mut().terminal.lock();
-        if terminal.display_offset() != 0 {
-            terminal.scroll_display(Scroll::Bottom);
-        }
-        drop(terminal);
+        self.ctx_mut()
+            .current_mut()
+            .terminal
+            .lock()
+            .scroll_to_bottom_on_keystroke();
     }
 
     #[inline]
@@ -612,12 +681,10 @@ impl Screen<'_> {
                 return;
             }
 
-            let mut bytes = Vec::with_capacity(text.len() + 1);
-            if self.alt_send_esc(key, text) && text.len() == 1 {
-                bytes.push(b'\x1b');
-            }
-            bytes.extend_from_slice(text.as_bytes());
-            bytes
+            crate::bindings::kitty_keyboard::build_legacy_sequence(
+                text,
+                self.alt_send_esc(key, text),
+            )
         } else {
             // We use legacy input when we have associated text with
             // the given key and we have one of the following situations:
@@ -636,13 +703,10 @@ impl Screen<'_> {
 
             // Handle legacy char writing.
             if write_legacy {
-                let mut bytes = Vec::with_capacity(text.len() + 1);
-                if self.alt_send_esc(key, text) && text.len() == 1 {
-                    bytes.push(b'\x1b');
-                }
-
-                bytes.extend_from_slice(text.as_bytes());
-                bytes
+                crate::bindings::kitty_keyboard::build_legacy_sequence(
+                    text,
+                    self.alt_send_esc(key, text),
+                )
             } else {
                 // Otherwise we should build the key sequence for the given input.
                 crate::bindings::kitty_keyboard::build_key_sequence(key, mods, mode)
@@ -956,10 +1020,7 @@ impl Screen<'_> {
                         let mut terminal =
                             self.context_manager.current_mut().terminal.lock();
                         terminal.scroll_display(Scroll::Top);
-
-                        let topmost_line = terminal.grid.topmost_line();
-                        terminal.vi_mode_cursor.pos.row = topmost_line;
-                        terminal.vi_motion(ViMotion::FirstOccupied);
+                        terminal.vi_motion(ViMotion::Top);
                         drop(terminal);
                         self.render();
                     }
@@ -967,13 +1028,7 @@ impl Screen<'_> {
                         let mut terminal =
                             self.context_manager.current_mut().terminal.lock();
                         terminal.scroll_display(Scroll::Bottom);
-
-                        // Move vi mode cursor.
-                        terminal.vi_mode_cursor.pos.row = terminal.grid.bottommost_line();
-
-                        // Move to beginning twice, to always jump across linewraps.
-                        terminal.vi_motion(ViMotion::FirstOccupied);
-                        terminal.vi_motion(ViMotion::FirstOccupied);
+                        terminal.vi_motion(ViMotion::Bottom);
                         drop(terminal);
                         self.render();
                     }
@@ -987,7 +1042,7 @@ impl Screen<'_> {
                     Act::ClearHistory => {
                         let mut terminal =
                             self.context_manager.current_mut().terminal.lock();
-                        terminal.clear_saved_history();
+                        terminal.clear_history();
                         drop(terminal);
                         self.render();
                     }
@@ -1059,6 +1114,11 @@ impl Screen<'_> {
         let rich_text_id = self.sugarloaf.create_rich_text();
         self.context_manager
             .split_from_config(rich_text_id, false, config);
+        apply_profile_font_size(
+            &mut self.sugarloaf,
+            &mut self.context_manager,
+            rich_text_id,
+        );
 
         self.render();
     }
@@ -1066,6 +1126,11 @@ impl Screen<'_> {
     pub fn split_right(&mut self) {
         let rich_text_id = self.sugarloaf.create_rich_text();
         self.context_manager.split(rich_text_id, false);
+        apply_profile_font_size(
+            &mut self.sugarloaf,
+            &mut self.context_manager,
+            rich_text_id,
+        );
 
         self.render();
     }
@@ -1073,6 +1138,11 @@ impl Screen<'_> {
     pub fn split_down(&mut self) {
         let rich_text_id = self.sugarloaf.create_rich_text();
         self.context_manager.split(rich_text_id, true);
+        apply_profile_font_size(
+            &mut self.sugarloaf,
+            &mut self.context_manager,
+            rich_text_id,
+        );
 
         self.render();
     }
@@ -1082,6 +1152,11 @@ impl Screen<'_> {
 
         let rich_text_id = self.sugarloaf.create_rich_text();
         self.context_manager.add_context(redirect, rich_text_id);
+        apply_profile_font_size(
+            &mut self.sugarloaf,
+            &mut self.context_manager,
+            rich_text_id,
+        );
 
         let num_tabs = self.ctx().len();
         self.cancel_search();
@@ -1811,19 +1886,6 @@ impl Screen<'_> {
         }
     }
 
-    fn sgr_mouse_report(&mut self, pos: Pos, button: u8, state: ElementState) {
-        let c = match state {
-            ElementState::Pressed => 'M',
-            ElementState::Released => 'm',
-        };
-
-        let msg = format!("\x1b[<{};{};{}{}", button, pos.col + 1, pos.row + 1, c);
-        self.ctx_mut()
-            .current_mut()
-            .messenger
-            .send_bytes(msg.into_bytes());
-    }
-
     #[inline]
     pub fn has_mouse_motion_and_drag(&mut self) -> bool {
         self.get_mode()
@@ -1838,85 +1900,33 @@ impl Screen<'_> {
     #[inline]
     pub fn mouse_report(&mut self, button: u8, state: ElementState) {
         let terminal = self.ctx().current().terminal.lock();
-        let display_offset = terminal.display_offset();
-        let mode = terminal.mode();
+        let pos = self.mouse_position(terminal.display_offset());
+        let report = terminal.mouse_report(
+            button,
+            pos,
+            state == ElementState::Pressed,
+            self.modifiers.state(),
+        );
         drop(terminal);
 
-        let pos = self.mouse_position(display_offset);
-
-        // Assure the mouse pos is not in the scrollback.
-        if pos.row < 0 {
-            return;
-        }
-
-        // Calculate modifiers value.
-        let mut mods = 0;
-        let mod_state = self.modifiers.state();
-        if mod_state.shift_key() {
-            mods += 4;
-        }
-        if mod_state.alt_key() {
-            mods += 8;
-        }
-        if mod_state.control_key() {
-            mods += 16;
-        }
-
-        // Report mouse events.
-        if mode.contains(Mode::SGR_MOUSE) {
-            self.sgr_mouse_report(pos, button + mods, state);
-        } else if let ElementState::Released = state {
-            self.normal_mouse_report(pos, 3 + mods);
-        } else {
-            self.normal_mouse_report(pos, button + mods);
+        if let Some(msg) = report {
+            self.ctx_mut().current_mut().messenger.send_bytes(msg);
         }
     }
 
-    #[inline]
-    fn normal_mouse_report(&mut self, position: Pos, button: u8) {
-        let Pos { row, col } = position;
-        let utf8 = self.get_mode().contains(Mode::UTF8_MOUSE);
-
-        let max_point = if utf8 { 2015 } else { 223 };
-
-        if row >= max_point || col >= max_point {
-            return;
-        }
-
-        let mut msg = vec![b'\x1b', b'[', b'M', 32 + button];
-
-        let mouse_pos_encode = |pos: usize| -> Vec<u8> {
-            let pos = 32 + 1 + pos;
-            let first = 0xC0 + pos / 64;
-            let second = 0x80 + (pos & 63);
-            vec![first as u8, second as u8]
-        };
-
-        if utf8 && col >= Column(95) {
-            msg.append(&mut mouse_pos_encode(col.0));
-        } else {
-            msg.push(32 + 1 + col.0 as u8);
-        }
-
-        if utf8 && row >= 95 {
-            msg.append(&mut mouse_pos_encode(row.0 as usize));
-        } else {
-            msg.push(32 + 1 + row.0 as u8);
-        }
-
-        self.ctx_mut().current_mut().messenger.send_bytes(msg);
-    }
-
     #[inline]
     pub fn on_focus_change(&mut self, is_focused: bool) {
-        if self.get_mode().contains(Mode::FOCUS_IN_OUT) {
-            let chr = if is_focused { "I" } else { "O" };
+        self.renderer.set_focused(is_focused);
+        self.update_window_opacity();
 
-            let msg = format!("\x1b[{}", chr);
-            self.ctx_mut()
-                .current_mut()
-                .messenger
-                .send_bytes(msg.into_bytes());
+        let report = self
+            .ctx()
+            .current()
+            .terminal
+            .lock()
+            .focus_report(is_focused);
+        if let Some(msg) = report {
+            self.ctx_mut().current_mut().messenger.send_bytes(msg);
         }
     }
 
@@ -2078,7 +2088,70 @@ impl Screen<'_> {
         self.sugarloaf.render();
     }
 
+    /// Starts moving the window opacity toward the value for the focus.
+    fn update_window_opacity(&mut self) {
+        if !self.sugarloaf.animations().is_animating() {
+            self.animation_tick = Instant::now();
+        }
+        self.sugarloaf
+            .animations_mut()
+            .set_opacity_target(self.renderer.window_opacity());
+    }
+
+    /// Advances the animations up to now, returns `true` while they need
+    /// more frames.
+    pub fn tick_animations(&mut self) -> bool {
+        let now = Instant::now();
+        let is_animating = self
+            .sugarloaf
+            .tick(now.saturating_duration_since(self.animation_tick));
+        self.animation_tick = now;
+        is_animating
+    }
+
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.sugarloaf.animations().is_animating()
+    }
+
+    /// Window opacity for the current frame.
+    #[inline]
+    pub fn window_opacity(&self) -> f32 {
+        self.sugarloaf.animations().opacity()
+    }
+
+    /// Whether the window opacity changed since the last frame.
+    #[inline]
+    pub fn has_opacity_changed(&self) -> bool {
+        self.window_opacity() != self.rendered_opacity
+    }
+
+    /// Whether a frame is needed to show or start the opacity change.
+    #[inline]
+    pub fn needs_opacity_frame(&self) -> bool {
+        needs_opacity_frame(self.sugarloaf.animations(), self.rendered_opacity)
+    }
+
     pub fn render(&mut self) {
+        // Windows are still rendered while unfocused until they reach the
+        // unfocused opacity.
+        let has_opacity_changed = self.has_opacity_changed();
+        if !self.renderer.should_render() && !has_opacity_changed {
+            return;
+        }
+
+        if has_opacity_changed {
+            let opacity = self.window_opacity();
+            self.rendered_opacity = opacity;
+            if let Some(background) = self.renderer.window_background(opacity) {
+                if !cfg!(target_os = "macos") {
+                    self.sugarloaf.set_background_color(Some(background));
+                }
+            }
+        }
+        self.sugarloaf
+            .set_background_alpha(self.renderer.background_alpha());
+
         // let start_total = std::time::Instant::now();
         // println!("_____________________________\nrender time elapsed");
         let is_search_active = self.search_active();
@@ -2112,19 +2185,80 @@ impl Screen<'_> {
         // In this case the configuration of blinking cursor is enabled
         // and the terminal also have instructions of blinking enabled
         // TODO: enable blinking for selection after adding debounce (https://github.com/raphamorim/rio/issues/437)
-        if self.renderer.config_has_blinking_enabled
+        let is_cursor_blinking = self.renderer.config_has_blinking_enabled
             && self.selection_is_empty()
             && self
                 .context_manager
                 .current()
                 .renderable_content
-                .has_blinking_enabled
-        {
-            self.context_manager
-                .blink_cursor(self.renderer.config_blinking_interval);
+                .has_blinking_enabled;
+        if let Some(delay) = self.renderer.next_blink(is_cursor_blinking) {
+            self.context_manager.blink_cursor(delay);
         }
 
         // let duration = start_total.elapsed();
         // println!("Total whole render function is: {:?}\n", duration);
     }
 }
+
+/// Applies the font size of the profile matched by the context created for
+/// `rich_text_id`, if it is the current one.
+fn apply_profile_font_size(
+    sugarloaf: &mut Sugarloaf,
+    context_manager: &mut ContextManager<EventProxy>,
+    rich_text_id: usize,
+) {
+    let context = context_manager.current();
+    if context.rich_text_id != rich_text_id {
+        return;
+    }
+    let Some(font_size) = context.font_size else {
+        return;
+    };
+
+    sugarloaf.set_rich_text_font_size(&context.rich_text_id, font_size);
+    context_manager
+        .current_grid_mut()
+        .update_dimensions(sugarloaf);
+    let context = context_manager.current();
+    context
+        .terminal
+        .lock()
+        .resize::<ContextDimension>(context.dimension);
+}
+
+/// A fade toward a new target did not move the opacity yet, it still needs
+/// a frame to start before the next `tick` covers the whole idle time.
+#[inline]
+fn needs_opacity_frame(animations: &Animations, rendered_opacity: f32) -> bool {
+    animations.is_animating() || animations.opacity() != rendered_opacity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_focus_loss_requests_frame() {
+        let mut animations = Animations::new(true, Duration::from_millis(100));
+        assert!(!needs_opacity_frame(&animations, 1.));
+
+        // Losing focus only sets the target, the opacity did not move yet.
+        animations.set_opacity_target(0.5);
+        assert_eq!(animations.opacity(), 1.);
+        assert!(needs_opacity_frame(&animations, 1.));
+
+        animations.tick(Duration::from_millis(100));
+        assert!(needs_opacity_frame(&animations, 1.));
+        assert!(!needs_opacity_frame(&animations, 0.5));
+    }
+
+    #[test]
+    fn test_focus_loss_without_animations_requests_frame() {
+        let mut animations = Animations::new(false, Duration::from_millis(100));
+        animations.set_opacity_target(0.5);
+        assert!(needs_opacity_frame(&animations, 1.));
+        assert!(!needs_opacity_frame(&animations, 0.5));
+    }
+}
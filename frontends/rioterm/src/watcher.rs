@@ -1,9 +1,11 @@
 use crate::event::{EventListener, RioEvent};
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rio_backend::config::renderer::{Performance, PowerSource};
 use std::path::Path;
 use std::time::Duration;
 
 const POLLING_TIMEOUT: Duration = Duration::from_secs(2);
+const POWER_POLLING_INTERVAL: Duration = Duration::from_secs(10);
 
 pub fn configuration_file_updates<
     P: AsRef<Path> + std::marker::Send + 'static,
@@ -56,3 +58,80 @@ pub fn configuration_file_updates<
 
     Ok(())
 }
+
+/// Resolves `renderer.performance = "auto"` from a power source query.
+pub struct PerformanceMonitor<F> {
+    power_source: F,
+    performance: Performance,
+}
+
+impl<F: FnMut() -> Option<PowerSource>> PerformanceMonitor<F> {
+    pub fn new(mut power_source: F) -> Self {
+        let performance = Performance::Auto.resolve(power_source());
+        Self {
+            power_source,
+            performance,
+        }
+    }
+
+    #[inline]
+    pub fn performance(&self) -> Performance {
+        self.performance
+    }
+
+    /// Queries the power source again, returns the new performance when it
+    /// has changed.
+    pub fn poll(&mut self) -> Option<Performance> {
+        let performance = Performance::Auto.resolve((self.power_source)());
+        if performance == self.performance {
+            return None;
+        }
+
+        self.performance = performance;
+        Some(performance)
+    }
+}
+
+/// Watches the power source for `renderer.performance = "auto"`, returns
+/// the current performance and sends `PerformanceChanged` on changes.
+pub fn power_source_updates<T: EventListener + std::marker::Send + 'static>(
+    event_proxy: T,
+) -> Performance {
+    let mut monitor = PerformanceMonitor::new(crate::platform::power_source);
+    let performance = monitor.performance();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POWER_POLLING_INTERVAL);
+        if let Some(performance) = monitor.poll() {
+            tracing::info!("power source changed, performance is now {performance}");
+            event_proxy.send_event(
+                RioEvent::PerformanceChanged(performance),
+                rio_backend::event::WindowId::from(0),
+            );
+        }
+    });
+
+    performance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_performance_monitor() {
+        let power_source = Cell::new(Some(PowerSource::Ac));
+        let mut monitor = PerformanceMonitor::new(|| power_source.get());
+        assert_eq!(monitor.performance(), Performance::High);
+        assert_eq!(monitor.poll(), None);
+
+        power_source.set(Some(PowerSource::Battery));
+        assert_eq!(monitor.poll(), Some(Performance::Low));
+        assert_eq!(monitor.performance(), Performance::Low);
+        assert_eq!(monitor.poll(), None);
+
+        // Unknown power sources behave as AC.
+        power_source.set(None);
+        assert_eq!(monitor.poll(), Some(Performance::High));
+    }
+}
@@ -35,6 +35,13 @@ impl CursorShape {
     }
 }
 
+/// Shape of the cursor and whether it blinks.
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
 impl From<CursorShape> for char {
     fn from(value: CursorShape) -> Self {
         match value {
@@ -0,0 +1,50 @@
+use crate::config::colors::{deserialize_to_arr, ColorArray};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Serialize, Deserialize, Copy, Debug, PartialEq)]
+pub enum BellMode {
+    // Flashes the window with `bell.color`
+    #[serde(alias = "visual")]
+    Visual,
+    // Plays the system alert sound
+    #[serde(alias = "audible")]
+    Audible,
+    #[default]
+    #[serde(alias = "none")]
+    None,
+}
+
+#[inline]
+fn default_bell_color() -> ColorArray {
+    [1.0, 1.0, 1.0, 0.25]
+}
+
+#[inline]
+fn default_bell_duration() -> u64 {
+    150
+}
+
+#[derive(Clone, Serialize, Deserialize, Copy, Debug, PartialEq)]
+pub struct Bell {
+    #[serde(default)]
+    pub mode: BellMode,
+    // Color of the visual bell flash
+    #[serde(
+        default = "default_bell_color",
+        deserialize_with = "deserialize_to_arr"
+    )]
+    pub color: ColorArray,
+    // Duration of the visual bell flash in milliseconds
+    #[serde(default = "default_bell_duration")]
+    pub duration: u64,
+}
+
+impl Default for Bell {
+    fn default() -> Bell {
+        Bell {
+            mode: BellMode::default(),
+            color: default_bell_color(),
+            duration: default_bell_duration(),
+        }
+    }
+}
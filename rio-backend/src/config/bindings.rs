@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 // { key = "w", mods: "super", action = "quit" }
 // Bytes[27, 91, 53, 126] is equivalent to "\x1b[5~"
 // { key = "Home", mods: "super | shift", bytes = [27, 91, 53, 126] }
+// { key = "Home", mods: "super | shift", bytes = "\u001b[5~" }
+// { key = "e", mods: "super", action = "spawn", program = "code", args = ["."] }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct KeyBinding {
@@ -14,10 +16,33 @@ pub struct KeyBinding {
     pub action: String,
     #[serde(default = "String::default")]
     pub text: String,
-    #[serde(default = "Vec::default")]
+    #[serde(default = "Vec::default", deserialize_with = "deserialize_bytes")]
     pub bytes: Vec<u8>,
     #[serde(default = "String::default")]
     pub mode: String,
+    /// Program started by the `spawn` action.
+    #[serde(default = "String::default")]
+    pub program: String,
+    #[serde(default = "Vec::default")]
+    pub args: Vec<String>,
+}
+
+/// Accepts `bytes` as a list of bytes or as a string.
+fn deserialize_bytes<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BytesOrString {
+        Bytes(Vec<u8>),
+        String(String),
+    }
+
+    match BytesOrString::deserialize(deserializer)? {
+        BytesOrString::Bytes(bytes) => Ok(bytes),
+        BytesOrString::String(string) => Ok(string.into_bytes()),
+    }
 }
 
 pub type KeyBindings = Vec<KeyBinding>;
@@ -104,6 +129,46 @@ mod tests {
         assert_eq!(std::str::from_utf8(&binding).unwrap(), "\x1bOH".to_string());
     }
 
+    #[test]
+    fn test_bytes_from_string() {
+        let content = r#"
+            [bindings]
+            keys = [
+                { key = 'Up', with = 'super', bytes = "\u001b[A" },
+            ]
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.bindings.keys[0].bytes, b"\x1b[A");
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        let content = r#"
+            [bindings]
+            keys = [
+                { key = 'Up', bytes = [27, 256] },
+            ]
+        "#;
+
+        assert!(toml::from_str::<Root>(content).is_err());
+    }
+
+    #[test]
+    fn test_spawn_action() {
+        let content = r#"
+            [bindings]
+            keys = [
+                { key = 'e', with = 'super', action = 'Spawn', program = 'code', args = ['--new-window', '.'] },
+            ]
+        "#;
+
+        let decoded = toml::from_str::<Root>(content).unwrap();
+        assert_eq!(decoded.bindings.keys[0].action, "Spawn");
+        assert_eq!(decoded.bindings.keys[0].program, "code");
+        assert_eq!(decoded.bindings.keys[0].args, ["--new-window", "."]);
+    }
+
     #[test]
     fn test_multi_key_actions() {
         let content = r#"
@@ -265,38 +265,3 @@ pub fn light_yellow() -> ColorArray {
         .unwrap()
         .to_arr()
 }
-
-#[inline]
-pub fn selection_foreground() -> ColorArray {
-    ColorBuilder::from_hex(String::from("#44C9F0"), Format::SRGB0_1)
-        .unwrap()
-        .to_arr()
-}
-
-#[inline]
-pub fn selection_background() -> ColorArray {
-    ColorBuilder::from_hex(String::from("#1C191A"), Format::SRGB0_1)
-        .unwrap()
-        .to_arr()
-}
-
-#[inline]
-pub fn search_match_background() -> ColorArray {
-    ColorBuilder::from_hex(String::from("#44C9F0"), Format::SRGB0_1)
-        .unwrap()
-        .to_arr()
-}
-#[inline]
-pub fn search_match_foreground() -> ColorArray {
-    [1., 1., 1., 1.]
-}
-#[inline]
-pub fn search_focused_match_background() -> ColorArray {
-    ColorBuilder::from_hex(String::from("#E6A003"), Format::SRGB0_1)
-        .unwrap()
-        .to_arr()
-}
-#[inline]
-pub fn search_focused_match_foreground() -> ColorArray {
-    [1., 1., 1., 1.]
-}
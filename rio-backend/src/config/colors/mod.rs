@@ -6,6 +6,14 @@ use regex::Regex;
 use serde::Serialize;
 use serde::{de, Deserialize};
 use std::num::ParseIntError;
+use std::sync::LazyLock;
+
+static NON_HEX_CHARS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)[^#a-f\d]").unwrap());
+
+// match valid 6 or 8 hex characters
+static VALID_HEX_SIZE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^#?[a-f\d]{6}([a-f\d]{2})?$").unwrap());
 
 pub type ColorWGPU = wgpu::Color;
 pub type ColorArray = [f32; 4];
@@ -53,10 +61,11 @@ pub enum AnsiColor {
     Indexed(u8),
 }
 
-#[derive(Debug, Copy, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Copy, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Colors {
     #[serde(
         deserialize_with = "deserialize_to_composition",
+        serialize_with = "serialize_composition",
         default = "defaults::background"
     )]
     /// Background is a special color type called ColorComposition
@@ -65,207 +74,303 @@ pub struct Colors {
     pub background: ColorComposition,
     #[serde(
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         default = "defaults::foreground"
     )]
     pub foreground: ColorArray,
-    #[serde(deserialize_with = "deserialize_to_arr", default = "defaults::blue")]
+    #[serde(
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
+        default = "defaults::blue"
+    )]
     pub blue: ColorArray,
-    #[serde(deserialize_with = "deserialize_to_arr", default = "defaults::green")]
+    #[serde(
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
+        default = "defaults::green"
+    )]
     pub green: ColorArray,
-    #[serde(deserialize_with = "deserialize_to_arr", default = "defaults::red")]
+    #[serde(
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
+        default = "defaults::red"
+    )]
     pub red: ColorArray,
-    #[serde(deserialize_with = "deserialize_to_arr", default = "defaults::yellow")]
+    #[serde(
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
+        default = "defaults::yellow"
+    )]
     pub yellow: ColorArray,
     #[serde(
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         default = "defaults::tabs_active",
         rename = "tabs-active"
     )]
     pub tabs_active: ColorArray,
     #[serde(
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         default = "defaults::tabs_active_foreground",
         rename = "tabs-active-foreground"
     )]
     pub tabs_active_foreground: ColorArray,
     #[serde(
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         default = "defaults::tabs_foreground",
         rename = "tabs-foreground"
     )]
     pub tabs_foreground: ColorArray,
-    #[serde(default = "defaults::cursor", deserialize_with = "deserialize_to_arr")]
+    #[serde(
+        default = "defaults::cursor",
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
     pub cursor: ColorArray,
     #[serde(
         default = "defaults::vi_cursor",
         rename = "vi-cursor",
-        deserialize_with = "deserialize_to_arr"
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
     )]
     pub vi_cursor: ColorArray,
-    #[serde(default = "defaults::black", deserialize_with = "deserialize_to_arr")]
+    #[serde(
+        default = "defaults::black",
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
     pub black: ColorArray,
-    #[serde(default = "defaults::cyan", deserialize_with = "deserialize_to_arr")]
+    #[serde(
+        default = "defaults::cyan",
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
     pub cyan: ColorArray,
-    #[serde(default = "defaults::magenta", deserialize_with = "deserialize_to_arr")]
+    #[serde(
+        default = "defaults::magenta",
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
     pub magenta: ColorArray,
-    #[serde(default = "defaults::tabs", deserialize_with = "deserialize_to_arr")]
+    #[serde(
+        default = "defaults::tabs",
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
     pub tabs: ColorArray,
-    #[serde(default = "defaults::bar", deserialize_with = "deserialize_to_arr")]
+    #[serde(
+        default = "defaults::bar",
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
     pub bar: ColorArray,
     #[serde(
         default = "defaults::tabs_active_highlight",
         rename = "tabs-active-highlight",
-        deserialize_with = "deserialize_to_arr"
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
     )]
     pub tabs_active_highlight: ColorArray,
-    #[serde(default = "defaults::white", deserialize_with = "deserialize_to_arr")]
+    #[serde(
+        default = "defaults::white",
+        deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
     pub white: ColorArray,
     #[serde(
         default = "defaults::dim_black",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-black"
     )]
     pub dim_black: ColorArray,
     #[serde(
         default = "defaults::dim_blue",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-blue"
     )]
     pub dim_blue: ColorArray,
     #[serde(
         default = "defaults::dim_cyan",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-cyan"
     )]
     pub dim_cyan: ColorArray,
     #[serde(
         default = "defaults::dim_foreground",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-foreground"
     )]
     pub dim_foreground: ColorArray,
     #[serde(
         default = "defaults::dim_green",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-green"
     )]
     pub dim_green: ColorArray,
     #[serde(
         default = "defaults::dim_magenta",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-magenta"
     )]
     pub dim_magenta: ColorArray,
     #[serde(
         default = "defaults::dim_red",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-red"
     )]
     pub dim_red: ColorArray,
     #[serde(
         default = "defaults::dim_white",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-white"
     )]
     pub dim_white: ColorArray,
     #[serde(
         default = "defaults::dim_yellow",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "dim-yellow"
     )]
     pub dim_yellow: ColorArray,
     #[serde(
         default = "defaults::light_black",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-black"
     )]
     pub light_black: ColorArray,
     #[serde(
         default = "defaults::light_blue",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-blue"
     )]
     pub light_blue: ColorArray,
     #[serde(
         default = "defaults::light_cyan",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-cyan"
     )]
     pub light_cyan: ColorArray,
     #[serde(
         default = "defaults::light_foreground",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-foreground"
     )]
     pub light_foreground: ColorArray,
     #[serde(
         default = "defaults::light_green",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-green"
     )]
     pub light_green: ColorArray,
     #[serde(
         default = "defaults::light_magenta",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-magenta"
     )]
     pub light_magenta: ColorArray,
     #[serde(
         default = "defaults::light_red",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-red"
     )]
     pub light_red: ColorArray,
     #[serde(
         default = "defaults::light_white",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-white"
     )]
     pub light_white: ColorArray,
     #[serde(
         default = "defaults::light_yellow",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr",
         rename = "light-yellow"
     )]
     pub light_yellow: ColorArray,
+    /// Derived from foreground and background when unset,
+    /// see `Colors::selection_background`.
     #[serde(
-        default = "defaults::selection_background",
-        deserialize_with = "deserialize_to_arr",
+        default = "Option::default",
+        deserialize_with = "deserialize_to_optional_arr",
+        serialize_with = "serialize_optional_arr",
+        skip_serializing_if = "Option::is_none",
         rename = "selection-background"
     )]
-    pub selection_background: ColorArray,
+    pub selection_background: Option<ColorArray>,
+    /// Derived from foreground and background when unset,
+    /// see `Colors::selection_foreground`.
     #[serde(
-        default = "defaults::selection_foreground",
-        deserialize_with = "deserialize_to_arr",
+        default = "Option::default",
+        deserialize_with = "deserialize_to_optional_arr",
+        serialize_with = "serialize_optional_arr",
+        skip_serializing_if = "Option::is_none",
         rename = "selection-foreground"
     )]
-    pub selection_foreground: ColorArray,
-    #[serde(default = "defaults::split", deserialize_with = "deserialize_to_arr")]
-    pub split: ColorArray,
+    pub selection_foreground: Option<ColorArray>,
     #[serde(
-        default = "defaults::search_match_background",
+        default = "defaults::split",
         deserialize_with = "deserialize_to_arr",
+        serialize_with = "serialize_arr"
+    )]
+    pub split: ColorArray,
+    /// Derived from the selection colors when unset,
+    /// see `Colors::search_match_background`.
+    #[serde(
+        default = "Option::default",
+        deserialize_with = "deserialize_to_optional_arr",
+        serialize_with = "serialize_optional_arr",
+        skip_serializing_if = "Option::is_none",
         rename = "search-match-background"
     )]
-    pub search_match_background: ColorArray,
+    pub search_match_background: Option<ColorArray>,
+    /// Derived from the selection colors when unset,
+    /// see `Colors::search_match_foreground`.
     #[serde(
-        default = "defaults::search_match_foreground",
-        deserialize_with = "deserialize_to_arr",
+        default = "Option::default",
+        deserialize_with = "deserialize_to_optional_arr",
+        serialize_with = "serialize_optional_arr",
+        skip_serializing_if = "Option::is_none",
         rename = "search-match-foreground"
     )]
-    pub search_match_foreground: ColorArray,
+    pub search_match_foreground: Option<ColorArray>,
+    /// Derived from the selection colors when unset,
+    /// see `Colors::search_focused_match_background`.
     #[serde(
-        default = "defaults::search_focused_match_background",
-        deserialize_with = "deserialize_to_arr",
+        default = "Option::default",
+        deserialize_with = "deserialize_to_optional_arr",
+        serialize_with = "serialize_optional_arr",
+        skip_serializing_if = "Option::is_none",
         rename = "search-focused-match-background"
     )]
-    pub search_focused_match_background: ColorArray,
+    pub search_focused_match_background: Option<ColorArray>,
+    /// Derived from the selection colors when unset,
+    /// see `Colors::search_focused_match_foreground`.
     #[serde(
-        default = "defaults::search_focused_match_foreground",
-        deserialize_with = "deserialize_to_arr",
+        default = "Option::default",
+        deserialize_with = "deserialize_to_optional_arr",
+        serialize_with = "serialize_optional_arr",
+        skip_serializing_if = "Option::is_none",
         rename = "search-focused-match-foreground"
     )]
-    pub search_focused_match_foreground: ColorArray,
+    pub search_focused_match_foreground: Option<ColorArray>,
 }
 
 impl Default for Colors {
@@ -308,16 +413,109 @@ impl Default for Colors {
             light_red: defaults::light_red(),
             light_white: defaults::light_white(),
             light_yellow: defaults::light_yellow(),
-            selection_background: defaults::selection_background(),
-            selection_foreground: defaults::selection_foreground(),
-            search_match_background: defaults::search_match_background(),
-            search_match_foreground: defaults::search_match_foreground(),
-            search_focused_match_background: defaults::search_focused_match_background(),
-            search_focused_match_foreground: defaults::search_focused_match_foreground(),
+            selection_background: None,
+            selection_foreground: None,
+            search_match_background: None,
+            search_match_foreground: None,
+            search_focused_match_background: None,
+            search_focused_match_foreground: None,
+        }
+    }
+}
+
+impl Colors {
+    #[inline]
+    pub fn selection_foreground(&self) -> ColorArray {
+        self.selection_foreground
+            .unwrap_or_else(|| derive_selection(self.foreground, self.background.0).0)
+    }
+
+    #[inline]
+    pub fn selection_background(&self) -> ColorArray {
+        self.selection_background
+            .unwrap_or_else(|| derive_selection(self.foreground, self.background.0).1)
+    }
+
+    /// Matches of the search are drawn like the selection with half of
+    /// its background alpha, so the focused match stands out.
+    #[inline]
+    pub fn search_match_background(&self) -> ColorArray {
+        self.search_match_background.unwrap_or_else(|| {
+            let mut background = self.selection_background();
+            background[3] *= 0.5;
+            background
+        })
+    }
+
+    #[inline]
+    pub fn search_match_foreground(&self) -> ColorArray {
+        self.search_match_foreground
+            .unwrap_or_else(|| self.selection_foreground())
+    }
+
+    /// The focused match of the search is drawn like the selection.
+    #[inline]
+    pub fn search_focused_match_background(&self) -> ColorArray {
+        self.search_focused_match_background
+            .unwrap_or_else(|| self.selection_background())
+    }
+
+    #[inline]
+    pub fn search_focused_match_foreground(&self) -> ColorArray {
+        self.search_focused_match_foreground
+            .unwrap_or_else(|| self.selection_foreground())
+    }
+
+    /// Color of the theme for `color`.
+    pub fn resolve(&self, color: NamedColor) -> ColorArray {
+        match color {
+            NamedColor::Black => self.black,
+            NamedColor::Red => self.red,
+            NamedColor::Green => self.green,
+            NamedColor::Yellow => self.yellow,
+            NamedColor::Blue => self.blue,
+            NamedColor::Magenta => self.magenta,
+            NamedColor::Cyan => self.cyan,
+            NamedColor::White => self.white,
+            NamedColor::LightBlack => self.light_black,
+            NamedColor::LightRed => self.light_red,
+            NamedColor::LightGreen => self.light_green,
+            NamedColor::LightYellow => self.light_yellow,
+            NamedColor::LightBlue => self.light_blue,
+            NamedColor::LightMagenta => self.light_magenta,
+            NamedColor::LightCyan => self.light_cyan,
+            NamedColor::LightWhite => self.light_white,
+            NamedColor::Foreground => self.foreground,
+            NamedColor::Background => self.background.0,
+            NamedColor::Cursor => self.cursor,
+            NamedColor::DimBlack => self.dim_black,
+            NamedColor::DimRed => self.dim_red,
+            NamedColor::DimGreen => self.dim_green,
+            NamedColor::DimYellow => self.dim_yellow,
+            NamedColor::DimBlue => self.dim_blue,
+            NamedColor::DimMagenta => self.dim_magenta,
+            NamedColor::DimCyan => self.dim_cyan,
+            NamedColor::DimWhite => self.dim_white,
+            NamedColor::LightForeground => self.light_foreground,
+            NamedColor::DimForeground => self.dim_foreground,
         }
     }
 }
 
+/// Derives the selection colors `(foreground, background)` from the
+/// terminal foreground and background by inverting them, so selected
+/// text stays readable with any theme.
+#[inline]
+pub fn derive_selection(
+    foreground: ColorArray,
+    background: ColorArray,
+) -> (ColorArray, ColorArray) {
+    (
+        [background[0], background[1], background[2], 1.0],
+        foreground,
+    )
+}
+
 pub fn hex_to_color_arr(s: &str) -> ColorArray {
     ColorBuilder::from_hex(s.to_string(), Format::SRGB0_1)
         .unwrap_or_default()
@@ -330,6 +528,18 @@ pub fn hex_to_color_wgpu(s: &str) -> ColorWGPU {
         .to_wgpu()
 }
 
+/// Formats a color as `#rrggbb`, the alpha channel is only appended
+/// (`#rrggbbaa`) when the color is not opaque.
+pub fn to_hex(color: ColorArray) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let [r, g, b, a] = color.map(channel);
+    if a == u8::MAX {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
 pub enum NamedColor {
     /// Black.
@@ -479,20 +689,16 @@ impl ColorBuilder {
 
     pub fn from_hex(mut hex: String, conversion_type: Format) -> Result<Self, String> {
         let mut alpha: f64 = 1.0;
-        let non_hex_chars = Regex::new(r"(?i)[^#a-f\d]").unwrap();
-
-        // match valid 6 or 8 hex characters
-        let valid_hex_size = Regex::new(r"(?i)^#?[a-f\d]{6}([a-f\d]{2})?$").unwrap();
 
-        if non_hex_chars.is_match(&hex) {
+        if NON_HEX_CHARS.is_match(&hex) {
             return Err(String::from("Error: Character is not valid"));
         }
 
-        if !valid_hex_size.is_match(&hex) {
+        if !VALID_HEX_SIZE.is_match(&hex) {
             return Err(String::from("Error: Hex String size is not valid"));
         }
 
-        hex = hex.replace('#', "");
+        hex.retain(|c| c != '#');
 
         if hex.len() == 8 {
             let (rgb_part, alpha_part) = hex.split_at(6);
@@ -633,6 +839,45 @@ where
     }
 }
 
+pub fn deserialize_to_optional_arr<'de, D>(
+    deserializer: D,
+) -> Result<Option<ColorArray>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    deserialize_to_arr(deserializer).map(Some)
+}
+
+pub fn serialize_composition<S>(
+    color: &ColorComposition,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serialize_arr(&color.0, serializer)
+}
+
+pub fn serialize_arr<S>(color: &ColorArray, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&to_hex(*color))
+}
+
+pub fn serialize_optional_arr<S>(
+    color: &Option<ColorArray>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match color {
+        Some(color) => serialize_arr(color, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -650,6 +895,25 @@ mod tests {
         assert_eq!(invalid_character_color, "Error: Character is not valid");
     }
 
+    #[test]
+    fn test_conversion_from_hex_batch() {
+        // Regexes are compiled once, parsing a whole palette many times
+        // over should stay cheap and give the same results.
+        for i in 0..20_000u32 {
+            let [_, r, g, b] = i.wrapping_mul(2_654_435_761).to_be_bytes();
+            let hex = format!("#{r:02x}{g:02X}{b:02x}");
+            let color = ColorBuilder::from_hex(hex.clone(), Format::SRGB0_255).unwrap();
+            assert_eq!(
+                (color.red, color.green, color.blue, color.alpha),
+                (r as f64, g as f64, b as f64, 1.0),
+                "{hex}"
+            );
+
+            let invalid = format!("#{r:02x}{g:02x}z{b:02x}");
+            assert!(ColorBuilder::from_hex(invalid, Format::SRGB0_255).is_err());
+        }
+    }
+
     #[test]
     fn test_default_as_black() {
         let default_color: ColorBuilder = ColorBuilder::default();
@@ -787,4 +1051,47 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_colors_serialize_round_trip() {
+        let theme = r##"
+            background = "#0F0D0E"
+            foreground = "#f9f4da"
+            cursor = "#f38ba3"
+            selection-background = "#33467c"
+            split = "#ffffff40"
+        "##;
+        let colors: Colors = toml::from_str(theme).unwrap();
+        let serialized = toml::to_string(&colors).unwrap();
+        let table: toml::Table = toml::from_str(&serialized).unwrap();
+
+        assert_eq!(table["background"].as_str(), Some("#0f0d0e"));
+        assert_eq!(table["foreground"].as_str(), Some("#f9f4da"));
+        assert_eq!(table["cursor"].as_str(), Some("#f38ba3"));
+        assert_eq!(table["selection-background"].as_str(), Some("#33467c"));
+        assert_eq!(table["split"].as_str(), Some("#ffffff40"));
+        assert!(!table.contains_key("selection-foreground"));
+
+        let deserialized: Colors = toml::from_str(&serialized).unwrap();
+        assert_eq!(toml::to_string(&deserialized).unwrap(), serialized);
+    }
+
+    #[test]
+    fn test_resolve_named_colors() {
+        let colors = Colors {
+            red: [1.0, 0.0, 0.0, 1.0],
+            light_red: [1.0, 0.5, 0.5, 1.0],
+            dim_red: [0.5, 0.0, 0.0, 1.0],
+            ..Colors::default()
+        };
+
+        assert_eq!(colors.resolve(NamedColor::Red), colors.red);
+        assert_eq!(colors.resolve(NamedColor::LightRed), colors.light_red);
+        assert_eq!(colors.resolve(NamedColor::DimRed), colors.dim_red);
+        assert_eq!(colors.resolve(NamedColor::Red.to_light()), colors.light_red);
+        assert_eq!(colors.resolve(NamedColor::Red.to_dim()), colors.dim_red);
+        assert_eq!(colors.resolve(NamedColor::Foreground), colors.foreground);
+        assert_eq!(colors.resolve(NamedColor::Background), colors.background.0);
+        assert_eq!(colors.resolve(NamedColor::Cursor), colors.cursor);
+    }
 }
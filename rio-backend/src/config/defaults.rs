@@ -15,6 +15,11 @@ pub fn default_cursor_interval() -> u64 {
     800
 }
 
+#[inline]
+pub fn default_animation_duration() -> u64 {
+    100
+}
+
 #[inline]
 pub fn default_padding_y() -> [f32; 2] {
     [0., 0.]
@@ -27,6 +32,7 @@ pub fn default_shell() -> crate::config::Shell {
         crate::config::Shell {
             program: String::from(""),
             args: vec![String::from("--login")],
+            environment: None,
         }
     }
 
@@ -35,6 +41,7 @@ pub fn default_shell() -> crate::config::Shell {
         crate::config::Shell {
             program: String::from("powershell"),
             args: vec![],
+            environment: None,
         }
     }
 }
@@ -89,6 +96,7 @@ pub fn default_editor() -> Shell {
         Shell {
             program: String::from("vi"),
             args: vec![],
+            environment: None,
         }
     }
 
@@ -97,6 +105,7 @@ pub fn default_editor() -> Shell {
         Shell {
             program: String::from("notepad"),
             args: vec![],
+            environment: None,
         }
     }
 }
@@ -188,11 +197,20 @@ pub fn default_config_file_content() -> String {
 #
 # Example:
 # working-dir = "/Users/raphael/Documents/"
+#
+# Set it to "inherit-from-active-tab" to start new tabs and splits
+# in the working directory of the focused one.
+#
+# working-dir = "inherit-from-active-tab"
 
 # Environment variables
 #
 # Example:
 # env-vars = []
+#
+# Prefer `shell.environment`, which takes precedence when set:
+# [shell]
+# environment = { EDITOR = "vim", LESS = "-R" }
 
 # Use fork
 #
@@ -208,14 +226,53 @@ pub fn default_config_file_content() -> String {
 #
 # confirm-before-quit = false
 
+# Animations
+# Interpolates cursor movement and scrolling, and fades the window
+# opacity when the focus changes, default is `false`
+#
+# animations = true
+
+# Animation duration
+# Duration of the animations in milliseconds, default is `100`
+#
+# animation-duration = 150
+
+# Bell
+#
+# mode - Either "visual", "audible" or "none", default is "none"
+# color - Color of the visual bell flash
+# duration - Duration of the visual bell flash in milliseconds
+#
+# [bell]
+# mode = "visual"
+# color = '#ffffff40'
+# duration = 150
+
+# Title
+#
+# template - Window and tab title, accepts {index}, {cwd}, {command} and {title}
+# prefer-osc - Use titles set by programs as is instead of the template, default is true
+#
+# [title]
+# template = "{index}: {cwd} — {command}"
+# prefer-osc = true
+
+# Terminal
+#
+# term - Value of the `TERM` environment variable (POSIX-based systems only).
+# By default it is "rio" when its terminfo is installed, otherwise "xterm-256color"
+#
+# [terminal]
+# term = "xterm-256color"
+
 # Cursor
 #
 # shape - Default cursor shape is 'block'
 # Other available options are: 'underline', 'beam' or 'hidden'
 #
-# blinking - Whether the cursor blinks. The default is false
+# blinking (or blink) - Whether the cursor blinks. The default is false
 #
-# blinking-interval - Cursor update on milliseconds interval
+# blinking-interval (or blink-interval) - Cursor update on milliseconds interval
 #
 # [cursor]
 # shape = 'block'
@@ -248,6 +305,9 @@ pub fn default_config_file_content() -> String {
 #
 # • opacity - Set window opacity
 #
+# • unfocused-opacity - Set window opacity while the window is not focused,
+#   the change fades in when `animations` is enabled
+#
 # • blur - Set blur on the window background. Changing this config requires restarting Rio to take effect.
 #
 # • decorations - Set window decorations, options: "Enabled", "Disabled", "Transparent", "Buttonless"
@@ -257,6 +317,7 @@ pub fn default_config_file_content() -> String {
 # width = 600
 # height = 400
 # mode = "windowed"
+# position = [100, 100]
 # opacity = 1.0
 # blur = false
 # decorations = "enabled"
@@ -266,6 +327,7 @@ pub fn default_config_file_content() -> String {
 # • Performance: Set WGPU rendering performance
 #   - High: Adapter that has the highest performance. This is often a discrete GPU.
 #   - Low: Adapter that uses the least possible power. This is often an integrated GPU.
+#   - Auto: High on AC power and Low on battery.
 #
 # • Backend: Set WGPU rendering backend
 #   - Automatic: Leave Sugarloaf/WGPU to decide
@@ -294,6 +356,9 @@ pub fn default_config_file_content() -> String {
 
 # Keyboard
 #
+# protocol - Keyboard protocol, "kitty" or "legacy"
+#   - Takes precedence over use-kitty-keyboard-protocol
+#
 # use-kitty-keyboard-protocol - Enable Kitty Keyboard protocol
 #
 # disable-ctlseqs-alt - Disable ctlseqs with ALT keys
@@ -301,7 +366,7 @@ pub fn default_config_file_content() -> String {
 #
 # Example:
 # [keyboard]
-# use-kitty-keyboard-protocol = false
+# protocol = "kitty"
 # disable-ctlseqs-alt = false
 
 # Fonts
@@ -330,6 +395,11 @@ pub fn default_config_file_content() -> String {
 # [fonts]
 # hinting = false
 #
+# Box-drawing characters and block elements use the glyphs from the font
+# by default, to draw them from the cell size instead:
+# [fonts]
+# use-drawable-chars = true
+#
 # Example:
 # [fonts]
 # size = 18
@@ -365,10 +435,16 @@ pub fn default_config_file_content() -> String {
 #
 # Multiplier default is 3.0.
 # Divider default is 1.0.
+#
+# When scrolled back in the history, "on-output" jumps to the bottom when
+# new output is printed and "on-keystroke" when a key is sent.
+# Defaults are false and true.
 # Example:
 # [scroll]
 # multiplier = 3.0
 # divider = 1.0
+# on-output = false
+# on-keystroke = true
 
 # Navigation
 #
@@ -459,8 +535,8 @@ pub fn default_config_file_content() -> String {
 # Platform
 #
 # Rio now allows you to have different configurations per OS
-# You can write ovewrite properties like `Shell`, `Navigation`
-# and `Window`.
+# You can overwrite any property (e.g. `shell`, `window.opacity`
+# or `option-as-alt`), only the keys present are overwritten.
 #
 # Example:
 # [shell]
@@ -476,14 +552,19 @@ pub fn default_config_file_content() -> String {
 # # Linux overwrite
 # linux.shell.program = "tmux"
 # linux.shell.args = ["new-session", "-c", "/var/www"]
+#
+# # MacOS overwrite
+# macos.option-as-alt = "both"
 
 # Log level
 #
 # This property enables log level filter and file. The default level is "OFF" and the logs are not logged to a file as default.
+# `record-session` writes the raw shell output to a file, to reproduce rendering issues.
 #
 # Example:
 # [developer]
 # log-level = "OFF"
 # enable-log-file = false
+# record-session = "/tmp/rio-session.log"
 "#.to_string()
 }
@@ -2,8 +2,21 @@ use serde::{Deserialize, Serialize};
 
 use super::defaults::{default_bool_true, default_disable_ctlseqs_alt};
 
+#[derive(Clone, Serialize, Deserialize, Copy, Debug, PartialEq)]
+pub enum KeyboardProtocol {
+    // Classic encoding, key releases are never reported
+    #[serde(alias = "legacy")]
+    Legacy,
+    // Kitty keyboard protocol, CSI u sequences once requested by the application
+    #[serde(alias = "kitty")]
+    Kitty,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub struct Keyboard {
+    // Keyboard protocol, takes precedence over `use-kitty-keyboard-protocol`
+    #[serde(default = "Option::default")]
+    pub protocol: Option<KeyboardProtocol>,
     // Enable kitty keyboard protocol
     #[serde(default = "default_bool_true", rename = "use-kitty-keyboard-protocol")]
     pub use_kitty_keyboard_protocol: bool,
@@ -20,6 +33,7 @@ pub struct Keyboard {
 impl Default for Keyboard {
     fn default() -> Keyboard {
         Keyboard {
+            protocol: None,
             use_kitty_keyboard_protocol: true,
             #[cfg(target_os = "macos")]
             disable_ctlseqs_alt: true,
@@ -28,3 +42,15 @@ impl Default for Keyboard {
         }
     }
 }
+
+impl Keyboard {
+    /// Keyboard protocol in use, `protocol` when set or the one chosen by
+    /// `use-kitty-keyboard-protocol`.
+    pub fn protocol(&self) -> KeyboardProtocol {
+        match self.protocol {
+            Some(protocol) => protocol,
+            None if self.use_kitty_keyboard_protocol => KeyboardProtocol::Kitty,
+            None => KeyboardProtocol::Legacy,
+        }
+    }
+}
@@ -1,23 +1,31 @@
+pub mod bell;
 pub mod bindings;
 pub mod colors;
 pub mod defaults;
 pub mod keyboard;
 pub mod navigation;
+pub mod profile;
 pub mod renderer;
 pub mod theme;
+pub mod title;
 pub mod window;
 
 use crate::ansi::CursorShape;
+use crate::config::bell::Bell;
 use crate::config::bindings::Bindings;
 use crate::config::defaults::*;
 use crate::config::keyboard::Keyboard;
 use crate::config::navigation::Navigation;
+use crate::config::profile::Profile;
 use crate::config::renderer::Renderer;
+use crate::config::title::Title;
 use crate::config::window::Window;
 use colors::Colors;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::{default::Default, fs::File};
 use sugarloaf::font::fonts::SugarloafFonts;
 use theme::{AdaptiveColors, AdaptiveTheme, Theme};
@@ -35,28 +43,33 @@ pub struct Shell {
     pub program: String,
     #[serde(default)]
     pub args: Vec<String>,
+    /// Environment variables, used instead of `env-vars` when present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<BTreeMap<String, String>>,
 }
 
+/// Per-OS configuration overrides.
+///
+/// Each table holds regular configuration keys (e.g. `shell.program` or
+/// `option-as-alt`) that are merged field by field over the top-level
+/// configuration when running on the matching platform.
 #[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Platform {
-    pub linux: Option<PlatformConfig>,
-    pub windows: Option<PlatformConfig>,
-    pub macos: Option<PlatformConfig>,
-}
-
-/// Other platform specific configuration options can be added here.
-#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
-pub struct PlatformConfig {
-    shell: Option<Shell>,
-    navigation: Option<Navigation>,
-    window: Option<Window>,
-    renderer: Option<Renderer>,
+    pub linux: Option<toml::Table>,
+    pub windows: Option<toml::Table>,
+    pub macos: Option<toml::Table>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Scroll {
     pub multiplier: f64,
     pub divider: f64,
+    // Jump to the bottom when new output arrives while scrolled back
+    #[serde(default = "bool::default", rename = "on-output")]
+    pub on_output: bool,
+    // Jump to the bottom when a key is sent while scrolled back
+    #[serde(default = "default_bool_true", rename = "on-keystroke")]
+    pub on_keystroke: bool,
 }
 
 impl Default for Scroll {
@@ -64,6 +77,8 @@ impl Default for Scroll {
         Scroll {
             multiplier: 3.0,
             divider: 1.0,
+            on_output: false,
+            on_keystroke: true,
         }
     }
 }
@@ -76,6 +91,8 @@ pub struct Developer {
     pub log_level: String,
     #[serde(rename = "enable-log-file", default)]
     pub enable_log_file: bool,
+    #[serde(rename = "record-session", default)]
+    pub record_session: Option<String>,
 }
 
 impl Default for Developer {
@@ -84,13 +101,21 @@ impl Default for Developer {
             log_level: default_log_level(),
             enable_log_file: false,
             enable_fps_counter: false,
+            record_session: None,
         }
     }
 }
 
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Terminal {
+    // Overrides the `TERM` detected from the installed terminfo entries
+    #[serde(default, alias = "term-name")]
+    pub term: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Config {
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_cursor")]
     pub cursor: CursorConfig,
     #[serde(default = "Navigation::default")]
     pub navigation: Navigation,
@@ -102,6 +127,9 @@ pub struct Config {
     pub platform: Platform,
     #[serde(default = "default_use_fork", rename = "use-fork")]
     pub use_fork: bool,
+    /// Whether the pseudoterminal handles input as UTF-8 (`IUTF8`).
+    #[serde(default = "default_bool_true")]
+    pub utf8: bool,
     #[serde(default = "Keyboard::default")]
     pub keyboard: Keyboard,
     #[serde(default = "default_working_dir", rename = "working-dir")]
@@ -136,6 +164,8 @@ pub struct Config {
     pub adaptive_colors: Option<AdaptiveColors>,
     #[serde(default = "Developer::default")]
     pub developer: Developer,
+    #[serde(default = "Terminal::default")]
+    pub terminal: Terminal,
     #[serde(default = "Bindings::default")]
     pub bindings: bindings::Bindings,
     #[serde(
@@ -145,6 +175,14 @@ pub struct Config {
     pub ignore_selection_fg_color: bool,
     #[serde(default = "default_bool_true", rename = "confirm-before-quit")]
     pub confirm_before_quit: bool,
+    #[serde(default = "bool::default")]
+    pub animations: bool,
+    #[serde(default = "default_animation_duration", rename = "animation-duration")]
+    pub animation_duration: u64,
+    #[serde(default = "Bell::default")]
+    pub bell: Bell,
+    #[serde(default = "Title::default")]
+    pub title: Title,
     #[serde(
         default = "bool::default",
         rename = "hide-mouse-cursor-when-typing",
@@ -153,23 +191,71 @@ pub struct Config {
     pub hide_cursor_when_typing: bool,
     #[serde(default = "Renderer::default")]
     pub renderer: Renderer,
+    #[serde(default = "Vec::default")]
+    pub profiles: Vec<Profile>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CursorConfig {
     #[serde(default = "default_cursor")]
     pub shape: CursorShape,
-    #[serde(default = "bool::default")]
+    #[serde(default = "bool::default", alias = "blink")]
     pub blinking: bool,
-    #[serde(default = "default_cursor_interval", rename = "blinking-interval")]
+    #[serde(
+        default = "default_cursor_interval",
+        rename = "blinking-interval",
+        alias = "blink-interval"
+    )]
     pub blinking_interval: u64,
 }
 
+/// Accepts the `[cursor]` table and the deprecated `cursor = '_'` char.
+fn deserialize_cursor<'de, D>(deserializer: D) -> Result<CursorConfig, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum CursorOrChar {
+        Cursor(CursorConfig),
+        Char(char),
+    }
+
+    match CursorOrChar::deserialize(deserializer)? {
+        CursorOrChar::Cursor(cursor) => Ok(cursor),
+        CursorOrChar::Char(c) => {
+            warn!("`cursor = '{c}'` is deprecated, use `[cursor]` with `shape` instead");
+            Ok(CursorConfig {
+                shape: CursorShape::from_char(c),
+                ..CursorConfig::default()
+            })
+        }
+    }
+}
+
+/// Environment variable pointing at a config file to use instead of the
+/// one in `config_dir_path`.
+pub const CONFIG_FILE_ENV: &str = "RIO_CONFIG";
+
 #[cfg(not(target_os = "windows"))]
 #[inline]
 pub fn config_dir_path() -> PathBuf {
     let home_dir = dirs::home_dir().unwrap();
-    home_dir.join(".config").join("rio")
+    config_dir_path_in(&home_dir, std::env::var_os("XDG_CONFIG_HOME"))
+}
+
+/// `$XDG_CONFIG_HOME/rio`, falling back to `~/.config/rio` when it is unset
+/// or not an absolute path, as the XDG base directory spec requires.
+#[cfg(not(target_os = "windows"))]
+fn config_dir_path_in(
+    home_dir: &Path,
+    xdg_config_home: Option<std::ffi::OsString>,
+) -> PathBuf {
+    let config_home = xdg_config_home
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| home_dir.join(".config"));
+    config_home.join("rio")
 }
 
 #[cfg(target_os = "windows")]
@@ -179,9 +265,38 @@ pub fn config_dir_path() -> PathBuf {
     home_dir.join("AppData").join("Local").join("rio")
 }
 
+static CONFIG_FILE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Resolves the config file once for the whole process, from `explicit`
+/// (`--config-file` or `RIO_CONFIG`) or the config directory. Later
+/// changes of the working directory or of `RIO_CONFIG` are ignored.
+pub fn init_config_file_path(explicit: Option<std::ffi::OsString>) {
+    let _ = CONFIG_FILE_PATH.set(config_file_path_from(explicit, config_dir_path()));
+}
+
+/// The file set by `init_config_file_path`, otherwise the one set by
+/// `RIO_CONFIG` or `config.toml` in `config_dir_path`.
 #[inline]
 pub fn config_file_path() -> PathBuf {
-    config_dir_path().join("config.toml")
+    match CONFIG_FILE_PATH.get() {
+        Some(path) => path.clone(),
+        None => {
+            config_file_path_from(std::env::var_os(CONFIG_FILE_ENV), config_dir_path())
+        }
+    }
+}
+
+/// Relative explicit paths are resolved against the working directory.
+fn config_file_path_from(
+    explicit: Option<std::ffi::OsString>,
+    config_dir: PathBuf,
+) -> PathBuf {
+    match explicit {
+        Some(path) if !path.is_empty() => {
+            std::path::absolute(&path).unwrap_or_else(|_| PathBuf::from(path))
+        }
+        _ => config_dir.join("config.toml"),
+    }
 }
 
 #[inline]
@@ -201,7 +316,10 @@ pub fn create_config_file(path: Option<PathBuf>) {
     }
 
     if path.is_none() {
-        let default_dir_path = config_dir_path();
+        let default_dir_path = default_file_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(config_dir_path);
         match std::fs::create_dir_all(&default_dir_path) {
             Ok(_) => {
                 tracing::info!(
@@ -235,13 +353,52 @@ pub fn create_config_file(path: Option<PathBuf>) {
     }
 }
 
+/// Accepted range for `line-height`.
+pub const LINE_HEIGHT_RANGE: std::ops::RangeInclusive<f32> = 0.5..=4.0;
+
+/// Value of `working-dir` that starts new tabs and splits in the working
+/// directory of the focused one.
+pub const INHERIT_WORKING_DIR: &str = "inherit-from-active-tab";
+
 impl Config {
+    /// Whether `working-dir` is set to `INHERIT_WORKING_DIR`.
+    #[inline]
+    pub fn inherits_working_dir(&self) -> bool {
+        self.working_dir.as_deref() == Some(INHERIT_WORKING_DIR)
+    }
+
+    /// Whether closing a window with running programs asks for
+    /// confirmation, `window.confirm-before-quit` takes precedence over
+    /// the top level `confirm-before-quit`.
+    #[inline]
+    pub fn should_confirm_before_quit(&self) -> bool {
+        self.window
+            .confirm_before_quit
+            .unwrap_or(self.confirm_before_quit)
+    }
+
+    /// Environment variables to set for the shell, `shell.environment`
+    /// takes precedence over the `KEY=VALUE` entries of `env-vars`.
+    pub fn environment_variables(&self) -> Vec<(&str, &str)> {
+        match &self.shell.environment {
+            Some(environment) => environment
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect(),
+            None => self
+                .env_vars
+                .iter()
+                .filter_map(|env_var| env_var.split_once('='))
+                .collect(),
+        }
+    }
+
     #[cfg(test)]
     fn load_from_path(path: &PathBuf) -> Self {
         if path.exists() {
             let content = std::fs::read_to_string(path).unwrap();
             let decoded: Config =
-                toml::from_str(&content).unwrap_or_else(|_| Config::default());
+                Config::from_toml_str(&content).unwrap_or_else(|_| Config::default());
             decoded
         } else {
             Config::default()
@@ -251,7 +408,7 @@ impl Config {
     fn load_from_path_without_fallback(path: &PathBuf) -> Result<Self, String> {
         if path.exists() {
             let content = std::fs::read_to_string(path).unwrap();
-            match toml::from_str::<Config>(&content) {
+            match Config::from_toml_str(&content) {
                 Ok(mut decoded) => {
                     let theme = &decoded.theme;
                     if theme.is_empty() {
@@ -304,15 +461,133 @@ impl Config {
         }
     }
 
-    fn load_theme(path: &PathBuf) -> Result<Theme, String> {
-        if path.exists() {
-            let content = std::fs::read_to_string(path).unwrap();
-            match toml::from_str::<Theme>(&content) {
-                Ok(decoded) => Ok(decoded),
-                Err(err_message) => Err(format!("error parsing: {:?}", err_message)),
+    /// Switches `colors` to the adaptive theme matching the system
+    /// appearance. Returns whether the colors changed, so callers only
+    /// redraw when needed. Without `adaptive-theme` nothing changes.
+    pub fn apply_system_theme(
+        &mut self,
+        system_theme: Option<rio_window::window::Theme>,
+    ) -> bool {
+        let colors = system_theme.and_then(|theme| {
+            self.adaptive_colors
+                .as_ref()
+                .and_then(|adaptive_colors| adaptive_colors.for_theme(theme))
+        });
+
+        match colors {
+            Some(colors) if colors != self.colors => {
+                self.colors = colors;
+                true
             }
-        } else {
-            Err(String::from("filepath does not exist"))
+            _ => false,
+        }
+    }
+
+    fn load_theme(path: &Path) -> Result<Theme, String> {
+        let table = Config::load_theme_table(path, &mut Vec::new())?;
+        match toml::Value::Table(table).try_into::<Theme>() {
+            Ok(decoded) => Ok(decoded),
+            Err(err_message) => Err(format!("error parsing: {:?}", err_message)),
+        }
+    }
+
+    /// Reads a theme file, resolving `extends` by layering the theme on top
+    /// of its parent. Parents are looked up next to the theme, `visited`
+    /// holds the themes of the chain to detect cycles.
+    fn load_theme_table(
+        path: &Path,
+        visited: &mut Vec<PathBuf>,
+    ) -> Result<toml::Table, String> {
+        if !path.exists() {
+            return Err(String::from("filepath does not exist"));
+        }
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if visited.contains(&canonical) {
+            return Err(format!("theme {} extends itself", path.display()));
+        }
+        visited.push(canonical);
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("error reading: {:?}", err))?;
+        let mut table = toml::from_str::<toml::Table>(&content)
+            .map_err(|err_message| format!("error parsing: {:?}", err_message))?;
+
+        let Some(extends) = table.remove("extends") else {
+            return Ok(table);
+        };
+        let Some(parent) = extends.as_str() else {
+            return Err(String::from("`extends` must be the name of a theme"));
+        };
+
+        let parent_path = path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(format!("{parent}.toml"));
+        let mut merged = Config::load_theme_table(&parent_path, visited)
+            .map_err(|err| format!("failed to load parent theme {parent}: {err}"))?;
+        merge_toml_tables(&mut merged, table);
+        Ok(merged)
+    }
+
+    /// Returns an error naming the first size out of the range needed for a
+    /// usable layout.
+    pub fn validate(&self) -> Result<(), String> {
+        if !LINE_HEIGHT_RANGE.contains(&self.line_height) {
+            return Err(format!(
+                "`line-height` must be between {} and {}, got {}",
+                LINE_HEIGHT_RANGE.start(),
+                LINE_HEIGHT_RANGE.end(),
+                self.line_height
+            ));
+        }
+
+        if self.padding_x.is_nan() || self.padding_x < 0. {
+            return Err(format!(
+                "`padding-x` must not be negative, got {}",
+                self.padding_x
+            ));
+        }
+
+        if self
+            .padding_y
+            .iter()
+            .any(|padding| padding.is_nan() || *padding < 0.)
+        {
+            return Err(format!(
+                "`padding-y` must not be negative, got {:?}",
+                self.padding_y
+            ));
+        }
+
+        if self.fonts.size.is_nan() || self.fonts.size <= 0. {
+            return Err(format!(
+                "`fonts.size` must be greater than 0, got {}",
+                self.fonts.size
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Brings the sizes rejected by `validate` back into their range.
+    pub fn sanitize(&mut self) {
+        if let Err(err_message) = self.validate() {
+            warn!("{err_message}, using the closest valid value");
+        }
+
+        if self.line_height.is_nan() {
+            self.line_height = default_line_height();
+        }
+        self.line_height = self
+            .line_height
+            .clamp(*LINE_HEIGHT_RANGE.start(), *LINE_HEIGHT_RANGE.end());
+        self.padding_x = self.padding_x.max(0.);
+        for padding in &mut self.padding_y {
+            *padding = padding.max(0.);
+        }
+        if self.fonts.size.is_nan() || self.fonts.size <= 0. {
+            self.fonts.size = SugarloafFonts::default().size;
         }
     }
 
@@ -325,8 +600,10 @@ impl Config {
         let path = config_file_path();
         if path.exists() {
             let content = std::fs::read_to_string(path).unwrap();
-            match toml::from_str::<Config>(&content) {
+            match Config::from_toml_str(&content) {
                 Ok(mut decoded) => {
+                    decoded.sanitize();
+
                     let theme = &decoded.theme;
                     if theme.is_empty() {
                         return decoded;
@@ -358,8 +635,10 @@ impl Config {
         let path = config_file_path();
         if path.exists() {
             match std::fs::read_to_string(path) {
-                Ok(content) => match toml::from_str::<Config>(&content) {
+                Ok(content) => match Config::from_toml_str(&content) {
                     Ok(mut decoded) => {
+                        decoded.validate().map_err(ConfigError::ErrLoadingConfig)?;
+
                         let theme = &decoded.theme;
                         let theme_path = config_dir_path().join("themes");
                         if !theme.is_empty() {
@@ -434,38 +713,44 @@ impl Config {
         }
     }
 
-    pub fn overwrite_based_on_platform(&mut self) {
-        #[cfg(windows)]
-        if let Some(windows) = &self.platform.windows {
-            self.overwrite_with_platform_config(windows.clone());
-        }
-
-        #[cfg(target_os = "linux")]
-        if let Some(linux) = &self.platform.linux {
-            self.overwrite_with_platform_config(linux.clone());
-        }
-
-        #[cfg(target_os = "macos")]
-        if let Some(macos) = &self.platform.macos {
-            self.overwrite_with_platform_config(macos.clone());
-        }
+    /// Parses a configuration file, applying the `[platform]` overrides
+    /// of the current operating system.
+    pub fn from_toml_str(content: &str) -> Result<Self, toml::de::Error> {
+        Config::from_toml_str_for_platform(content, std::env::consts::OS)
     }
 
-    fn overwrite_with_platform_config(&mut self, platform_config: PlatformConfig) {
-        if let Some(shell_overwrite) = &platform_config.shell {
-            self.shell = shell_overwrite.clone();
-        }
-
-        if let Some(window_overwrite) = &platform_config.window {
-            self.window = window_overwrite.clone();
+    /// Same as [`Config::from_toml_str`] but for a given platform name
+    /// ("linux", "macos" or "windows").
+    pub fn from_toml_str_for_platform(
+        content: &str,
+        platform: &str,
+    ) -> Result<Self, toml::de::Error> {
+        let mut table = toml::from_str::<toml::Table>(content)?;
+
+        let overrides = table
+            .get("platform")
+            .and_then(|value| value.get(platform))
+            .and_then(|value| value.as_table())
+            .cloned();
+        if let Some(overrides) = overrides {
+            merge_toml_tables(&mut table, overrides);
         }
 
-        if let Some(navigation_overwrite) = &platform_config.navigation {
-            self.navigation = navigation_overwrite.clone();
-        }
+        toml::Value::Table(table).try_into()
+    }
+}
 
-        if let Some(renderer_overwrite) = &platform_config.renderer {
-            self.renderer = renderer_overwrite.clone();
+/// Merges `overrides` into `base` field by field, nested tables are merged
+/// recursively and any other value present in `overrides` replaces the base one.
+fn merge_toml_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => {
+                merge_toml_tables(base_table, table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
         }
     }
 }
@@ -482,6 +767,7 @@ impl Default for Config {
             scroll: Scroll::default(),
             keyboard: Keyboard::default(),
             developer: Developer::default(),
+            terminal: Terminal::default(),
             env_vars: vec![],
             fonts: SugarloafFonts::default(),
             line_height: default_line_height(),
@@ -490,14 +776,20 @@ impl Default for Config {
             padding_x: f32::default(),
             padding_y: default_padding_y(),
             renderer: Renderer::default(),
+            profiles: Vec::default(),
             shell: default_shell(),
             platform: Platform::default(),
             theme: String::default(),
             use_fork: default_use_fork(),
+            utf8: default_bool_true(),
             window: Window::default(),
             working_dir: default_working_dir(),
             ignore_selection_fg_color: false,
             confirm_before_quit: true,
+            animations: false,
+            animation_duration: default_animation_duration(),
+            bell: Bell::default(),
+            title: Title::default(),
             hide_cursor_when_typing: false,
         }
     }
@@ -636,6 +928,34 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_auto_performance() {
+        use renderer::{Performance, PowerSource};
+
+        let result = create_temporary_config(
+            "auto-performance",
+            r#"
+            [renderer]
+            performance = "auto"
+        "#,
+        );
+        let performance = result.renderer.performance;
+        assert_eq!(performance, Performance::Auto);
+        assert_eq!(
+            performance.resolve(Some(PowerSource::Battery)),
+            Performance::Low
+        );
+        assert_eq!(
+            performance.resolve(Some(PowerSource::Ac)),
+            Performance::High
+        );
+        assert_eq!(performance.resolve(None), Performance::High);
+        assert_eq!(
+            Performance::High.resolve(Some(PowerSource::Battery)),
+            Performance::High
+        );
+    }
+
     #[test]
     fn test_change_config_renderer() {
         let result = create_temporary_config(
@@ -658,6 +978,30 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_change_config_target_fps() {
+        let result = create_temporary_config("target-fps-default", "");
+        assert_eq!(result.renderer.target_fps, None);
+
+        let result = create_temporary_config(
+            "target-fps",
+            r#"
+            [renderer]
+            target-fps = 30
+        "#,
+        );
+        assert_eq!(result.renderer.target_fps, Some(30));
+
+        let result = create_temporary_config(
+            "max-fps",
+            r#"
+            [renderer]
+            max-fps = 0
+        "#,
+        );
+        assert_eq!(result.renderer.target_fps, Some(0));
+    }
+
     #[test]
     fn test_change_config_environment_variables() {
         let result = create_temporary_config(
@@ -676,15 +1020,48 @@ mod tests {
         assert_eq!(result.colors.background, colors::defaults::background());
         assert_eq!(result.colors.foreground, colors::defaults::foreground());
         assert_eq!(result.colors.tabs_active, colors::defaults::tabs_active());
+        assert_eq!(result.colors.selection_background, None);
+        assert_eq!(result.colors.selection_foreground, None);
+        assert_eq!(result.colors.cursor, colors::defaults::cursor());
+    }
+
+    #[test]
+    fn test_shell_environment() {
+        let result = create_temporary_config(
+            "shell-environment",
+            r#"
+            env-vars = ['A=5']
+
+            [shell]
+            program = "/bin/fish"
+            environment = { B = "8", QUERY = "a=b&c=d" }
+        "#,
+        );
+
+        let environment = result.shell.environment.as_ref().unwrap();
+        assert_eq!(environment["B"], "8");
+        assert_eq!(environment["QUERY"], "a=b&c=d");
+        // `shell.environment` takes precedence over `env-vars`.
         assert_eq!(
-            result.colors.selection_background,
-            colors::defaults::selection_background()
+            result.environment_variables(),
+            [("B", "8"), ("QUERY", "a=b&c=d")]
         );
+    }
+
+    #[test]
+    fn test_env_vars_with_equals_in_value() {
+        let result = create_temporary_config(
+            "env-vars-equals",
+            r#"
+            env-vars = ['A=5', 'QUERY=a=b', 'INVALID']
+        "#,
+        );
+
+        assert_eq!(result.shell.environment, None);
         assert_eq!(
-            result.colors.selection_foreground,
-            colors::defaults::selection_foreground()
+            result.environment_variables(),
+            [("A", "5"), ("QUERY", "a=b")]
         );
-        assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
     #[test]
@@ -709,6 +1086,50 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_cursor_table() {
+        let result = create_temporary_config(
+            "cursor-table",
+            r#"
+            [cursor]
+            shape = 'beam'
+            blink = true
+            blink-interval = 500
+        "#,
+        );
+
+        assert_eq!(result.cursor.shape, CursorShape::Beam);
+        assert!(result.cursor.blinking);
+        assert_eq!(result.cursor.blinking_interval, 500);
+
+        let result = create_temporary_config(
+            "cursor-table-blinking",
+            r#"
+            [cursor]
+            blinking = true
+            blinking-interval = 1000
+        "#,
+        );
+
+        assert_eq!(result.cursor.shape, CursorShape::Block);
+        assert!(result.cursor.blinking);
+        assert_eq!(result.cursor.blinking_interval, 1000);
+    }
+
+    #[test]
+    fn test_cursor_deprecated_char() {
+        let result = create_temporary_config("cursor-char-underline", "cursor = '_'");
+        assert_eq!(result.cursor.shape, CursorShape::Underline);
+        assert!(!result.cursor.blinking);
+        assert_eq!(result.cursor.blinking_interval, default_cursor_interval());
+
+        let result = create_temporary_config("cursor-char-beam", "cursor = '|'");
+        assert_eq!(result.cursor.shape, CursorShape::Beam);
+
+        let result = create_temporary_config("cursor-char-block", "cursor = '▇'");
+        assert_eq!(result.cursor.shape, CursorShape::Block);
+    }
+
     #[test]
     fn test_change_option_as_alt() {
         let result = create_temporary_config(
@@ -818,6 +1239,179 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_window_startup_mode() {
+        use crate::config::window::WindowMode;
+
+        let result = create_temporary_config("window-mode-default", "[window]");
+        assert_eq!(result.window.mode, WindowMode::Windowed);
+
+        for (prefix, value, expected) in [
+            ("window-mode-windowed", "Windowed", WindowMode::Windowed),
+            ("window-mode-maximized", "maximized", WindowMode::Maximized),
+            (
+                "window-mode-fullscreen",
+                "Fullscreen",
+                WindowMode::Fullscreen,
+            ),
+        ] {
+            let result = create_temporary_config(
+                prefix,
+                &format!("[window]\nstartup-mode = \"{value}\""),
+            );
+            assert_eq!(result.window.mode, expected);
+        }
+
+        let result = create_temporary_config(
+            "window-mode-key",
+            r#"
+            [window]
+            mode = "Maximized"
+        "#,
+        );
+        assert_eq!(result.window.mode, WindowMode::Maximized);
+    }
+
+    #[test]
+    fn test_window_decorations() {
+        use crate::config::window::Decorations;
+
+        let result = create_temporary_config("window-decorations-default", "[window]");
+        assert_eq!(result.window.decorations, Decorations::default());
+        if cfg!(target_os = "macos") {
+            assert_eq!(Decorations::default(), Decorations::Transparent);
+        } else {
+            assert_eq!(Decorations::default(), Decorations::Enabled);
+        }
+
+        for (prefix, value, expected) in [
+            (
+                "window-decorations-enabled",
+                "Enabled",
+                Decorations::Enabled,
+            ),
+            ("window-decorations-full", "full", Decorations::Enabled),
+            (
+                "window-decorations-disabled",
+                "disabled",
+                Decorations::Disabled,
+            ),
+            ("window-decorations-none", "none", Decorations::Disabled),
+            (
+                "window-decorations-transparent",
+                "Transparent",
+                Decorations::Transparent,
+            ),
+            (
+                "window-decorations-buttonless",
+                "buttonless",
+                Decorations::Buttonless,
+            ),
+        ] {
+            let result = create_temporary_config(
+                prefix,
+                &format!("[window]\ndecorations = \"{value}\""),
+            );
+            assert_eq!(result.window.decorations, expected);
+        }
+    }
+
+    #[test]
+    fn test_decorations_fallback() {
+        use crate::config::window::Decorations;
+
+        assert_eq!(Decorations::Enabled.supported(), Decorations::Enabled);
+        assert_eq!(Decorations::Disabled.supported(), Decorations::Disabled);
+        for decorations in [Decorations::Transparent, Decorations::Buttonless] {
+            let expected = if cfg!(target_os = "macos") {
+                decorations
+            } else {
+                Decorations::Enabled
+            };
+            assert_eq!(decorations.supported(), expected);
+        }
+    }
+
+    #[test]
+    fn test_window_position() {
+        let result = create_temporary_config("window-position-default", "[window]");
+        assert_eq!(result.window.position, None);
+
+        let result = create_temporary_config(
+            "window-position",
+            r#"
+            [window]
+            position = [120, -40]
+        "#,
+        );
+        assert_eq!(result.window.position, Some([120, -40]));
+    }
+
+    #[test]
+    fn test_confirm_before_quit() {
+        let result = create_temporary_config("confirm-before-quit-default", "");
+        assert!(result.confirm_before_quit);
+        assert_eq!(result.window.confirm_before_quit, None);
+        assert!(result.should_confirm_before_quit());
+
+        let result = create_temporary_config(
+            "confirm-before-quit",
+            r#"
+            confirm-before-quit = false
+        "#,
+        );
+        assert!(!result.should_confirm_before_quit());
+
+        let result = create_temporary_config(
+            "confirm-before-quit-window",
+            r#"
+            confirm-before-quit = false
+
+            [window]
+            confirm-before-quit = true
+        "#,
+        );
+        assert!(!result.confirm_before_quit);
+        assert!(result.should_confirm_before_quit());
+    }
+
+    #[test]
+    fn test_keyboard_protocol() {
+        use crate::config::keyboard::KeyboardProtocol;
+
+        let result = create_temporary_config("keyboard-protocol-default", "");
+        assert_eq!(result.keyboard.protocol, None);
+        assert_eq!(result.keyboard.protocol(), KeyboardProtocol::Kitty);
+
+        let result = create_temporary_config(
+            "keyboard-protocol-use-kitty",
+            r#"
+            [keyboard]
+            use-kitty-keyboard-protocol = false
+        "#,
+        );
+        assert_eq!(result.keyboard.protocol(), KeyboardProtocol::Legacy);
+
+        let result = create_temporary_config(
+            "keyboard-protocol",
+            r#"
+            [keyboard]
+            protocol = "kitty"
+            use-kitty-keyboard-protocol = false
+        "#,
+        );
+        assert_eq!(result.keyboard.protocol(), KeyboardProtocol::Kitty);
+
+        let result = create_temporary_config(
+            "keyboard-protocol-legacy",
+            r#"
+            [keyboard]
+            protocol = "legacy"
+        "#,
+        );
+        assert_eq!(result.keyboard.protocol(), KeyboardProtocol::Legacy);
+    }
+
     #[test]
     fn test_change_theme() {
         let result = create_temporary_config(
@@ -866,6 +1460,167 @@ mod tests {
         assert_eq!(result.colors.background.0, hex_to_color_arr("#2B3E50"));
     }
 
+    #[test]
+    fn test_theme_extends_parent() {
+        create_temporary_theme(
+            "extends-base-dark",
+            r#"
+            [colors]
+            background = '#2B3E50'
+            foreground = '#F8F8F2'
+            cursor     = '#FF0000'
+        "#,
+        );
+        create_temporary_theme(
+            "extends-child",
+            r#"
+            extends = "extends-base-dark"
+
+            [colors]
+            foreground = '#000000'
+        "#,
+        );
+
+        let path = tmp_dir().join("extends-child").with_extension("toml");
+        let theme = Config::load_theme(&path).unwrap();
+        assert_eq!(theme.colors.foreground, hex_to_color_arr("#000000"));
+        assert_eq!(theme.colors.background.0, hex_to_color_arr("#2B3E50"));
+        assert_eq!(theme.colors.cursor, hex_to_color_arr("#FF0000"));
+        assert_eq!(theme.colors.tabs_active, colors::defaults::tabs_active());
+
+        let result = create_temporary_config(
+            "extends-theme",
+            r#"
+            theme = "extends-child"
+        "#,
+        );
+        assert_eq!(result.colors.foreground, hex_to_color_arr("#000000"));
+        assert_eq!(result.colors.background.0, hex_to_color_arr("#2B3E50"));
+    }
+
+    #[test]
+    fn test_theme_extends_parent_with_dot() {
+        // `with_extension` would replace `.dark` in the parent name.
+        std::fs::write(
+            tmp_dir().join("extends-base.dark.toml"),
+            r#"
+            [colors]
+            background = '#2B3E50'
+        "#,
+        )
+        .unwrap();
+        create_temporary_theme(
+            "extends-dotted-child",
+            r#"
+            extends = "extends-base.dark"
+        "#,
+        );
+
+        let path = tmp_dir()
+            .join("extends-dotted-child")
+            .with_extension("toml");
+        let theme = Config::load_theme(&path).unwrap();
+        assert_eq!(theme.colors.background.0, hex_to_color_arr("#2B3E50"));
+    }
+
+    #[test]
+    fn test_theme_extends_errors() {
+        create_temporary_theme(
+            "extends-missing-parent",
+            r#"
+            extends = "it-should-never-exist"
+        "#,
+        );
+        let path = tmp_dir()
+            .join("extends-missing-parent")
+            .with_extension("toml");
+        assert!(Config::load_theme(&path).is_err());
+
+        create_temporary_theme("extends-cycle-a", r#"extends = "extends-cycle-b""#);
+        create_temporary_theme("extends-cycle-b", r#"extends = "extends-cycle-a""#);
+        let path = tmp_dir().join("extends-cycle-a").with_extension("toml");
+        let err = Config::load_theme(&path).unwrap_err();
+        assert!(err.contains("extends itself"), "{err}");
+    }
+
+    #[test]
+    fn test_apply_system_theme() {
+        use rio_window::window::Theme;
+
+        create_temporary_theme(
+            "adaptive-light",
+            r#"
+            [colors]
+            background = '#FFFFFF'
+        "#,
+        );
+        create_temporary_theme(
+            "adaptive-dark",
+            r#"
+            [colors]
+            background = '#000000'
+        "#,
+        );
+        let mut result = create_temporary_config(
+            "adaptive-theme",
+            r#"
+            theme = "adaptive-light"
+
+            [adaptive-theme]
+            light = "adaptive-light"
+            dark = "adaptive-dark"
+        "#,
+        );
+        assert_eq!(result.colors.background.0, hex_to_color_arr("#FFFFFF"));
+
+        // Unknown appearance keeps the current colors.
+        assert!(!result.apply_system_theme(None));
+
+        assert!(result.apply_system_theme(Some(Theme::Dark)));
+        assert_eq!(result.colors.background.0, hex_to_color_arr("#000000"));
+        // Same appearance again is not a change.
+        assert!(!result.apply_system_theme(Some(Theme::Dark)));
+
+        assert!(result.apply_system_theme(Some(Theme::Light)));
+        assert_eq!(result.colors.background.0, hex_to_color_arr("#FFFFFF"));
+
+        let mut result = create_temporary_config(
+            "no-adaptive-theme",
+            r#"
+            theme = "adaptive-light"
+        "#,
+        );
+        assert!(!result.apply_system_theme(Some(Theme::Dark)));
+        assert_eq!(result.colors.background.0, hex_to_color_arr("#FFFFFF"));
+    }
+
+    #[test]
+    fn test_scroll_on_output_and_keystroke() {
+        let result = create_temporary_config(
+            "scroll-defaults",
+            r#"
+            [scroll]
+            multiplier = 3.0
+            divider = 1.0
+        "#,
+        );
+        assert!(!result.scroll.on_output);
+        assert!(result.scroll.on_keystroke);
+
+        let result = create_temporary_config(
+            "scroll-on-output",
+            r#"
+            [scroll]
+            multiplier = 3.0
+            divider = 1.0
+            on-output = true
+            on-keystroke = false
+        "#,
+        );
+        assert!(result.scroll.on_output);
+        assert!(!result.scroll.on_keystroke);
+    }
+
     #[test]
     fn test_change_one_color() {
         let result = create_temporary_config(
@@ -882,6 +1637,105 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_colors_vi_cursor_and_selection() {
+        let result = create_temporary_config(
+            "colors-vi-cursor-selection",
+            r#"
+            [colors]
+            foreground = '#FFFFFF'
+            background = '#000000'
+            vi-cursor = '#12D0FF'
+            selection-background = '#333333'
+        "#,
+        );
+
+        assert_eq!(result.colors.vi_cursor, hex_to_color_arr("#12D0FF"));
+        assert_eq!(
+            result.colors.selection_background(),
+            hex_to_color_arr("#333333")
+        );
+        // Unset selection foreground is derived from the terminal colors.
+        assert_eq!(result.colors.selection_foreground, None);
+        assert_eq!(
+            result.colors.selection_foreground(),
+            colors::derive_selection(
+                hex_to_color_arr("#FFFFFF"),
+                hex_to_color_arr("#000000")
+            )
+            .0
+        );
+        assert_eq!(
+            result.colors.selection_foreground(),
+            hex_to_color_arr("#000000")
+        );
+    }
+
+    #[test]
+    fn test_search_colors() {
+        let result = create_temporary_config(
+            "colors-search",
+            r#"
+            [colors]
+            search-match-background = '#333333'
+            search-focused-match-foreground = '#12D0FF'
+        "#,
+        );
+
+        let colors = result.colors;
+        assert_eq!(
+            colors.search_match_background(),
+            hex_to_color_arr("#333333")
+        );
+        assert_eq!(
+            colors.search_focused_match_foreground(),
+            hex_to_color_arr("#12D0FF")
+        );
+        // Unset keys are derived from the selection colors.
+        assert_eq!(
+            colors.search_match_foreground(),
+            colors.selection_foreground()
+        );
+        assert_eq!(
+            colors.search_focused_match_background(),
+            colors.selection_background()
+        );
+
+        let colors = Colors::default();
+        let mut match_background = colors.selection_background();
+        match_background[3] *= 0.5;
+        assert_eq!(colors.search_match_background(), match_background);
+        assert_eq!(
+            colors.search_focused_match_background(),
+            colors.selection_background()
+        );
+        assert_ne!(
+            colors.search_focused_match_background(),
+            colors.search_match_background()
+        );
+    }
+
+    #[test]
+    fn test_selection_derived_from_theme_colors() {
+        let result = create_temporary_config(
+            "colors-derived-selection",
+            r#"
+            [colors]
+            foreground = '#F8F8F2'
+            background = '#2B3E50'
+        "#,
+        );
+
+        assert_eq!(
+            result.colors.selection_background(),
+            hex_to_color_arr("#F8F8F2")
+        );
+        assert_eq!(
+            result.colors.selection_foreground(),
+            hex_to_color_arr("#2B3E50")
+        );
+    }
+
     #[test]
     fn test_change_colors() {
         let result = create_temporary_config(
@@ -965,14 +1819,227 @@ mod tests {
         assert_eq!(result.colors.light_yellow, hex_to_color_arr("#030303"));
         assert_eq!(
             result.colors.selection_background,
-            hex_to_color_arr("#111111")
+            Some(hex_to_color_arr("#111111"))
         );
         assert_eq!(
             result.colors.selection_foreground,
-            hex_to_color_arr("#222222")
+            Some(hex_to_color_arr("#222222"))
+        );
+        assert_eq!(
+            result.colors.selection_background(),
+            hex_to_color_arr("#111111")
         );
     }
 
+    #[test]
+    fn test_font_features() {
+        let result = create_temporary_config(
+            "font-features",
+            r#"
+            [fonts]
+            features = ["+zero", "-calt", "ss01"]
+        "#,
+        );
+        assert_eq!(
+            result.fonts.features,
+            Some(vec![
+                String::from("+zero"),
+                String::from("-calt"),
+                String::from("ss01")
+            ])
+        );
+
+        let err = Config::from_toml_str("[fonts]\nfeatures = [\"bad\"]").unwrap_err();
+        assert!(err.message().contains("invalid font feature \"bad\""));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let result = create_temporary_config(
+            "profiles",
+            r##"
+            [colors]
+            foreground = "#cccccc"
+
+            [[profiles]]
+            match = "~/work/*"
+            colors = { background = "#2e0000" }
+
+            [[profiles]]
+            match = "htop"
+            env = { RIO_PROFILE = "htop", LANG = "C" }
+            fonts = { size = 20 }
+        "##,
+        );
+        assert_eq!(result.profiles.len(), 2);
+        assert!(result.profiles[0].environment_variables().is_empty());
+        assert_eq!(result.profiles[0].fonts.size, None);
+        assert_eq!(
+            result.profiles[1].environment_variables(),
+            [
+                (String::from("LANG"), String::from("C")),
+                (String::from("RIO_PROFILE"), String::from("htop")),
+            ]
+        );
+        assert_eq!(result.profiles[1].fonts.size, Some(20.));
+
+        let home = dirs::home_dir().unwrap();
+        let work = profile::find(&result.profiles, &home.join("work/rio"), "zsh")
+            .and_then(|profile| profile.colors(&result.colors))
+            .unwrap();
+        assert_eq!(work.background.0, hex_to_color_arr("#2e0000"));
+        assert_eq!(work.foreground, result.colors.foreground);
+
+        let personal = profile::find(&result.profiles, &home.join("personal"), "zsh");
+        assert_eq!(personal, None);
+        let htop = profile::find(&result.profiles, &home.join("personal"), "htop");
+        assert_eq!(
+            htop.and_then(|profile| profile.colors(&result.colors)),
+            None
+        );
+
+        let err = Config::from_toml_str(
+            "[[profiles]]\nmatch = \"htop\"\ncolors = { background = \"red\" }",
+        )
+        .unwrap_err();
+        assert!(err.message().contains("background"));
+    }
+
+    #[test]
+    fn test_emoji_font() {
+        let result = create_temporary_config("emoji-font-default", "");
+        assert_eq!(result.fonts.emoji.family, None);
+        assert!(result.fonts.emoji.color);
+
+        let result = create_temporary_config(
+            "emoji-font",
+            r#"
+            [fonts]
+            emoji = { family = "Noto Emoji", color = false }
+        "#,
+        );
+        assert_eq!(result.fonts.emoji.family, Some(String::from("Noto Emoji")));
+        assert!(!result.fonts.emoji.color);
+
+        // Face keys of the previous `[fonts.emoji]` table are still read.
+        let result = create_temporary_config(
+            "emoji-font-face",
+            r#"
+            [fonts.emoji]
+            family = "Noto Emoji"
+            style = "Italic"
+            weight = 700
+        "#,
+        );
+        assert_eq!(
+            result.fonts.emoji.style,
+            sugarloaf::font::fonts::SugarloafFontStyle::Italic
+        );
+        assert_eq!(result.fonts.emoji.weight, Some(700));
+    }
+
+    #[test]
+    fn test_bell() {
+        let result = create_temporary_config("bell-default", "");
+        assert_eq!(result.bell, Bell::default());
+        assert_eq!(result.bell.mode, bell::BellMode::None);
+
+        let result = create_temporary_config(
+            "bell",
+            r##"
+            [bell]
+            mode = "visual"
+            color = "#ff0000"
+            duration = 300
+        "##,
+        );
+        assert_eq!(result.bell.mode, bell::BellMode::Visual);
+        assert_eq!(result.bell.color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(result.bell.duration, 300);
+
+        let result = create_temporary_config("bell-audible", "bell.mode = 'audible'");
+        assert_eq!(result.bell.mode, bell::BellMode::Audible);
+    }
+
+    #[test]
+    fn test_title() {
+        let result = create_temporary_config("title-default", "");
+        assert_eq!(result.title.template, None);
+        assert!(result.title.prefer_osc);
+
+        let result = create_temporary_config(
+            "title",
+            r#"
+            [title]
+            template = "{index}: {cwd}"
+            prefer-osc = false
+        "#,
+        );
+        assert_eq!(result.title.template, Some(String::from("{index}: {cwd}")));
+        assert!(!result.title.prefer_osc);
+    }
+
+    #[test]
+    fn test_validate_sizes() {
+        let result = create_temporary_config("line-height-valid", "line-height = 1.5");
+        assert_eq!(result.validate(), Ok(()));
+
+        let result = create_temporary_config("line-height-negative", "line-height = -1");
+        let err = result.validate().unwrap_err();
+        assert!(err.contains("line-height") && err.contains("-1"), "{err}");
+
+        let mut result = create_temporary_config(
+            "sizes-invalid",
+            r#"
+            line-height = 10.0
+            padding-x = -5.0
+            padding-y = [-1.0, 2.0]
+            [fonts]
+            size = 0
+        "#,
+        );
+        assert!(result.validate().is_err());
+
+        result.sanitize();
+        assert_eq!(result.line_height, 4.0);
+        assert_eq!(result.padding_x, 0.0);
+        assert_eq!(result.padding_y, [0.0, 2.0]);
+        assert_eq!(result.fonts.size, SugarloafFonts::default().size);
+        assert_eq!(result.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_working_dir_inherit() {
+        let result = create_temporary_config("working-dir-default", "");
+        assert!(!result.inherits_working_dir());
+
+        let result = create_temporary_config("working-dir", "working-dir = '/Users/rio'");
+        assert!(!result.inherits_working_dir());
+
+        let result = create_temporary_config(
+            "working-dir-inherit",
+            "working-dir = 'inherit-from-active-tab'",
+        );
+        assert!(result.inherits_working_dir());
+    }
+
+    #[test]
+    fn test_animations() {
+        let result = create_temporary_config("animations-default", "");
+        assert!(!result.animations);
+        assert_eq!(result.animation_duration, 100);
+
+        let result = create_temporary_config(
+            "animations",
+            r#"
+            animations = true
+            animation-duration = 250
+        "#,
+        );
+        assert!(result.animations);
+        assert_eq!(result.animation_duration, 250);
+    }
+
     #[test]
     fn test_use_fork() {
         let result = create_temporary_config(
@@ -998,6 +2065,39 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_utf8() {
+        let result = create_temporary_config("utf8-default", "");
+        assert!(result.utf8);
+
+        let result = create_temporary_config("utf8-disabled", "utf8 = false");
+        assert!(!result.utf8);
+    }
+
+    #[test]
+    fn test_terminal_term() {
+        let result = create_temporary_config("terminal-term-default", "");
+        assert_eq!(result.terminal.term, None);
+
+        let result = create_temporary_config(
+            "terminal-term",
+            r#"
+            [terminal]
+            term = "xterm-256color"
+        "#,
+        );
+        assert_eq!(result.terminal.term.as_deref(), Some("xterm-256color"));
+
+        let result = create_temporary_config(
+            "terminal-term-name",
+            r#"
+            [terminal]
+            term-name = "xterm-kitty"
+        "#,
+        );
+        assert_eq!(result.terminal.term.as_deref(), Some("xterm-kitty"));
+    }
+
     #[test]
     fn test_shell() {
         let result = create_temporary_config(
@@ -1036,6 +2136,7 @@ mod tests {
             [developer]
             enable-fps-counter = true
             log-level = "INFO"
+            record-session = "/tmp/rio-session.log"
         "#,
         );
 
@@ -1044,6 +2145,10 @@ mod tests {
         // Developer
         assert_eq!(result.developer.log_level, String::from("INFO"));
         assert!(result.developer.enable_fps_counter);
+        assert_eq!(
+            result.developer.record_session,
+            Some(String::from("/tmp/rio-session.log"))
+        );
 
         // Colors
         assert_eq!(result.colors.background, colors::defaults::background());
@@ -1051,4 +2156,123 @@ mod tests {
         assert_eq!(result.colors.tabs_active, colors::defaults::tabs_active());
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
+
+    #[test]
+    fn test_platform_overwrite() {
+        let content = r#"
+            option-as-alt = "left"
+
+            [shell]
+            program = "/bin/fish"
+            args = ["--login"]
+
+            [window]
+            width = 400
+            opacity = 0.8
+
+            [platform]
+            macos.shell.program = "/bin/zsh"
+            macos.window.opacity = 0.5
+            macos.option-as-alt = "both"
+            linux.shell.program = "tmux"
+        "#;
+
+        let macos = Config::from_toml_str_for_platform(content, "macos").unwrap();
+        assert_eq!(macos.shell.program, "/bin/zsh");
+        // Keys missing on the platform table are kept from the base
+        assert_eq!(macos.shell.args, ["--login"]);
+        assert_eq!(macos.window.opacity, 0.5);
+        assert_eq!(macos.window.width, 400);
+        assert_eq!(macos.option_as_alt, "both");
+
+        let linux = Config::from_toml_str_for_platform(content, "linux").unwrap();
+        assert_eq!(linux.shell.program, "tmux");
+        assert_eq!(linux.shell.args, ["--login"]);
+        assert_eq!(linux.window.opacity, 0.8);
+        assert_eq!(linux.option_as_alt, "left");
+
+        let windows = Config::from_toml_str_for_platform(content, "windows").unwrap();
+        assert_eq!(windows.shell.program, "/bin/fish");
+        assert_eq!(windows.window.opacity, 0.8);
+        assert_eq!(windows.option_as_alt, "left");
+    }
+
+    #[test]
+    fn test_unfocused_opacity() {
+        let result = create_temporary_config("unfocused-opacity-default", "");
+        assert_eq!(result.window.unfocused_opacity, None);
+        assert_eq!(result.window.effective_opacity(false), 1.0);
+        assert!(!result.window.is_transparent());
+
+        let result = create_temporary_config(
+            "unfocused-opacity",
+            r#"
+            [window]
+            opacity = 0.9
+            unfocused-opacity = 0.6
+        "#,
+        );
+        assert_eq!(result.window.unfocused_opacity, Some(0.6));
+        assert_eq!(result.window.effective_opacity(true), 0.9);
+        assert_eq!(result.window.effective_opacity(false), 0.6);
+        assert!(result.window.is_transparent());
+
+        // Out of range values are clamped.
+        let result = create_temporary_config(
+            "unfocused-opacity-clamped",
+            r#"
+            [window]
+            opacity = 1.5
+            unfocused-opacity = -0.5
+        "#,
+        );
+        assert_eq!(result.window.effective_opacity(true), 1.0);
+        assert_eq!(result.window.effective_opacity(false), 0.0);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_config_dir_respects_xdg_config_home() {
+        let home = Path::new("/home/rio");
+        assert_eq!(
+            config_dir_path_in(home, None),
+            PathBuf::from("/home/rio/.config/rio")
+        );
+        assert_eq!(
+            config_dir_path_in(home, Some("/tmp/xdg".into())),
+            PathBuf::from("/tmp/xdg/rio")
+        );
+        // Empty and relative values are ignored.
+        assert_eq!(
+            config_dir_path_in(home, Some("".into())),
+            PathBuf::from("/home/rio/.config/rio")
+        );
+        assert_eq!(
+            config_dir_path_in(home, Some("relative".into())),
+            PathBuf::from("/home/rio/.config/rio")
+        );
+    }
+
+    #[test]
+    fn test_explicit_config_file_path() {
+        let config_dir = PathBuf::from("/home/rio/.config/rio");
+        assert_eq!(
+            config_file_path_from(None, config_dir.clone()),
+            config_dir.join("config.toml")
+        );
+        assert_eq!(
+            config_file_path_from(Some("".into()), config_dir.clone()),
+            config_dir.join("config.toml")
+        );
+        let absolute = std::env::temp_dir().join("rio.toml");
+        assert_eq!(
+            config_file_path_from(Some(absolute.clone().into()), config_dir.clone()),
+            absolute
+        );
+
+        // Watching the parent of a relative path would watch nothing.
+        let relative = config_file_path_from(Some("rio.toml".into()), config_dir);
+        assert!(relative.is_absolute());
+        assert_eq!(relative, std::env::current_dir().unwrap().join("rio.toml"));
+    }
 }
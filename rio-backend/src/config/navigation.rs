@@ -125,6 +125,17 @@ pub struct Navigation {
     pub use_split: bool,
     #[serde(default = "default_bool_true", rename = "open-config-with-split")]
     pub open_config_with_split: bool,
+    // Multiplies the alpha of the content of splits that are not focused
+    #[serde(
+        default = "default_unfocused_split_opacity",
+        rename = "unfocused-split-opacity"
+    )]
+    pub unfocused_split_opacity: f32,
+}
+
+#[inline]
+fn default_unfocused_split_opacity() -> f32 {
+    1.0
 }
 
 impl Default for Navigation {
@@ -138,6 +149,7 @@ impl Default for Navigation {
             hide_if_single: true,
             use_split: true,
             open_config_with_split: true,
+            unfocused_split_opacity: default_unfocused_split_opacity(),
         }
     }
 }
@@ -0,0 +1,204 @@
+use crate::config::colors::Colors;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Overrides applied to the panes started in a directory or running a
+/// program matched by `matcher`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Profile {
+    // Directory glob, like `~/work/*`, or the name of a program
+    #[serde(rename = "match")]
+    pub matcher: String,
+    // Colors replacing the ones of the configuration, keys left out
+    // keep the configured value
+    #[serde(
+        default = "Option::default",
+        deserialize_with = "deserialize_profile_colors"
+    )]
+    pub colors: Option<toml::Table>,
+    // Environment variables added to the shell
+    #[serde(default = "Option::default")]
+    pub env: Option<BTreeMap<String, String>>,
+    #[serde(default = "ProfileFonts::default")]
+    pub fonts: ProfileFonts,
+}
+
+/// Font overrides of a profile, font families are shared by every pane of
+/// a window so only the size can change.
+#[derive(Default, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProfileFonts {
+    #[serde(default = "Option::default")]
+    pub size: Option<f32>,
+}
+
+fn deserialize_profile_colors<'de, D>(
+    deserializer: D,
+) -> Result<Option<toml::Table>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let colors: Option<toml::Table> = Option::deserialize(deserializer)?;
+    if let Some(colors) = &colors {
+        overlay_colors(&Colors::default(), colors).map_err(serde::de::Error::custom)?;
+    }
+
+    Ok(colors)
+}
+
+fn overlay_colors(base: &Colors, overrides: &toml::Table) -> Result<Colors, String> {
+    let mut colors = toml::Table::try_from(base).map_err(|err| err.to_string())?;
+    colors.extend(overrides.clone());
+    Colors::deserialize(toml::Value::Table(colors)).map_err(|err| err.to_string())
+}
+
+impl Profile {
+    /// Whether a pane started in `working_dir` running `program` uses
+    /// this profile. Programs are compared by file name, directories
+    /// match when they or one of their ancestors match the glob.
+    pub fn matches(&self, working_dir: &Path, program: &str) -> bool {
+        if is_directory_pattern(&self.matcher) {
+            let pattern = expand_home(&self.matcher);
+            return working_dir
+                .ancestors()
+                .any(|dir| glob_match(&pattern, &dir.to_string_lossy()));
+        }
+
+        let program = Path::new(program)
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        !program.is_empty() && program == self.matcher.as_str()
+    }
+
+    /// Environment variables to add to the shell of the pane.
+    pub fn environment_variables(&self) -> Vec<(String, String)> {
+        self.env
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Configured `colors` with the overrides of the profile.
+    pub fn colors(&self, base: &Colors) -> Option<Colors> {
+        let overrides = self.colors.as_ref()?;
+        match overlay_colors(base, overrides) {
+            Ok(colors) => Some(colors),
+            Err(err) => {
+                tracing::warn!("invalid colors in profile {}: {err}", self.matcher);
+                None
+            }
+        }
+    }
+}
+
+/// First profile matching a pane started in `working_dir` running
+/// `program`.
+pub fn find<'a>(
+    profiles: &'a [Profile],
+    working_dir: &Path,
+    program: &str,
+) -> Option<&'a Profile> {
+    profiles
+        .iter()
+        .find(|profile| profile.matches(working_dir, program))
+}
+
+#[inline]
+fn is_directory_pattern(pattern: &str) -> bool {
+    pattern.starts_with('~') || pattern.contains(['/', '\\'])
+}
+
+fn expand_home(pattern: &str) -> String {
+    match (pattern.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}{rest}", home.display()),
+        _ => pattern.to_string(),
+    }
+}
+
+/// Matches `path` against `pattern`, `*` matches any sequence of
+/// characters but a path separator and `?` a single character.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    let path = path.trim_end_matches('/');
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+
+    let (mut p, mut s) = (0, 0);
+    // Position after the last `*` in the pattern and in the path.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while s < path.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, s));
+            }
+            Some(&c) if (c == '?' && path[s] != '/') || c == path[s] => {
+                p += 1;
+                s += 1;
+            }
+            _ => match backtrack {
+                // A star never crosses a path separator.
+                Some((star_p, star_s)) if path[star_s] != '/' => {
+                    p = star_p;
+                    s = star_s + 1;
+                    backtrack = Some((star_p, s));
+                }
+                _ => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(matcher: &str) -> Profile {
+        Profile {
+            matcher: matcher.to_string(),
+            colors: None,
+            env: None,
+            fonts: ProfileFonts::default(),
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("/home/rio/work/*", "/home/rio/work/sugarloaf"));
+        assert!(!glob_match("/home/rio/work/*", "/home/rio/work/"));
+        assert!(!glob_match("/home/rio/work/*", "/home/rio/work/a/b"));
+        assert!(!glob_match("/home/rio/work/*", "/home/rio/personal"));
+        assert!(glob_match("/home/rio/*/src", "/home/rio/copa/src"));
+        assert!(glob_match("/tmp/rio-?", "/tmp/rio-1"));
+        assert!(!glob_match("/tmp/rio-?", "/tmp/rio-10"));
+        assert!(glob_match("/home/rio", "/home/rio/"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let work = profile("/home/rio/work/*");
+        assert!(work.matches(Path::new("/home/rio/work/rio"), "zsh"));
+        assert!(work.matches(Path::new("/home/rio/work/rio/src"), "zsh"));
+        assert!(!work.matches(Path::new("/home/rio/personal"), "zsh"));
+
+        let htop = profile("htop");
+        assert!(htop.matches(Path::new("/"), "/usr/bin/htop"));
+        assert!(htop.matches(Path::new("/"), "htop"));
+        assert!(!htop.matches(Path::new("/"), "/usr/bin/top"));
+        assert!(!profile("").matches(Path::new("/"), ""));
+    }
+
+    #[test]
+    fn test_find_first_matching_profile() {
+        let profiles = [profile("/srv/*"), profile("/srv/app"), profile("nvim")];
+        let found = find(&profiles, Path::new("/srv/app"), "nvim");
+        assert_eq!(found, Some(&profiles[0]));
+        let found = find(&profiles, Path::new("/home"), "nvim");
+        assert_eq!(found, Some(&profiles[2]));
+        assert_eq!(find(&profiles, Path::new("/home"), "zsh"), None);
+    }
+}
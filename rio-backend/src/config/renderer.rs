@@ -9,7 +9,8 @@ pub struct Renderer {
     pub backend: Backend,
     #[serde(default = "bool::default", rename = "disable-unfocused-render")]
     pub disable_unfocused_render: bool,
-    #[serde(default = "Option::default", rename = "target-fps")]
+    /// Caps how many frames are presented per second, `0` removes the cap.
+    #[serde(default = "Option::default", rename = "target-fps", alias = "max-fps")]
     pub target_fps: Option<u64>,
     #[serde(default = "Vec::default")]
     pub filters: Vec<String>,
@@ -59,6 +60,29 @@ pub enum Performance {
     High,
     #[serde(alias = "low")]
     Low,
+    // High on AC power and Low on battery
+    #[serde(alias = "auto")]
+    Auto,
+}
+
+/// Where the system is drawing power from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+impl Performance {
+    /// Performance to use with the given power source, `Auto` is `Low` on
+    /// battery and `High` otherwise, including when the source is unknown.
+    #[inline]
+    pub fn resolve(self, power_source: Option<PowerSource>) -> Performance {
+        match (self, power_source) {
+            (Performance::Auto, Some(PowerSource::Battery)) => Performance::Low,
+            (Performance::Auto, _) => Performance::High,
+            (performance, _) => performance,
+        }
+    }
 }
 
 impl Display for Performance {
@@ -70,6 +94,9 @@ impl Display for Performance {
             Performance::Low => {
                 write!(f, "Low")
             }
+            Performance::Auto => {
+                write!(f, "Auto")
+            }
         }
     }
 }
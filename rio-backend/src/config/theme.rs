@@ -9,6 +9,18 @@ pub struct AdaptiveColors {
     pub light: Option<Colors>,
 }
 
+impl AdaptiveColors {
+    /// Colors for the given system appearance, `None` if its theme could
+    /// not be loaded.
+    #[inline]
+    pub fn for_theme(&self, theme: rio_window::window::Theme) -> Option<Colors> {
+        match theme {
+            rio_window::window::Theme::Light => self.light,
+            rio_window::window::Theme::Dark => self.dark,
+        }
+    }
+}
+
 #[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct AdaptiveTheme {
     pub dark: String,
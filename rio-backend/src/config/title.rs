@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+#[inline]
+fn default_prefer_osc() -> bool {
+    true
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Title {
+    // Template used to build window and tab titles, see `render`
+    #[serde(default = "Option::default")]
+    pub template: Option<String>,
+    // Titles set by programs (OSC 0/2) take precedence over the template
+    #[serde(default = "default_prefer_osc", rename = "prefer-osc")]
+    pub prefer_osc: bool,
+}
+
+impl Default for Title {
+    fn default() -> Title {
+        Title {
+            template: None,
+            prefer_osc: default_prefer_osc(),
+        }
+    }
+}
+
+/// Values available to a title template.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TitleVariables<'a> {
+    /// Position of the tab, starting at 1.
+    pub index: usize,
+    pub cwd: &'a str,
+    pub command: &'a str,
+    /// Title set by the running program.
+    pub title: &'a str,
+}
+
+impl Title {
+    /// Builds the title for a tab, returns `None` when no template is
+    /// configured or when the title set by the program should be used as is.
+    pub fn compose(&self, variables: &TitleVariables) -> Option<String> {
+        let template = self.template.as_ref()?;
+        if self.prefer_osc && !variables.title.is_empty() {
+            return None;
+        }
+
+        Some(render(template, variables))
+    }
+}
+
+/// Replaces `{index}`, `{cwd}`, `{command}` and `{title}` in `template`.
+/// Unknown placeholders are kept as they are.
+pub fn render(template: &str, variables: &TitleVariables) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        match &rest[1..end] {
+            "index" => rendered.push_str(&variables.index.to_string()),
+            "cwd" => rendered.push_str(variables.cwd),
+            "command" => rendered.push_str(variables.command),
+            "title" => rendered.push_str(variables.title),
+            _ => rendered.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_all_variables() {
+        let variables = TitleVariables {
+            index: 2,
+            cwd: "/home/rio",
+            command: "nvim",
+            title: "README.md",
+        };
+
+        assert_eq!(
+            render("{index}: {cwd} — {command} [{title}]", &variables),
+            "2: /home/rio — nvim [README.md]"
+        );
+    }
+
+    #[test]
+    fn test_render_missing_and_unknown_variables() {
+        let variables = TitleVariables {
+            index: 1,
+            command: "zsh",
+            ..TitleVariables::default()
+        };
+
+        assert_eq!(render("{command} {title}", &variables), "zsh ");
+        assert_eq!(render("{user}@{command}", &variables), "{user}@zsh");
+        assert_eq!(render("{index} {", &variables), "1 {");
+    }
+
+    #[test]
+    fn test_compose_prefers_osc_title() {
+        let mut title = Title {
+            template: Some(String::from("{index}: {command}")),
+            ..Title::default()
+        };
+        let variables = TitleVariables {
+            index: 1,
+            command: "zsh",
+            title: "vim",
+            ..TitleVariables::default()
+        };
+
+        assert_eq!(title.compose(&variables), None);
+
+        title.prefer_osc = false;
+        assert_eq!(title.compose(&variables), Some(String::from("1: zsh")));
+        assert_eq!(Title::default().compose(&variables), None);
+    }
+}
@@ -16,9 +16,9 @@ pub enum WindowMode {
 
 #[derive(Clone, Serialize, Deserialize, Copy, Debug, PartialEq)]
 pub enum Decorations {
-    #[serde(alias = "enabled")]
+    #[serde(alias = "enabled", alias = "full")]
     Enabled,
-    #[serde(alias = "disabled")]
+    #[serde(alias = "disabled", alias = "none")]
     Disabled,
     #[serde(alias = "transparent")]
     Transparent,
@@ -39,16 +39,36 @@ impl Default for Decorations {
     }
 }
 
+impl Decorations {
+    /// Closest decorations supported by the platform, transparent and
+    /// buttonless titlebars are only available on MacOS.
+    pub fn supported(self) -> Decorations {
+        match self {
+            Decorations::Transparent | Decorations::Buttonless
+                if !cfg!(target_os = "macos") =>
+            {
+                Decorations::Enabled
+            }
+            decorations => decorations,
+        }
+    }
+}
+
 #[derive(PartialEq, Serialize, Deserialize, Clone, Debug)]
 pub struct Window {
     #[serde(default = "default_window_width")]
     pub width: i32,
     #[serde(default = "default_window_height")]
     pub height: i32,
-    #[serde(default = "WindowMode::default")]
+    #[serde(default = "WindowMode::default", alias = "startup-mode")]
     pub mode: WindowMode,
+    #[serde(default = "Option::default")]
+    pub position: Option<[i32; 2]>,
     #[serde(default = "default_opacity")]
     pub opacity: f32,
+    // Opacity while the window is not focused, `opacity` if unset
+    #[serde(default = "Option::default", rename = "unfocused-opacity")]
+    pub unfocused_opacity: Option<f32>,
     #[serde(default = "bool::default")]
     pub blur: bool,
     #[serde(rename = "background-image", skip_serializing)]
@@ -59,6 +79,9 @@ pub struct Window {
     pub macos_use_unified_titlebar: bool,
     #[serde(rename = "initial-title", skip_serializing)]
     pub initial_title: Option<String>,
+    // Overrides the top level `confirm-before-quit` for windows
+    #[serde(default = "Option::default", rename = "confirm-before-quit")]
+    pub confirm_before_quit: Option<bool>,
 }
 
 impl Default for Window {
@@ -67,12 +90,15 @@ impl Default for Window {
             width: default_window_width(),
             height: default_window_height(),
             mode: WindowMode::default(),
+            position: None,
             opacity: default_opacity(),
+            unfocused_opacity: None,
             background_image: None,
             decorations: Decorations::default(),
             blur: false,
             macos_use_unified_titlebar: false,
             initial_title: None,
+            confirm_before_quit: None,
         }
     }
 }
@@ -81,4 +107,18 @@ impl Window {
     pub fn is_fullscreen(&self) -> bool {
         self.mode == WindowMode::Fullscreen
     }
+
+    /// Opacity of the window background, clamped to `0.0..=1.0`.
+    pub fn effective_opacity(&self, is_focused: bool) -> f32 {
+        let opacity = match self.unfocused_opacity {
+            Some(unfocused_opacity) if !is_focused => unfocused_opacity,
+            _ => self.opacity,
+        };
+        opacity.clamp(0., 1.)
+    }
+
+    /// Whether the window background is ever translucent.
+    pub fn is_transparent(&self) -> bool {
+        self.effective_opacity(true) < 1. || self.effective_opacity(false) < 1.
+    }
 }
@@ -82,6 +82,8 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
         let current_history_size = self.history_size();
         if current_history_size > history_size {
             self.raw.shrink_lines(current_history_size - history_size);
+        } else {
+            self.raw.reserve(history_size - current_history_size);
         }
         self.display_offset = min(self.display_offset, history_size);
         self.max_scroll_limit = history_size;
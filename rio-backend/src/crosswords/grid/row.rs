@@ -166,6 +166,34 @@ impl<T> Row<T> {
         split
     }
 
+    /// Number of squares which may have been modified since the last reset,
+    /// every square after it is equal to the last one of the row.
+    #[inline]
+    pub fn occupied(&self) -> usize {
+        self.occ
+    }
+
+    /// Replace every square from `at` to the end of the row with `template`,
+    /// the cleared squares no longer count as occupied.
+    #[inline]
+    pub fn clear_from(&mut self, at: Column, template: T)
+    where
+        T: Clone + PartialEq,
+    {
+        let at = min(at.0, self.inner.len());
+
+        // Squares after `occ` are already equal to the last one.
+        if at >= self.occ && self.inner.last() == Some(&template) {
+            return;
+        }
+
+        for item in &mut self.inner[at..] {
+            *item = template.clone();
+        }
+
+        self.occ = at;
+    }
+
     #[inline]
     pub fn is_clear(&self) -> bool
     where
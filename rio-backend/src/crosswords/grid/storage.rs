@@ -142,6 +142,16 @@ impl<T> Storage<T> {
         self.len += additional_rows;
     }
 
+    /// Reserve room for `additional` lines, so filling them through
+    /// [`Storage::initialize`] does not reallocate the buffer.
+    pub fn reserve(&mut self, additional: usize) {
+        let missing = (self.len + additional).saturating_sub(self.inner.len());
+        if missing != 0 {
+            // `initialize` grows in chunks of at least `MAX_CACHE_SIZE`.
+            self.inner.reserve(missing + MAX_CACHE_SIZE);
+        }
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.len
@@ -750,6 +760,36 @@ mod tests {
         assert_eq!(storage.inner, expected_storage.inner);
     }
 
+    #[test]
+    fn reserve_then_initialize_does_not_reallocate() {
+        let mut storage: Storage<char> = Storage::with_capacity(3, 1);
+        storage.reserve(2 * MAX_CACHE_SIZE + 1);
+        let capacity = storage.inner.capacity();
+
+        for _ in 0..2 * MAX_CACHE_SIZE + 1 {
+            storage.initialize(1, 1);
+        }
+
+        assert_eq!(storage.len, 2 * MAX_CACHE_SIZE + 4);
+        assert_eq!(storage.inner.capacity(), capacity);
+    }
+
+    #[test]
+    fn shrink_then_initialize_reuses_rows() {
+        let mut storage: Storage<char> = Storage::with_capacity(3, 1);
+        storage.initialize(MAX_CACHE_SIZE, 1);
+        storage.rotate(5);
+        let inner_len = storage.inner.len();
+
+        storage.shrink_lines(MAX_CACHE_SIZE / 2);
+        storage.reserve(MAX_CACHE_SIZE / 2);
+        storage.initialize(MAX_CACHE_SIZE / 2, 1);
+
+        assert_eq!(storage.len, MAX_CACHE_SIZE + 3);
+        assert_eq!(storage.inner.len(), inner_len);
+        assert!(storage.zero < storage.inner.len());
+    }
+
     #[test]
     fn initialize_with_square() {
         use crate::crosswords::Square;
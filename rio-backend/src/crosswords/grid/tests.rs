@@ -353,6 +353,77 @@ fn shrink_reflow_disabled() {
     assert_eq!(grid[Line(0)][Column(1)], cell('2'));
 }
 
+#[test]
+fn row_occupied() {
+    let mut row = Row::<usize>::new(10);
+    assert_eq!(row.occupied(), 0);
+
+    row[Column(5)] = 1;
+    assert_eq!(row.occupied(), 6);
+
+    // Clearing the tail shrinks the occupied squares.
+    row.clear_from(Column(3), 0);
+    assert_eq!(row.occupied(), 3);
+    assert!(row.is_clear());
+
+    // Clearing past the occupied squares with an equal square is a noop.
+    row.clear_from(Column(8), 0);
+    assert_eq!(row.occupied(), 3);
+
+    // A different square makes everything up to it occupied.
+    row.clear_from(Column(8), 2);
+    assert_eq!(row.occupied(), 8);
+    assert_eq!(row[Column(9)], 2);
+}
+
+// Shrinking history below the display offset keeps the viewport inside it.
+#[test]
+fn shrink_history_clamps_display_offset() {
+    let mut grid = Grid::<usize>::new(2, 1, 20);
+    for i in 1..=22 {
+        grid.scroll_up::<usize>(&(Line(0)..Line(2)), 1);
+        grid[Line(1)][Column(0)] = i;
+    }
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.history_size(), 20);
+    assert_eq!(grid.display_offset(), 20);
+
+    grid.update_history(5);
+
+    assert_eq!(grid.history_size(), 5);
+    assert_eq!(grid.display_offset(), 5);
+    // The most recent lines are kept.
+    assert_eq!(grid[Line(-5)][Column(0)], 16);
+    assert_eq!(grid[Line(-1)][Column(0)], 20);
+    assert_eq!(grid[Line(1)][Column(0)], 22);
+
+    grid.scroll_display(Scroll::Delta(3));
+    assert_eq!(grid.display_offset(), 5);
+}
+
+// Growing history again fills it with new lines only.
+#[test]
+fn grow_history_then_fill() {
+    let mut grid = Grid::<usize>::new(2, 1, 10);
+    for i in 1..=12 {
+        grid.scroll_up::<usize>(&(Line(0)..Line(2)), 1);
+        grid[Line(1)][Column(0)] = i;
+    }
+    grid.update_history(3);
+    grid.update_history(30);
+    assert_eq!(grid.history_size(), 3);
+
+    for i in 13..=50 {
+        grid.scroll_up::<usize>(&(Line(0)..Line(2)), 1);
+        grid[Line(1)][Column(0)] = i;
+    }
+
+    assert_eq!(grid.history_size(), 30);
+    for line in -30..2 {
+        assert_eq!(grid[Line(line)][Column(0)], (50 - 1 + line) as usize);
+    }
+}
+
 // https://github.com/rust-lang/rust-clippy/pull/6375
 #[allow(clippy::all)]
 fn cell(c: char) -> Square {
@@ -30,7 +30,7 @@ use crate::ansi::mode::NamedPrivateMode;
 use crate::ansi::mode::PrivateMode;
 use crate::ansi::sixel;
 use crate::ansi::{
-    mode::Mode as AnsiMode, ClearMode, CursorShape, KeyboardModes,
+    mode::Mode as AnsiMode, ClearMode, CursorShape, CursorStyle, KeyboardModes,
     KeyboardModesApplyBehavior, LineClearMode, TabulationClearMode,
 };
 use crate::clipboard::ClipboardType;
@@ -52,14 +52,17 @@ use grid::row::Row;
 use pos::{
     Boundary, CharsetIndex, Column, Cursor, CursorState, Direction, Line, Pos, Side,
 };
+use rio_window::keyboard::ModifiersState;
 use square::{Hyperlink, LineLength, Square};
 use std::collections::HashSet;
 use std::mem;
 use std::ops::{Index, IndexMut, Range};
 use std::option::Option;
+use std::path::{Path, PathBuf};
 use std::ptr;
 use std::sync::Arc;
 use sugarloaf::{GraphicData, MAX_GRAPHIC_DIMENSIONS};
+use teletypewriter::WinsizeBuilder;
 use tracing::{debug, info, trace, warn};
 use unicode_width::UnicodeWidthChar;
 use vi_mode::{ViModeCursor, ViMotion};
@@ -72,8 +75,39 @@ pub const MIN_LINES: usize = 1;
 /// Max. number of graphics stored in a single cell.
 const MAX_GRAPHICS_PER_CELL: usize = 20;
 
+/// Content and style of a single square in a [`GridSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotSquare {
+    pub c: char,
+    pub fg: AnsiColor,
+    pub bg: AnsiColor,
+    pub flags: square::Flags,
+}
+
+impl From<&Square> for SnapshotSquare {
+    fn from(square: &Square) -> Self {
+        Self {
+            c: square.c,
+            fg: square.fg,
+            bg: square.bg,
+            flags: square.flags,
+        }
+    }
+}
+
+/// Owned copy of the visible screen state, meant to assert the exact
+/// screen content in tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridSnapshot {
+    /// Visible rows from top to bottom.
+    pub rows: Vec<Vec<SnapshotSquare>>,
+    /// Cursor position in the grid, not affected by the display offset.
+    pub cursor: Pos,
+    pub mode: Mode,
+}
+
 bitflags! {
-    #[derive(Debug, Copy, Clone)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
      pub struct Mode: u32 {
         const NONE                    = 0;
         const SHOW_CURSOR             = 1;
@@ -99,12 +133,20 @@ bitflags! {
         const REPORT_ALTERNATE_KEYS   = 1 << 20;
         const REPORT_ALL_KEYS_AS_ESC  = 1 << 21;
         const REPORT_ASSOCIATED_TEXT  = 1 << 22;
+        const LEFT_RIGHT_MARGIN       = 1 << 23;
+        const REVERSE_WRAP            = 1 << 24;
         const MOUSE_MODE = Self::MOUSE_REPORT_CLICK.bits() | Self::MOUSE_MOTION.bits() | Self::MOUSE_DRAG.bits();
         const KITTY_KEYBOARD_PROTOCOL = Self::DISAMBIGUATE_ESC_CODES.bits()
                                       | Self::REPORT_EVENT_TYPES.bits()
                                       | Self::REPORT_ALTERNATE_KEYS.bits()
                                       | Self::REPORT_ALL_KEYS_AS_ESC.bits()
                                       | Self::REPORT_ASSOCIATED_TEXT.bits();
+        // Modes saved when entering the alternate screen and restored on exit.
+        const ALT_SCREEN_SAVED_MODES  = Self::SHOW_CURSOR.bits()
+                                      | Self::APP_CURSOR.bits()
+                                      | Self::APP_KEYPAD.bits()
+                                      | Self::ORIGIN.bits()
+                                      | Self::LINE_WRAP.bits();
         const ANY                    = u32::MAX;
 
         const SIXEL_DISPLAY             = 1 << 28;
@@ -392,17 +434,25 @@ where
     pub grid: Grid<Square>,
     inactive_grid: Grid<Square>,
     scroll_region: Range<Line>,
+    // Left and right margins, only used while DECLRMM is enabled.
+    horizontal_region: Range<Column>,
     tabs: TabStops,
     event_proxy: U,
     pub selection: Option<Selection>,
     #[allow(dead_code)]
     colors: List,
     pub title: String,
+    // Working directory reported by the shell through OSC 7.
+    current_directory: Option<PathBuf>,
     damage: TermDamageState,
     pub graphics: Graphics,
-    pub cursor_shape: CursorShape,
-    pub default_cursor_shape: CursorShape,
-    pub blinking_cursor: bool,
+    // Cursor style set by DECSCUSR, or the default one from the config.
+    cursor_style: CursorStyle,
+    default_cursor_style: CursorStyle,
+    /// Jump to the bottom of the history when output is printed.
+    pub scroll_on_output: bool,
+    /// Jump to the bottom of the history when a key is sent to the PTY.
+    pub scroll_on_keystroke: bool,
     pub window_id: WindowId,
     pub route_id: usize,
     title_stack: Vec<String>,
@@ -413,6 +463,9 @@ where
 
     // Currently inactive keyboard mode stack.
     inactive_keyboard_mode_stack: Vec<KeyboardModes>,
+
+    // Primary screen modes saved while the alternate screen is active.
+    saved_primary_modes: Mode,
 }
 
 impl<U: EventListener> Crosswords<U> {
@@ -444,10 +497,12 @@ impl<U: EventListener> Crosswords<U> {
             inactive_grid: alt,
             active_charset: CharsetIndex::default(),
             scroll_region,
+            horizontal_region: Column(0)..Column(cols),
             event_proxy,
             colors,
             hyperlink_re: regex::Regex::new(url_regex).unwrap(),
             title: String::from(""),
+            current_directory: None,
             tabs: TabStops::new(cols),
             mode: Mode::SHOW_CURSOR
                 | Mode::LINE_WRAP
@@ -455,17 +510,61 @@ impl<U: EventListener> Crosswords<U> {
                 | Mode::URGENCY_HINTS,
             damage: TermDamageState::new(cols, rows),
             graphics: Graphics::new(&dimensions),
-            default_cursor_shape: cursor_shape,
-            cursor_shape,
-            blinking_cursor: false,
+            cursor_style: CursorStyle {
+                shape: cursor_shape,
+                blinking: false,
+            },
+            default_cursor_style: CursorStyle {
+                shape: cursor_shape,
+                blinking: false,
+            },
+            scroll_on_output: false,
+            scroll_on_keystroke: true,
             window_id,
             route_id,
             title_stack: Default::default(),
             keyboard_mode_stack: Default::default(),
             inactive_keyboard_mode_stack: Default::default(),
+            saved_primary_modes: Mode::empty(),
+        }
+    }
+
+    /// Square whose colors, flags and extras are copied into every square
+    /// written at the cursor.
+    #[inline]
+    pub fn cursor_template(&self) -> &Square {
+        &self.grid.cursor.template
+    }
+
+    /// Mutable access to the cursor template, updated by SGR.
+    #[inline]
+    pub fn cursor_template_mut(&mut self) -> &mut Square {
+        &mut self.grid.cursor.template
+    }
+
+    /// Current cursor style.
+    #[inline]
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.cursor_style
+    }
+
+    /// Change the cursor style, the UI is notified when blinking changes.
+    pub fn set_cursor_style(&mut self, style: CursorStyle) {
+        let blinking_changed = self.cursor_style.blinking != style.blinking;
+        self.cursor_style = style;
+        if blinking_changed {
+            self.event_proxy
+                .send_event(RioEvent::CursorBlinkingChange, self.window_id);
         }
     }
 
+    /// Set the cursor style from the config, restored by DECSCUSR 0 and
+    /// terminal resets.
+    pub fn set_default_cursor_style(&mut self, style: CursorStyle) {
+        self.default_cursor_style = style;
+        self.cursor_style = style;
+    }
+
     pub fn mark_fully_damaged(&mut self) {
         self.damage.is_fully_damaged = true;
     }
@@ -480,9 +579,59 @@ impl<U: EventListener> Crosswords<U> {
         self.grid.display_offset()
     }
 
+    /// Converts a position on screen, where line 0 is the top of the
+    /// viewport, into a position in the buffer. Lines outside of the
+    /// viewport are clamped to the buffer.
+    pub fn viewport_to_point(&self, viewport: Pos) -> Pos {
+        let row = viewport.row - self.grid.display_offset();
+        Pos::new(
+            row.grid_clamp(self, Boundary::Grid),
+            std::cmp::min(viewport.col, self.grid.last_column()),
+        )
+    }
+
+    /// Converts a position in the buffer into a position on screen,
+    /// returns `None` when the line is scrolled out of the viewport.
+    pub fn point_to_viewport(&self, buffer: Pos) -> Option<Pos> {
+        let row = buffer.row + self.grid.display_offset();
+        if row < 0 || row.0 >= self.grid.screen_lines() as i32 {
+            return None;
+        }
+
+        Some(Pos::new(row, buffer.col))
+    }
+
+    /// Scrolls back to the bottom before a key is sent to the PTY, unless
+    /// disabled by `scroll_on_keystroke`.
     #[inline]
-    pub fn clear_saved_history(&mut self) {
-        self.clear_screen(ClearMode::Saved);
+    pub fn scroll_to_bottom_on_keystroke(&mut self) {
+        if self.scroll_on_keystroke && self.grid.display_offset() != 0 {
+            self.scroll_display(Scroll::Bottom);
+        }
+    }
+
+    /// Drops every line of the scrollback and scrolls back to the bottom,
+    /// the visible screen is kept as it is.
+    pub fn clear_history(&mut self) {
+        // We have no history to clear.
+        if self.history_size() == 0 {
+            return;
+        }
+
+        self.grid.clear_history();
+
+        self.vi_mode_cursor.pos.row = self
+            .vi_mode_cursor
+            .pos
+            .row
+            .grid_clamp(&self.grid, Boundary::Cursor);
+
+        self.selection = self
+            .selection
+            .take()
+            .filter(|s| !s.intersects_range(..Line(0)));
+
+        self.mark_fully_damaged();
     }
 
     #[inline]
@@ -535,14 +684,36 @@ impl<U: EventListener> Crosswords<U> {
             .send_event(RioEvent::CloseTerminal(self.route_id), self.window_id);
     }
 
+    /// Window size reported to the PTY, the pixel dimensions are the ones
+    /// of the cells.
+    pub fn winsize(&self) -> WinsizeBuilder {
+        let cols = self.grid.columns();
+        let rows = self.grid.screen_lines();
+        WinsizeBuilder {
+            cols: cols as u16,
+            rows: rows as u16,
+            width: (self.graphics.cell_width * cols as f32) as u16,
+            height: (self.graphics.cell_height * rows as f32) as u16,
+        }
+    }
+
+    /// Resizes the grid, a `RioEvent::Resize` is sent whenever the size
+    /// reported to the PTY changes.
     pub fn resize<S: Dimensions>(&mut self, size: S) {
         let old_cols = self.grid.columns();
         let old_lines = self.grid.screen_lines();
         let num_cols = size.columns();
         let num_lines = size.screen_lines();
+        let old_winsize = self.winsize();
+
+        // Update size information for graphics.
+        self.graphics.resize(&size);
 
         if old_cols == num_cols && old_lines == num_lines {
             info!("Crosswords::resize dimensions unchanged");
+            if self.winsize() != old_winsize {
+                self.send_resize();
+            }
             return;
         }
         // Move vi mode cursor with the content.
@@ -581,12 +752,20 @@ impl<U: EventListener> Crosswords<U> {
 
         // Reset scrolling region.
         self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.horizontal_region = Column(0)..Column(self.grid.columns());
 
         // Resize damage information.
         self.damage.resize(num_cols, num_lines);
 
-        // Update size information for graphics.
-        self.graphics.resize(&size);
+        self.send_resize();
+    }
+
+    #[inline]
+    fn send_resize(&self) {
+        self.event_proxy.send_event(
+            RioEvent::Resize(self.route_id, self.winsize()),
+            self.window_id,
+        );
     }
 
     /// Toggle the vi mode.
@@ -719,6 +898,32 @@ impl<U: EventListener> Crosswords<U> {
         &self.semantic_escape_chars
     }
 
+    /// Moves the cursor from the first column to the last column of the
+    /// line above when reverse wrap is enabled and that line soft wrapped.
+    /// Returns whether the cursor moved.
+    fn reverse_wrap(&mut self) -> bool {
+        let cursor = self.grid.cursor.pos;
+        if !self.mode.contains(Mode::REVERSE_WRAP) || cursor.col > 0 || cursor.row <= 0 {
+            return false;
+        }
+
+        let last_column = self.grid.last_column();
+        let previous: Line = cursor.row - 1;
+        if !self.grid[previous][last_column]
+            .flags
+            .contains(square::Flags::WRAPLINE)
+        {
+            return false;
+        }
+
+        self.damage.damage_line(cursor.row.0 as usize, 0, 0);
+        self.damage
+            .damage_line(previous.0 as usize, last_column.0, last_column.0);
+        self.grid.cursor.pos = Pos::new(previous, last_column);
+        self.grid.cursor.should_wrap = false;
+        true
+    }
+
     #[inline]
     pub fn wrapline(&mut self) {
         if !self.mode.contains(Mode::LINE_WRAP) {
@@ -770,6 +975,11 @@ impl<U: EventListener> Crosswords<U> {
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_horizontal_region(region, lines, false);
+            return;
+        }
+
         // Scroll selection.
         self.selection = self
             .selection
@@ -798,6 +1008,11 @@ impl<U: EventListener> Crosswords<U> {
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_horizontal_region(region, lines, true);
+            return;
+        }
+
         // Scroll selection.
         self.selection = self
             .selection
@@ -991,6 +1206,26 @@ impl<U: EventListener> Crosswords<U> {
         visible_rows
     }
 
+    /// Snapshot of the squares currently on screen, the cursor position
+    /// and the active modes.
+    pub fn snapshot(&self) -> GridSnapshot {
+        let display_offset = self.grid.display_offset() as i32;
+        let rows = (0..self.grid.screen_lines() as i32)
+            .map(|line| {
+                self.grid[Line(line - display_offset)][..]
+                    .iter()
+                    .map(SnapshotSquare::from)
+                    .collect()
+            })
+            .collect();
+
+        GridSnapshot {
+            rows,
+            cursor: self.grid.cursor.pos,
+            mode: self.mode,
+        }
+    }
+
     fn deccolm(&mut self)
     where
         U: EventListener,
@@ -998,6 +1233,8 @@ impl<U: EventListener> Crosswords<U> {
         // Setting 132 column font makes no sense, but run the other side effects.
         // Clear scrolling region.
         self.set_scrolling_region(1, None);
+        self.mode.remove(Mode::LEFT_RIGHT_MARGIN);
+        self.horizontal_region = Column(0)..Column(self.grid.columns());
 
         // Clear grid.
         self.grid.reset_region(..);
@@ -1008,9 +1245,143 @@ impl<U: EventListener> Crosswords<U> {
         self.mode
     }
 
+    /// Working directory last reported by the shell through OSC 7.
+    #[inline]
+    pub fn current_directory(&self) -> Option<&Path> {
+        self.current_directory.as_deref()
+    }
+
+    /// Whether DECLRMM is enabled with margins narrower than the screen.
+    #[inline]
+    fn has_horizontal_margins(&self) -> bool {
+        self.mode.contains(Mode::LEFT_RIGHT_MARGIN)
+            && self.horizontal_region != (Column(0)..Column(self.grid.columns()))
+    }
+
+    /// Right bound for character insertion and deletion at the cursor, `None`
+    /// when the cursor is outside of the left and right margins.
+    #[inline]
+    fn cursor_right_bound(&self) -> Option<usize> {
+        if !self.has_horizontal_margins() {
+            return Some(self.grid.columns());
+        }
+
+        self.horizontal_region
+            .contains(&self.grid.cursor.pos.col)
+            .then_some(self.horizontal_region.end.0)
+    }
+
+    /// Scrolls only the squares between the left and right margins.
+    fn scroll_horizontal_region(&mut self, region: Range<Line>, lines: usize, up: bool) {
+        let columns = self.horizontal_region.clone();
+        let bg = self.grid.cursor.template.bg;
+        let height = (region.end - region.start).0 as usize;
+
+        for offset in 0..height {
+            let (dest, src) = if up {
+                (region.start + offset, region.start + (offset + lines))
+            } else {
+                (region.end - (offset + 1), region.end - (offset + 1 + lines))
+            };
+
+            for column in columns.start.0..columns.end.0 {
+                self.grid[dest][Column(column)] = if offset + lines < height {
+                    self.grid[src][Column(column)].clone()
+                } else {
+                    bg.into()
+                };
+            }
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Bytes reporting a focus change to the PTY, `None` unless focus
+    /// reporting (`CSI ? 1004 h`) is enabled.
+    pub fn focus_report(&self, focused: bool) -> Option<Vec<u8>> {
+        if !self.mode.contains(Mode::FOCUS_IN_OUT) {
+            return None;
+        }
+
+        let report: &[u8] = if focused { b"\x1b[I" } else { b"\x1b[O" };
+        Some(report.to_vec())
+    }
+
+    /// Encodes a mouse event for the PTY according to the active mouse
+    /// reporting mode (SGR, UTF-8 or X10).
+    ///
+    /// Returns `None` when mouse reporting is disabled or when the position
+    /// can't be represented by the active encoding.
+    pub fn mouse_report(
+        &self,
+        button: u8,
+        pos: Pos,
+        pressed: bool,
+        modifiers: ModifiersState,
+    ) -> Option<Vec<u8>> {
+        // Assure the mouse pos is not in the scrollback.
+        if !self.mode.intersects(Mode::MOUSE_MODE) || pos.row < 0 {
+            return None;
+        }
+
+        let mut mods = 0;
+        if modifiers.shift_key() {
+            mods += 4;
+        }
+        if modifiers.alt_key() {
+            mods += 8;
+        }
+        if modifiers.control_key() {
+            mods += 16;
+        }
+
+        if self.mode.contains(Mode::SGR_MOUSE) {
+            let c = if pressed { 'M' } else { 'm' };
+            let msg = format!(
+                "\x1b[<{};{};{}{}",
+                button + mods,
+                pos.col + 1,
+                pos.row + 1,
+                c
+            );
+            return Some(msg.into_bytes());
+        }
+
+        // Legacy encodings can't tell which button was released.
+        let button = if pressed { button + mods } else { 3 + mods };
+        let utf8 = self.mode.contains(Mode::UTF8_MOUSE);
+        let max_point = if utf8 { 2015 } else { 223 };
+        if pos.row >= max_point || pos.col >= max_point {
+            return None;
+        }
+
+        let mut msg = vec![b'\x1b', b'[', b'M', 32 + button];
+
+        let mouse_pos_encode = |pos: usize| -> Vec<u8> {
+            let pos = 32 + 1 + pos;
+            let first = 0xC0 + pos / 64;
+            let second = 0x80 + (pos & 63);
+            vec![first as u8, second as u8]
+        };
+
+        if utf8 && pos.col >= Column(95) {
+            msg.append(&mut mouse_pos_encode(pos.col.0));
+        } else {
+            msg.push(32 + 1 + pos.col.0 as u8);
+        }
+
+        if utf8 && pos.row >= 95 {
+            msg.append(&mut mouse_pos_encode(pos.row.0 as usize));
+        } else {
+            msg.push(32 + 1 + pos.row.0 as u8);
+        }
+
+        Some(msg)
+    }
+
     #[inline]
     pub fn cursor(&self) -> CursorState {
-        let mut content = self.cursor_shape;
+        let mut content = self.cursor_style.shape;
         let vi_mode = self.mode.contains(Mode::VI);
         let scroll = self.display_offset() as i32;
         let mut pos = if vi_mode {
@@ -1039,7 +1410,7 @@ impl<U: EventListener> Crosswords<U> {
 
         // If is not using app cursor then use default
         if content != CursorShape::Hidden && !self.mode.contains(Mode::ALT_SCREEN) {
-            content = self.default_cursor_shape;
+            content = self.default_cursor_style.shape;
         }
 
         CursorState { pos, content }
@@ -1055,6 +1426,12 @@ impl<U: EventListener> Crosswords<U> {
 
             // Reset alternate screen contents.
             self.inactive_grid.reset_region(..);
+
+            self.saved_primary_modes = self.mode & Mode::ALT_SCREEN_SAVED_MODES;
+        } else {
+            // Restore the modes the primary screen had before entering the alternate one.
+            self.mode.remove(Mode::ALT_SCREEN_SAVED_MODES);
+            self.mode.insert(self.saved_primary_modes);
         }
 
         mem::swap(
@@ -1172,8 +1549,10 @@ impl<U: EventListener> Crosswords<U> {
                 }
             }
 
-            if cell.c == '\t' {
+            if cell.flags.contains(square::Flags::TAB) {
                 tab_mode = true;
+                text.push('\t');
+                continue;
             }
 
             if !cell.flags.intersects(
@@ -1214,7 +1593,6 @@ impl<U: EventListener> Crosswords<U> {
 
     #[inline]
     fn set_keyboard_mode(&mut self, mode: Mode, apply: KeyboardModesApplyBehavior) {
-        // println!("{:?}", mode);
         let active_mode = self.mode & Mode::KITTY_KEYBOARD_PROTOCOL;
         self.mode &= !Mode::KITTY_KEYBOARD_PROTOCOL;
         let new_mode = match apply {
@@ -1385,12 +1763,17 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
             NamedPrivateMode::AlternateScroll => self.mode.insert(Mode::ALTERNATE_SCROLL),
             NamedPrivateMode::LineWrap => self.mode.insert(Mode::LINE_WRAP),
+            NamedPrivateMode::ReverseWrap => self.mode.insert(Mode::REVERSE_WRAP),
             NamedPrivateMode::Origin => self.mode.insert(Mode::ORIGIN),
+            NamedPrivateMode::LeftRightMargin => {
+                self.mode.insert(Mode::LEFT_RIGHT_MARGIN)
+            }
             NamedPrivateMode::ColumnMode => self.deccolm(),
             NamedPrivateMode::BlinkingCursor => {
-                self.blinking_cursor = true;
-                self.event_proxy
-                    .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+                self.set_cursor_style(CursorStyle {
+                    blinking: true,
+                    ..self.cursor_style
+                });
             }
             NamedPrivateMode::SyncUpdate => (),
         }
@@ -1457,13 +1840,18 @@ impl<U: EventListener> Handler for Crosswords<U> {
             NamedPrivateMode::Utf8Mouse => self.mode.remove(Mode::UTF8_MOUSE),
             NamedPrivateMode::AlternateScroll => self.mode.remove(Mode::ALTERNATE_SCROLL),
             NamedPrivateMode::LineWrap => self.mode.remove(Mode::LINE_WRAP),
+            NamedPrivateMode::ReverseWrap => self.mode.remove(Mode::REVERSE_WRAP),
             NamedPrivateMode::Origin => self.mode.remove(Mode::ORIGIN),
+            NamedPrivateMode::LeftRightMargin => {
+                self.mode.remove(Mode::LEFT_RIGHT_MARGIN);
+                self.horizontal_region = Column(0)..Column(self.grid.columns());
+            }
             NamedPrivateMode::ColumnMode => self.deccolm(),
             NamedPrivateMode::BlinkingCursor => {
-                // TODO: Update it
-                // self.blinking_cursor = false;
-                // self.event_proxy
-                // .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+                self.set_cursor_style(CursorStyle {
+                    blinking: false,
+                    ..self.cursor_style
+                });
             }
             NamedPrivateMode::SyncUpdate => (),
         }
@@ -1478,8 +1866,14 @@ impl<U: EventListener> Handler for Crosswords<U> {
                     self.mode.contains(Mode::APP_CURSOR).into()
                 }
                 NamedPrivateMode::Origin => self.mode.contains(Mode::ORIGIN).into(),
+                NamedPrivateMode::LeftRightMargin => {
+                    self.mode.contains(Mode::LEFT_RIGHT_MARGIN).into()
+                }
                 NamedPrivateMode::LineWrap => self.mode.contains(Mode::LINE_WRAP).into(),
-                NamedPrivateMode::BlinkingCursor => self.blinking_cursor.into(),
+                NamedPrivateMode::ReverseWrap => {
+                    self.mode.contains(Mode::REVERSE_WRAP).into()
+                }
+                NamedPrivateMode::BlinkingCursor => self.cursor_style.blinking.into(),
                 NamedPrivateMode::ShowCursor => {
                     self.mode.contains(Mode::SHOW_CURSOR).into()
                 }
@@ -1582,14 +1976,23 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn move_backward(&mut self, cols: Column) {
-        let column = self.grid.cursor.pos.col.saturating_sub(cols.0);
+        let mut cols = cols.0;
+        loop {
+            let column = self.grid.cursor.pos.col.saturating_sub(cols);
+            cols -= self.grid.cursor.pos.col.0 - column;
 
-        let cursor_line = self.grid.cursor.pos.row.0 as usize;
-        self.damage
-            .damage_line(cursor_line, column, self.grid.cursor.pos.col.0);
+            let cursor_line = self.grid.cursor.pos.row.0 as usize;
+            self.damage
+                .damage_line(cursor_line, column, self.grid.cursor.pos.col.0);
 
-        self.grid.cursor.pos.col = Column(column);
-        self.grid.cursor.should_wrap = false;
+            self.grid.cursor.pos.col = Column(column);
+            self.grid.cursor.should_wrap = false;
+
+            if cols == 0 || !self.reverse_wrap() {
+                break;
+            }
+            cols -= 1;
+        }
     }
 
     #[inline]
@@ -1625,7 +2028,12 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn goto_col(&mut self, col: Column) {
-        self.goto(self.grid.cursor.pos.row, col)
+        // `goto` expects a line relative to the scrolling region in origin mode.
+        let mut line = self.grid.cursor.pos.row;
+        if self.mode.contains(Mode::ORIGIN) {
+            line -= self.scroll_region.start.0;
+        }
+        self.goto(line, col)
     }
 
     #[inline]
@@ -1638,6 +2046,12 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
         }
 
+        // DECALN also resets the margins and homes the cursor.
+        self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.horizontal_region = Column(0)..Column(self.grid.columns());
+        self.grid.cursor.pos = Pos::default();
+        self.grid.cursor.should_wrap = false;
+
         self.mark_fully_damaged();
     }
 
@@ -1714,23 +2128,31 @@ impl<U: EventListener> Handler for Crosswords<U> {
         let line = cursor.pos.row;
         self.damage.damage_line(line.0 as usize, start.0, end.0);
         let row = &mut self.grid[line];
-        for cell in &mut row[start..end] {
-            *cell = bg.into();
+        if end.0 == row.len() {
+            row.clear_from(start, bg.into());
+        } else {
+            for cell in &mut row[start..end] {
+                *cell = bg.into();
+            }
         }
     }
 
     #[inline]
     fn delete_chars(&mut self, count: usize) {
-        let columns = self.grid.columns();
+        // Characters are only deleted up to the right margin.
+        let Some(right) = self.cursor_right_bound() else {
+            return;
+        };
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
 
+        let start = cursor.pos.col.0;
+
         // Ensure deleting within terminal bounds.
-        let count = std::cmp::min(count, columns);
+        let count = std::cmp::min(count, right - start);
 
-        let start = cursor.pos.col.0;
-        let end = std::cmp::min(start + count, columns - 1);
-        let num_cells = columns - end;
+        let end = start + count;
+        let num_cells = right - end;
 
         let line = cursor.pos.row;
         self.damage
@@ -1741,10 +2163,9 @@ impl<U: EventListener> Handler for Crosswords<U> {
             row.swap(start + offset, end + offset);
         }
 
-        // Clear last `count` cells in the row. If deleting 1 char, need to delete
-        // 1 cell.
-        let end = columns - count;
-        for cell in &mut row[end..] {
+        // Clear last `count` cells before the margin. If deleting 1 char, need
+        // to delete 1 cell.
+        for cell in &mut row[right - count..right] {
             *cell = bg.into();
         }
     }
@@ -1765,15 +2186,19 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn insert_blank(&mut self, count: usize) {
+        // Squares are only shifted up to the right margin.
+        let Some(right) = self.cursor_right_bound() else {
+            return;
+        };
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
 
         // Ensure inserting within terminal bounds
-        let count = std::cmp::min(count, self.grid.columns() - cursor.pos.col.0);
+        let count = std::cmp::min(count, right - cursor.pos.col.0);
 
         let source = cursor.pos.col;
         let destination = cursor.pos.col.0 + count;
-        let num_cells = self.grid.columns() - destination;
+        let num_cells = right - destination;
 
         let line = cursor.pos.row;
         self.damage
@@ -1811,10 +2236,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
             std::mem::swap(&mut self.grid, &mut self.inactive_grid);
         }
         self.active_charset = Default::default();
-        self.cursor_shape = self.default_cursor_shape;
+        self.cursor_style = self.default_cursor_style;
         self.grid.reset();
         self.inactive_grid.reset();
         self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.horizontal_region = Column(0)..Column(self.grid.columns());
         self.tabs = TabStops::new(self.grid.columns());
         self.title_stack = Vec::new();
         self.keyboard_mode_stack = Vec::new();
@@ -1823,6 +2249,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
         self.vi_mode_cursor = Default::default();
         self.keyboard_mode_stack = Default::default();
         self.inactive_keyboard_mode_stack = Default::default();
+        self.saved_primary_modes = Mode::empty();
 
         // Preserve vi mode across resets.
         self.mode &= Mode::VI;
@@ -1836,67 +2263,61 @@ impl<U: EventListener> Handler for Crosswords<U> {
     #[inline]
     fn terminal_attribute(&mut self, attr: Attr) {
         trace!("Setting attribute: {:?}", attr);
-        let cursor = &mut self.grid.cursor;
+        let template = self.cursor_template_mut();
         match attr {
-            Attr::Foreground(color) => cursor.template.fg = color,
-            Attr::Background(color) => cursor.template.bg = color,
-            Attr::UnderlineColor(color) => cursor.template.set_underline_color(color),
+            Attr::Foreground(color) => template.fg = color,
+            Attr::Background(color) => template.bg = color,
+            Attr::UnderlineColor(color) => template.set_underline_color(color),
             Attr::Reset => {
-                cursor.template.fg = AnsiColor::Named(NamedColor::Foreground);
-                cursor.template.bg = AnsiColor::Named(NamedColor::Background);
-                cursor.template.flags = square::Flags::empty();
-                cursor.template.set_underline_color(None);
-            }
-            Attr::Reverse => cursor.template.flags.insert(square::Flags::INVERSE),
-            Attr::CancelReverse => cursor.template.flags.remove(square::Flags::INVERSE),
-            Attr::Bold => cursor.template.flags.insert(square::Flags::BOLD),
-            Attr::CancelBold => cursor.template.flags.remove(square::Flags::BOLD),
-            Attr::Dim => cursor.template.flags.insert(square::Flags::DIM),
-            Attr::CancelBoldDim => cursor
-                .template
+                template.fg = AnsiColor::Named(NamedColor::Foreground);
+                template.bg = AnsiColor::Named(NamedColor::Background);
+                template.flags = square::Flags::empty();
+                template.set_underline_color(None);
+            }
+            Attr::Reverse => template.flags.insert(square::Flags::INVERSE),
+            Attr::CancelReverse => template.flags.remove(square::Flags::INVERSE),
+            Attr::Bold => template.flags.insert(square::Flags::BOLD),
+            Attr::CancelBold => template.flags.remove(square::Flags::BOLD),
+            Attr::Dim => template.flags.insert(square::Flags::DIM),
+            Attr::CancelBoldDim => template
                 .flags
                 .remove(square::Flags::BOLD | square::Flags::DIM),
-            Attr::Italic => cursor.template.flags.insert(square::Flags::ITALIC),
-            Attr::CancelItalic => cursor.template.flags.remove(square::Flags::ITALIC),
+            Attr::Italic => template.flags.insert(square::Flags::ITALIC),
+            Attr::CancelItalic => template.flags.remove(square::Flags::ITALIC),
             Attr::Underline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor.template.flags.insert(square::Flags::UNDERLINE);
+                template.flags.remove(square::Flags::ALL_UNDERLINES);
+                template.flags.insert(square::Flags::UNDERLINE);
             }
             Attr::DoubleUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DOUBLE_UNDERLINE);
+                template.flags.remove(square::Flags::ALL_UNDERLINES);
+                template.flags.insert(square::Flags::DOUBLE_UNDERLINE);
             }
             Attr::Undercurl => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor.template.flags.insert(square::Flags::UNDERCURL);
+                template.flags.remove(square::Flags::ALL_UNDERLINES);
+                template.flags.insert(square::Flags::UNDERCURL);
             }
             Attr::DottedUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DOTTED_UNDERLINE);
+                template.flags.remove(square::Flags::ALL_UNDERLINES);
+                template.flags.insert(square::Flags::DOTTED_UNDERLINE);
             }
             Attr::DashedUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DASHED_UNDERLINE);
+                template.flags.remove(square::Flags::ALL_UNDERLINES);
+                template.flags.insert(square::Flags::DASHED_UNDERLINE);
             }
-            Attr::CancelUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES)
+            Attr::CancelUnderline => template.flags.remove(square::Flags::ALL_UNDERLINES),
+            Attr::Hidden => template.flags.insert(square::Flags::HIDDEN),
+            Attr::CancelHidden => template.flags.remove(square::Flags::HIDDEN),
+            Attr::Strike => template.flags.insert(square::Flags::STRIKEOUT),
+            Attr::CancelStrike => template.flags.remove(square::Flags::STRIKEOUT),
+            Attr::BlinkSlow => {
+                template.flags.remove(square::Flags::ALL_BLINKS);
+                template.flags.insert(square::Flags::BLINK_SLOW);
             }
-            Attr::Hidden => cursor.template.flags.insert(square::Flags::HIDDEN),
-            Attr::CancelHidden => cursor.template.flags.remove(square::Flags::HIDDEN),
-            Attr::Strike => cursor.template.flags.insert(square::Flags::STRIKEOUT),
-            Attr::CancelStrike => cursor.template.flags.remove(square::Flags::STRIKEOUT),
-            _ => {
-                warn!("Term got unhandled attr: {:?}", attr);
+            Attr::BlinkFast => {
+                template.flags.remove(square::Flags::ALL_BLINKS);
+                template.flags.insert(square::Flags::BLINK_FAST);
             }
+            Attr::CancelBlink => template.flags.remove(square::Flags::ALL_BLINKS),
         }
     }
 
@@ -1905,21 +2326,19 @@ impl<U: EventListener> Handler for Crosswords<U> {
     }
 
     #[inline]
-    fn set_cursor_style(&mut self, style: Option<CursorShape>, _blinking: bool) {
-        if let Some(cursor_shape) = style {
-            self.cursor_shape = cursor_shape;
-        } else {
-            self.cursor_shape = self.default_cursor_shape;
-        }
+    fn set_current_directory(&mut self, path: PathBuf) {
+        self.current_directory = Some(path);
+    }
 
-        // self.blinking_cursor = blinking;
-        // self.event_proxy
-        //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+    #[inline]
+    fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
+        let style = style.unwrap_or(self.default_cursor_style);
+        Crosswords::set_cursor_style(self, style);
     }
 
     #[inline]
     fn set_cursor_shape(&mut self, shape: CursorShape) {
-        self.cursor_shape = shape;
+        self.cursor_style.shape = shape;
     }
 
     #[inline]
@@ -1966,10 +2385,16 @@ impl<U: EventListener> Handler for Crosswords<U> {
     #[inline(never)]
     fn input(&mut self, c: char) {
         let width = match c.width() {
+            // NUL has no width but must not be stored as a zero-width glyph.
+            Some(_) if c.is_control() => return,
             Some(width) => width,
             None => return,
         };
 
+        if self.scroll_on_output && self.grid.display_offset() != 0 {
+            self.scroll_display(Scroll::Bottom);
+        }
+
         // Handle zero-width characters.
         if width == 0 {
             // // Get previous column.
@@ -2010,6 +2435,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
         if width == 1 {
             self.write_at_cursor(c);
         } else {
+            // A wide glyph and its spacer can never fit in a single column.
+            if columns < 2 {
+                return;
+            }
+
             if self.grid.cursor.pos.col + 1 >= columns {
                 if self.mode.contains(Mode::LINE_WRAP) {
                     // Insert placeholder before wide char if glyph does not fit in this row.
@@ -2066,6 +2496,68 @@ impl<U: EventListener> Handler for Crosswords<U> {
         }
     }
 
+    fn input_str(&mut self, s: &str) {
+        let mut rest = s;
+        while !rest.is_empty() {
+            // Printable ASCII is always one column wide, runs of it that
+            // fit before the right margin are written without going
+            // through `input` for each character.
+            let run = rest
+                .bytes()
+                .take_while(|byte| (0x20..0x7f).contains(byte))
+                .count();
+            if run == 0
+                || self.grid.cursor.should_wrap
+                || self.mode.contains(Mode::INSERT)
+            {
+                let c = rest.chars().next().unwrap_or_default();
+                self.input(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+
+            if self.scroll_on_output && self.grid.display_offset() != 0 {
+                self.scroll_display(Scroll::Bottom);
+            }
+
+            let columns = self.grid.columns();
+            let Pos { row, col } = self.grid.cursor.pos;
+            let count = run.min(columns - col.0);
+            let (chars, remaining) = rest.split_at(count);
+            let charset = self.grid.cursor.charsets[self.active_charset];
+
+            let has_wide_chars = self.grid[row][col..col + count].iter().any(|cell| {
+                cell.flags.intersects(
+                    square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER,
+                )
+            });
+            if has_wide_chars {
+                // Overwritten wide chars have to drop their spacers.
+                for (offset, c) in chars.chars().enumerate() {
+                    self.grid.cursor.pos.col = col + offset;
+                    self.write_at_cursor(c);
+                }
+            } else {
+                let template = self.grid.cursor.template.clone();
+                for (cell, c) in self.grid[row][col..col + count]
+                    .iter_mut()
+                    .zip(chars.chars())
+                {
+                    *cell = template.clone();
+                    cell.c = charset.map(c);
+                }
+            }
+
+            if col.0 + count < columns {
+                self.grid.cursor.pos.col = col + count;
+            } else {
+                self.grid.cursor.pos.col = Column(columns - 1);
+                self.grid.cursor.should_wrap = true;
+            }
+            rest = remaining;
+        }
+    }
+
     #[inline]
     fn identify_terminal(&mut self, intermediate: Option<char>) {
         match intermediate {
@@ -2101,7 +2593,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
     #[inline]
     fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
         if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
-            let _removed = self.title_stack.remove(0);
+            let _removed = self.keyboard_mode_stack.remove(0);
         }
 
         self.keyboard_mode_stack.push(mode);
@@ -2131,6 +2623,15 @@ impl<U: EventListener> Handler for Crosswords<U> {
         mode: KeyboardModes,
         apply: KeyboardModesApplyBehavior,
     ) {
+        // The flags of the current stack entry are changed, so they are
+        // restored when the entries pushed above it are popped.
+        if let Some(active_mode) = self.keyboard_mode_stack.last_mut() {
+            *active_mode = match apply {
+                KeyboardModesApplyBehavior::Replace => mode,
+                KeyboardModesApplyBehavior::Union => active_mode.union(mode),
+                KeyboardModesApplyBehavior::Difference => active_mode.difference(mode),
+            };
+        }
         self.set_keyboard_mode(mode.into(), apply);
     }
 
@@ -2170,6 +2671,8 @@ impl<U: EventListener> Handler for Crosswords<U> {
             self.grid.cursor.pos.col -= 1;
             self.grid.cursor.should_wrap = false;
             self.damage.damage_line(line, column - 1, column);
+        } else {
+            self.reverse_wrap();
         }
     }
 
@@ -2230,22 +2733,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
                 self.selection = None;
             }
-            ClearMode::Saved if self.history_size() > 0 => {
-                self.grid.clear_history();
-
-                self.vi_mode_cursor.pos.row = self
-                    .vi_mode_cursor
-                    .pos
-                    .row
-                    .grid_clamp(&self.grid, Boundary::Cursor);
-
-                self.selection = self
-                    .selection
-                    .take()
-                    .filter(|s| !s.intersects_range(..Line(0)));
-            }
-            // We have no history to clear.
-            ClearMode::Saved => (),
+            ClearMode::Saved => self.clear_history(),
         }
 
         self.mark_fully_damaged();
@@ -2308,7 +2796,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn bell(&mut self) {
-        warn!("[unimplemented] Bell");
+        self.event_proxy.send_event(RioEvent::Bell, self.window_id);
     }
 
     #[inline]
@@ -2346,13 +2834,15 @@ impl<U: EventListener> Handler for Crosswords<U> {
             return;
         }
 
+        let old_col = self.grid.cursor.pos.col.0;
         while self.grid.cursor.pos.col < self.grid.columns() && count != 0 {
             count -= 1;
 
-            let c = self.grid.cursor.charsets[self.active_charset].map('\t');
+            // Tabs are kept as blank squares, so they are never drawn as a
+            // glyph, flagged to be copied back as `\t`.
             let cell = self.grid.cursor_square();
             if cell.c == ' ' {
-                cell.c = c;
+                cell.flags.insert(square::Flags::TAB);
             }
 
             loop {
@@ -2367,6 +2857,10 @@ impl<U: EventListener> Handler for Crosswords<U> {
                 }
             }
         }
+
+        let line = self.grid.cursor.pos.row.0 as usize;
+        self.damage
+            .damage_line(line, old_col, self.grid.cursor.pos.col.0);
     }
 
     #[inline]
@@ -2438,8 +2932,12 @@ impl<U: EventListener> Handler for Crosswords<U> {
             .damage_line(point.row.0 as usize, left.0, right.0 - 1);
 
         let row = &mut self.grid[point.row];
-        for cell in &mut row[left..right] {
-            *cell = bg.into();
+        if right.0 == row.len() {
+            row.clear_from(left, bg.into());
+        } else {
+            for cell in &mut row[left..right] {
+                *cell = bg.into();
+            }
         }
 
         let range = self.grid.cursor.pos.row..=self.grid.cursor.pos.row;
@@ -2471,6 +2969,27 @@ impl<U: EventListener> Handler for Crosswords<U> {
         self.goto(Line(0), Column(0));
     }
 
+    #[inline]
+    fn set_left_right_margins(&mut self, left: usize, right: Option<usize>) {
+        // Without DECLRMM the sequence is SCOSC.
+        if !self.mode.contains(Mode::LEFT_RIGHT_MARGIN) {
+            self.save_cursor_position();
+            return;
+        }
+
+        let columns = self.grid.columns();
+        let right = std::cmp::min(right.unwrap_or(columns), columns);
+        if left >= right {
+            warn!("Invalid left and right margins: ({};{})", left, right);
+            return;
+        }
+
+        debug!("Setting left and right margins: ({};{})", left, right);
+
+        self.horizontal_region = Column(left.max(1) - 1)..Column(right);
+        self.goto(Line(0), Column(0));
+    }
+
     #[inline]
     fn text_area_size_pixels(&mut self) {
         debug!("text_area_size_pixels");
@@ -2895,11 +3414,11 @@ impl Dimensions for CrosswordsSize {
     }
 
     fn square_width(&self) -> f32 {
-        0.
+        self.square_width as f32
     }
 
     fn square_height(&self) -> f32 {
-        0.
+        self.square_height as f32
     }
 }
 
@@ -2934,29 +3453,930 @@ mod tests {
     use crate::crosswords::pos::{Column, Line, Pos, Side};
     use crate::crosswords::CrosswordsSize;
     use crate::event::VoidListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Collects everything the terminal writes back to the PTY.
+    #[derive(Clone, Default)]
+    struct PtyWriteListener(Arc<Mutex<Vec<String>>>);
+
+    impl EventListener for PtyWriteListener {
+        fn event(&self) -> (Option<RioEvent>, bool) {
+            (None, false)
+        }
+
+        fn send_event(&self, event: RioEvent, _id: WindowId) {
+            if let RioEvent::PtyWrite(text) = event {
+                self.0.lock().unwrap().push(text);
+            }
+        }
+    }
+
+    fn advance_bytes<U: EventListener>(cw: &mut Crosswords<U>, bytes: &[u8]) {
+        let mut parser = crate::performer::handler::ParserProcessor::new();
+        parser.advance_bytes(cw, bytes);
+    }
 
     #[test]
-    fn scroll_up() {
-        let size = CrosswordsSize::new(1, 10);
+    fn test_absolute_cursor_moves() {
+        let size = CrosswordsSize::new(20, 10);
         let window_id = crate::event::WindowId::from(0);
         let mut cw =
             Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
-        for i in 0..10 {
-            cw.grid[Line(i)][Column(0)].c = i as u8 as char;
-        }
 
-        cw.grid.scroll_up(&(Line(0)..Line(10)), 2);
+        advance_bytes(&mut cw, b"\x1b[5;10H");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(4), Column(9)));
 
-        assert_eq!(cw.grid[Line(0)][Column(0)].c, '\u{2}');
-        assert_eq!(cw.grid[Line(0)].occ, 1);
-        assert_eq!(cw.grid[Line(1)][Column(0)].c, '\u{3}');
-        assert_eq!(cw.grid[Line(1)].occ, 1);
-        assert_eq!(cw.grid[Line(2)][Column(0)].c, '\u{4}');
-        assert_eq!(cw.grid[Line(2)].occ, 1);
-        assert_eq!(cw.grid[Line(3)][Column(0)].c, '\u{5}');
-        assert_eq!(cw.grid[Line(3)].occ, 1);
-        assert_eq!(cw.grid[Line(4)][Column(0)].c, '\u{6}');
-        assert_eq!(cw.grid[Line(4)].occ, 1);
+        advance_bytes(&mut cw, b"\x1b[3G");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(4), Column(2)));
+
+        advance_bytes(&mut cw, b"\x1b[7d");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(6), Column(2)));
+
+        // Missing parameters default to 1.
+        advance_bytes(&mut cw, b"\x1b[f");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+    }
+
+    #[test]
+    fn test_absolute_cursor_moves_in_origin_mode() {
+        let size = CrosswordsSize::new(20, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        advance_bytes(&mut cw, b"\x1b[5;8r\x1b[?6h");
+        advance_bytes(&mut cw, b"\x1b[2;4H");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(5), Column(3)));
+
+        advance_bytes(&mut cw, b"\x1b[7G");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(5), Column(6)));
+
+        // Rows are clamped to the scrolling region.
+        advance_bytes(&mut cw, b"\x1b[9d");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(7), Column(6)));
+    }
+
+    fn line_text<U: EventListener>(cw: &Crosswords<U>, line: usize) -> String {
+        cw.grid[Line(line as i32)][..]
+            .iter()
+            .map(|square| square.c)
+            .collect()
+    }
+
+    #[test]
+    fn test_left_right_margins_insert_and_delete() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        advance_bytes(&mut cw, b"abcdefghij\x1b[?69h\x1b[3;6s");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+
+        // ICH only shifts squares up to the right margin.
+        advance_bytes(&mut cw, b"\x1b[4G\x1b[@");
+        assert_eq!(line_text(&cw, 0), "abc deghij");
+
+        // DCH pulls squares from the right margin.
+        advance_bytes(&mut cw, b"\x1b[P\x1b[P");
+        assert_eq!(line_text(&cw, 0), "abce  ghij");
+
+        // Outside of the margins both are ignored.
+        advance_bytes(&mut cw, b"\x1b[9G\x1b[@\x1b[P");
+        assert_eq!(line_text(&cw, 0), "abce  ghij");
+    }
+
+    #[test]
+    fn test_scroll_up_and_down_in_region() {
+        let size = CrosswordsSize::new(3, 6);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        advance_bytes(&mut cw, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee\r\nfff");
+        // Region on lines 2 to 5, then move the cursor inside of it.
+        advance_bytes(&mut cw, b"\x1b[2;5r\x1b[3;2H");
+
+        advance_bytes(&mut cw, b"\x1b[2S");
+        let lines: Vec<String> = (0..6).map(|line| line_text(&cw, line)).collect();
+        assert_eq!(lines, ["aaa", "ddd", "eee", "   ", "   ", "fff"]);
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(2), Column(1)));
+
+        advance_bytes(&mut cw, b"\x1b[2T");
+        let lines: Vec<String> = (0..6).map(|line| line_text(&cw, line)).collect();
+        assert_eq!(lines, ["aaa", "   ", "   ", "ddd", "eee", "fff"]);
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(2), Column(1)));
+
+        // The count defaults to 1.
+        advance_bytes(&mut cw, b"\x1b[S");
+        assert_eq!(line_text(&cw, 1), "   ");
+        assert_eq!(line_text(&cw, 2), "ddd");
+        advance_bytes(&mut cw, b"\x1b[T");
+        assert_eq!(line_text(&cw, 3), "ddd");
+    }
+
+    #[test]
+    fn test_clear_history() {
+        let size = CrosswordsSize::new(3, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Nothing to clear yet.
+        cw.clear_history();
+        assert_eq!(cw.history_size(), 0);
+
+        advance_bytes(&mut cw, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee");
+        assert_eq!(cw.history_size(), 2);
+        cw.scroll_display(Scroll::Delta(1));
+        assert_eq!(cw.grid.display_offset(), 1);
+
+        cw.clear_history();
+        assert_eq!(cw.history_size(), 0);
+        assert_eq!(cw.total_lines(), cw.screen_lines());
+        assert_eq!(cw.grid.display_offset(), 0);
+        let lines: Vec<String> = (0..3).map(|line| line_text(&cw, line)).collect();
+        assert_eq!(lines, ["ccc", "ddd", "eee"]);
+
+        // CSI 3 J goes through the same path.
+        advance_bytes(&mut cw, b"\r\nfff\x1b[3J");
+        assert_eq!(cw.history_size(), 0);
+        assert_eq!(line_text(&cw, 2), "fff");
+    }
+
+    #[test]
+    fn test_combining_mark_after_line_filling_print() {
+        let size = CrosswordsSize::new(3, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        advance_bytes(&mut cw, "abce\u{301}".as_bytes());
+        assert!(!cw.grid.cursor.should_wrap);
+        assert_eq!(
+            cw.grid[Line(1)][Column(0)].zerowidth(),
+            Some(&['\u{301}'][..])
+        );
+
+        // The mark attaches to the last printed square, not the next line.
+        advance_bytes(&mut cw, "\x1b[H\x1b[2Jxyz\u{301}".as_bytes());
+        assert!(cw.grid.cursor.should_wrap);
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+        assert_eq!(
+            cw.grid[Line(0)][Column(2)].zerowidth(),
+            Some(&['\u{301}'][..])
+        );
+        assert_eq!(line_text(&cw, 1), "   ");
+        assert_eq!(cw.grid[Line(1)][Column(0)].zerowidth(), None);
+
+        // Same for a wide character, through its spacer.
+        advance_bytes(&mut cw, "\x1b[H\x1b[2Ja\u{4e2d}\u{301}".as_bytes());
+        assert!(cw.grid.cursor.should_wrap);
+        assert_eq!(cw.grid[Line(0)][Column(1)].c, '\u{4e2d}');
+        assert_eq!(
+            cw.grid[Line(0)][Column(1)].zerowidth(),
+            Some(&['\u{301}'][..])
+        );
+        assert_eq!(cw.grid[Line(0)][Column(2)].zerowidth(), None);
+    }
+
+    #[test]
+    fn test_blink_attributes() {
+        let size = CrosswordsSize::new(4, 1);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        advance_bytes(&mut cw, b"\x1b[5ma\x1b[6mb\x1b[25mc\x1b[5;0md");
+        let flags = |column: usize| cw.grid[Line(0)][Column(column)].flags;
+        assert_eq!(flags(0), square::Flags::BLINK_SLOW);
+        assert_eq!(flags(1), square::Flags::BLINK_FAST);
+        assert!(!flags(2).intersects(square::Flags::ALL_BLINKS));
+        assert!(!flags(3).intersects(square::Flags::ALL_BLINKS));
+    }
+
+    #[test]
+    fn test_cursor_moves_damage_lines() {
+        let size = CrosswordsSize::new(20, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let damaged_lines = |cw: &Crosswords<VoidListener>| -> Vec<usize> {
+            cw.damage
+                .lines
+                .iter()
+                .filter(|line| line.is_damaged())
+                .map(|line| line.line)
+                .collect()
+        };
+
+        advance_bytes(&mut cw, b"\x1b[1;5H");
+        cw.reset_damage();
+        advance_bytes(&mut cw, b"\n");
+        assert_eq!(damaged_lines(&cw), [0, 1]);
+
+        cw.reset_damage();
+        advance_bytes(&mut cw, b"\x1b[3;2H");
+        assert_eq!(damaged_lines(&cw), [1, 2]);
+
+        for bytes in [&b"\x08"[..], b"\r", b"\t"] {
+            cw.reset_damage();
+            advance_bytes(&mut cw, bytes);
+            assert_eq!(damaged_lines(&cw), [2], "{bytes:?}");
+        }
+
+        // The tab damages every column the cursor moved over.
+        assert_eq!(cw.damage.lines[2].left, 0);
+        assert_eq!(cw.damage.lines[2].right, 8);
+
+        // Wrapping moves the cursor to the next line.
+        advance_bytes(&mut cw, b"\x1b[3;20H");
+        cw.reset_damage();
+        advance_bytes(&mut cw, b"xy");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(3), Column(1)));
+        assert_eq!(damaged_lines(&cw), [2, 3]);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let size = CrosswordsSize::new(3, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let mut mode = cw.mode();
+
+        advance_bytes(&mut cw, b"\x1b[1;31ma\x1b[0;44mb\x1b[0m\r\n\x1b[38;5;100mc");
+        advance_bytes(&mut cw, b"\x1b[?25l\x1b[4h");
+
+        let square = |c, fg, bg, flags| SnapshotSquare { c, fg, bg, flags };
+        let fg = AnsiColor::Named(NamedColor::Foreground);
+        let bg = AnsiColor::Named(NamedColor::Background);
+        let blank = square(' ', fg, bg, square::Flags::empty());
+
+        mode.remove(Mode::SHOW_CURSOR);
+        mode.insert(Mode::INSERT);
+
+        let expected = GridSnapshot {
+            rows: vec![
+                vec![
+                    square(
+                        'a',
+                        AnsiColor::Named(NamedColor::Red),
+                        bg,
+                        square::Flags::BOLD,
+                    ),
+                    square(
+                        'b',
+                        fg,
+                        AnsiColor::Named(NamedColor::Blue),
+                        square::Flags::empty(),
+                    ),
+                    blank,
+                ],
+                vec![
+                    square('c', AnsiColor::Indexed(100), bg, square::Flags::empty()),
+                    blank,
+                    blank,
+                ],
+            ],
+            cursor: Pos::new(Line(1), Column(1)),
+            mode,
+        };
+        assert_eq!(cw.snapshot(), expected);
+
+        // Snapshots compare every cell attribute, not only the text.
+        advance_bytes(&mut cw, b"\x1b[H\x1b[32ma");
+        assert_ne!(cw.snapshot(), expected);
+    }
+
+    #[test]
+    fn test_nul_and_del_are_ignored() {
+        let size = CrosswordsSize::new(4, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        advance_bytes(&mut cw, b"a\x00\x7fb");
+        assert_eq!(line_text(&cw, 0), "ab  ");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+        assert_eq!(cw.grid[Line(0)][Column(0)].zerowidth(), None);
+        assert_eq!(cw.grid[Line(0)][Column(1)].zerowidth(), None);
+
+        // NUL reaching `input` directly is not stored either.
+        cw.input('\0');
+        assert_eq!(cw.grid[Line(0)][Column(1)].zerowidth(), None);
+
+        // REP repeats the character printed before DEL.
+        advance_bytes(&mut cw, b"\x1b[Hab\x7f\x1b[b");
+        assert_eq!(line_text(&cw, 0), "abb ");
+    }
+
+    #[test]
+    fn test_left_right_margins_scroll() {
+        let size = CrosswordsSize::new(6, 3);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        advance_bytes(&mut cw, b"abcdef\r\nghijkl\r\nmnopqr");
+        advance_bytes(&mut cw, b"\x1b[?69h\x1b[2;4s\x1b[S");
+        assert_eq!(line_text(&cw, 0), "ahijef");
+        assert_eq!(line_text(&cw, 1), "gnopkl");
+        assert_eq!(line_text(&cw, 2), "m   qr");
+
+        advance_bytes(&mut cw, b"\x1b[2T");
+        assert_eq!(line_text(&cw, 0), "a   ef");
+        assert_eq!(line_text(&cw, 1), "g   kl");
+        assert_eq!(line_text(&cw, 2), "mhijqr");
+    }
+
+    #[test]
+    fn test_left_right_margins_require_declrmm() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Without DECLRMM, `CSI s` saves the cursor (SCOSC).
+        advance_bytes(&mut cw, b"abcdefghij\x1b[1;4H\x1b[3;6s\x1b[H\x1b[u");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(3)));
+
+        advance_bytes(&mut cw, b"\x1b[@");
+        assert_eq!(line_text(&cw, 0), "abc defghi");
+
+        // Resetting DECLRMM drops the margins.
+        advance_bytes(&mut cw, b"\x1b[?69h\x1b[3;6s\x1b[?69l\x1b[4G\x1b[P");
+        assert_eq!(line_text(&cw, 0), "abcdefghi ");
+    }
+
+    #[test]
+    fn test_tabs_are_copied_as_tabs() {
+        let size = CrosswordsSize::new(20, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // Tabs are also stored as blanks with the DEC special graphics charset.
+        advance_bytes(&mut cw, b"a\tb\x1b(0\t\tc");
+        assert_eq!(cw.grid[Line(0)][Column(1)].c, ' ');
+        assert!(cw.grid[Line(0)][Column(1)]
+            .flags
+            .contains(square::Flags::TAB));
+
+        let end = Pos::new(Line(0), Column(19));
+        assert_eq!(
+            cw.bounds_to_string(Pos::new(Line(0), Column(0)), end),
+            "a\tb\t\t\u{240c}"
+        );
+
+        // Text written over a tab leaves the squares it spanned as spaces.
+        advance_bytes(&mut cw, b"\x1b(B\x1b[1;2Hx");
+        assert_eq!(
+            cw.bounds_to_string(Pos::new(Line(0), Column(0)), end),
+            "ax      b\t\t\u{240c}"
+        );
+    }
+
+    #[test]
+    fn test_row_occupied() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        advance_bytes(&mut cw, b"\x1b[6Gx");
+        assert!(cw.grid[Line(0)].occupied() >= 6);
+
+        // EL clears to the end of the line.
+        advance_bytes(&mut cw, b"\x1b[3G\x1b[K");
+        assert_eq!(cw.grid[Line(0)].occupied(), 2);
+
+        // ECH reaching the end of the line does too.
+        advance_bytes(&mut cw, b"\x1b[2;1Habcdef\x1b[2;4H\x1b[10X");
+        assert_eq!(cw.grid[Line(1)].occupied(), 3);
+
+        // Writing past it grows it again.
+        advance_bytes(&mut cw, b"\x1b[2;9Hy");
+        assert_eq!(cw.grid[Line(1)].occupied(), 9);
+    }
+
+    #[test]
+    fn test_osc7_sets_current_directory() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert_eq!(cw.current_directory(), None);
+
+        advance_bytes(&mut cw, b"\x1b]7;file://host/home/user\x07");
+        assert_eq!(cw.current_directory(), Some(Path::new("/home/user")));
+
+        advance_bytes(&mut cw, b"\x1b]7;file:///tmp/my%20dir;x\x1b\\");
+        assert_eq!(cw.current_directory(), Some(Path::new("/tmp/my dir;x")));
+
+        // Anything other than a file URL is ignored.
+        advance_bytes(&mut cw, b"\x1b]7;/etc\x07");
+        assert_eq!(cw.current_directory(), Some(Path::new("/tmp/my dir;x")));
+
+        // Paths that are not valid UTF-8 are kept byte for byte.
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+
+            advance_bytes(&mut cw, b"\x1b]7;file:///tmp/caf%E9\x07");
+            assert_eq!(
+                cw.current_directory(),
+                Some(Path::new(OsStr::from_bytes(b"/tmp/caf\xE9")))
+            );
+        }
+    }
+
+    #[test]
+    fn test_viewport_point_conversion() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        // Push 6 lines to the history.
+        advance_bytes(&mut cw, b"1\r\n2\r\n3\r\n4\r\n5\r\n6\r\n7\r\n8\r\n9\r\n10");
+
+        let viewport = Pos::new(Line(2), Column(3));
+        assert_eq!(cw.viewport_to_point(viewport), viewport);
+        assert_eq!(cw.point_to_viewport(viewport), Some(viewport));
+        assert_eq!(cw.point_to_viewport(Pos::new(Line(-1), Column(3))), None);
+
+        cw.scroll_display(Scroll::Delta(3));
+        assert_eq!(cw.display_offset(), 3);
+        let buffer = cw.viewport_to_point(viewport);
+        assert_eq!(buffer, Pos::new(Line(-1), Column(3)));
+        assert_eq!(cw.point_to_viewport(buffer), Some(viewport));
+        assert_eq!(cw.grid[buffer.row][Column(0)].c, '6');
+
+        // The bottom line of the screen is scrolled off.
+        assert_eq!(cw.point_to_viewport(Pos::new(Line(3), Column(0))), None);
+        assert_eq!(
+            cw.point_to_viewport(Pos::new(Line(-3), Column(0))),
+            Some(Pos::new(Line(0), Column(0)))
+        );
+
+        // Lines out of the buffer are clamped.
+        cw.scroll_display(Scroll::Top);
+        assert_eq!(
+            cw.viewport_to_point(Pos::new(Line(-2), Column(20))),
+            Pos::new(Line(-6), Column(9))
+        );
+    }
+
+    #[test]
+    fn test_scroll_on_output_and_keystroke() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        advance_bytes(&mut cw, b"1\r\n2\r\n3\r\n4\r\n5\r\n6\r\n7\r\n8");
+        cw.scroll_display(Scroll::Delta(2));
+
+        // By default output keeps the scrolled back lines in place.
+        advance_bytes(&mut cw, b"\r\nx");
+        assert_eq!(cw.display_offset(), 3);
+
+        cw.scroll_on_keystroke = false;
+        cw.scroll_to_bottom_on_keystroke();
+        assert_eq!(cw.display_offset(), 3);
+
+        cw.scroll_on_keystroke = true;
+        cw.scroll_to_bottom_on_keystroke();
+        assert_eq!(cw.display_offset(), 0);
+
+        cw.scroll_display(Scroll::Delta(2));
+        cw.scroll_on_output = true;
+        advance_bytes(&mut cw, b"y");
+        assert_eq!(cw.display_offset(), 0);
+    }
+
+    #[test]
+    fn test_focus_report() {
+        let size = CrosswordsSize::new(10, 4);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert_eq!(cw.focus_report(true), None);
+
+        advance_bytes(&mut cw, b"\x1b[?1004h");
+        assert_eq!(cw.focus_report(true), Some(b"\x1b[I".to_vec()));
+        assert_eq!(cw.focus_report(false), Some(b"\x1b[O".to_vec()));
+
+        advance_bytes(&mut cw, b"\x1b[?1004l");
+        assert_eq!(cw.focus_report(false), None);
+    }
+
+    #[test]
+    fn test_mouse_report_sgr() {
+        let size = CrosswordsSize::new(20, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let pos = Pos::new(Line(4), Column(9));
+
+        assert_eq!(cw.mouse_report(0, pos, true, ModifiersState::empty()), None);
+
+        cw.set_private_mode(PrivateMode::new(1000));
+        cw.set_private_mode(PrivateMode::new(1006));
+        assert_eq!(
+            cw.mouse_report(0, pos, true, ModifiersState::empty()),
+            Some(b"\x1b[<0;10;5M".to_vec())
+        );
+        assert_eq!(
+            cw.mouse_report(0, pos, false, ModifiersState::empty()),
+            Some(b"\x1b[<0;10;5m".to_vec())
+        );
+        assert_eq!(
+            cw.mouse_report(2, pos, true, ModifiersState::CONTROL),
+            Some(b"\x1b[<18;10;5M".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_mouse_report_x10() {
+        let size = CrosswordsSize::new(300, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.set_private_mode(PrivateMode::new(1000));
+
+        let pos = Pos::new(Line(4), Column(9));
+        assert_eq!(
+            cw.mouse_report(0, pos, true, ModifiersState::empty()),
+            Some(vec![0x1b, b'[', b'M', 32, 32 + 10, 32 + 5])
+        );
+        assert_eq!(
+            cw.mouse_report(0, pos, false, ModifiersState::empty()),
+            Some(vec![0x1b, b'[', b'M', 32 + 3, 32 + 10, 32 + 5])
+        );
+
+        // Out of range for X10, UTF-8 encodes large coordinates on two bytes.
+        let far = Pos::new(Line(4), Column(250));
+        assert_eq!(cw.mouse_report(0, far, true, ModifiersState::empty()), None);
+        cw.set_private_mode(PrivateMode::new(1005));
+        assert_eq!(
+            cw.mouse_report(0, far, true, ModifiersState::empty()),
+            Some(vec![0x1b, b'[', b'M', 32, 0xC4, 0x80 + 27, 32 + 5])
+        );
+    }
+
+    #[test]
+    fn test_resize_sends_event() {
+        #[derive(Clone, Default)]
+        struct ResizeListener(Arc<Mutex<Vec<(usize, WinsizeBuilder)>>>);
+
+        impl EventListener for ResizeListener {
+            fn event(&self) -> (Option<RioEvent>, bool) {
+                (None, false)
+            }
+
+            fn send_event(&self, event: RioEvent, _id: WindowId) {
+                if let RioEvent::Resize(route_id, winsize) = event {
+                    self.0.lock().unwrap().push((route_id, winsize));
+                }
+            }
+        }
+
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let listener = ResizeListener::default();
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, listener.clone(), window_id, 3);
+        assert!(listener.0.lock().unwrap().is_empty());
+
+        cw.resize(CrosswordsSize::new_with_dimensions(10, 4, 0, 0, 8, 16));
+        let winsize = WinsizeBuilder {
+            cols: 10,
+            rows: 4,
+            width: 80,
+            height: 64,
+        };
+        assert_eq!(*listener.0.lock().unwrap(), [(3, winsize)]);
+        assert_eq!(cw.winsize(), winsize);
+
+        // Nothing changed, nothing to tell.
+        cw.resize(CrosswordsSize::new_with_dimensions(10, 4, 0, 0, 8, 16));
+        assert_eq!(listener.0.lock().unwrap().len(), 1);
+
+        // Same grid with bigger cells, like after a font size change.
+        cw.resize(CrosswordsSize::new_with_dimensions(10, 4, 0, 0, 10, 20));
+        let winsize = WinsizeBuilder {
+            cols: 10,
+            rows: 4,
+            width: 100,
+            height: 80,
+        };
+        assert_eq!(listener.0.lock().unwrap()[1], (3, winsize));
+    }
+
+    #[test]
+    fn test_bell_sends_event() {
+        #[derive(Clone, Default)]
+        struct BellListener(Arc<Mutex<usize>>);
+
+        impl EventListener for BellListener {
+            fn event(&self) -> (Option<RioEvent>, bool) {
+                (None, false)
+            }
+
+            fn send_event(&self, event: RioEvent, _id: WindowId) {
+                if let RioEvent::Bell = event {
+                    *self.0.lock().unwrap() += 1;
+                }
+            }
+        }
+
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let listener = BellListener::default();
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, listener.clone(), window_id, 0);
+        cw.bell();
+        cw.bell();
+
+        assert_eq!(*listener.0.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_set_and_unset_modes_through_escapes() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert!(cw.mode().contains(Mode::SHOW_CURSOR));
+
+        advance_bytes(&mut cw, b"\x1b[?25l");
+        assert!(!cw.mode().contains(Mode::SHOW_CURSOR));
+        advance_bytes(&mut cw, b"\x1b[?25h");
+        assert!(cw.mode().contains(Mode::SHOW_CURSOR));
+
+        advance_bytes(&mut cw, b"\x1b[?2004h");
+        assert!(cw.mode().contains(Mode::BRACKETED_PASTE));
+        advance_bytes(&mut cw, b"\x1b[?2004l");
+        assert!(!cw.mode().contains(Mode::BRACKETED_PASTE));
+
+        advance_bytes(&mut cw, b"\x1b[?1049h");
+        assert!(cw.mode().contains(Mode::ALT_SCREEN));
+        advance_bytes(&mut cw, b"\x1b[?1049l");
+        assert!(!cw.mode().contains(Mode::ALT_SCREEN));
+
+        // Several modes in one sequence, and standard modes.
+        advance_bytes(&mut cw, b"\x1b[?1000;1006h\x1b[4h");
+        assert!(cw
+            .mode()
+            .contains(Mode::MOUSE_REPORT_CLICK | Mode::SGR_MOUSE));
+        assert!(cw.mode().contains(Mode::INSERT));
+        advance_bytes(&mut cw, b"\x1b[?1000;1006l\x1b[4l");
+        assert!(!cw
+            .mode()
+            .intersects(Mode::MOUSE_REPORT_CLICK | Mode::SGR_MOUSE));
+        assert!(!cw.mode().contains(Mode::INSERT));
+
+        // Unknown modes are ignored.
+        let mode = cw.mode();
+        advance_bytes(&mut cw, b"\x1b[?9999h\x1b[9999h");
+        assert_eq!(cw.mode(), mode);
+    }
+
+    #[test]
+    fn test_cursor_style() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let config = crate::config::Config::default();
+        let default_style = CursorStyle {
+            shape: config.cursor.shape,
+            blinking: config.cursor.blinking,
+        };
+
+        let mut cw =
+            Crosswords::new(size, config.cursor.shape, VoidListener {}, window_id, 0);
+        cw.set_default_cursor_style(default_style);
+        assert_eq!(cw.cursor_style(), default_style);
+
+        let beam = CursorStyle {
+            shape: CursorShape::Beam,
+            blinking: false,
+        };
+        cw.set_cursor_style(beam);
+        assert_eq!(cw.cursor_style(), beam);
+
+        // DECSCUSR, odd values blink and 0 restores the default.
+        advance_bytes(&mut cw, b"\x1b[3 q");
+        assert_eq!(
+            cw.cursor_style(),
+            CursorStyle {
+                shape: CursorShape::Underline,
+                blinking: true,
+            }
+        );
+        advance_bytes(&mut cw, b"\x1b[0 q");
+        assert_eq!(cw.cursor_style(), default_style);
+
+        // DECSET/DECRST 12 only toggle blinking.
+        advance_bytes(&mut cw, b"\x1b[5 q\x1b[?12l");
+        assert_eq!(
+            cw.cursor_style(),
+            CursorStyle {
+                shape: CursorShape::Beam,
+                blinking: false,
+            }
+        );
+        advance_bytes(&mut cw, b"\x1b[?12h");
+        assert!(cw.cursor_style().blinking);
+    }
+
+    #[test]
+    fn test_vi_motion_keeps_terminal_cursor() {
+        let size = CrosswordsSize::new(10, 5);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        advance_bytes(&mut cw, b"one two\r\nthree");
+        let cursor = cw.grid.cursor.pos;
+
+        // Motions are ignored outside of vi mode.
+        cw.vi_motion(ViMotion::WordRight);
+        assert_eq!(cw.vi_mode_cursor.pos, Pos::new(Line(0), Column(0)));
+
+        cw.toggle_vi_mode();
+        assert_eq!(cw.vi_mode_cursor.pos, cursor);
+
+        cw.vi_motion(ViMotion::Top);
+        assert_eq!(cw.vi_mode_cursor.pos, Pos::new(Line(0), Column(0)));
+        cw.vi_motion(ViMotion::WordRight);
+        assert_eq!(cw.vi_mode_cursor.pos, Pos::new(Line(0), Column(4)));
+        cw.vi_motion(ViMotion::Down);
+        assert_eq!(cw.vi_mode_cursor.pos, Pos::new(Line(1), Column(4)));
+        assert_eq!(cw.grid.cursor.pos, cursor);
+
+        // Output still goes to the terminal cursor.
+        advance_bytes(&mut cw, b"!");
+        assert_eq!(cw.grid[Line(1)][Column(5)].c, '!');
+        assert_eq!(cw.vi_mode_cursor.pos, Pos::new(Line(1), Column(4)));
+    }
+
+    #[test]
+    fn test_cursor_template_applied_to_input() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        let green = AnsiColor::Named(NamedColor::Green);
+        let template = cw.cursor_template_mut();
+        template.fg = green;
+        template.flags.insert(square::Flags::BOLD);
+
+        cw.input('a');
+        cw.input('b');
+        for col in 0..2 {
+            let cell = &cw.grid[Line(0)][Column(col)];
+            assert_eq!(cell.fg, green);
+            assert_eq!(cell.bg, AnsiColor::Named(NamedColor::Background));
+            assert_eq!(cell.flags, square::Flags::BOLD);
+        }
+
+        // SGR updates the same template.
+        advance_bytes(&mut cw, b"\x1b[0mc");
+        assert_eq!(cw.cursor_template().flags, square::Flags::empty());
+        let cell = &cw.grid[Line(0)][Column(2)];
+        assert_eq!(cell.fg, AnsiColor::Named(NamedColor::Foreground));
+        assert_eq!(cell.flags, square::Flags::empty());
+    }
+
+    #[test]
+    fn test_keyboard_mode_stack() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        assert!(!cw.mode().intersects(Mode::KITTY_KEYBOARD_PROTOCOL));
+
+        // Push disambiguate, then add event types on top of it.
+        advance_bytes(&mut cw, b"\x1b[>1u");
+        assert_eq!(
+            cw.mode() & Mode::KITTY_KEYBOARD_PROTOCOL,
+            Mode::DISAMBIGUATE_ESC_CODES
+        );
+        advance_bytes(&mut cw, b"\x1b[=2;2u");
+        assert_eq!(
+            cw.mode() & Mode::KITTY_KEYBOARD_PROTOCOL,
+            Mode::DISAMBIGUATE_ESC_CODES | Mode::REPORT_EVENT_TYPES
+        );
+        advance_bytes(&mut cw, b"\x1b[=1;3u");
+        assert_eq!(
+            cw.mode() & Mode::KITTY_KEYBOARD_PROTOCOL,
+            Mode::REPORT_EVENT_TYPES
+        );
+
+        // Popping restores the flags below, or none at the bottom.
+        advance_bytes(&mut cw, b"\x1b[>8u");
+        assert_eq!(
+            cw.mode() & Mode::KITTY_KEYBOARD_PROTOCOL,
+            Mode::REPORT_ALL_KEYS_AS_ESC
+        );
+        advance_bytes(&mut cw, b"\x1b[<u");
+        assert_eq!(
+            cw.mode() & Mode::KITTY_KEYBOARD_PROTOCOL,
+            Mode::REPORT_EVENT_TYPES
+        );
+        advance_bytes(&mut cw, b"\x1b[<5u");
+        assert!(!cw.mode().intersects(Mode::KITTY_KEYBOARD_PROTOCOL));
+
+        // The oldest entries are dropped once the stack is full.
+        cw.title_stack.push(String::from("rio"));
+        for _ in 0..=KEYBOARD_MODE_STACK_MAX_DEPTH {
+            advance_bytes(&mut cw, b"\x1b[>1u");
+        }
+        assert_eq!(cw.keyboard_mode_stack.len(), KEYBOARD_MODE_STACK_MAX_DEPTH);
+        assert_eq!(cw.title_stack, [String::from("rio")]);
+    }
+
+    #[test]
+    fn test_alt_screen_restores_primary_modes() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.set_private_mode(PrivateMode::new(1049));
+        cw.set_private_mode(PrivateMode::new(1));
+        cw.unset_private_mode(PrivateMode::new(25));
+        cw.unset_private_mode(PrivateMode::new(7));
+        assert!(cw.mode().contains(Mode::APP_CURSOR));
+        cw.unset_private_mode(PrivateMode::new(1049));
+
+        assert!(!cw.mode().contains(Mode::ALT_SCREEN));
+        assert!(!cw.mode().contains(Mode::APP_CURSOR));
+        assert!(cw.mode().contains(Mode::SHOW_CURSOR));
+        assert!(cw.mode().contains(Mode::LINE_WRAP));
+
+        cw.set_private_mode(PrivateMode::new(1));
+        cw.set_private_mode(PrivateMode::new(1049));
+        cw.unset_private_mode(PrivateMode::new(1));
+        cw.unset_private_mode(PrivateMode::new(1049));
+
+        assert!(cw.mode().contains(Mode::APP_CURSOR));
+    }
+
+    #[test]
+    fn test_report_private_mode() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+        let listener = PtyWriteListener::default();
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, listener.clone(), window_id, 0);
+        cw.report_private_mode(PrivateMode::new(2004));
+        cw.set_private_mode(PrivateMode::new(2004));
+        cw.report_private_mode(PrivateMode::new(2004));
+        cw.report_private_mode(PrivateMode::new(25));
+        cw.unset_private_mode(PrivateMode::new(7));
+        cw.report_private_mode(PrivateMode::new(7));
+        cw.report_private_mode(PrivateMode::new(9999));
+
+        assert_eq!(
+            *listener.0.lock().unwrap(),
+            [
+                "\x1b[?2004;2$y",
+                "\x1b[?2004;1$y",
+                "\x1b[?25;1$y",
+                "\x1b[?7;2$y",
+                "\x1b[?9999;0$y",
+            ]
+        );
+    }
+
+    #[test]
+    fn scroll_up() {
+        let size = CrosswordsSize::new(1, 10);
+        let window_id = crate::event::WindowId::from(0);
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for i in 0..10 {
+            cw.grid[Line(i)][Column(0)].c = i as u8 as char;
+        }
+
+        cw.grid.scroll_up(&(Line(0)..Line(10)), 2);
+
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '\u{2}');
+        assert_eq!(cw.grid[Line(0)].occ, 1);
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, '\u{3}');
+        assert_eq!(cw.grid[Line(1)].occ, 1);
+        assert_eq!(cw.grid[Line(2)][Column(0)].c, '\u{4}');
+        assert_eq!(cw.grid[Line(2)].occ, 1);
+        assert_eq!(cw.grid[Line(3)][Column(0)].c, '\u{5}');
+        assert_eq!(cw.grid[Line(3)].occ, 1);
+        assert_eq!(cw.grid[Line(4)][Column(0)].c, '\u{6}');
+        assert_eq!(cw.grid[Line(4)].occ, 1);
         assert_eq!(cw.grid[Line(5)][Column(0)].c, '\u{7}');
         assert_eq!(cw.grid[Line(5)].occ, 1);
         assert_eq!(cw.grid[Line(6)][Column(0)].c, '\u{8}');
@@ -2982,6 +4402,37 @@ mod tests {
         assert_eq!(cw.grid.total_lines(), 2);
     }
 
+    #[test]
+    fn test_linefeed_new_line_mode() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+
+        // A bare LF keeps the column by default.
+        advance_bytes(&mut cw, b"ab\n");
+        assert_eq!(cw.cursor().pos, Pos::new(Line(1), Column(2)));
+
+        // With LNM set LF, VT and FF also return the carriage.
+        advance_bytes(&mut cw, b"\x1b[20h");
+        assert!(cw.mode().contains(Mode::LINE_FEED_NEW_LINE));
+        advance_bytes(&mut cw, b"\n");
+        assert_eq!(cw.cursor().pos, Pos::new(Line(2), Column(0)));
+        advance_bytes(&mut cw, b"\x1b[1;3H\x0b");
+        assert_eq!(cw.cursor().pos, Pos::new(Line(1), Column(0)));
+        advance_bytes(&mut cw, b"\x1b[1;3H\x0c");
+        assert_eq!(cw.cursor().pos, Pos::new(Line(1), Column(0)));
+
+        // IND is a line feed regardless of LNM.
+        advance_bytes(&mut cw, b"\x1b[1;3H\x1bD");
+        assert_eq!(cw.cursor().pos, Pos::new(Line(1), Column(2)));
+
+        advance_bytes(&mut cw, b"\x1b[20l\x1b[1;3H\n");
+        assert!(!cw.mode().contains(Mode::LINE_FEED_NEW_LINE));
+        assert_eq!(cw.cursor().pos, Pos::new(Line(1), Column(2)));
+    }
+
     #[test]
     fn test_linefeed_moving_cursor() {
         let size = CrosswordsSize::new(1, 3);
@@ -3031,6 +4482,319 @@ mod tests {
         assert_eq!(cw.grid[Line(0)][Column(4)].c, ' ');
     }
 
+    #[test]
+    fn test_input_last_column_defers_wrap() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for c in "abcde".chars() {
+            cw.input(c);
+        }
+
+        assert_eq!(cw.grid[Line(0)][Column(4)].c, 'e');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+        assert!(cw.grid.cursor.should_wrap);
+
+        cw.input('f');
+
+        assert!(cw.grid[Line(0)][Column(4)]
+            .flags
+            .contains(square::Flags::WRAPLINE));
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, 'f');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+        assert!(!cw.grid.cursor.should_wrap);
+    }
+
+    #[test]
+    fn test_backspace_reverse_wrap() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.set_private_mode(PrivateMode::new(45));
+        for c in "abcdef".chars() {
+            cw.input(c);
+        }
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+
+        cw.backspace();
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(0)));
+        cw.backspace();
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+
+        // Cursor left crosses the wrap as well.
+        cw.goto(Line(1), Column(1));
+        cw.move_backward(Column(3));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(3)));
+
+        // Lines ended by a hard newline are not crossed.
+        cw.goto(Line(2), Column(0));
+        cw.backspace();
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(2), Column(0)));
+    }
+
+    #[test]
+    fn test_backspace_without_reverse_wrap() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for c in "abcdef".chars() {
+            cw.input(c);
+        }
+        assert!(!cw.mode().contains(Mode::REVERSE_WRAP));
+
+        cw.backspace();
+        cw.backspace();
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(0)));
+
+        cw.move_backward(Column(3));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(0)));
+    }
+
+    #[test]
+    fn test_input_str() {
+        let window_id = crate::event::WindowId::from(0);
+        let new = || {
+            let size = CrosswordsSize::new(5, 3);
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0)
+        };
+
+        let mut chars = new();
+        let mut run = new();
+        for c in "hello".chars() {
+            chars.input(c);
+        }
+        run.input_str("hello");
+        assert!(run.grid.cursor.should_wrap);
+        assert_eq!(run.grid.cursor.pos, chars.grid.cursor.pos);
+        assert_eq!(run.visible_rows(), chars.visible_rows());
+
+        // Wide and zero width chars, wraps and overwritten wide chars.
+        for cw in [&mut chars, &mut run] {
+            cw.goto(Line(1), Column(0));
+            for c in "\u{4f60}\u{597d}e\u{301}".chars() {
+                cw.input(c);
+            }
+            cw.goto(Line(1), Column(0));
+        }
+        let text = "a\u{4f60}bcdefgh\u{4f60}";
+        for c in text.chars() {
+            chars.input(c);
+        }
+        run.input_str(text);
+        assert_eq!(run.grid.cursor.pos, chars.grid.cursor.pos);
+        assert_eq!(run.visible_rows(), chars.visible_rows());
+    }
+
+    #[test]
+    fn test_input_without_autowrap_overwrites_last_column() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.unset_private_mode(PrivateMode::new(7));
+        for c in "abcdefgh".chars() {
+            cw.input(c);
+        }
+
+        assert_eq!(cw.grid[Line(0)][Column(3)].c, 'd');
+        assert_eq!(cw.grid[Line(0)][Column(4)].c, 'h');
+        assert!(!cw.grid[Line(0)][Column(4)]
+            .flags
+            .contains(square::Flags::WRAPLINE));
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, ' ');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+
+        cw.set_private_mode(PrivateMode::new(7));
+        cw.input('i');
+
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, 'i');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+    }
+
+    #[test]
+    fn test_input_wide_char_last_column() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for c in "abcd".chars() {
+            cw.input(c);
+        }
+        cw.input('汉');
+
+        let leading = &cw.grid[Line(0)][Column(4)];
+        assert_eq!(leading.c, ' ');
+        assert!(leading
+            .flags
+            .contains(square::Flags::LEADING_WIDE_CHAR_SPACER));
+        assert_eq!(cw.grid[Line(1)][Column(0)].c, '汉');
+        assert!(cw.grid[Line(1)][Column(0)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR));
+        assert!(cw.grid[Line(1)][Column(1)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(1), Column(2)));
+        assert!(!cw.grid.cursor.should_wrap);
+    }
+
+    #[test]
+    fn test_input_wide_char_ending_at_last_column() {
+        let size = CrosswordsSize::new(5, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for c in "abc".chars() {
+            cw.input(c);
+        }
+        cw.input('汉');
+
+        assert_eq!(cw.grid[Line(0)][Column(3)].c, '汉');
+        assert!(cw.grid[Line(0)][Column(4)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER));
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+        assert!(cw.grid.cursor.should_wrap);
+    }
+
+    #[test]
+    fn test_input_wide_char_single_column() {
+        let size = CrosswordsSize::new(1, 2);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.input('汉');
+
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, ' ');
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+    }
+
+    #[test]
+    fn test_insert_mode_shifts_right() {
+        let size = CrosswordsSize::new(5, 1);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for c in "abc".chars() {
+            cw.input(c);
+        }
+        cw.set_mode(AnsiMode::Named(NamedMode::Insert));
+        cw.goto(Line(0), Column(1));
+        cw.input('x');
+
+        let line: String = (0..4).map(|i| cw.grid[Line(0)][Column(i)].c).collect();
+        assert_eq!(line, "axbc");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+
+        cw.unset_mode(AnsiMode::Named(NamedMode::Insert));
+        cw.input('y');
+
+        let line: String = (0..4).map(|i| cw.grid[Line(0)][Column(i)].c).collect();
+        assert_eq!(line, "axyc");
+    }
+
+    #[test]
+    fn test_insert_blank() {
+        let size = CrosswordsSize::new(5, 1);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        for c in "abcde".chars() {
+            cw.input(c);
+        }
+        cw.goto(Line(0), Column(1));
+        cw.insert_blank(2);
+
+        let line: String = (0..5).map(|i| cw.grid[Line(0)][Column(i)].c).collect();
+        assert_eq!(line, "a  bc");
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(1)));
+
+        // Inserting past the right edge only clears until the end of the line.
+        cw.goto(Line(0), Column(3));
+        cw.insert_blank(10);
+
+        let line: String = (0..5).map(|i| cw.grid[Line(0)][Column(i)].c).collect();
+        assert_eq!(line, "a    ");
+    }
+
+    #[test]
+    fn test_decaln() {
+        let size = CrosswordsSize::new(5, 3);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.input('a');
+        cw.set_scrolling_region(2, Some(3));
+        cw.goto(Line(1), Column(3));
+        cw.decaln();
+
+        for line in 0..3 {
+            for column in 0..5 {
+                assert_eq!(cw.grid[Line(line)][Column(column)].c, 'E');
+            }
+        }
+        assert_eq!(cw.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+        assert_eq!(cw.scroll_region, Line(0)..Line(3));
+    }
+
+    #[test]
+    fn test_charset_special_graphics() {
+        let size = CrosswordsSize::new(5, 1);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.configure_charset(
+            CharsetIndex::G0,
+            pos::StandardCharset::SpecialCharacterAndLineDrawing,
+        );
+        cw.input('q');
+        cw.input('x');
+        cw.configure_charset(CharsetIndex::G0, pos::StandardCharset::Ascii);
+        cw.input('q');
+
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '─');
+        assert_eq!(cw.grid[Line(0)][Column(1)].c, '│');
+        assert_eq!(cw.grid[Line(0)][Column(2)].c, 'q');
+    }
+
+    #[test]
+    fn test_charset_shift_in_shift_out() {
+        let size = CrosswordsSize::new(5, 1);
+        let window_id = crate::event::WindowId::from(0);
+
+        let mut cw =
+            Crosswords::new(size, CursorShape::Block, VoidListener {}, window_id, 0);
+        cw.configure_charset(
+            CharsetIndex::G1,
+            pos::StandardCharset::SpecialCharacterAndLineDrawing,
+        );
+        cw.input('l');
+        // Shift out (SO) into G1.
+        cw.set_active_charset(CharsetIndex::G1);
+        cw.input('l');
+        // Shift in (SI) back to G0.
+        cw.set_active_charset(CharsetIndex::G0);
+        cw.input('l');
+
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, 'l');
+        assert_eq!(cw.grid[Line(0)][Column(1)].c, '┌');
+        assert_eq!(cw.grid[Line(0)][Column(2)].c, 'l');
+    }
+
     #[test]
     fn simple_selection_works() {
         let size = CrosswordsSize::new(5, 5);
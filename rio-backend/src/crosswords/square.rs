@@ -12,7 +12,7 @@ use std::sync::Arc;
 
 bitflags! {
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-    pub struct Flags: u16 {
+    pub struct Flags: u32 {
         const INVERSE                   = 0b0000_0000_0000_0001;
         const BOLD                      = 0b0000_0000_0000_0010;
         const ITALIC                    = 0b0000_0000_0000_0100;
@@ -31,6 +31,11 @@ bitflags! {
         const DOTTED_UNDERLINE          = 0b0010_0000_0000_0000;
         const DASHED_UNDERLINE          = 0b0100_0000_0000_0000;
         const GRAPHICS                  = 0b1000_0000_0000_0000;
+        /// Blank square where a tab was written, copied back as `\t`.
+        const TAB                       = 0b0001_0000_0000_0000_0000;
+        const BLINK_SLOW                = 0b0010_0000_0000_0000_0000;
+        const BLINK_FAST                = 0b0100_0000_0000_0000_0000;
+        const ALL_BLINKS                = Self::BLINK_SLOW.bits() | Self::BLINK_FAST.bits();
         const ALL_UNDERLINES            = Self::UNDERLINE.bits() | Self::DOUBLE_UNDERLINE.bits()
                                         | Self::UNDERCURL.bits() | Self::DOTTED_UNDERLINE.bits()
                                         | Self::DASHED_UNDERLINE.bits();
@@ -330,6 +335,10 @@ mod tests {
 
         // Ensure that cell size isn't growning by accident.
         assert!(mem::size_of::<Square>() <= EXPECTED_SIZE);
+
+        // Flags are u32 since `TAB` and the blink flags were added, the
+        // square still fits the size above.
+        assert_eq!(mem::size_of::<Flags>(), 4);
     }
 
     #[test]
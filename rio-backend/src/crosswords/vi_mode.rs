@@ -32,6 +32,10 @@ pub enum ViMotion {
     Middle,
     /// Move to bottom of screen.
     Low,
+    /// Move to the first occupied cell of the scrollback.
+    Top,
+    /// Move to the first occupied cell of the last line of the buffer.
+    Bottom,
     /// Move to start of semantically separated word.
     SemanticLeft,
     /// Move to start of next semantically separated word.
@@ -133,6 +137,22 @@ impl ViModeCursor {
                 let col = first_occupied_in_line(term, line).unwrap_or_default().col;
                 self.pos = Pos::new(line, col);
             }
+            ViMotion::Top => {
+                let line = term.grid.topmost_line();
+                let pos = Pos::new(line, Column(0));
+                self.pos = first_occupied_in_line(term, line).unwrap_or(pos);
+            }
+            ViMotion::Bottom => {
+                // Start of the last line, including the lines it wraps from.
+                let mut line = term.grid.bottommost_line();
+                while line > term.grid.topmost_line()
+                    && is_wrap(term, Pos::new(line - 1, term.grid.last_column()))
+                {
+                    line -= 1;
+                }
+                let pos = Pos::new(line, Column(0));
+                self.pos = first_occupied_in_line(term, line).unwrap_or(pos);
+            }
             ViMotion::SemanticLeft => {
                 self.pos = semantic(term, self.pos, Direction::Left, Side::Left);
             }
@@ -745,6 +765,54 @@ mod tests {
         assert_eq!(cursor.pos, Pos::new(Line(0), Column(1)));
     }
 
+    #[test]
+    fn motion_top_bottom() {
+        let mut term = term();
+
+        // Create 40 lines of scrollback.
+        for _ in 0..59 {
+            term.newline();
+        }
+        term.grid[Line(-40)][Column(2)].c = 'a';
+        term.grid[Line(19)][Column(3)].c = 'b';
+
+        let mut cursor = ViModeCursor::new(Pos::new(Line(5), Column(5)));
+
+        cursor = cursor.motion(&mut term, ViMotion::Top);
+        assert_eq!(cursor.pos, Pos::new(Line(-40), Column(2)));
+        assert_eq!(term.display_offset(), 40);
+
+        cursor = cursor.motion(&mut term, ViMotion::Bottom);
+        assert_eq!(cursor.pos, Pos::new(Line(19), Column(3)));
+        assert_eq!(term.display_offset(), 0);
+    }
+
+    #[test]
+    fn motion_clamped_at_buffer_boundaries() {
+        let mut term = term();
+
+        // Create 1 line of scrollback.
+        for _ in 0..20 {
+            term.newline();
+        }
+
+        let mut cursor = ViModeCursor::new(Pos::new(Line(-1), Column(0)));
+        cursor = cursor.motion(&mut term, ViMotion::Up);
+        assert_eq!(cursor.pos, Pos::new(Line(-1), Column(0)));
+        cursor = cursor.motion(&mut term, ViMotion::Left);
+        assert_eq!(cursor.pos, Pos::new(Line(-1), Column(0)));
+        cursor = cursor.motion(&mut term, ViMotion::WordLeft);
+        assert_eq!(cursor.pos, Pos::new(Line(-1), Column(0)));
+
+        let mut cursor = ViModeCursor::new(Pos::new(Line(19), Column(19)));
+        cursor = cursor.motion(&mut term, ViMotion::Down);
+        assert_eq!(cursor.pos, Pos::new(Line(19), Column(19)));
+        cursor = cursor.motion(&mut term, ViMotion::Right);
+        assert_eq!(cursor.pos, Pos::new(Line(19), Column(19)));
+        cursor = cursor.motion(&mut term, ViMotion::WordRight);
+        assert_eq!(cursor.pos, Pos::new(Line(19), Column(19)));
+    }
+
     #[test]
     fn scroll_word() {
         let mut term = term();
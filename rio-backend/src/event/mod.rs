@@ -2,6 +2,7 @@ pub mod sync;
 
 use crate::clipboard::ClipboardType;
 use crate::config::colors::ColorRgb;
+use crate::config::renderer::Performance;
 use crate::crosswords::grid::Scroll;
 use crate::crosswords::pos::{Direction, Pos};
 use crate::crosswords::search::{Match, RegexSearch};
@@ -133,6 +134,13 @@ pub enum RioEvent {
 
     UpdateGraphicLibrary,
 
+    /// Grid of the terminal of a route was resized, its PTY has to be told
+    /// the new window size.
+    Resize(usize, WinsizeBuilder),
+
+    /// Performance picked by `renderer.performance = "auto"` has changed.
+    PerformanceChanged(Performance),
+
     // No operation
     Noop,
 }
@@ -195,6 +203,12 @@ impl Debug for RioEvent {
             RioEvent::Paste => write!(f, "Paste"),
             RioEvent::UpdateFontSize(action) => write!(f, "UpdateFontSize({action:?})"),
             RioEvent::UpdateGraphicLibrary => write!(f, "UpdateGraphicLibrary"),
+            RioEvent::Resize(route_id, winsize) => {
+                write!(f, "Resize({route_id}, {winsize:?})")
+            }
+            RioEvent::PerformanceChanged(performance) => {
+                write!(f, "PerformanceChanged({performance})")
+            }
         }
     }
 }
@@ -1,10 +1,11 @@
 use crate::ansi::iterm2_image_protocol;
-use crate::ansi::CursorShape;
 use crate::ansi::{sixel, KeyboardModes, KeyboardModesApplyBehavior};
+use crate::ansi::{CursorShape, CursorStyle};
 use crate::config::colors::{AnsiColor, ColorRgb, NamedColor};
 use crate::crosswords::pos::{CharsetIndex, Column, Line, StandardCharset};
 use crate::crosswords::square::Hyperlink;
 use cursor_icon::CursorIcon;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 use std::time::Instant;
@@ -113,6 +114,40 @@ fn parse_number(input: &[u8]) -> Option<u8> {
     Some(num)
 }
 
+/// Extracts the percent-decoded path of an OSC 7 `file://host/path` URL.
+fn parse_file_url(url: &[u8]) -> Option<PathBuf> {
+    let rest = url.strip_prefix(b"file://")?;
+    let path = &rest[rest.iter().position(|b| *b == b'/')?..];
+
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.iter();
+    while let Some(byte) = bytes.next() {
+        if *byte == b'%' {
+            let hex = bytes.as_slice().get(..2).and_then(|hex| {
+                u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()
+            });
+            if let Some(hex) = hex {
+                decoded.push(hex);
+                bytes.nth(1);
+                continue;
+            }
+        }
+        decoded.push(*byte);
+    }
+
+    // Paths are bytes on unix, they do not have to be valid UTF-8.
+    #[cfg(unix)]
+    {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        Some(PathBuf::from(OsStr::from_bytes(&decoded)))
+    }
+
+    #[cfg(not(unix))]
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
 fn parse_sgr_color(params: &mut dyn Iterator<Item = u16>) -> Option<AnsiColor> {
     match params.next() {
         Some(2) => Some(AnsiColor::Spec(ColorRgb {
@@ -138,8 +173,11 @@ pub trait Handler {
     /// OSC to set window title.
     fn set_title(&mut self, _: Option<String>) {}
 
-    /// Set the cursor style.
-    fn set_cursor_style(&mut self, _style: Option<CursorShape>, _blinking: bool) {}
+    /// OSC to report the working directory of the shell.
+    fn set_current_directory(&mut self, _: PathBuf) {}
+
+    /// Set the cursor style, `None` restores the default one.
+    fn set_cursor_style(&mut self, _style: Option<CursorStyle>) {}
 
     /// Set the cursor shape.
     fn set_cursor_shape(&mut self, _shape: CursorShape) {}
@@ -147,6 +185,14 @@ pub trait Handler {
     /// A character to be displayed.
     fn input(&mut self, _c: char) {}
 
+    /// A run of characters to be displayed, same as calling `input` for
+    /// each of them.
+    fn input_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.input(c);
+        }
+    }
+
     /// Set cursor to position.
     fn goto(&mut self, _: Line, _: Column) {}
 
@@ -288,6 +334,14 @@ pub trait Handler {
     /// DECSTBM - Set the terminal scrolling region.
     fn set_scrolling_region(&mut self, _top: usize, _bottom: Option<usize>) {}
 
+    /// DECSLRM - Set the left and right margins.
+    ///
+    /// The sequence is shared with SCOSC, handlers without DECLRMM support
+    /// save the cursor position instead.
+    fn set_left_right_margins(&mut self, _left: usize, _right: Option<usize>) {
+        self.save_cursor_position();
+    }
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits).
     fn set_keypad_application_mode(&mut self) {}
 
@@ -389,6 +443,9 @@ struct ProcessorState {
     /// Last processed character for repetition.
     preceding_char: Option<char>,
 
+    /// Printed characters not passed to the handler yet.
+    pending_input: String,
+
     /// State for synchronized terminal updates.
     sync_state: SyncState,
 }
@@ -424,6 +481,29 @@ impl ParserProcessor {
         Self::default()
     }
 
+    /// Process new bytes from the PTY.
+    ///
+    /// Runs of printed characters reach the handler at once through
+    /// [`Handler::input_str`].
+    pub fn advance_bytes<H>(&mut self, handler: &mut H, mut bytes: &[u8])
+    where
+        H: Handler,
+    {
+        while !bytes.is_empty() {
+            if self.state.sync_state.timeout.is_some() {
+                self.advance_sync(handler, bytes[0]);
+                bytes = &bytes[1..];
+                continue;
+            }
+
+            // Stops when a synchronized update starts.
+            let mut performer = Performer::new(&mut self.state, handler);
+            let advanced = self.parser.advance_until_terminated(&mut performer, bytes);
+            performer.flush_input();
+            bytes = &bytes[advanced.consumed..];
+        }
+    }
+
     /// Process a new byte from the PTY.
     #[inline]
     pub fn advance<H>(&mut self, handler: &mut H, byte: u8)
@@ -433,6 +513,7 @@ impl ParserProcessor {
         if self.state.sync_state.timeout.is_none() {
             let mut performer = Performer::new(&mut self.state, handler);
             self.parser.advance(&mut performer, byte);
+            performer.flush_input();
         } else {
             self.advance_sync(handler, byte);
         }
@@ -448,6 +529,7 @@ impl ParserProcessor {
             let byte = self.state.sync_state.buffer[i];
             let mut performer = Performer::new(&mut self.state, handler);
             self.parser.advance(&mut performer, byte);
+            performer.flush_input();
         }
 
         // Report that update ended, since we could end due to timeout.
@@ -518,26 +600,45 @@ impl<'a, H: Handler + 'a> Performer<'a, H> {
     ) -> Performer<'b, H> {
         Performer { state, handler }
     }
+
+    /// Passes the pending printed characters to the handler, must be called
+    /// before any other handler call to keep the order of the input.
+    #[inline]
+    fn flush_input(&mut self) {
+        if !self.state.pending_input.is_empty() {
+            self.handler.input_str(&self.state.pending_input);
+            self.state.pending_input.clear();
+        }
+    }
 }
 
 impl<U: Handler> copa::Perform for Performer<'_, U> {
     fn print(&mut self, c: char) {
-        self.handler.input(c);
+        // DEL is printable for the parser but must be ignored, it should not
+        // become the character repeated by REP either.
+        if c == C0::DEL as char {
+            return;
+        }
+
+        self.state.pending_input.push(c);
         self.state.preceding_char = Some(c);
     }
 
     fn execute(&mut self, byte: u8) {
         tracing::trace!("[execute] {byte:04x}");
+        self.flush_input();
 
         match byte {
             C0::HT => self.handler.put_tab(1),
             C0::BS => self.handler.backspace(),
             C0::CR => self.handler.carriage_return(),
-            C0::LF | C0::VT | C0::FF => self.handler.linefeed(),
+            C0::LF | C0::VT | C0::FF => self.handler.newline(),
             C0::BEL => self.handler.bell(),
             C0::SUB => self.handler.substitute(),
             C0::SI => self.handler.set_active_charset(CharsetIndex::G0),
             C0::SO => self.handler.set_active_charset(CharsetIndex::G1),
+            // Padding, ignored.
+            C0::NUL | C0::DEL => (),
             _ => warn!("[unhandled] execute byte={byte:02x}"),
         }
     }
@@ -549,6 +650,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
         ignore: bool,
         action: char,
     ) {
+        self.flush_input();
         match (action, intermediates) {
             ('q', []) => {
                 self.handler.sixel_graphic_start(params);
@@ -561,6 +663,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
     }
 
     fn put(&mut self, byte: u8) {
+        self.flush_input();
         if self.handler.is_sixel_graphic_active() {
             if let Err(err) = self.handler.sixel_graphic_put(byte) {
                 tracing::warn!("Failed to parse Sixel data: {}", err);
@@ -573,6 +676,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
 
     #[inline]
     fn unhook(&mut self) {
+        self.flush_input();
         if self.handler.is_sixel_graphic_active() {
             self.handler.sixel_graphic_finish();
         } else {
@@ -580,7 +684,17 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
         }
     }
 
+    fn sequence_aborted(&mut self) {
+        warn!("[sequence_aborted] escape sequence too long, dropped");
+    }
+
+    #[inline]
+    fn terminated(&self) -> bool {
+        self.state.sync_state.timeout.is_some()
+    }
+
     fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
+        self.flush_input();
         debug!("[osc_dispatch] params={params:?} bell_terminated={bell_terminated}");
 
         let terminator = if bell_terminated { "\x07" } else { "\x1b\\" };
@@ -618,6 +732,18 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 unhandled(params);
             }
 
+            // Report current working directory.
+            b"7" => {
+                if params.len() >= 2 {
+                    let url = params[1..].join(&b';');
+                    if let Some(path) = parse_file_url(&url) {
+                        self.handler.set_current_directory(path);
+                        return;
+                    }
+                }
+                unhandled(params);
+            }
+
             // Set color index.
             b"4" => {
                 if params.len() <= 1 || params.len() % 2 == 0 {
@@ -796,6 +922,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
         action: char,
     ) {
         debug!("[csi_dispatch] {params:?} {action:?}");
+        self.flush_input();
         macro_rules! csi_unhandled {
             () => {{
                 warn!(
@@ -811,10 +938,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
         let mut params_iter = params.iter();
         let handler = &mut self.handler;
 
-        let mut next_param_or = |default: u16| match params_iter.next() {
-            Some(&[param, ..]) if param != 0 => param,
-            _ => default,
-        };
+        let mut next_param_or = |default: u16| params_iter.next_or(default);
 
         match (action, intermediates) {
             ('@', []) => handler.insert_blank(next_param_or(1) as usize),
@@ -951,7 +1075,9 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                     }
                 };
 
-                handler.set_cursor_style(shape, cursor_style_id % 2 == 1);
+                let blinking = cursor_style_id % 2 == 1;
+                handler
+                    .set_cursor_style(shape.map(|shape| CursorStyle { shape, blinking }));
             }
             ('r', []) => {
                 let top = next_param_or(1) as usize;
@@ -966,7 +1092,15 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             ('S', [b'?']) => {
                 handler.graphics_attribute(next_param_or(0), next_param_or(0))
             }
-            ('s', []) => handler.save_cursor_position(),
+            ('s', []) => {
+                let left = next_param_or(1) as usize;
+                let right = params_iter
+                    .next()
+                    .map(|param| param[0] as usize)
+                    .filter(|&param| param != 0);
+
+                handler.set_left_right_margins(left, right);
+            }
             ('T', []) => handler.scroll_down(next_param_or(1) as usize),
             ('t', []) => match next_param_or(1) as usize {
                 14 => handler.text_area_size_pixels(),
@@ -1003,6 +1137,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
     }
 
     fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        self.flush_input();
         macro_rules! unhandled {
             () => {{
                 warn!(
@@ -1147,3 +1282,62 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
 
     attrs
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct InputRecorder {
+        calls: Vec<String>,
+    }
+
+    impl Handler for InputRecorder {
+        fn input(&mut self, c: char) {
+            self.calls.push(c.to_string());
+        }
+
+        fn input_str(&mut self, s: &str) {
+            self.calls.push(s.to_string());
+        }
+
+        fn carriage_return(&mut self) {
+            self.calls.push(String::from("\r"));
+        }
+    }
+
+    #[test]
+    fn test_printed_runs_use_input_str() {
+        let mut processor = ParserProcessor::new();
+        let mut handler = InputRecorder::default();
+
+        processor.advance_bytes(&mut handler, b"hello\r\x1b[1mworld\x7f!");
+
+        assert_eq!(handler.calls, ["hello", "\r", "world!"]);
+    }
+
+    #[test]
+    fn test_printed_runs_split_across_chunks() {
+        let mut processor = ParserProcessor::new();
+        let mut handler = InputRecorder::default();
+
+        processor.advance_bytes(&mut handler, b"ab");
+        processor.advance_bytes(&mut handler, "c\u{e9}\r".as_bytes());
+
+        assert_eq!(handler.calls, ["ab", "c\u{e9}", "\r"]);
+    }
+
+    #[test]
+    fn test_printed_runs_around_synchronized_update() {
+        let mut processor = ParserProcessor::new();
+        let mut handler = InputRecorder::default();
+
+        processor.advance_bytes(&mut handler, b"a\x1b[?2026hbc");
+        assert_eq!(handler.calls, ["a"]);
+        assert!(processor.sync_timeout().is_some());
+
+        processor.advance_bytes(&mut handler, b"\x1b[?2026ld");
+        assert_eq!(handler.calls.concat(), "abcd");
+        assert!(processor.sync_timeout().is_none());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod handler;
+pub mod recorder;
 
 use crate::crosswords::Crosswords;
 use crate::event::sync::FairMutex;
@@ -8,13 +9,14 @@ use corcovado::channel;
 #[cfg(unix)]
 use corcovado::unix::UnixReady;
 use corcovado::{self, Events, PollOpt, Ready};
+use recorder::SessionRecorder;
 use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::io::{self, ErrorKind, Read, Write};
 use std::sync::Arc;
 use std::thread::{Builder, JoinHandle};
 use std::time::Instant;
-use tracing::error;
+use tracing::{error, warn};
 
 /// Like `thread::spawn`, but with a `name` argument.
 pub fn spawn_named<F, T, S>(name: S, f: F) -> JoinHandle<T>
@@ -29,6 +31,19 @@ where
         .expect("thread spawn works")
 }
 
+/// Whether a PTY read error means the child side of the PTY is gone.
+#[cfg(unix)]
+#[inline]
+fn is_child_exit_error(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::EIO)
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn is_child_exit_error(_err: &io::Error) -> bool {
+    false
+}
+
 const READ_BUFFER_SIZE: usize = 0x10_0000;
 /// Max bytes to read from the PTY while the terminal is locked.
 const MAX_LOCKED_READ: usize = u16::MAX as usize;
@@ -42,6 +57,7 @@ pub struct Machine<T: teletypewriter::EventedPty, U: EventListener> {
     event_proxy: U,
     window_id: WindowId,
     route_id: usize,
+    recorder: Option<SessionRecorder>,
 }
 
 #[derive(Default)]
@@ -134,9 +150,17 @@ where
             event_proxy,
             window_id,
             route_id,
+            recorder: None,
         })
     }
 
+    /// Tee every byte read from the PTY into `recorder` before parsing.
+    #[must_use]
+    pub fn with_session_recorder(mut self, recorder: SessionRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     #[inline]
     fn pty_read(&mut self, state: &mut State, buf: &mut [u8]) -> io::Result<()> {
         let mut unprocessed = 0;
@@ -151,7 +175,19 @@ where
             match self.pty.reader().read(&mut buf[unprocessed..]) {
                 // This is received on Windows/macOS when no more data is readable from the PTY.
                 Ok(0) if unprocessed == 0 => break,
-                Ok(got) => unprocessed += got,
+                Ok(got) => {
+                    if let Some(recorder) = &mut self.recorder {
+                        let read = &buf[unprocessed..unprocessed + got];
+                        if let Err(err) = recorder.record(read) {
+                            warn!(
+                                "Stopped recording session to {:?}: {err}",
+                                recorder.path()
+                            );
+                            self.recorder = None;
+                        }
+                    }
+                    unprocessed += got;
+                }
                 Err(err) => match err.kind() {
                     ErrorKind::Interrupted | ErrorKind::WouldBlock => {
                         // Go back to mio if we're caught up on parsing and the PTY would block.
@@ -177,9 +213,9 @@ where
             };
 
             // Parse the incoming bytes.
-            for byte in &buf[..unprocessed] {
-                state.parser.advance(&mut **terminal, *byte);
-            }
+            state
+                .parser
+                .advance_bytes(&mut **terminal, &buf[..unprocessed]);
 
             processed += unprocessed;
             unprocessed = 0;
@@ -267,11 +303,18 @@ where
         Ok(())
     }
 
+    #[inline]
+    fn child_exited(&mut self) {
+        self.terminal.lock().exit();
+        self.event_proxy
+            .send_event(RioEvent::Render, self.window_id);
+    }
+
     pub fn channel(&self) -> channel::Sender<Msg> {
         self.sender.clone()
     }
 
-    pub fn spawn(mut self) {
+    pub fn spawn(mut self) -> JoinHandle<(Self, State)> {
         spawn_named("PTY reader", move || {
             let mut state = State::default();
             let mut buf = [0u8; READ_BUFFER_SIZE];
@@ -291,6 +334,9 @@ where
                 .unwrap();
 
             let mut events = Events::with_capacity(1024);
+            // The child side of the PTY hung up, only child events are
+            // polled until the child exits.
+            let mut is_pty_hung_up = false;
 
             'event_loop: loop {
                 // Wakeup the event loop when a synchronized update timeout was reached.
@@ -335,11 +381,7 @@ where
                                 //     self.terminal.lock().exit();
                                 // }
 
-                                self.terminal.lock().exit();
-
-                                self.event_proxy
-                                    .send_event(RioEvent::Render, self.window_id);
-
+                                self.child_exited();
                                 break 'event_loop;
                             }
                         }
@@ -355,11 +397,11 @@ where
                             }
                             if event.readiness().is_readable() {
                                 if let Err(err) = self.pty_read(&mut state, &mut buf) {
-                                    // On Linux, a `read` on the master side of a PTY can fail
-                                    // with `EIO` if the client side hangs up.  In that case,
-                                    // just loop back round for the inevitable `Exited` event.
-                                    #[cfg(target_os = "linux")]
-                                    if err.raw_os_error() == Some(libc::EIO) {
+                                    // A `read` on the master side of a PTY fails with `EIO`
+                                    // once the client side hangs up, the loop keeps running
+                                    // until the `Exited` event so the child is reaped.
+                                    if is_child_exit_error(&err) {
+                                        is_pty_hung_up = true;
                                         continue;
                                     }
 
@@ -382,6 +424,11 @@ where
                     }
                 }
 
+                // The PTY is not registered again once it hung up.
+                if is_pty_hung_up {
+                    continue;
+                }
+
                 // Register write interest if necessary.
                 let mut interest = Ready::readable();
                 if state.needs_write() {
@@ -398,6 +445,255 @@ where
             let _ = self.pty.deregister(&self.poll);
 
             (self, state)
-        });
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::ansi::CursorShape;
+    use crate::crosswords::pos::{Column, Line};
+    use crate::crosswords::CrosswordsSize;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<RioEvent>>>);
+
+    impl EventListener for Recorder {
+        fn event(&self) -> (Option<RioEvent>, bool) {
+            (None, false)
+        }
+
+        fn send_event(&self, event: RioEvent, _id: WindowId) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    struct EioReader {
+        output: Option<Vec<u8>>,
+        // Child exit signaled once the read fails, like the `SIGCHLD`
+        // received after the child side of the PTY hangs up.
+        child_exit: Option<corcovado::SetReadiness>,
+    }
+
+    impl Read for EioReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.output.take() {
+                Some(output) => {
+                    buf[..output.len()].copy_from_slice(&output);
+                    Ok(output.len())
+                }
+                None => {
+                    if let Some(child_exit) = self.child_exit.take() {
+                        child_exit.set_readiness(Ready::readable())?;
+                    }
+                    Err(io::Error::from_raw_os_error(libc::EIO))
+                }
+            }
+        }
+    }
+
+    /// PTY that is always readable, whose reads return its pending output
+    /// and then fail with `EIO`. Its child exits when the read fails.
+    struct EioPty {
+        reader: EioReader,
+        writer: io::Sink,
+        registration: corcovado::Registration,
+        set_readiness: corcovado::SetReadiness,
+        token: corcovado::Token,
+        child_registration: corcovado::Registration,
+        child_readiness: corcovado::SetReadiness,
+        child_token: corcovado::Token,
+    }
+
+    impl EioPty {
+        fn new() -> Self {
+            Self::with_output(None)
+        }
+
+        fn with_output(output: Option<Vec<u8>>) -> Self {
+            let mut pty = Self::without_child_exit();
+            pty.reader.output = output;
+            pty.reader.child_exit = Some(pty.child_readiness.clone());
+            pty
+        }
+
+        /// The child keeps running after the read fails, until its
+        /// readiness is set.
+        fn without_child_exit() -> Self {
+            let (registration, set_readiness) = corcovado::Registration::new2();
+            let (child_registration, child_readiness) = corcovado::Registration::new2();
+            Self {
+                reader: EioReader {
+                    output: None,
+                    child_exit: None,
+                },
+                writer: io::sink(),
+                registration,
+                set_readiness,
+                token: corcovado::Token(0),
+                child_registration,
+                child_readiness,
+                child_token: corcovado::Token(0),
+            }
+        }
+    }
+
+    impl teletypewriter::ProcessReadWrite for EioPty {
+        type Reader = EioReader;
+        type Writer = io::Sink;
+
+        fn reader(&mut self) -> &mut Self::Reader {
+            &mut self.reader
+        }
+
+        fn read_token(&self) -> corcovado::Token {
+            self.token
+        }
+
+        fn writer(&mut self) -> &mut Self::Writer {
+            &mut self.writer
+        }
+
+        fn write_token(&self) -> corcovado::Token {
+            self.token
+        }
+
+        fn set_winsize(&mut self, _: teletypewriter::WinsizeBuilder) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn register(
+            &mut self,
+            poll: &corcovado::Poll,
+            tokens: &mut dyn Iterator<Item = corcovado::Token>,
+            interest: Ready,
+            poll_opts: PollOpt,
+        ) -> io::Result<()> {
+            self.token = tokens.next().unwrap();
+            poll.register(&self.registration, self.token, interest, poll_opts)?;
+            self.child_token = tokens.next().unwrap();
+            poll.register(
+                &self.child_registration,
+                self.child_token,
+                Ready::readable(),
+                PollOpt::level(),
+            )?;
+            self.set_readiness.set_readiness(Ready::readable())
+        }
+
+        fn reregister(
+            &mut self,
+            poll: &corcovado::Poll,
+            interest: Ready,
+            poll_opts: PollOpt,
+        ) -> io::Result<()> {
+            poll.reregister(&self.registration, self.token, interest, poll_opts)
+        }
+
+        fn deregister(&mut self, poll: &corcovado::Poll) -> io::Result<()> {
+            poll.deregister(&self.registration)?;
+            poll.deregister(&self.child_registration)
+        }
+    }
+
+    impl teletypewriter::EventedPty for EioPty {
+        fn child_event_token(&self) -> corcovado::Token {
+            self.child_token
+        }
+
+        fn next_child_event(&mut self) -> Option<teletypewriter::ChildEvent> {
+            self.child_readiness
+                .readiness()
+                .is_readable()
+                .then_some(teletypewriter::ChildEvent::Exited)
+        }
+    }
+
+    #[test]
+    fn test_pty_read_eio_exits_loop() {
+        let window_id = WindowId::from(0);
+        let events = Recorder::default();
+        let terminal = Crosswords::new(
+            CrosswordsSize::new(10, 5),
+            CursorShape::Block,
+            events.clone(),
+            window_id,
+            0,
+        );
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        let machine =
+            Machine::new(terminal, EioPty::new(), events.clone(), window_id, 0).unwrap();
+        assert!(machine.spawn().join().is_ok());
+
+        let events = events.0.lock().unwrap();
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, RioEvent::CloseTerminal(0))));
+        assert!(events.iter().any(|event| matches!(event, RioEvent::Render)));
+    }
+
+    #[test]
+    fn test_pty_read_eio_waits_for_child_exit() {
+        let window_id = WindowId::from(0);
+        let events = Recorder::default();
+        let terminal = Crosswords::new(
+            CrosswordsSize::new(10, 5),
+            CursorShape::Block,
+            events.clone(),
+            window_id,
+            0,
+        );
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        let pty = EioPty::without_child_exit();
+        let child_readiness = pty.child_readiness.clone();
+        let handle = Machine::new(terminal, pty, events.clone(), window_id, 0)
+            .unwrap()
+            .spawn();
+
+        // The read fails with `EIO`, but the loop waits for the child.
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(!handle.is_finished());
+        assert!(events.0.lock().unwrap().is_empty());
+
+        child_readiness.set_readiness(Ready::readable()).unwrap();
+        assert!(handle.join().is_ok());
+        assert!(events
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, RioEvent::CloseTerminal(0))));
+    }
+
+    #[test]
+    fn test_pty_read_records_session() {
+        let path = std::env::temp_dir()
+            .join(format!("rio-test-record-session-{}", std::process::id()));
+        let output = b"\x1b[1;31mrio\x1b[0m\r\n\xe6\xb1\x89".to_vec();
+
+        let window_id = WindowId::from(0);
+        let events = Recorder::default();
+        let terminal = Crosswords::new(
+            CrosswordsSize::new(10, 5),
+            CursorShape::Block,
+            events.clone(),
+            window_id,
+            0,
+        );
+        let terminal = Arc::new(FairMutex::new(terminal));
+
+        let pty = EioPty::with_output(Some(output.clone()));
+        let machine = Machine::new(terminal.clone(), pty, events, window_id, 0)
+            .unwrap()
+            .with_session_recorder(SessionRecorder::new(&path).unwrap());
+        assert!(machine.spawn().join().is_ok());
+
+        assert_eq!(std::fs::read(&path).unwrap(), output);
+        assert_eq!(terminal.lock().grid[Line(0)][Column(0)].c, 'r');
+        let _ = std::fs::remove_file(&path);
     }
 }
@@ -0,0 +1,105 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Size a recording can reach before it is rotated.
+pub const MAX_RECORDING_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Writes the raw bytes read from the PTY into a file, so a session can be
+/// replayed to reproduce rendering issues.
+///
+/// Once the file reaches its size cap it is renamed with an `.old`
+/// extension, replacing the previous one, and a new file is started.
+pub struct SessionRecorder {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_size: u64,
+}
+
+impl SessionRecorder {
+    pub fn new(path: impl Into<PathBuf>) -> io::Result<SessionRecorder> {
+        let path = path.into();
+        let file = File::create(&path)?;
+
+        Ok(SessionRecorder {
+            path,
+            file,
+            written: 0,
+            max_size: MAX_RECORDING_SIZE,
+        })
+    }
+
+    /// Sets the size at which the recording is rotated.
+    #[must_use]
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size.max(1);
+        self
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the previous recording, once the current one was rotated.
+    pub fn rotated_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".old");
+        PathBuf::from(path)
+    }
+
+    pub fn record(&mut self, mut bytes: &[u8]) -> io::Result<()> {
+        while !bytes.is_empty() {
+            if self.written >= self.max_size {
+                self.rotate()?;
+            }
+
+            let available = (self.max_size - self.written) as usize;
+            let (chunk, rest) = bytes.split_at(bytes.len().min(available));
+            self.file.write_all(chunk)?;
+            self.written += chunk.len() as u64;
+            bytes = rest;
+        }
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        fs::rename(&self.path, self.rotated_path())?;
+        self.file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_rotates_at_max_size() {
+        let path = std::env::temp_dir()
+            .join(format!("rio-test-recording-rotate-{}", std::process::id()));
+        let mut recorder = SessionRecorder::new(&path).unwrap().with_max_size(4);
+
+        recorder.record(b"abc").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"abc");
+
+        recorder.record(b"defgh").unwrap();
+        assert_eq!(fs::read(recorder.rotated_path()).unwrap(), b"abcd");
+        assert_eq!(fs::read(&path).unwrap(), b"efgh");
+
+        recorder.record(b"i").unwrap();
+        assert_eq!(fs::read(recorder.rotated_path()).unwrap(), b"efgh");
+        assert_eq!(fs::read(&path).unwrap(), b"i");
+
+        let _ = fs::remove_file(recorder.rotated_path());
+        let _ = fs::remove_file(&path);
+    }
+}
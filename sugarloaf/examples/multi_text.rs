@@ -171,8 +171,11 @@ impl ApplicationHandler for Application {
             } => {
                 let scale_factor_f32 = scale_factor as f32;
                 let new_inner_size = window.inner_size();
-                sugarloaf.rescale(scale_factor_f32);
-                sugarloaf.resize(new_inner_size.width, new_inner_size.height);
+                sugarloaf.reconfigure(
+                    new_inner_size.width,
+                    new_inner_size.height,
+                    scale_factor_f32,
+                );
                 window.request_redraw();
             }
             WindowEvent::Resized(new_size) => {
@@ -84,8 +84,8 @@ impl FiltersBrush {
         }
     }
 
-    /// Render the filters on top of the src_texture to dst_texture.
-    /// If the filters are not set, the src_texture is copied to dst_texture.
+    /// Render the filters on top of the src_texture to dst_texture, in the
+    /// order they were configured. Without filters this is a no-op.
     #[inline]
     pub fn render(
         &mut self,
@@ -135,6 +135,9 @@ impl FiltersBrush {
             new_src_texture
         };
 
+        // Framecount should be added forever: https://github.com/raphamorim/rio/issues/753
+        self.framecount = self.framecount.wrapping_add(1);
+
         let view_size = Size::new(ctx.size.width as u32, ctx.size.height as u32);
         for (idx, filter) in self.filter_chains.iter_mut().enumerate() {
             let filter_src_texture: Arc<wgpu::Texture>;
@@ -167,8 +170,6 @@ impl FiltersBrush {
             let dst_viewport =
                 Viewport::new_render_target_sized_origin(dst_output_view, None).unwrap();
 
-            // Framecount should be added forever: https://github.com/raphamorim/rio/issues/753
-            let _ = self.framecount.wrapping_add(1);
             if let Err(err) = filter.frame(
                 filter_src_texture,
                 &dst_viewport,
@@ -28,6 +28,43 @@ pub struct RunUnderline {
     shape: UnderlineShape,
 }
 
+impl RunUnderline {
+    /// Underline or strikethrough for `decoration`, with its offset and
+    /// thickness converted to whole device pixels.
+    fn new(
+        decoration: FragmentStyleDecoration,
+        color: [f32; 4],
+        line_height: f32,
+        scale: f32,
+    ) -> Self {
+        match decoration {
+            FragmentStyleDecoration::Underline(info) => RunUnderline {
+                enabled: true,
+                offset: (info.offset * scale).round() as i32,
+                size: device_size(info.size, scale),
+                color,
+                is_doubled: info.is_doubled,
+                shape: info.shape,
+            },
+            FragmentStyleDecoration::Strikethrough => RunUnderline {
+                enabled: true,
+                offset: (line_height / 3.5).round() as i32,
+                size: device_size(2.0, scale),
+                color,
+                is_doubled: false,
+                shape: UnderlineShape::Regular,
+            },
+        }
+    }
+}
+
+/// Size in device pixels of a line `size` logical pixels thick, rounded
+/// to whole device pixels so it stays crisp and never thinner than one.
+#[inline]
+fn device_size(size: f32, scale: f32) -> f32 {
+    (size * scale).round().max(1.0)
+}
+
 pub struct Compositor {
     batches: BatchManager,
 }
@@ -140,30 +177,27 @@ impl Compositor {
     ) {
         let rect = rect.into();
 
-        let underline = match style.decoration {
-            Some(FragmentStyleDecoration::Underline(info)) => Some(RunUnderline {
-                enabled: true,
-                offset: info.offset.round() as i32,
-                size: info.size,
-                color: style.decoration_color.unwrap_or(style.color),
-                is_doubled: info.is_doubled,
-                shape: info.shape,
-            }),
-            Some(FragmentStyleDecoration::Strikethrough) => Some(RunUnderline {
-                enabled: true,
-                offset: (style.line_height / 3.5).round() as i32,
-                size: 2.0,
-                color: style.decoration_color.unwrap_or(style.color),
-                is_doubled: false,
-                shape: UnderlineShape::Regular,
-            }),
-            _ => None,
-        };
+        let scale = style.scale_factor;
+        let underline = style.decoration.map(|decoration| {
+            RunUnderline::new(
+                decoration,
+                style.decoration_color.unwrap_or(style.color),
+                style.line_height,
+                scale,
+            )
+        });
 
         let subpx_bias = (0.125, 0.);
         let color = style.color;
 
-        for glyph in glyphs {
+        // Drawable characters are drawn with rects after the cursor.
+        let glyphs_to_render: &[Glyph] = if style.drawable_char.is_some() {
+            &[]
+        } else {
+            glyphs
+        };
+
+        for glyph in glyphs_to_render {
             let entry = session.get(glyph.id);
             if let Some(entry) = entry {
                 if let Some(img) = session.get_image(entry.image) {
@@ -218,12 +252,13 @@ impl Compositor {
                 );
 
                 if let Some(bg_color) = style.background_color {
+                    let border = device_size(2.0, scale);
                     self.batches.add_rect(
                         &Rect::new(
-                            rect.x + 2.0,
-                            style.topline + 2.0,
-                            rect.width - 4.0,
-                            style.line_height - 4.0,
+                            rect.x + border,
+                            style.topline + border,
+                            rect.width - border * 2.0,
+                            style.line_height - border * 2.0,
                         ),
                         depth,
                         &bg_color,
@@ -232,7 +267,12 @@ impl Compositor {
             }
             Some(SugarCursor::Caret(cursor_color)) => {
                 self.batches.add_rect(
-                    &Rect::new(rect.x, style.topline, 3.0, style.line_height),
+                    &Rect::new(
+                        rect.x.round(),
+                        style.topline,
+                        device_size(3.0, scale),
+                        style.line_height,
+                    ),
                     depth,
                     &cursor_color,
                 );
@@ -240,6 +280,24 @@ impl Compositor {
             _ => {}
         }
 
+        if let Some(drawable_char) = style.drawable_char {
+            let cell_width = rect.width / glyphs.len().max(1) as f32;
+            for glyph in glyphs {
+                drawable_char.draw(
+                    glyph.x,
+                    style.topline,
+                    cell_width,
+                    style.line_height,
+                    |drawable_rect, coverage| {
+                        let mut color = color;
+                        color[3] *= coverage;
+                        self.batches
+                            .add_rect(&Rect::from(drawable_rect), depth, &color);
+                    },
+                );
+            }
+        }
+
         if let Some(underline) = underline {
             self.draw_underline(
                 &underline,
@@ -248,6 +306,7 @@ impl Compositor {
                 style.baseline,
                 depth,
                 style.line_height,
+                scale,
             );
         }
 
@@ -256,6 +315,7 @@ impl Compositor {
     }
 
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     fn draw_underline(
         &mut self,
         underline: &RunUnderline,
@@ -264,11 +324,14 @@ impl Compositor {
         baseline: f32,
         depth: f32,
         line_height: f32,
+        scale: f32,
     ) {
         if underline.enabled {
-            let ux = x;
-            let uy = baseline - underline.offset as f32;
-            let end = x + advance;
+            // Lines start and end on device pixels, so runs next to each
+            // other still join without a gap.
+            let ux = x.round();
+            let uy = (baseline - underline.offset as f32).round();
+            let end = (x + advance).round();
             if ux < end {
                 match underline.shape {
                     UnderlineShape::Regular => {
@@ -291,32 +354,36 @@ impl Compositor {
                         }
                     }
                     UnderlineShape::Dashed => {
+                        let dash = device_size(6.0, scale);
+                        let step = device_size(8.0, scale);
                         let mut start = ux;
                         while start < end {
                             start = start.min(end);
                             self.batches.add_rect(
-                                &Rect::new(start, uy, 6.0, underline.size),
+                                &Rect::new(start, uy, dash, underline.size),
                                 depth,
                                 &underline.color,
                             );
-                            start += 8.0;
+                            start += step;
                         }
                     }
                     UnderlineShape::Dotted => {
+                        let dot = device_size(2.0, scale);
+                        let step = device_size(4.0, scale);
                         let mut start = ux;
                         while start < end {
                             start = start.min(end);
                             self.batches.add_rect(
-                                &Rect::new(start, uy, 2.0, underline.size),
+                                &Rect::new(start, uy, dot, underline.size),
                                 depth,
                                 &underline.color,
                             );
-                            start += 4.0;
+                            start += step;
                         }
                     }
                     UnderlineShape::Curly => {
                         let style_line_height = (line_height / 10.).clamp(2.0, 16.0);
-                        let size = (style_line_height / 1.5).clamp(1.0, 4.0);
+                        let size = (style_line_height / 1.5).clamp(1.0, 4.0).round();
                         let offset = style_line_height * 1.6;
 
                         let mut curly_width = ux;
@@ -335,7 +402,7 @@ impl Compositor {
                             self.batches.add_rect(
                                 &Rect::new(
                                     curly_width,
-                                    uy - (dot_bottom_offset - offset),
+                                    (uy - (dot_bottom_offset - offset)).round(),
                                     rect_width,
                                     size,
                                 ),
@@ -351,3 +418,59 @@ impl Compositor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::UnderlineInfo;
+
+    fn underline_rects(
+        underline: &RunUnderline,
+        x: f32,
+        baseline: f32,
+        scale: f32,
+    ) -> Vec<[f32; 4]> {
+        let mut compositor = Compositor::new();
+        compositor.begin();
+        compositor.draw_underline(underline, x, 20.3, baseline, 0.0, 34.0, scale);
+        let mut list = DisplayList::new();
+        compositor.finish(&mut list);
+        list.vertices
+            .chunks(4)
+            .map(|rect| {
+                let (top_left, bottom_right) = (rect[0].pos, rect[2].pos);
+                [
+                    top_left[0],
+                    top_left[1],
+                    bottom_right[0] - top_left[0],
+                    bottom_right[1] - top_left[1],
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decorations_snap_to_device_pixels() {
+        let color = [1.0, 1.0, 1.0, 1.0];
+        let hairline = FragmentStyleDecoration::Underline(UnderlineInfo {
+            offset: -1.0,
+            size: 0.5,
+            is_doubled: false,
+            shape: UnderlineShape::Regular,
+        });
+
+        let underline = RunUnderline::new(hairline, color, 34.0, 2.0);
+        assert_eq!(underline.size, 1.0);
+        assert_eq!(underline.offset, -2);
+        let rects = underline_rects(&underline, 10.4, 26.6, 2.0);
+        assert_eq!(rects, [[10.0, 29.0, 21.0, 1.0]]);
+
+        // Never thinner than a device pixel, even at a lower scale.
+        let underline = RunUnderline::new(hairline, color, 34.0, 1.0);
+        assert_eq!(underline.size, 1.0);
+        let strikethrough =
+            RunUnderline::new(FragmentStyleDecoration::Strikethrough, color, 34.0, 1.5);
+        assert_eq!(strikethrough.size, 3.0);
+        assert_eq!(device_size(3.0, 1.25), 4.0);
+    }
+}
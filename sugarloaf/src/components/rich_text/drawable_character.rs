@@ -0,0 +1,459 @@
+// Copyright (c) 2023-present, Raphael Amorim.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+//! Box-drawing (U+2500–U+257F) and block elements (U+2580–U+259F) drawn
+//! as rectangles computed from the cell size instead of font glyphs, so
+//! lines are aligned to the pixel grid and connect between cells.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stroke {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+impl Stroke {
+    #[inline]
+    fn from_byte(byte: u8) -> Stroke {
+        match byte {
+            b'l' => Stroke::Light,
+            b'h' => Stroke::Heavy,
+            b'd' => Stroke::Double,
+            _ => Stroke::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawableChar {
+    /// Lines from the center of the cell toward each of its sides.
+    Lines {
+        up: Stroke,
+        right: Stroke,
+        down: Stroke,
+        left: Stroke,
+    },
+    /// Line across the cell split in `dashes` segments.
+    Dashed {
+        vertical: bool,
+        heavy: bool,
+        dashes: u8,
+    },
+    /// Rectangle with its bounds in eighths of the cell.
+    Block {
+        left: u8,
+        top: u8,
+        right: u8,
+        bottom: u8,
+    },
+    /// Quarters of the cell, one bit per quarter: upper left, upper right,
+    /// lower left and lower right.
+    Quadrants(u8),
+    /// Cell covered by the given number of quarters of the color.
+    Shade(u8),
+}
+
+const UPPER_LEFT: u8 = 0b0001;
+const UPPER_RIGHT: u8 = 0b0010;
+const LOWER_LEFT: u8 = 0b0100;
+const LOWER_RIGHT: u8 = 0b1000;
+
+/// Strokes going up, right, down and left of U+2500 to U+257F, empty
+/// for dashed lines and diagonals. Arcs are drawn as square corners.
+#[rustfmt::skip]
+const LINES: [&[u8]; 0x80] = [
+    b".l.l", b".h.h", b"l.l.", b"h.h.", b"", b"", b"", b"",
+    b"", b"", b"", b"", b".ll.", b".hl.", b".lh.", b".hh.",
+    b"..ll", b"..lh", b"..hl", b"..hh", b"ll..", b"lh..", b"hl..", b"hh..",
+    b"l..l", b"l..h", b"h..l", b"h..h", b"lll.", b"lhl.", b"hll.", b"llh.",
+    b"hlh.", b"hhl.", b"lhh.", b"hhh.", b"l.ll", b"l.lh", b"h.ll", b"l.hl",
+    b"h.hl", b"h.lh", b"l.hh", b"h.hh", b".lll", b".llh", b".hll", b".hlh",
+    b".lhl", b".lhh", b".hhl", b".hhh", b"ll.l", b"ll.h", b"lh.l", b"lh.h",
+    b"hl.l", b"hl.h", b"hh.l", b"hh.h", b"llll", b"lllh", b"lhll", b"lhlh",
+    b"hlll", b"llhl", b"hlhl", b"hllh", b"hhll", b"llhh", b"lhhl", b"hhlh",
+    b"lhhh", b"hlhh", b"hhhl", b"hhhh", b"", b"", b"", b"",
+    b".d.d", b"d.d.", b".dl.", b".ld.", b".dd.", b"..ld", b"..dl", b"..dd",
+    b"ld..", b"dl..", b"dd..", b"l..d", b"d..l", b"d..d", b"ldl.", b"dld.",
+    b"ddd.", b"l.ld", b"d.dl", b"d.dd", b".dld", b".ldl", b".ddd", b"ld.d",
+    b"dl.l", b"dd.d", b"ldld", b"dldl", b"dddd", b".ll.", b"..ll", b"l..l",
+    b"ll..", b"", b"", b"", b"...l", b"l...", b".l..", b"..l.",
+    b"...h", b"h...", b".h..", b"..h.", b".h.l", b"l.h.", b".l.h", b"h.l.",
+];
+
+/// Returns how to draw `c` if it is a box-drawing or block element
+/// character which can be drawn without the font.
+pub fn drawable_character(c: char) -> Option<DrawableChar> {
+    let dashed = |vertical, heavy, dashes| {
+        Some(DrawableChar::Dashed {
+            vertical,
+            heavy,
+            dashes,
+        })
+    };
+    let block = |left, top, right, bottom| {
+        Some(DrawableChar::Block {
+            left,
+            top,
+            right,
+            bottom,
+        })
+    };
+
+    match c {
+        '\u{2504}' => dashed(false, false, 3),
+        '\u{2505}' => dashed(false, true, 3),
+        '\u{2506}' => dashed(true, false, 3),
+        '\u{2507}' => dashed(true, true, 3),
+        '\u{2508}' => dashed(false, false, 4),
+        '\u{2509}' => dashed(false, true, 4),
+        '\u{250A}' => dashed(true, false, 4),
+        '\u{250B}' => dashed(true, true, 4),
+        '\u{254C}' => dashed(false, false, 2),
+        '\u{254D}' => dashed(false, true, 2),
+        '\u{254E}' => dashed(true, false, 2),
+        '\u{254F}' => dashed(true, true, 2),
+        '\u{2500}'..='\u{257F}' => {
+            let strokes = LINES[c as usize - 0x2500];
+            if strokes.is_empty() {
+                return None;
+            }
+
+            Some(DrawableChar::Lines {
+                up: Stroke::from_byte(strokes[0]),
+                right: Stroke::from_byte(strokes[1]),
+                down: Stroke::from_byte(strokes[2]),
+                left: Stroke::from_byte(strokes[3]),
+            })
+        }
+        '\u{2580}' => block(0, 0, 8, 4),
+        '\u{2581}'..='\u{2588}' => block(0, (0x2588 - c as u32) as u8, 8, 8),
+        '\u{2589}'..='\u{258F}' => block(0, 0, (0x2590 - c as u32) as u8, 8),
+        '\u{2590}' => block(4, 0, 8, 8),
+        '\u{2591}' => Some(DrawableChar::Shade(1)),
+        '\u{2592}' => Some(DrawableChar::Shade(2)),
+        '\u{2593}' => Some(DrawableChar::Shade(3)),
+        '\u{2594}' => block(0, 0, 8, 1),
+        '\u{2595}' => block(7, 0, 8, 8),
+        '\u{2596}' => Some(DrawableChar::Quadrants(LOWER_LEFT)),
+        '\u{2597}' => Some(DrawableChar::Quadrants(LOWER_RIGHT)),
+        '\u{2598}' => Some(DrawableChar::Quadrants(UPPER_LEFT)),
+        '\u{2599}' => Some(DrawableChar::Quadrants(
+            UPPER_LEFT | LOWER_LEFT | LOWER_RIGHT,
+        )),
+        '\u{259A}' => Some(DrawableChar::Quadrants(UPPER_LEFT | LOWER_RIGHT)),
+        '\u{259B}' => Some(DrawableChar::Quadrants(
+            UPPER_LEFT | UPPER_RIGHT | LOWER_LEFT,
+        )),
+        '\u{259C}' => Some(DrawableChar::Quadrants(
+            UPPER_LEFT | UPPER_RIGHT | LOWER_RIGHT,
+        )),
+        '\u{259D}' => Some(DrawableChar::Quadrants(UPPER_RIGHT)),
+        '\u{259E}' => Some(DrawableChar::Quadrants(UPPER_RIGHT | LOWER_LEFT)),
+        '\u{259F}' => Some(DrawableChar::Quadrants(
+            UPPER_RIGHT | LOWER_LEFT | LOWER_RIGHT,
+        )),
+        _ => None,
+    }
+}
+
+/// Offset centering a line of `thickness` in `extent`.
+#[inline]
+fn center(extent: f32, thickness: f32) -> f32 {
+    ((extent - thickness) / 2.).round()
+}
+
+/// Position of `eighths` of `extent`, the far edge is kept exact so
+/// adjacent cells share it.
+#[inline]
+fn eighth(extent: f32, eighths: u8) -> f32 {
+    if eighths >= 8 {
+        extent
+    } else {
+        (extent * eighths as f32 / 8.).round()
+    }
+}
+
+impl DrawableChar {
+    /// Calls `rect` with `[x, y, width, height]` and the coverage of the
+    /// color for every rectangle needed to draw the character in the cell.
+    pub fn draw(
+        self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        mut rect: impl FnMut([f32; 4], f32),
+    ) {
+        let light = (width / 8.).round().max(1.);
+
+        match self {
+            DrawableChar::Lines {
+                up,
+                right,
+                down,
+                left,
+            } => {
+                draw_lines(
+                    [width, height],
+                    light,
+                    [left, right],
+                    [up, down],
+                    |a0, a1, c0, c1| rect([x + a0, y + c0, a1 - a0, c1 - c0], 1.),
+                );
+                draw_lines(
+                    [height, width],
+                    light,
+                    [up, down],
+                    [left, right],
+                    |a0, a1, c0, c1| rect([x + c0, y + a0, c1 - c0, a1 - a0], 1.),
+                );
+            }
+            DrawableChar::Dashed {
+                vertical,
+                heavy,
+                dashes,
+            } => {
+                let thickness = if heavy { light * 2. } else { light };
+                let (along, across) = if vertical {
+                    (height, width)
+                } else {
+                    (width, height)
+                };
+                let c0 = center(across, thickness);
+                let segment = along / dashes as f32;
+                let gap = (segment / 4.).round().max(1.);
+                for dash in 0..dashes {
+                    let a0 = (dash as f32 * segment).round() + (gap / 2.).floor();
+                    let a1 = ((dash + 1) as f32 * segment).round() - (gap / 2.).ceil();
+                    if vertical {
+                        rect([x + c0, y + a0, thickness, a1 - a0], 1.);
+                    } else {
+                        rect([x + a0, y + c0, a1 - a0, thickness], 1.);
+                    }
+                }
+            }
+            DrawableChar::Block {
+                left,
+                top,
+                right,
+                bottom,
+            } => {
+                let x0 = eighth(width, left);
+                let y0 = eighth(height, top);
+                rect(
+                    [
+                        x + x0,
+                        y + y0,
+                        eighth(width, right) - x0,
+                        eighth(height, bottom) - y0,
+                    ],
+                    1.,
+                );
+            }
+            DrawableChar::Quadrants(quadrants) => {
+                let half_width = eighth(width, 4);
+                let half_height = eighth(height, 4);
+                for (quadrant, qx, qy) in [
+                    (UPPER_LEFT, 0., 0.),
+                    (UPPER_RIGHT, half_width, 0.),
+                    (LOWER_LEFT, 0., half_height),
+                    (LOWER_RIGHT, half_width, half_height),
+                ] {
+                    if quadrants & quadrant != 0 {
+                        let qw = if qx == 0. { half_width } else { width - qx };
+                        let qh = if qy == 0. { half_height } else { height - qy };
+                        rect([x + qx, y + qy, qw, qh], 1.);
+                    }
+                }
+            }
+            DrawableChar::Shade(quarters) => {
+                rect([x, y, width, height], quarters as f32 / 4.);
+            }
+        }
+    }
+}
+
+/// Draws the lines of one axis, `extent` is the size of the cell along and
+/// across the axis. `sides` are the strokes toward the start and the end of
+/// the axis and `perpendicular` the ones of the other axis, which the lines
+/// have to join. Rectangles are emitted as `(a0, a1, c0, c1)` along and
+/// across the axis.
+fn draw_lines(
+    extent: [f32; 2],
+    light: f32,
+    sides: [Stroke; 2],
+    perpendicular: [Stroke; 2],
+    mut emit: impl FnMut(f32, f32, f32, f32),
+) {
+    let [along, across] = extent;
+    let double_center = center(along, light);
+    // Span of a perpendicular stroke along this axis.
+    let span = |stroke: Stroke| match stroke {
+        Stroke::None => None,
+        Stroke::Light => Some((center(along, light), center(along, light) + light)),
+        Stroke::Heavy => Some((
+            center(along, light * 2.),
+            center(along, light * 2.) + light * 2.,
+        )),
+        Stroke::Double => Some((double_center - light, double_center + light * 2.)),
+    };
+    let has_perpendicular = perpendicular != [Stroke::None, Stroke::None];
+    let crosses_double = perpendicular == [Stroke::Double, Stroke::Double];
+    let perpendicular_spans = perpendicular.map(span);
+    let outer_start = perpendicular_spans
+        .iter()
+        .flatten()
+        .map(|span| span.0)
+        .fold(f32::MAX, f32::min);
+    let outer_end = perpendicular_spans
+        .iter()
+        .flatten()
+        .map(|span| span.1)
+        .fold(f32::MIN, f32::max);
+
+    for (index, stroke) in sides.into_iter().enumerate() {
+        let is_start = index == 0;
+        match stroke {
+            Stroke::None => {}
+            Stroke::Light | Stroke::Heavy => {
+                let thickness = if stroke == Stroke::Heavy {
+                    light * 2.
+                } else {
+                    light
+                };
+                let c0 = center(across, thickness);
+                if sides[0] == sides[1] {
+                    if is_start {
+                        emit(0., along, c0, c0 + thickness);
+                    }
+                    continue;
+                }
+
+                // A double line going through is only touched, a double
+                // corner is joined on its far line.
+                let (start, end) = if !has_perpendicular {
+                    let start = center(along, thickness);
+                    (start, start + thickness)
+                } else if crosses_double {
+                    (double_center + light, double_center)
+                } else {
+                    (outer_start, outer_end)
+                };
+
+                if is_start {
+                    emit(0., end, c0, c0 + thickness);
+                } else {
+                    emit(start, along, c0, c0 + thickness);
+                }
+            }
+            Stroke::Double => {
+                let c_center = center(across, light);
+                if sides[0] == sides[1] && !has_perpendicular {
+                    if is_start {
+                        emit(0., along, c_center - light, c_center);
+                        emit(0., along, c_center + light, c_center + light * 2.);
+                    }
+                    continue;
+                }
+
+                // Each line stops at the inner edge of the perpendicular
+                // stroke on its side, or at the outer edge when there is
+                // none so the corner is closed.
+                for (side, c0) in perpendicular
+                    .into_iter()
+                    .zip([c_center - light, c_center + light])
+                {
+                    if is_start {
+                        let end = match (side, span(side)) {
+                            (Stroke::Double, _) => double_center,
+                            (_, Some((side_start, _))) => side_start,
+                            (_, None) if has_perpendicular => outer_end,
+                            _ => double_center + light * 2.,
+                        };
+                        emit(0., end, c0, c0 + light);
+                    } else {
+                        let start = match (side, span(side)) {
+                            (Stroke::Double, _) => double_center + light,
+                            (_, Some((_, side_end))) => side_end,
+                            (_, None) if has_perpendicular => outer_start,
+                            _ => double_center - light,
+                        };
+                        emit(start, along, c0, c0 + light);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rects(c: char, width: f32, height: f32) -> Vec<([f32; 4], f32)> {
+        let mut rects = Vec::new();
+        drawable_character(c)
+            .unwrap()
+            .draw(10., 20., width, height, |rect, alpha| {
+                rects.push((rect, alpha))
+            });
+        rects
+    }
+
+    #[test]
+    fn test_horizontal_line_spans_cell_width() {
+        let rects = rects('─', 9., 18.);
+        assert_eq!(rects.len(), 1);
+        let ([x, y, width, height], alpha) = rects[0];
+        assert_eq!((x, width, alpha), (10., 9., 1.));
+        assert_eq!(height, 1.);
+        // Vertically centered in the cell.
+        assert!((y + height / 2. - (20. + 9.)).abs() <= 1.);
+    }
+
+    #[test]
+    fn test_vertical_line_spans_cell_height() {
+        let rects = rects('│', 9., 18.);
+        assert_eq!(rects.len(), 1);
+        let ([x, y, width, height], _) = rects[0];
+        assert_eq!((y, height), (20., 18.));
+        assert_eq!(width, 1.);
+        assert!((x + width / 2. - (10. + 4.5)).abs() <= 1.);
+    }
+
+    #[test]
+    fn test_lines_join_at_the_center() {
+        // The horizontal half of a corner reaches the vertical one.
+        let corner = rects('┌', 8., 16.);
+        assert_eq!(corner.len(), 2);
+        let [hx, hy, hw, _] = corner[0].0;
+        let [vx, vy, _, vh] = corner[1].0;
+        assert_eq!(hx, vx);
+        assert_eq!(hx + hw, 18.);
+        assert_eq!(vy, hy);
+        assert_eq!(vy + vh, 36.);
+
+        assert_eq!(rects('┼', 8., 16.).len(), 2);
+        assert_eq!(rects('╬', 8., 16.).len(), 8);
+    }
+
+    #[test]
+    fn test_blocks_and_shades() {
+        assert_eq!(rects('█', 9., 18.), [([10., 20., 9., 18.], 1.)]);
+        assert_eq!(rects('▄', 9., 18.), [([10., 29., 9., 9.], 1.)]);
+        assert_eq!(rects('▐', 9., 18.), [([15., 20., 4., 18.], 1.)]);
+        assert_eq!(rects('▚', 9., 18.).len(), 2);
+        assert_eq!(rects('░', 9., 18.), [([10., 20., 9., 18.], 0.25)]);
+        assert_eq!(rects('▓', 9., 18.), [([10., 20., 9., 18.], 0.75)]);
+    }
+
+    #[test]
+    fn test_undrawable_characters() {
+        assert_eq!(drawable_character('a'), None);
+        assert_eq!(drawable_character('╱'), None);
+        assert!(drawable_character('┄').is_some());
+    }
+}
@@ -83,8 +83,9 @@ impl AtlasAllocator {
                         self.slots[prev as usize].next = slot.next;
                     } else if slot.next == !0 {
                         // We're filling the last slot with no previous
-                        // slot. Revert to the offset state.
-                        self.lines[line_index].state = slot_end;
+                        // slot, the line is full. Revert to the offset
+                        // state pointing at its end.
+                        self.lines[line_index].state = self.width as u32;
                     } else {
                         self.lines[line_index].state = FRAGMENTED_BIT | slot.next;
                     }
@@ -96,7 +97,6 @@ impl AtlasAllocator {
     }
 
     /// Deallocates the slot with the specified coordinates and width.
-    #[allow(unused)]
     pub fn deallocate(&mut self, x: u16, y: u16, width: u16) -> bool {
         let res = self.deallocate_impl(x, y, width).is_some();
         while self.lines.last().map(|l| l.state) == Some(0) {
@@ -106,7 +106,6 @@ impl AtlasAllocator {
         res
     }
 
-    #[allow(unused)]
     fn deallocate_impl(&mut self, x: u16, y: u16, width: u16) -> Option<()> {
         let (line_index, &line) = if y == 0 {
             self.lines
@@ -219,7 +218,6 @@ impl AtlasAllocator {
         Some(line_index)
     }
 
-    #[allow(unused)]
     fn allocate_slot(&mut self, x: u16, width: u16) -> Option<u32> {
         let slot = Slot { x, width, next: !0 };
         if self.free_slot != !0 {
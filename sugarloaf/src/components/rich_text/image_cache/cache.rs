@@ -6,6 +6,10 @@ use super::*;
 #[derive(Default)]
 pub struct Entry {
     allocated: bool,
+    /// Incremented every time the entry is reused for another image.
+    generation: u32,
+    /// Frame in which the image was last used.
+    last_used: u64,
     /// X coordinate of the image in an atlas.
     x: u16,
     /// Y coordinate of the image in an atlas.
@@ -23,10 +27,19 @@ pub struct Atlas {
     dirty: bool,
 }
 
-pub struct ImageCache {
+/// Images packed into the atlas buffer, least recently used images are
+/// evicted when the atlas is full. Images used by the current frame are
+/// never evicted.
+pub struct ImageStore {
     pub entries: Vec<Entry>,
+    free_entries: Vec<u32>,
     atlas: Atlas,
     max_texture_size: u16,
+    frame: u64,
+}
+
+pub struct ImageCache {
+    pub store: ImageStore,
     texture: wgpu::Texture,
     pub texture_view: wgpu::TextureView,
 }
@@ -40,35 +53,13 @@ pub fn buffer_size(width: u32, height: u32) -> Option<usize> {
 
 pub const SIZE: u16 = 2048;
 
-impl ImageCache {
-    /// Creates a new image cache.
-    pub fn new(context: &Context) -> Self {
-        let device = &context.device;
-        // let max_texture_size = max_texture_size.clamp(1024, 8192);
-        let max_texture_size = SIZE;
-
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("rich_text create texture"),
-            size: wgpu::Extent3d {
-                width: SIZE as u32,
-                height: SIZE as u32,
-                depth_or_array_layers: 1,
-            },
-            view_formats: &[],
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
-            mip_level_count: 1,
-            sample_count: 1,
-        });
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        let alloc = AtlasAllocator::new(max_texture_size, max_texture_size);
-
+impl ImageStore {
+    pub fn new(max_texture_size: u16) -> Self {
         Self {
             entries: Vec::new(),
+            free_entries: Vec::new(),
             atlas: Atlas {
-                alloc,
+                alloc: AtlasAllocator::new(max_texture_size, max_texture_size),
                 buffer: vec![
                     0u8;
                     max_texture_size as usize * max_texture_size as usize * 4
@@ -77,11 +68,17 @@ impl ImageCache {
                 dirty: true,
             },
             max_texture_size,
-            texture_view,
-            texture,
+            frame: 0,
         }
     }
 
+    /// Starts a new frame, images used by the previous frames become
+    /// candidates for eviction.
+    #[inline]
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+
     /// Allocates a new image and optionally fills it with the specified data.
     pub fn allocate(&mut self, request: AddImage) -> Option<ImageId> {
         let width = request.width;
@@ -95,36 +92,34 @@ impl ImageCache {
             return None;
         }
 
-        let atlas_data = self.atlas.alloc.allocate(width, height);
-        // if atlas_data.is_none() {
-        // return None;
-        // Grow atlas to fit
-        // self.max_texture_size += SIZE;
-        // self.atlas.fresh = true;
-        // self.atlas.dirty = true;
-        // self.entries.clear();
-        // println!("{:?}", self.max_texture_size);
-        // self.atlas.alloc = AtlasAllocator::new(self.max_texture_size, self.max_texture_size);
-        // self.atlas.buffer = vec![
-        //     0u8;
-        //     self.max_texture_size as usize * self.max_texture_size as usize * 4
-        // ];
-        // atlas_data = self.atlas.alloc.allocate(width, height);
-        // println!("{:?}", atlas_data);
-
-        // if self.max_texture_size > MAX_SIZE {
-        //     println!("should try to grow or reset atlas");
-        // }
-        // }
-        let (x, y) = atlas_data?;
-        let entry_index = self.entries.len();
-        self.entries.push(Entry {
+        let (x, y) = match self.atlas.alloc.allocate(width, height) {
+            Some(position) => position,
+            None => self.evict_for(width, height)?,
+        };
+
+        let entry = Entry {
             allocated: true,
+            generation: 0,
+            last_used: self.frame,
             x,
             y,
             width,
             height,
-        });
+        };
+        let entry_index = match self.free_entries.pop() {
+            Some(index) => {
+                let previous = &mut self.entries[index as usize];
+                *previous = Entry {
+                    generation: previous.generation.wrapping_add(1),
+                    ..entry
+                };
+                index
+            }
+            None => {
+                self.entries.push(entry);
+                (self.entries.len() - 1) as u32
+            }
+        };
         if let Some(data) = request.data() {
             fill(
                 x,
@@ -137,31 +132,90 @@ impl ImageCache {
             );
             self.atlas.dirty = true;
         }
-        ImageId::new(entry_index as u32, request.has_alpha)
+        ImageId::new(
+            entry_index,
+            self.entries[entry_index as usize].generation,
+            request.has_alpha,
+        )
+    }
+
+    /// Evicts images from the least recently used until a `width` by
+    /// `height` image fits, images used by the current frame are kept.
+    fn evict_for(&mut self, width: u16, height: u16) -> Option<(u16, u16)> {
+        let mut candidates: Vec<(u64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.allocated && entry.last_used < self.frame)
+            .map(|(index, entry)| (entry.last_used, index))
+            .collect();
+        candidates.sort_unstable();
+
+        for (_, index) in candidates {
+            self.evict(index);
+            if let Some(position) = self.atlas.alloc.allocate(width, height) {
+                return Some(position);
+            }
+        }
+
+        None
+    }
+
+    fn evict(&mut self, index: usize) {
+        let entry = &mut self.entries[index];
+        self.atlas.alloc.deallocate(entry.x, entry.y, entry.width);
+        entry.allocated = false;
+        clear(
+            entry.x,
+            entry.y,
+            entry.width,
+            entry.height,
+            self.max_texture_size,
+            &mut self.atlas.buffer,
+        );
+        self.atlas.dirty = true;
+        self.free_entries.push(index as u32);
     }
 
-    // Evaluate if does make sense to deallocate from atlas and if yes, which case?
-    // considering that a terminal uses a short/limited of glyphs compared to a wide text editor
-    // if deallocate an image then is necessary to cleanup cache of draw_layout fn
     /// Deallocates the specified image.
     #[allow(unused)]
     pub fn deallocate(&mut self, image: ImageId) -> Option<()> {
-        let entry = self.entries.get_mut(image.index())?;
-        if !entry.allocated {
+        if !self.is_valid(image) {
             return None;
         }
 
-        self.atlas.alloc.deallocate(entry.x, entry.y, entry.width);
-        entry.allocated = false;
+        self.evict(image.index());
         Some(())
     }
 
-    /// Retrieves the image for the specified handle and updates the epoch.
+    /// Evicts every image not used by the current frame, returns the
+    /// number of evicted images.
+    pub fn trim(&mut self) -> usize {
+        let mut evicted = 0;
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            if entry.allocated && entry.last_used < self.frame {
+                self.evict(index);
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+
+    /// Marks the image as used by the current frame.
+    #[inline]
+    pub fn touch(&mut self, image: ImageId) {
+        if let Some(entry) = self.entries.get_mut(image.index()) {
+            entry.last_used = self.frame;
+        }
+    }
+
+    /// Retrieves the image for the specified handle.
     pub fn get(&self, handle: &ImageId) -> Option<ImageLocation> {
-        let entry = self.entries.get(handle.index())?;
-        if !entry.allocated {
+        if !self.is_valid(*handle) {
             return None;
         }
+        let entry = &self.entries[handle.index()];
         let s = 1. / self.max_texture_size as f32;
         Some(ImageLocation {
             min: (entry.x as f32 * s, entry.y as f32 * s),
@@ -175,12 +229,90 @@ impl ImageCache {
     /// Returns true if the image is valid.
     pub fn is_valid(&self, image: ImageId) -> bool {
         if let Some(entry) = self.entries.get(image.index()) {
-            entry.allocated
+            entry.allocated && entry.generation == image.generation()
         } else {
             false
         }
     }
 
+    pub fn stats(&self) -> AtlasStats {
+        let mut stats = AtlasStats {
+            capacity: self.max_texture_size as usize * self.max_texture_size as usize,
+            ..AtlasStats::default()
+        };
+        for entry in self.entries.iter().filter(|entry| entry.allocated) {
+            stats.images += 1;
+            stats.used += entry.width as usize * entry.height as usize;
+        }
+        stats
+    }
+}
+
+impl ImageCache {
+    /// Creates a new image cache.
+    pub fn new(context: &Context) -> Self {
+        let device = &context.device;
+        // let max_texture_size = max_texture_size.clamp(1024, 8192);
+        let max_texture_size = SIZE;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("rich_text create texture"),
+            size: wgpu::Extent3d {
+                width: SIZE as u32,
+                height: SIZE as u32,
+                depth_or_array_layers: 1,
+            },
+            view_formats: &[],
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            mip_level_count: 1,
+            sample_count: 1,
+        });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            store: ImageStore::new(max_texture_size),
+            texture_view,
+            texture,
+        }
+    }
+
+    #[inline]
+    pub fn begin_frame(&mut self) {
+        self.store.begin_frame();
+    }
+
+    #[inline]
+    pub fn allocate(&mut self, request: AddImage) -> Option<ImageId> {
+        self.store.allocate(request)
+    }
+
+    #[inline]
+    pub fn touch(&mut self, image: ImageId) {
+        self.store.touch(image);
+    }
+
+    #[inline]
+    pub fn get(&self, handle: &ImageId) -> Option<ImageLocation> {
+        self.store.get(handle)
+    }
+
+    #[inline]
+    pub fn is_valid(&self, image: ImageId) -> bool {
+        self.store.is_valid(image)
+    }
+
+    #[inline]
+    pub fn trim(&mut self) -> usize {
+        self.store.trim()
+    }
+
+    #[inline]
+    pub fn stats(&self) -> AtlasStats {
+        self.store.stats()
+    }
+
     /// Updates an image with the specified data.
     // pub fn update(&mut self, handle: ImageId, data: &[u8]) -> Option<()> {
     //     let entry = self.entries.get_mut(handle.index())?;
@@ -203,13 +335,14 @@ impl ImageCache {
     // }
     #[inline]
     pub fn process_atlases(&mut self, context: &mut Context) {
-        if !self.atlas.dirty {
+        let store = &mut self.store;
+        if !store.atlas.dirty {
             return;
         }
-        if self.atlas.fresh {
+        if store.atlas.fresh {
             let texture_size = wgpu::Extent3d {
-                width: (self.max_texture_size).into(),
-                height: (self.max_texture_size).into(),
+                width: (store.max_texture_size).into(),
+                height: (store.max_texture_size).into(),
                 depth_or_array_layers: 1,
             };
             let new_texture = context.device.create_texture(&wgpu::TextureDescriptor {
@@ -233,12 +366,12 @@ impl ImageCache {
                     aspect: wgpu::TextureAspect::All,
                 },
                 // The actual pixel data
-                &self.atlas.buffer,
+                &store.atlas.buffer,
                 // The layout of the texture
                 wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some((self.max_texture_size * 4).into()),
-                    rows_per_image: Some((self.max_texture_size).into()),
+                    bytes_per_row: Some((store.max_texture_size * 4).into()),
+                    rows_per_image: Some((store.max_texture_size).into()),
                 },
                 texture_size,
             );
@@ -249,8 +382,8 @@ impl ImageCache {
                 .create_view(&wgpu::TextureViewDescriptor::default());
         } else {
             let texture_size = wgpu::Extent3d {
-                width: (self.max_texture_size).into(),
-                height: (self.max_texture_size).into(),
+                width: (store.max_texture_size).into(),
+                height: (store.max_texture_size).into(),
                 depth_or_array_layers: 1,
             };
 
@@ -263,18 +396,18 @@ impl ImageCache {
                     aspect: wgpu::TextureAspect::All,
                 },
                 // The actual pixel data
-                &self.atlas.buffer,
+                &store.atlas.buffer,
                 // The layout of the texture
                 wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some((self.max_texture_size * 4).into()),
-                    rows_per_image: Some((self.max_texture_size).into()),
+                    bytes_per_row: Some((store.max_texture_size * 4).into()),
+                    rows_per_image: Some((store.max_texture_size).into()),
                 },
                 texture_size,
             );
         }
-        self.atlas.fresh = false;
-        self.atlas.dirty = false;
+        store.atlas.fresh = false;
+        store.atlas.dirty = false;
     }
 }
 
@@ -298,3 +431,80 @@ fn fill(
     }
     Some(())
 }
+
+fn clear(x: u16, y: u16, width: u16, height: u16, target_width: u16, target: &mut [u8]) {
+    let channels = 4;
+    let pitch = target_width as usize * channels;
+    for row in y as usize..(y + height) as usize {
+        let offset = row * pitch + x as usize * channels;
+        if let Some(dest) = target.get_mut(offset..offset + width as usize * channels) {
+            dest.fill(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 64x64 atlas fits 16 images of 15x15 pixels, 16x16 once padded.
+    fn add(store: &mut ImageStore) -> Option<ImageId> {
+        let data = vec![255u8; 15 * 15 * 4];
+        store.allocate(AddImage {
+            width: 15,
+            height: 15,
+            has_alpha: true,
+            data: ImageData::Borrowed(&data),
+        })
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut store = ImageStore::new(64);
+        store.begin_frame();
+        let older: Vec<ImageId> = (0..8).map(|_| add(&mut store).unwrap()).collect();
+        store.begin_frame();
+        let newer: Vec<ImageId> = (0..8).map(|_| add(&mut store).unwrap()).collect();
+        assert_eq!(store.stats().images, 16);
+
+        store.begin_frame();
+        store.touch(older[0]);
+        let image = add(&mut store).unwrap();
+        assert!(store.is_valid(image));
+        assert!(store.is_valid(older[0]));
+        assert!(!store.is_valid(older[1]));
+        assert!(older[2..].iter().all(|image| store.is_valid(*image)));
+        assert!(newer.iter().all(|image| store.is_valid(*image)));
+
+        // Filling the atlas again evicts everything but the images used
+        // by the current frame.
+        while add(&mut store).is_some() {}
+        assert!(store.is_valid(older[0]));
+        assert!(older[1..].iter().all(|image| !store.is_valid(*image)));
+        assert!(newer.iter().all(|image| !store.is_valid(*image)));
+        assert_eq!(store.stats().images, 16);
+    }
+
+    #[test]
+    fn test_trim() {
+        let mut store = ImageStore::new(64);
+        store.begin_frame();
+        let images: Vec<ImageId> = (0..4).map(|_| add(&mut store).unwrap()).collect();
+        assert_eq!(
+            store.stats(),
+            AtlasStats {
+                images: 4,
+                used: 4 * 15 * 15,
+                capacity: 64 * 64,
+            }
+        );
+        assert_eq!(store.trim(), 0);
+
+        store.begin_frame();
+        store.touch(images[2]);
+        assert_eq!(store.trim(), 3);
+        assert!(store.is_valid(images[2]));
+        assert_eq!(store.stats().images, 1);
+        assert!(store.get(&images[0]).is_none());
+    }
+}
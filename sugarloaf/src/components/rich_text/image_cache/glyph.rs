@@ -1,6 +1,6 @@
 use super::cache::ImageCache;
 use super::{AddImage, ImageData, ImageId, ImageLocation};
-use crate::font::FontLibrary;
+use crate::font::{FontLibrary, WEIGHT_AXIS};
 use crate::font_introspector::scale::{
     image::{Content, Image as GlyphImage},
     *,
@@ -13,13 +13,6 @@ use zeno::{Angle, Transform};
 
 // const IS_MACOS: bool = cfg!(target_os = "macos");
 
-const SOURCES: &[Source] = &[
-    Source::ColorOutline(0),
-    Source::ColorBitmap(StrikeWith::BestFit),
-    // Source::Bitmap(StrikeWith::ExactSize),
-    Source::Outline,
-];
-
 pub struct GlyphCache {
     scx: ScaleContext,
     fonts: FxHashMap<FontKey, FontEntry>,
@@ -120,6 +113,7 @@ impl GlyphCacheSession<'_> {
         };
         if let Some(entry) = self.entry.glyphs.get(&key) {
             if self.images.is_valid(entry.image) {
+                self.images.touch(entry.image);
                 return Some(*entry);
             }
         }
@@ -130,6 +124,8 @@ impl GlyphCacheSession<'_> {
         let font_data = font_library_data.get(&self.font);
         let should_embolden = font_data.should_embolden;
         let should_italicize = font_data.should_italicize;
+        let weight_variation = font_data.weight_variation;
+        let sources = font_library_data.glyph_sources();
 
         if let Some(data) = font_library_data.get_data(&self.font) {
             let mut scaler = self
@@ -143,11 +139,12 @@ impl GlyphCacheSession<'_> {
                 // .hint(!IS_MACOS)
                 .hint(enable_hint)
                 .size(self.quant_size.into())
+                .variations(weight_variation.map(|w| (WEIGHT_AXIS, w)))
                 // .normalized_coords(coords)
                 .build();
 
             // let embolden = if IS_MACOS { 0.25 } else { 0. };
-            if Render::new(SOURCES)
+            if Render::new(sources)
                 .format(Format::CustomSubpixel([0.3, 0., -0.3]))
                 // .format(Format::Alpha)
                 // .offset(Vector::new(subpx[0].to_f32(), subpx[1].to_f32()))
@@ -9,10 +9,15 @@ pub use glyph::GlyphCache;
 
 /// Identifier for an image in a cache.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
-pub struct ImageId(u32);
+pub struct ImageId {
+    handle: u32,
+    /// Generation of the cache entry, identifiers of evicted images stop
+    /// being valid once their entry is reused.
+    generation: u32,
+}
 
 impl ImageId {
-    fn new(index: u32, alpha: bool) -> Option<Self> {
+    fn new(index: u32, generation: u32, alpha: bool) -> Option<Self> {
         if index & ID_INDEX_MASK != index {
             return None;
         }
@@ -20,19 +25,34 @@ impl ImageId {
         if alpha {
             handle |= ID_ALPHA_BIT
         }
-        Some(Self(handle))
+        Some(Self { handle, generation })
     }
 
     fn index(self) -> usize {
-        (self.0 & ID_INDEX_MASK) as usize
+        (self.handle & ID_INDEX_MASK) as usize
+    }
+
+    fn generation(self) -> u32 {
+        self.generation
     }
 
     /// Returns true if the image contains transparency.
     pub fn has_alpha(self) -> bool {
-        self.0 & ID_ALPHA_BIT != 0
+        self.handle & ID_ALPHA_BIT != 0
     }
 }
 
+/// Usage of the glyph atlas, in pixels.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AtlasStats {
+    /// Number of images in the atlas.
+    pub images: usize,
+    /// Pixels covered by the images.
+    pub used: usize,
+    /// Pixels available in the atlas.
+    pub capacity: usize,
+}
+
 /// Location of an image in a texture.
 #[derive(Copy, Clone)]
 pub struct ImageLocation {
@@ -1,5 +1,6 @@
 mod batch;
 mod compositor;
+pub mod drawable_character;
 mod image_cache;
 pub mod text;
 
@@ -16,6 +17,8 @@ use std::{borrow::Cow, mem};
 use text::{Glyph, TextRunStyle};
 use wgpu::util::DeviceExt;
 
+pub use image_cache::AtlasStats;
+
 // Note: currently it's using Indexed drawing instead of Instance drawing could be worth to
 // evaluate if would make sense move to instance drawing instead
 // https://math.hws.edu/graphicsbook/c9/s2.html
@@ -263,6 +266,7 @@ impl RichTextBrush {
         }
 
         self.comp.begin();
+        self.images.begin_frame();
         let library = state.compositors.advanced.font_library();
         for rich_text in &state.rich_texts {
             if let Some(rt) = state.compositors.advanced.get_rich_text(&rich_text.id) {
@@ -279,6 +283,7 @@ impl RichTextBrush {
                     library,
                     &rt.layout.dimensions,
                     graphics,
+                    state.style.scale_factor,
                 );
             }
         }
@@ -314,6 +319,18 @@ impl RichTextBrush {
         self.glyphs = GlyphCache::new();
     }
 
+    #[inline]
+    pub fn atlas_stats(&self) -> AtlasStats {
+        self.images.stats()
+    }
+
+    /// Evicts the glyphs not used by the last frame from the atlas, they
+    /// are rasterized again when needed.
+    #[inline]
+    pub fn atlas_trim(&mut self) -> usize {
+        self.images.trim()
+    }
+
     #[inline]
     pub fn render<'pass>(
         &'pass mut self,
@@ -377,8 +394,8 @@ impl RichTextBrush {
             self.index_buffer_size = size;
         }
 
-        if self.textures_version != self.images.entries.len() {
-            self.textures_version = self.images.entries.len();
+        if self.textures_version != self.images.store.entries.len() {
+            self.textures_version = self.images.store.entries.len();
             self.layout_bind_group =
                 ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
                     layout: &self.layout_bind_group_layout,
@@ -405,6 +422,7 @@ impl RichTextBrush {
 }
 
 #[inline]
+#[allow(clippy::too_many_arguments)]
 fn draw_layout(
     comp: &mut compositor::Compositor,
     caches: (&mut ImageCache, &mut GlyphCache),
@@ -413,6 +431,7 @@ fn draw_layout(
     font_library: &FontLibrary,
     rect: &SugarDimensions,
     graphics: &mut Graphics,
+    scale_factor: f32,
 ) {
     // let start = std::time::Instant::now();
     let (x, y) = pos;
@@ -462,14 +481,17 @@ fn draw_layout(
 
             let run_x = px;
             for glyph in &run.glyphs {
-                let x = px;
-                let y = py;
-                px += rect.width * char_width;
+                let glyph = run.glyph(*glyph);
                 glyphs.push(Glyph {
-                    id: glyph.simple_data().0,
-                    x,
-                    y,
+                    id: glyph.id,
+                    x: px + glyph.x,
+                    y: py - glyph.y,
                 });
+                // Zero-width glyphs (e.g. combining marks) are drawn over
+                // the previous cell.
+                if glyph.advance > 0. {
+                    px += rect.width * char_width;
+                }
             }
             let style = TextRunStyle {
                 font_coords,
@@ -483,6 +505,8 @@ fn draw_layout(
                 advance: px - run_x,
                 decoration: run.span.decoration,
                 decoration_color: run.span.decoration_color,
+                drawable_char: run.span.drawable_char,
+                scale_factor,
             };
 
             if font != current_font || style.font_size != current_font_size {
@@ -572,17 +596,13 @@ fn fetch_dimensions(
         let run_x = px;
         glyphs.clear();
         for glyph in &run.glyphs {
-            // let x = px + glyph.x;
-            // let y = py - glyph.y;
-            let x = px;
-            let y = py;
-            // px += glyph.advance
-            px += glyph.simple_data().1 * char_width;
+            let glyph = run.glyph(*glyph);
             glyphs.push(Glyph {
-                id: glyph.simple_data().0,
-                x,
-                y,
+                id: glyph.id,
+                x: px + glyph.x,
+                y: py - glyph.y,
             });
+            px += glyph.advance * char_width;
         }
         let color = run.span.color;
 
@@ -598,6 +618,9 @@ fn fetch_dimensions(
             advance: px - run_x,
             decoration: None,
             decoration_color: None,
+            drawable_char: None,
+            // Only the dimensions are kept, nothing is drawn.
+            scale_factor: 1.0,
         };
 
         if style.advance > 0. && line_height > 0. {
@@ -9,6 +9,7 @@
 // Eventually the file had updates to support other features like background-color,
 // text color, underline color and etc.
 
+use crate::components::rich_text::drawable_character::DrawableChar;
 use crate::font_introspector::{GlyphId, NormalizedCoord};
 use crate::layout::FragmentStyleDecoration;
 use crate::sugarloaf::primitives::SugarCursor;
@@ -38,6 +39,11 @@ pub struct TextRunStyle<'a> {
     pub decoration_color: Option<[f32; 4]>,
     /// Cursor style.
     pub cursor: Option<SugarCursor>,
+    /// Drawn instead of the glyphs of the run.
+    pub drawable_char: Option<DrawableChar>,
+    /// Device pixels per logical pixel, decorations and cursors are
+    /// snapped to whole device pixels.
+    pub scale_factor: f32,
 }
 
 /// Positioned glyph in a text run.
@@ -115,8 +115,10 @@ impl Context<'_> {
                 if let Ok(result) = futures::executor::block_on(adapter.request_device(
                     // ADDRESS_MODE_CLAMP_TO_BORDER is required for librashader
                     &wgpu::DeviceDescriptor {
+                        // TIMESTAMP_QUERY is optional, used to measure GPU frame time
                         required_features: wgpu::Features::empty()
-                            | wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER,
+                            | wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER
+                            | (adapter.features() & wgpu::Features::TIMESTAMP_QUERY),
                         ..Default::default()
                     },
                     None,
@@ -201,6 +203,10 @@ impl Context<'_> {
         );
     }
 
+    pub fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.alpha_mode
+    }
+
     pub fn surface_caps(&self) -> &wgpu::SurfaceCapabilities {
         &self.surface_caps
     }
@@ -1,5 +1,5 @@
 use crate::font::DEFAULT_FONT_FAMILY;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
 pub enum SugarloafFontStyle {
@@ -45,6 +45,36 @@ impl Default for SugarloafFont {
     }
 }
 
+/// Font used for codepoints presented as emoji, `family` overrides the
+/// built-in Twemoji and `color` set to `false` renders them monochrome.
+/// `weight`, `style` and `width` select the face of `family` as for the
+/// other fonts.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SugarloafEmojiFont {
+    #[serde(default = "Option::default")]
+    pub family: Option<String>,
+    #[serde(default = "default_emoji_color")]
+    pub color: bool,
+    #[serde(default = "Option::default")]
+    pub weight: Option<u16>,
+    #[serde(default = "SugarloafFontStyle::default")]
+    pub style: SugarloafFontStyle,
+    #[serde(default = "Option::default")]
+    pub width: Option<SugarloafFontWidth>,
+}
+
+impl Default for SugarloafEmojiFont {
+    fn default() -> Self {
+        Self {
+            family: None,
+            color: default_emoji_color(),
+            weight: None,
+            style: SugarloafFontStyle::Normal,
+            width: None,
+        }
+    }
+}
+
 impl SugarloafFont {
     #[inline]
     pub fn is_default_family(&self) -> bool {
@@ -63,6 +93,16 @@ pub fn default_font_hinting() -> bool {
     true
 }
 
+#[inline]
+pub fn default_emoji_color() -> bool {
+    true
+}
+
+#[inline]
+pub fn default_use_drawable_chars() -> bool {
+    false
+}
+
 fn default_font_family() -> String {
     DEFAULT_FONT_FAMILY.to_string()
 }
@@ -103,13 +143,55 @@ pub fn default_font_bold_italic() -> SugarloafFont {
     }
 }
 
+/// Parses an OpenType feature toggle such as `"+zero"`, `"-calt"` or
+/// `"ss01"` into its tag and value. A `-` prefix disables the feature,
+/// `+` or no prefix enables it.
+pub fn parse_font_feature(feature: &str) -> Result<([u8; 4], u16), String> {
+    let (tag, value) = match feature.strip_prefix('-') {
+        Some(tag) => (tag, 0),
+        None => (feature.strip_prefix('+').unwrap_or(feature), 1),
+    };
+
+    let tag: [u8; 4] = tag
+        .as_bytes()
+        .try_into()
+        .ok()
+        .filter(|tag: &[u8; 4]| tag.iter().all(|b| (0x20..=0x7e).contains(b)))
+        .ok_or_else(|| {
+            format!("invalid font feature \"{feature}\": tags must be 4 ASCII characters")
+        })?;
+
+    Ok((tag, value))
+}
+
+fn deserialize_font_features<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let features: Option<Vec<String>> = Option::deserialize(deserializer)?;
+    if let Some(features) = &features {
+        for feature in features {
+            parse_font_feature(feature).map_err(serde::de::Error::custom)?;
+        }
+    }
+
+    Ok(features)
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct SugarloafFonts {
     #[serde(default = "default_font_size")]
     pub size: f32,
     #[serde(default = "default_font_hinting")]
     pub hinting: bool,
-    #[serde(default = "Option::default")]
+    #[serde(default = "default_use_drawable_chars", rename = "use-drawable-chars")]
+    pub use_drawable_chars: bool,
+    #[serde(
+        default = "Option::default",
+        deserialize_with = "deserialize_font_features"
+    )]
     pub features: Option<Vec<String>>,
     #[serde(default = "Option::default")]
     pub family: Option<String>,
@@ -123,8 +205,8 @@ pub struct SugarloafFonts {
     pub italic: SugarloafFont,
     #[serde(default = "Option::default")]
     pub ui: Option<SugarloafFont>,
-    #[serde(default = "Option::default")]
-    pub emoji: Option<SugarloafFont>,
+    #[serde(default = "SugarloafEmojiFont::default")]
+    pub emoji: SugarloafEmojiFont,
     #[serde(default = "Vec::default")]
     pub extras: Vec<SugarloafFont>,
 }
@@ -134,9 +216,10 @@ impl Default for SugarloafFonts {
         SugarloafFonts {
             features: None,
             hinting: true,
+            use_drawable_chars: default_use_drawable_chars(),
             size: default_font_size(),
             family: None,
-            emoji: None,
+            emoji: SugarloafEmojiFont::default(),
             ui: None,
             regular: default_font_regular(),
             bold: default_font_bold(),
@@ -146,3 +229,22 @@ impl Default for SugarloafFonts {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_font_feature() {
+        assert_eq!(parse_font_feature("+zero"), Ok((*b"zero", 1)));
+        assert_eq!(parse_font_feature("-calt"), Ok((*b"calt", 0)));
+        assert_eq!(parse_font_feature("ss01"), Ok((*b"ss01", 1)));
+    }
+
+    #[test]
+    fn test_parse_invalid_font_feature() {
+        assert!(parse_font_feature("bad").is_err());
+        assert!(parse_font_feature("+liga1").is_err());
+        assert!(parse_font_feature("-").is_err());
+    }
+}
@@ -8,15 +8,16 @@ pub const FONT_ID_REGULAR: usize = 0;
 
 use crate::font::constants::*;
 use crate::font::fonts::{SugarloafFontStyle, SugarloafFontWidth};
+use crate::font_introspector::scale::{Source, StrikeWith};
 use crate::font_introspector::text::cluster::Parser;
 use crate::font_introspector::text::cluster::Token;
 use crate::font_introspector::text::cluster::{CharCluster, Status};
 use crate::font_introspector::text::Codepoint;
 use crate::font_introspector::text::Script;
-use crate::font_introspector::{CacheKey, FontRef, Synthesis};
+use crate::font_introspector::{tag_from_bytes, CacheKey, FontRef, Synthesis, Tag};
 use crate::layout::FragmentStyle;
 use crate::SugarloafErrors;
-use ab_glyph::FontArc;
+use ab_glyph::{Font, FontArc, PxScale, ScaleFont};
 use lru::LruCache;
 use parking_lot::FairMutex;
 use rustc_hash::FxHashMap;
@@ -26,17 +27,46 @@ use std::sync::Arc;
 
 pub use crate::font_introspector::{Style, Weight};
 
+const GLYPH_SOURCES: &[Source] = &[
+    Source::ColorOutline(0),
+    Source::ColorBitmap(StrikeWith::BestFit),
+    // Source::Bitmap(StrikeWith::ExactSize),
+    Source::Outline,
+];
+
+const MONOCHROME_GLYPH_SOURCES: &[Source] = &[Source::Outline];
+
+/// Whether `ch` followed by `variation` should be presented as emoji.
+/// VS16 requests emoji presentation and VS15 text presentation, otherwise
+/// only the pictographic blocks default to emoji.
+#[inline]
+pub fn is_emoji_presentation(ch: char, variation: Option<char>) -> bool {
+    match variation {
+        Some('\u{FE0F}') => true,
+        Some('\u{FE0E}') => false,
+        _ => matches!(
+            ch as u32,
+            0x1F300..=0x1F64F | 0x1F680..=0x1F6FF | 0x1F900..=0x1FAFF
+        ),
+    }
+}
+
 pub fn lookup_for_font_match(
     cluster: &mut CharCluster,
     synth: &mut Synthesis,
     library: &mut FontLibraryData,
     spec_font_attr_opt: Option<&(crate::font_introspector::Style, bool)>,
+    skip_font_id: Option<usize>,
 ) -> Option<(usize, bool)> {
     let mut search_result = None;
     let mut font_synth = Synthesis::default();
     let fonts_len: usize = library.inner.len();
 
     for font_id in 0..fonts_len {
+        if skip_font_id == Some(font_id) {
+            continue;
+        }
+
         let mut is_emoji = false;
 
         if let Some(font) = library.inner.get(&font_id) {
@@ -76,7 +106,7 @@ pub fn lookup_for_font_match(
     // In case no font_id is found and exists a font spec requirement
     // then drop requirement and try to find something that can match.
     if search_result.is_none() && spec_font_attr_opt.is_some() {
-        return lookup_for_font_match(cluster, synth, library, None);
+        return lookup_for_font_match(cluster, synth, library, None, skip_font_id);
     }
 
     search_result
@@ -124,6 +154,10 @@ pub struct FontLibraryData {
     pub inner: FxHashMap<usize, FontData>,
     pub stash: LruCache<usize, SharedData>,
     pub hinting: bool,
+    /// Font used for emoji presentation, consulted before the other fonts.
+    pub emoji_font_id: Option<usize>,
+    /// Whether glyphs are rendered with their color layers and bitmaps.
+    pub emoji_color: bool,
 }
 
 impl Default for FontLibraryData {
@@ -133,15 +167,34 @@ impl Default for FontLibraryData {
             inner: FxHashMap::default(),
             stash: LruCache::new(NonZeroUsize::new(2).unwrap()),
             hinting: true,
+            emoji_font_id: None,
+            emoji_color: true,
         }
     }
 }
 
 impl FontLibraryData {
+    /// Advance of a character of the UI font, which is monospaced, when
+    /// text objects are drawn with `font_size`.
+    #[inline]
+    pub fn ui_char_width(&self, font_size: f32) -> f32 {
+        self.ui
+            .as_scaled(PxScale::from(font_size))
+            .h_advance(self.ui.glyph_id('M'))
+    }
+
+    /// Finds the font to render `ch` with, `variation` is the variation
+    /// selector following it in the cell if any.
+    ///
+    /// Codepoints presented as emoji, either by default or because of
+    /// VS16, look up the emoji font before any other font. Text
+    /// presentation, requested by VS15 or by disabling color emoji, only
+    /// falls back to the emoji font when no other font has the glyph.
     #[inline]
     pub fn find_best_font_match(
         &mut self,
         ch: char,
+        variation: Option<char>,
         fragment_style: &FragmentStyle,
     ) -> Option<(usize, bool)> {
         let mut synth = Synthesis::default();
@@ -173,18 +226,55 @@ impl FontLibraryData {
             None
         };
 
+        let emoji_font_id = self.emoji_font_id;
+        let is_emoji_presentation = is_emoji_presentation(ch, variation);
+        if let Some(emoji_font_id) = emoji_font_id {
+            if is_emoji_presentation && self.emoji_color {
+                if let Some(data) = self.get_data(&emoji_font_id) {
+                    let charmap = data.charmap();
+                    if char_cluster.map(|ch| charmap.map(ch)) != Status::Discard {
+                        return Some((emoji_font_id, true));
+                    }
+                }
+            }
+        }
+
+        let skip_font_id =
+            emoji_font_id.filter(|_| !is_emoji_presentation || !self.emoji_color);
         if let Some(result) = lookup_for_font_match(
             &mut char_cluster,
             &mut synth,
             self,
             spec_font_attr.as_ref(),
+            skip_font_id,
         ) {
             return Some(result);
         }
 
+        if let Some(emoji_font_id) = skip_font_id {
+            if let Some(data) = self.get_data(&emoji_font_id) {
+                let charmap = data.charmap();
+                if char_cluster.map(|ch| charmap.map(ch)) != Status::Discard {
+                    return Some((emoji_font_id, variation != Some('\u{FE0E}')));
+                }
+            }
+        }
+
         Some((0, false))
     }
 
+    /// Glyph sources used to rasterize glyphs, color layers and bitmaps
+    /// are left out when color emoji is disabled so fallback fonts with
+    /// color glyphs are rendered monochrome as well.
+    #[inline]
+    pub fn glyph_sources(&self) -> &'static [Source] {
+        if self.emoji_color {
+            GLYPH_SOURCES
+        } else {
+            MONOCHROME_GLYPH_SOURCES
+        }
+    }
+
     #[inline]
     pub fn insert(&mut self, font_data: FontData) {
         self.inner.insert(self.inner.len(), font_data);
@@ -333,7 +423,15 @@ impl FontLibraryData {
             }
         }
 
-        if let Some(emoji_font) = spec.emoji {
+        self.emoji_color = spec.emoji.color;
+        self.emoji_font_id = Some(self.len());
+        if let Some(family) = spec.emoji.family {
+            let emoji_font = SugarloafFont {
+                family,
+                weight: spec.emoji.weight,
+                style: spec.emoji.style,
+                width: spec.emoji.width,
+            };
             match find_font(&db, emoji_font, true, true) {
                 FindResult::Found(data) => {
                     self.insert(data);
@@ -440,6 +538,21 @@ pub struct FontData {
     pub should_embolden: bool,
     pub should_italicize: bool,
     pub is_emoji: bool,
+    /// Value of the `wght` axis when the font is variable and a weight
+    /// was requested, used by the shaper and the scaler instead of
+    /// emboldening.
+    pub weight_variation: Option<f32>,
+}
+
+/// Tag of the variable font weight axis.
+pub const WEIGHT_AXIS: Tag = tag_from_bytes(b"wght");
+
+/// Returns the value to set on the `wght` axis for `weight`, clamped to
+/// the range supported by the font. `None` if the font has no such axis.
+pub fn weight_variation(font: &FontRef, weight: Option<u16>) -> Option<f32> {
+    let weight = weight?;
+    let axis = font.variations().find_by_tag(WEIGHT_AXIS)?;
+    Some((weight as f32).clamp(axis.min_value(), axis.max_value()))
 }
 
 impl PartialEq for FontData {
@@ -471,7 +584,10 @@ impl FontData {
         let should_italicize =
             font_spec.style == SugarloafFontStyle::Italic && style != Style::Italic;
 
-        let should_embolden = font_spec.weight >= Some(700) && weight < Weight(700);
+        let weight_variation = weight_variation(&font, font_spec.weight);
+        let should_embolden = weight_variation.is_none()
+            && font_spec.weight >= Some(700)
+            && weight < Weight(700);
 
         let stretch = attributes.stretch();
         let synth = attributes.synthesize(attributes);
@@ -494,6 +610,7 @@ impl FontData {
             stretch,
             path: Some(path),
             is_emoji,
+            weight_variation,
         })
     }
 
@@ -524,6 +641,7 @@ impl FontData {
             stretch,
             path: None,
             is_emoji,
+            weight_variation: None,
         })
     }
 }
@@ -718,3 +836,147 @@ fn load_from_font_source(path: &PathBuf) -> Option<Vec<u8>> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_introspector::scale::image::Content;
+    use crate::font_introspector::scale::{Render, ScaleContext};
+    use crate::font_introspector::zeno::Format;
+
+    const VARIABLE_FONT: &[u8] =
+        include_bytes!("../../resources/test-fonts/Cantarell-VF.otf");
+
+    fn font_data(weight: Option<u16>) -> FontData {
+        let font_spec = SugarloafFont {
+            family: String::from("Cantarell"),
+            weight,
+            ..SugarloafFont::default()
+        };
+        FontData::from_data(
+            VARIABLE_FONT.to_vec(),
+            PathBuf::from("Cantarell-VF.otf"),
+            false,
+            false,
+            &font_spec,
+        )
+        .unwrap()
+    }
+
+    fn outline_points(font_data: &FontData) -> Vec<(f32, f32)> {
+        let font = FontRef::from_index(VARIABLE_FONT, 0).unwrap();
+        let glyph_id = font.charmap().map('a');
+        let mut context = ScaleContext::new();
+        let mut scaler = context
+            .builder(font)
+            .size(32.)
+            .variations(font_data.weight_variation.map(|w| (WEIGHT_AXIS, w)))
+            .build();
+        let outline = scaler.scale_outline(glyph_id).unwrap();
+        outline.points().iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    #[test]
+    fn test_weight_variation() {
+        let light = font_data(Some(350));
+        let regular = font_data(Some(400));
+        let bold = font_data(Some(700));
+
+        assert_eq!(light.weight_variation, Some(350.));
+        assert_eq!(bold.weight_variation, Some(700.));
+        // The axis is set instead of emboldening the regular instance.
+        assert!(!bold.should_embolden);
+
+        let light = outline_points(&light);
+        assert_ne!(light, outline_points(&regular));
+        assert_ne!(light, outline_points(&bold));
+
+        // Weights out of the axis range are clamped.
+        let font = FontRef::from_index(VARIABLE_FONT, 0).unwrap();
+        let axis = font.variations().find_by_tag(WEIGHT_AXIS).unwrap();
+        assert_eq!(
+            font_data(Some(1000)).weight_variation,
+            Some(axis.max_value())
+        );
+        assert_eq!(font_data(None).weight_variation, None);
+    }
+
+    #[test]
+    fn test_weight_variation_static_font() {
+        let data = include_bytes!("../../resources/test-fonts/DejaVuSansMono.ttf");
+        let font = FontRef::from_index(data, 0).unwrap();
+        assert_eq!(weight_variation(&font, Some(350)), None);
+    }
+
+    fn emoji_library(color: bool) -> FontLibraryData {
+        let mut library = FontLibraryData::default();
+        library.load(SugarloafFonts {
+            emoji: fonts::SugarloafEmojiFont {
+                color,
+                ..fonts::SugarloafEmojiFont::default()
+            },
+            ..SugarloafFonts::default()
+        });
+        library
+    }
+
+    #[test]
+    fn test_emoji_presentation() {
+        assert!(is_emoji_presentation('\u{1F600}', None));
+        assert!(!is_emoji_presentation('\u{1F600}', Some('\u{FE0E}')));
+        assert!(!is_emoji_presentation('\u{2764}', None));
+        assert!(is_emoji_presentation('\u{2764}', Some('\u{FE0F}')));
+        assert!(!is_emoji_presentation('a', None));
+
+        let style = FragmentStyle::default();
+        let mut library = emoji_library(true);
+        assert_eq!(
+            library.find_best_font_match('\u{1F600}', None, &style),
+            Some((library.emoji_font_id.unwrap(), true))
+        );
+        assert_eq!(
+            library.find_best_font_match('a', Some('\u{FE0F}'), &style),
+            Some((FONT_ID_REGULAR, false))
+        );
+    }
+
+    #[test]
+    fn test_monochrome_emoji() {
+        let style = FragmentStyle::default();
+
+        // The regular font has a text presentation of U+263A.
+        let mut library = emoji_library(true);
+        assert_eq!(
+            library.find_best_font_match('\u{263A}', Some('\u{FE0F}'), &style),
+            Some((library.emoji_font_id.unwrap(), true))
+        );
+        assert!(matches!(
+            library.glyph_sources(),
+            [Source::ColorOutline(_), ..]
+        ));
+
+        // Without color it is looked up like text presentation.
+        let mut library = emoji_library(false);
+        assert_eq!(
+            library.find_best_font_match('\u{263A}', Some('\u{FE0F}'), &style),
+            Some((FONT_ID_REGULAR, false))
+        );
+        assert!(matches!(library.glyph_sources(), [Source::Outline]));
+
+        // Glyphs of the emoji font are rasterized as a mask from their
+        // outline.
+        let emoji_font_id = library.emoji_font_id.unwrap();
+        let sources = library.glyph_sources();
+        let font = library.get_data(&emoji_font_id).unwrap();
+        let glyph_id = font.charmap().map('\u{1F600}');
+        let mut context = ScaleContext::new();
+        let mut scaler = context.builder(font).size(32.).build();
+        let image = Render::new(sources)
+            .format(Format::Alpha)
+            .render(&mut scaler, glyph_id)
+            .unwrap();
+        assert_eq!(image.content, Content::Mask);
+        assert!(image.placement.width > 0 && image.placement.height > 0);
+        assert!(image.data.iter().any(|alpha| *alpha > 0));
+    }
+}
@@ -870,6 +870,16 @@ impl<'a> Render<'a> {
                     }
                     scaler.state.outline.clear();
                     if scaler.scale_outline_impl(glyph_id, None, None) {
+                        // Color glyphs usually have an empty outline, when color
+                        // sources are not used their layers are drawn as one mask.
+                        if scaler.state.outline.verbs().is_empty()
+                            && scaler.has_color_outlines()
+                        {
+                            scaler.state.outline.clear();
+                            if !scaler.scale_color_outline_impl(glyph_id) {
+                                scaler.state.outline.clear();
+                            }
+                        }
                         let state = &mut scaler.state;
                         let rcx = &mut state.rcx;
                         let outline = &mut state.outline;
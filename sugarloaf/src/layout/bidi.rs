@@ -0,0 +1,282 @@
+// Copyright (c) 2023-present, Raphael Amorim.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+//! Bidirectional text for terminal lines.
+//!
+//! Implements the parts of the Unicode Bidirectional Algorithm (UAX #9)
+//! that apply to a line of a terminal: every line is a left to right
+//! paragraph and explicit embeddings, overrides and isolates are ignored.
+//! Lines keep their logical order in the grid, so cursor and selection
+//! stay addressed by logical columns, only the shaped runs are reordered.
+
+use crate::font_introspector::text::{BidiClass, Codepoint, Script};
+use std::ops::Range;
+
+/// Whether `text` contains characters written right to left.
+#[inline]
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        // Nothing below the Hebrew block is right to left.
+        c as u32 >= 0x0590 && matches!(c.bidi_class(), BidiClass::R | BidiClass::AL)
+    })
+}
+
+/// Direction resolved for a character, numbers are kept apart from right
+/// to left text so they are still displayed left to right.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Kind {
+    Left,
+    Right,
+    Number,
+    Neutral,
+}
+
+/// Embedding level of every character of `text`: 0 for left to right
+/// text, 1 for right to left text and 2 for numbers in right to left text.
+pub fn resolve_levels(text: &str) -> Vec<u8> {
+    let classes: Vec<BidiClass> = text.chars().map(|c| c.bidi_class()).collect();
+    let mut kinds = Vec::with_capacity(classes.len());
+    // Start of the line is left to right.
+    let mut last_strong = BidiClass::L;
+    let mut previous = Kind::Left;
+    for class in &classes {
+        let kind = match class {
+            BidiClass::L => Kind::Left,
+            BidiClass::R | BidiClass::AL => Kind::Right,
+            // European numbers after left to right text are left to right.
+            BidiClass::EN if last_strong == BidiClass::L => Kind::Left,
+            BidiClass::EN | BidiClass::AN => Kind::Number,
+            // Combining marks follow their base.
+            BidiClass::NSM => previous,
+            _ => Kind::Neutral,
+        };
+        if matches!(class, BidiClass::L | BidiClass::R | BidiClass::AL) {
+            last_strong = *class;
+        }
+        previous = kind;
+        kinds.push(kind);
+    }
+
+    // A single separator between two numbers and terminators next to
+    // numbers are part of the number, like `1.5` or `10%`.
+    for index in 0..kinds.len() {
+        if kinds[index] != Kind::Neutral {
+            continue;
+        }
+        let before = index.checked_sub(1).map(|i| kinds[i]);
+        let after = kinds.get(index + 1).copied();
+        let is_number = match classes[index] {
+            BidiClass::CS | BidiClass::ES => {
+                before == Some(Kind::Number) && after == Some(Kind::Number)
+            }
+            BidiClass::ET => {
+                before == Some(Kind::Number)
+                    || classes[index..]
+                        .iter()
+                        .zip(&kinds[index..])
+                        .find(|(class, _)| **class != BidiClass::ET)
+                        .is_some_and(|(_, kind)| *kind == Kind::Number)
+            }
+            _ => false,
+        };
+        if is_number {
+            kinds[index] = Kind::Number;
+        }
+    }
+
+    // Neutrals take the direction of the text around them when both sides
+    // agree, numbers counting as right to left, or the line direction.
+    let mut index = 0;
+    while index < kinds.len() {
+        if kinds[index] != Kind::Neutral {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < kinds.len() && kinds[index] == Kind::Neutral {
+            index += 1;
+        }
+        let direction = |kind: Option<&Kind>| match kind {
+            Some(Kind::Right | Kind::Number) => Kind::Right,
+            _ => Kind::Left,
+        };
+        let before = direction(start.checked_sub(1).map(|i| &kinds[i]));
+        let after = direction(kinds.get(index));
+        let resolved = if before == after { before } else { Kind::Left };
+        kinds[start..index].fill(resolved);
+    }
+
+    // Formatting characters, like joiners, are ignored and take the
+    // direction of the character before them, or after them at the start.
+    let first = classes.iter().position(|class| *class != BidiClass::BN);
+    for index in 0..kinds.len() {
+        if classes[index] != BidiClass::BN {
+            continue;
+        }
+        kinds[index] = match index.checked_sub(1) {
+            Some(previous) => kinds[previous],
+            None => first.map_or(Kind::Left, |first| kinds[first]),
+        };
+    }
+
+    kinds
+        .into_iter()
+        .map(|kind| match kind {
+            Kind::Right => 1,
+            Kind::Number => 2,
+            Kind::Left | Kind::Neutral => 0,
+        })
+        .collect()
+}
+
+/// Logical index of the item displayed at each position from left to
+/// right, given the embedding level of every item.
+pub fn visual_order(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = levels
+        .iter()
+        .copied()
+        .filter(|level| level % 2 == 1)
+        .min()
+        .unwrap_or(highest + 1);
+
+    // Reverse every sequence at or above each level, from the highest
+    // level down to the lowest odd one.
+    for level in (lowest_odd..=highest).rev() {
+        let mut index = 0;
+        while index < order.len() {
+            if levels[order[index]] < level {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < order.len() && levels[order[index]] >= level {
+                index += 1;
+            }
+            order[start..index].reverse();
+        }
+    }
+
+    order
+}
+
+/// Part of a fragment shaped on its own, the characters at `range` share
+/// the same embedding level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BidiRun {
+    pub fragment: usize,
+    pub range: Range<usize>,
+    pub level: u8,
+}
+
+impl BidiRun {
+    #[inline]
+    pub fn is_rtl(&self) -> bool {
+        self.level % 2 == 1
+    }
+}
+
+/// Splits the fragments of a line in runs of a single fragment and
+/// embedding level, returned in visual order from left to right.
+pub fn visual_runs<'a>(fragments: impl Iterator<Item = &'a str> + Clone) -> Vec<BidiRun> {
+    let text: String = fragments.clone().collect();
+    let levels = resolve_levels(&text);
+
+    let mut runs: Vec<BidiRun> = Vec::new();
+    let mut levels = levels.into_iter();
+    for (fragment, content) in fragments.enumerate() {
+        for ((offset, c), level) in content.char_indices().zip(levels.by_ref()) {
+            match runs.last_mut() {
+                Some(run) if run.fragment == fragment && run.level == level => {
+                    run.range.end = offset + c.len_utf8();
+                }
+                _ => runs.push(BidiRun {
+                    fragment,
+                    range: offset..offset + c.len_utf8(),
+                    level,
+                }),
+            }
+        }
+    }
+
+    let run_levels: Vec<u8> = runs.iter().map(|run| run.level).collect();
+    visual_order(&run_levels)
+        .into_iter()
+        .map(|index| runs[index].clone())
+        .collect()
+}
+
+/// Script used to shape `text`, the first one that is not shared between
+/// scripts or `Latin` when there is none.
+pub fn script(text: &str) -> Script {
+    text.chars()
+        .map(|c| c.script())
+        .find(|script| {
+            !matches!(script, Script::Common | Script::Inherited | Script::Unknown)
+        })
+        .unwrap_or(Script::Latin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_levels() {
+        assert_eq!(resolve_levels("abc 12"), [0, 0, 0, 0, 0, 0]);
+        // Spaces between right to left words are right to left.
+        assert_eq!(resolve_levels("אב גד"), [1, 1, 1, 1, 1]);
+        // Spaces between directions and trailing spaces follow the line.
+        assert_eq!(resolve_levels("a אב "), [0, 0, 1, 1, 0]);
+        // Numbers in right to left text, with their separators.
+        assert_eq!(resolve_levels("א 1.5%"), [1, 1, 2, 2, 2, 2]);
+        // Joiners follow the text they join.
+        assert_eq!(resolve_levels("\u{200d}א\u{200d}"), [1, 1, 1]);
+    }
+
+    #[test]
+    fn test_visual_order() {
+        assert_eq!(visual_order(&[0, 0, 0]), [0, 1, 2]);
+        assert_eq!(visual_order(&[1, 1, 1]), [2, 1, 0]);
+        assert_eq!(visual_order(&[0, 1, 1, 0]), [0, 2, 1, 3]);
+        assert_eq!(visual_order(&[1, 2, 2, 1]), [3, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_visual_runs() {
+        assert!(!has_rtl("rio 1.5"));
+        assert!(has_rtl("rio שלום"));
+
+        // "ab", " ", "אב", " ", "גד 12" as fragments.
+        let fragments = ["ab", " ", "אב", " ", "גד 12"];
+        let runs = visual_runs(fragments.iter().copied());
+        let visual: Vec<(usize, &str, bool)> = runs
+            .iter()
+            .map(|run| {
+                let content = &fragments[run.fragment][run.range.clone()];
+                (run.fragment, content, run.is_rtl())
+            })
+            .collect();
+        assert_eq!(
+            visual,
+            [
+                (0, "ab", false),
+                (1, " ", false),
+                (4, "12", false),
+                (4, "גד ", true),
+                (3, " ", true),
+                (2, "אב", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_script() {
+        assert_eq!(script("12 سلام"), Script::Arabic);
+        assert_eq!(script("שלום"), Script::Hebrew);
+        assert_eq!(script("1.5"), Script::Latin);
+    }
+}
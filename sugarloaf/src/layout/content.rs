@@ -3,13 +3,15 @@
 // This source code is licensed under the MIT license found in the
 // LICENSE file in the root directory of this source tree.
 
+use crate::components::rich_text::drawable_character::DrawableChar;
 use crate::components::rich_text::RichTextBrush;
-use crate::font::FontLibrary;
+use crate::font::{FontLibrary, WEIGHT_AXIS};
 use crate::font_introspector::shape::cluster::GlyphCluster;
 use crate::font_introspector::shape::cluster::OwnedGlyphCluster;
-use crate::font_introspector::shape::ShapeContext;
+use crate::font_introspector::shape::{Direction, ShapeContext};
 use crate::font_introspector::text::Script;
 use crate::font_introspector::Metrics;
+use crate::layout::bidi;
 use crate::layout::render_data::RenderData;
 use crate::layout::RichTextLayout;
 use lru::LruCache;
@@ -219,6 +221,8 @@ pub struct FragmentStyle {
     pub cursor: Option<SugarCursor>,
     /// Media
     pub media: Option<Graphic>,
+    /// Box-drawing or block element drawn without the font.
+    pub drawable_char: Option<DrawableChar>,
 }
 
 impl Default for FragmentStyle {
@@ -237,6 +241,7 @@ impl Default for FragmentStyle {
             decoration: None,
             decoration_color: None,
             media: None,
+            drawable_char: None,
         }
     }
 }
@@ -285,6 +290,12 @@ impl Content {
         }
     }
 
+    /// Number of runs whose shaping was reused from the word cache.
+    #[inline]
+    pub fn word_cache_hits(&self) -> usize {
+        self.word_cache.hits()
+    }
+
     #[inline]
     pub fn get_state(&self, state_id: &usize) -> Option<&BuilderState> {
         self.states.get(state_id)
@@ -300,6 +311,9 @@ impl Content {
         &mut self,
         font_features: Vec<crate::font_introspector::Setting<u16>>,
     ) {
+        if self.font_features != font_features {
+            self.word_cache = WordCache::new();
+        }
         self.font_features = font_features;
     }
 
@@ -454,65 +468,12 @@ impl Content {
     #[inline]
     pub fn build(&mut self) {
         if let Some(selector) = self.selector {
-            if let Some(state) = self.states.get_mut(&selector) {
-                let script = Script::Latin;
-                for line_number in 0..state.lines.len() {
-                    let line = &mut state.lines[line_number];
-                    for item in &line.fragments {
-                        let vars = state.vars.get(item.style.font_vars);
-                        let shaper_key = &item.content;
-
-                        // println!("{:?} -> {:?}", item.style.font_id, shaper_key);
-
-                        if let Some(shaper) =
-                            self.word_cache.get(&item.style.font_id, shaper_key)
-                        {
-                            if let Some(metrics) =
-                                state.metrics_cache.inner.get(&item.style.font_id)
-                            {
-                                if line.render_data.push_run_without_shaper(
-                                    item.style,
-                                    state.scaled_font_size,
-                                    line_number as u32,
-                                    shaper,
-                                    metrics,
-                                ) {
-                                    continue;
-                                }
-                            }
-                        }
-
-                        self.word_cache.font_id = item.style.font_id;
-                        self.word_cache.content = item.content.clone();
-                        let font_library = { &mut self.fonts.inner.lock() };
-                        if let Some(data) = font_library.get_data(&item.style.font_id) {
-                            let mut shaper = self
-                                .scx
-                                .builder(data)
-                                .script(script)
-                                .size(state.scaled_font_size)
-                                .features(self.font_features.iter().copied())
-                                .variations(vars.iter().copied())
-                                .build();
-
-                            shaper.add_str(&self.word_cache.content);
-
-                            state
-                                .metrics_cache
-                                .inner
-                                .entry(item.style.font_id)
-                                .or_insert_with(|| shaper.metrics());
-
-                            line.render_data.push_run(
-                                item.style,
-                                state.scaled_font_size,
-                                line_number as u32,
-                                shaper,
-                                &mut self.word_cache,
-                            );
-                        }
-                    }
-                }
+            let lines = self
+                .states
+                .get(&selector)
+                .map_or(0, |state| state.lines.len());
+            for line_number in 0..lines {
+                self.shape_line(selector, line_number);
             }
         }
     }
@@ -520,73 +481,165 @@ impl Content {
     #[inline]
     pub fn build_line(&mut self, line_number: usize) {
         if let Some(selector) = self.selector {
-            if let Some(state) = self.states.get_mut(&selector) {
-                let script = Script::Latin;
-                let line = &mut state.lines[line_number];
-                for item in &line.fragments {
-                    let vars = state.vars.get(item.style.font_vars);
-                    let shaper_key = &item.content;
-
-                    // println!("{:?} -> {:?}", item.style.font_id, shaper_key);
-
-                    if let Some(shaper) =
-                        self.word_cache.get(&item.style.font_id, shaper_key)
-                    {
-                        if let Some(metrics) =
-                            state.metrics_cache.inner.get(&item.style.font_id)
-                        {
-                            if line.render_data.push_run_without_shaper(
-                                item.style,
-                                state.scaled_font_size,
-                                line_number as u32,
-                                shaper,
-                                metrics,
-                            ) {
-                                continue;
-                            }
-                        }
-                    }
-
-                    self.word_cache.font_id = item.style.font_id;
-                    self.word_cache.content = item.content.clone();
-                    let font_library = { &mut self.fonts.inner.lock() };
-                    if let Some(data) = font_library.get_data(&item.style.font_id) {
-                        let mut shaper = self
-                            .scx
-                            .builder(data)
-                            .script(script)
-                            .size(state.scaled_font_size)
-                            .features(self.font_features.iter().copied())
-                            .variations(vars.iter().copied())
-                            .build();
-
-                        shaper.add_str(&self.word_cache.content);
-
-                        state
-                            .metrics_cache
-                            .inner
-                            .entry(item.style.font_id)
-                            .or_insert_with(|| shaper.metrics());
-
-                        line.render_data.push_run(
-                            item.style,
-                            state.scaled_font_size,
-                            line_number as u32,
-                            shaper,
-                            &mut self.word_cache,
-                        );
-                    }
-                }
+            self.shape_line(selector, line_number);
+        }
+    }
+
+    /// Shapes the fragments of a line. Lines with right to left text are
+    /// shaped in runs of a single direction, laid out in visual order.
+    fn shape_line(&mut self, state_id: usize, line_number: usize) {
+        let Some(state) = self.states.get_mut(&state_id) else {
+            return;
+        };
+        let Some(line) = state.lines.get_mut(line_number) else {
+            return;
+        };
+
+        if !line
+            .fragments
+            .iter()
+            .any(|fragment| bidi::has_rtl(&fragment.content))
+        {
+            for item in &line.fragments {
+                shape_run(
+                    ShapeRun {
+                        content: &item.content,
+                        style: item.style,
+                        script: Script::Latin,
+                        direction: Direction::LeftToRight,
+                        line: line_number as u32,
+                    },
+                    &mut self.scx,
+                    &self.fonts,
+                    &self.font_features,
+                    &mut self.word_cache,
+                    &state.vars,
+                    &mut state.metrics_cache,
+                    state.scaled_font_size,
+                    &mut line.render_data,
+                );
             }
+            return;
+        }
+
+        let runs =
+            bidi::visual_runs(line.fragments.iter().map(|item| item.content.as_str()));
+        for run in runs {
+            let item = &line.fragments[run.fragment];
+            let content = &item.content[run.range.clone()];
+            let (script, direction) = if run.is_rtl() {
+                (bidi::script(content), Direction::RightToLeft)
+            } else {
+                (Script::Latin, Direction::LeftToRight)
+            };
+            shape_run(
+                ShapeRun {
+                    content,
+                    style: item.style,
+                    script,
+                    direction,
+                    line: line_number as u32,
+                },
+                &mut self.scx,
+                &self.fonts,
+                &self.font_features,
+                &mut self.word_cache,
+                &state.vars,
+                &mut state.metrics_cache,
+                state.scaled_font_size,
+                &mut line.render_data,
+            );
         }
     }
 }
 
+/// Text of a run with the style, script and direction to shape it with.
+struct ShapeRun<'a> {
+    content: &'a str,
+    style: FragmentStyle,
+    script: Script,
+    direction: Direction,
+    line: u32,
+}
+
+/// Shapes a run, or reuses its glyph clusters from the word cache, and
+/// pushes it to `render_data`.
+#[allow(clippy::too_many_arguments)]
+fn shape_run(
+    run: ShapeRun<'_>,
+    scx: &mut ShapeContext,
+    fonts: &FontLibrary,
+    font_features: &[Setting<u16>],
+    word_cache: &mut WordCache,
+    vars: &FontSettingCache<f32>,
+    metrics_cache: &mut MetricsCache,
+    scaled_font_size: f32,
+    render_data: &mut RenderData,
+) {
+    let ShapeRun {
+        content,
+        style,
+        script,
+        direction,
+        line,
+    } = run;
+    let rtl = direction == Direction::RightToLeft;
+    let vars = vars.get(style.font_vars);
+
+    if let Some(shaper) = word_cache.get(&style.font_id, scaled_font_size, rtl, content) {
+        if let Some(metrics) = metrics_cache.inner.get(&style.font_id) {
+            if render_data.push_run_without_shaper(
+                style,
+                scaled_font_size,
+                line,
+                shaper,
+                metrics,
+                rtl,
+            ) {
+                return;
+            }
+        }
+    }
+
+    word_cache.font_id = style.font_id;
+    word_cache.font_size = scaled_font_size;
+    word_cache.rtl = rtl;
+    word_cache.content = content.to_string();
+    let font_library = { &mut fonts.inner.lock() };
+    let weight_variation = font_library.get(&style.font_id).weight_variation;
+    if let Some(data) = font_library.get_data(&style.font_id) {
+        let mut shaper = scx
+            .builder(data)
+            .script(script)
+            .direction(direction)
+            .size(scaled_font_size)
+            .features(font_features.iter().copied())
+            .variations(vars.iter().copied())
+            .variations(weight_variation.map(|w| (WEIGHT_AXIS, w)))
+            .build();
+
+        shaper.add_str(&word_cache.content);
+
+        metrics_cache
+            .inner
+            .entry(style.font_id)
+            .or_insert_with(|| shaper.metrics());
+
+        render_data.push_run(style, scaled_font_size, line, shaper, word_cache, rtl);
+    }
+}
+
+/// Shaped glyph clusters of previously shaped runs, keyed by font id,
+/// scaled font size, direction and run content. Must be recreated whenever
+/// the font library or the font features change.
 pub struct WordCache {
-    pub inner: FxHashMap<usize, LruCache<String, Vec<OwnedGlyphCluster>>>,
+    pub inner: FxHashMap<(usize, u32, bool), LruCache<String, Vec<OwnedGlyphCluster>>>,
     stash: Vec<OwnedGlyphCluster>,
     font_id: usize,
+    font_size: f32,
+    rtl: bool,
     content: String,
+    hits: usize,
 }
 
 impl WordCache {
@@ -595,7 +648,10 @@ impl WordCache {
             inner: FxHashMap::default(),
             stash: vec![],
             font_id: 0,
+            font_size: 0.,
+            rtl: false,
             content: String::new(),
+            hits: 0,
         }
     }
 
@@ -603,12 +659,23 @@ impl WordCache {
     pub fn get(
         &mut self,
         font_id: &usize,
-        content: &String,
+        font_size: f32,
+        rtl: bool,
+        content: &str,
     ) -> Option<&Vec<OwnedGlyphCluster>> {
-        if let Some(cache) = self.inner.get_mut(font_id) {
-            return cache.get(content);
+        let cached = self
+            .inner
+            .get_mut(&(*font_id, font_size.to_bits(), rtl))
+            .and_then(|cache| cache.get(content));
+        if cached.is_some() {
+            self.hits += 1;
         }
-        None
+        cached
+    }
+
+    #[inline]
+    pub fn hits(&self) -> usize {
+        self.hits
     }
 
     #[inline]
@@ -619,7 +686,8 @@ impl WordCache {
     #[inline]
     pub fn finish(&mut self) {
         if !self.content.is_empty() && !self.stash.is_empty() {
-            if let Some(cache) = self.inner.get_mut(&self.font_id) {
+            let key = (self.font_id, self.font_size.to_bits(), self.rtl);
+            if let Some(cache) = self.inner.get_mut(&key) {
                 // println!("{:?} {:?}", self.content, cache.len());
                 cache.put(
                     std::mem::take(&mut self.content),
@@ -633,7 +701,7 @@ impl WordCache {
                     std::mem::take(&mut self.content),
                     std::mem::take(&mut self.stash),
                 );
-                self.inner.insert(self.font_id, cache);
+                self.inner.insert(key, cache);
             }
 
             self.font_id = 0;
@@ -649,3 +717,67 @@ impl WordCache {
 struct MetricsCache {
     pub inner: FxHashMap<usize, Metrics>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(content: &mut Content, id: usize, text: &str) -> Vec<u32> {
+        content.sel(id).clear();
+        content.add_text(text, FragmentStyle::default());
+        content.build();
+
+        content.get_state(&id).unwrap().lines[0].render_data.runs[0]
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.data)
+            .collect()
+    }
+
+    fn glyph_ids(content: &mut Content, id: usize, text: &str) -> Vec<u16> {
+        content.sel(id).clear();
+        content.add_text(text, FragmentStyle::default());
+        content.build();
+
+        let line = &content.get_state(&id).unwrap().lines[0];
+        line.render_data
+            .runs
+            .iter()
+            .flat_map(|run| run.glyphs.iter().map(|glyph| run.glyph(*glyph).id))
+            .collect()
+    }
+
+    #[test]
+    fn test_shaping_rtl_run() {
+        let font_library = FontLibrary::default();
+        let mut content = Content::new(&font_library);
+        let id = content.create_state(&RichTextLayout::default());
+
+        // Beh, yeh and teh joined: initial, medial and final forms.
+        let word = glyph_ids(&mut content, id, "\u{628}\u{64a}\u{62a}");
+        let initial = glyph_ids(&mut content, id, "\u{628}\u{200d}");
+        let medial = glyph_ids(&mut content, id, "\u{200d}\u{64a}\u{200d}");
+        let last = glyph_ids(&mut content, id, "\u{200d}\u{62a}");
+        let isolated = glyph_ids(&mut content, id, "\u{64a}");
+
+        // Right to left, the first letter is displayed last, and yeh joined
+        // on both sides does not use its isolated form.
+        assert_eq!(word, [&last[..], &medial, &initial].concat());
+        assert_ne!(medial, isolated);
+    }
+
+    #[test]
+    fn test_shaping_same_run_hits_word_cache() {
+        let font_library = FontLibrary::default();
+        let mut content = Content::new(&font_library);
+        let id = content.create_state(&RichTextLayout::default());
+
+        let first = shape(&mut content, id, "rio");
+        assert_eq!(first.len(), 3);
+        assert_eq!(content.word_cache_hits(), 0);
+
+        let second = shape(&mut content, id, "rio");
+        assert_eq!(content.word_cache_hits(), 1);
+        assert_eq!(first, second);
+    }
+}
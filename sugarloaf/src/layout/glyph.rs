@@ -69,6 +69,25 @@ pub struct RunData {
     pub advance: f32,
 }
 
+impl RunData {
+    /// Returns the shaped glyph, simple glyphs are not offset.
+    #[inline]
+    pub fn glyph(&self, data: GlyphData) -> Glyph {
+        if data.is_simple() {
+            let (id, advance) = data.simple_data();
+            Glyph {
+                id,
+                x: 0.,
+                y: 0.,
+                advance,
+                span: data.size,
+            }
+        } else {
+            self.detailed_glyphs[data.detail_index()]
+        }
+    }
+}
+
 /// Shaped glyph in a paragraph.
 #[derive(Copy, Debug, Clone)]
 pub struct Glyph {
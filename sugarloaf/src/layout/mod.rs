@@ -7,6 +7,7 @@
 // nav and span_style were originally retired from dfrg/swash_demo licensed under MIT
 // https://github.com/dfrg/swash_demo/blob/master/LICENSE
 
+mod bidi;
 mod content;
 mod glyph;
 mod render_data;
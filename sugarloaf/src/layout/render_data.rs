@@ -60,16 +60,19 @@ impl RenderData {
         line: u32,
         shaper: Shaper<'_>,
         shaper_cache: &mut WordCache,
+        rtl: bool,
     ) {
         // let clusters_start = self.data.clusters.len() as u32;
         let metrics = shaper.metrics();
 
         let mut glyphs = vec![];
         let mut detailed_glyphs = vec![];
+        let mut clusters = vec![];
         let mut advance = 0.;
 
         shaper.shape_with(|c| {
             shaper_cache.add_glyph_cluster(c);
+            clusters.push(glyphs.len());
 
             let mut cluster_advance = 0.;
             for glyph in c.glyphs {
@@ -97,6 +100,9 @@ impl RenderData {
             advance += cluster_advance;
         });
         shaper_cache.finish();
+        if rtl {
+            glyphs = visual_glyphs(&glyphs, &clusters);
+        }
         if let Some(graphic) = style.media {
             self.graphics.insert(graphic.id);
         }
@@ -128,14 +134,17 @@ impl RenderData {
         line: u32,
         glyph_clusters: &Vec<OwnedGlyphCluster>,
         metrics: &Metrics,
+        rtl: bool,
     ) -> bool {
         // In case is a new line,
         // then needs to recompute the span index again
         let mut advance = 0.;
         let mut glyphs = vec![];
         let mut detailed_glyphs = vec![];
+        let mut clusters = vec![];
 
         for c in glyph_clusters {
+            clusters.push(glyphs.len());
             let mut cluster_advance = 0.;
             for glyph in &c.glyphs {
                 cluster_advance += glyph.advance;
@@ -161,6 +170,9 @@ impl RenderData {
             }
             advance += cluster_advance;
         }
+        if rtl {
+            glyphs = visual_glyphs(&glyphs, &clusters);
+        }
         if let Some(graphic) = style.media {
             self.graphics.insert(graphic.id);
         }
@@ -185,6 +197,19 @@ impl RenderData {
     }
 }
 
+/// Glyphs of right to left runs come out of the shaper in logical order,
+/// reverses the clusters starting at `clusters` to lay them out from left
+/// to right.
+fn visual_glyphs(glyphs: &[GlyphData], clusters: &[usize]) -> Vec<GlyphData> {
+    let mut visual = Vec::with_capacity(glyphs.len());
+    let mut end = glyphs.len();
+    for start in clusters.iter().rev() {
+        visual.extend_from_slice(&glyphs[*start..end]);
+        end = *start;
+    }
+    visual
+}
+
 /// Sequence of clusters sharing the same font, size and span.
 #[derive(Copy, Clone)]
 pub struct Run<'a> {
@@ -8,6 +8,8 @@ mod sugarloaf;
 pub use font_introspector::{Stretch, Style, Weight};
 
 pub use crate::sugarloaf::{
+    animation::{Animated, Animations, DEFAULT_ANIMATION_DURATION},
+    blink::{BlinkPhase, BlinkRate},
     compositors::SugarCompositors,
     graphics::{
         ColorType, Graphic, GraphicData, GraphicId, Graphics, ResizeCommand,
@@ -19,6 +21,8 @@ pub use crate::sugarloaf::{
 };
 pub use components::quad::{ComposedQuad, Quad};
 pub use components::rect::Rect;
+pub use components::rich_text::drawable_character::{drawable_character, DrawableChar};
+pub use components::rich_text::AtlasStats;
 pub use layout::{
     Content, FragmentStyle, FragmentStyleDecoration, SugarDimensions, UnderlineInfo,
     UnderlineShape,
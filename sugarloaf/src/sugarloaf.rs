@@ -1,29 +1,38 @@
+pub mod animation;
+pub mod blink;
+pub mod capture;
 pub mod compositors;
 pub mod graphics;
 pub mod primitives;
 pub mod state;
+pub mod timestamps;
 
 use crate::components::core::{image::Handle, shapes::Rectangle};
 use crate::components::filters::FiltersBrush;
 use crate::components::layer::{self, LayerBrush};
 use crate::components::quad::QuadBrush;
 use crate::components::rect::{Rect, RectBrush};
-use crate::components::rich_text::RichTextBrush;
+use crate::components::rich_text::{AtlasStats, RichTextBrush};
 use crate::components::text;
 use crate::font::{fonts::SugarloafFont, FontLibrary};
 use crate::layout::{RichTextLayout, RootStyle};
-use crate::sugarloaf::graphics::{BottomLayer, Graphics};
+use crate::sugarloaf::graphics::{
+    BottomLayer, ColorType, GraphicData, GraphicId, Graphics,
+};
 use crate::sugarloaf::layer::types;
 use crate::Content;
 use crate::SugarDimensions;
 use crate::{context::Context, Object};
 use ab_glyph::{self, PxScale};
+use animation::Animations;
+use blink::{BlinkPhase, BlinkRate};
 use core::fmt::{Debug, Formatter};
 use primitives::ImageProperties;
 use raw_window_handle::{
     DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle,
 };
 use state::SugarState;
+use timestamps::GpuTimer;
 
 pub struct Sugarloaf<'a> {
     pub ctx: Context<'a>,
@@ -35,8 +44,12 @@ pub struct Sugarloaf<'a> {
     state: state::SugarState,
     pub background_color: Option<wgpu::Color>,
     pub background_image: Option<ImageProperties>,
+    background_alpha: f32,
     pub graphics: Graphics,
     filters_brush: FiltersBrush,
+    animations: Animations,
+    gpu_timer: GpuTimer,
+    blink_phase: BlinkPhase,
 }
 
 #[derive(Debug)]
@@ -138,6 +151,7 @@ impl Sugarloaf<'_> {
         let rich_text_brush = RichTextBrush::new(&ctx);
         let state = SugarState::new(layout, font_library, &font_features);
         let filters_brush = FiltersBrush::default();
+        let gpu_timer = GpuTimer::new(&ctx.device, &ctx.queue);
 
         let instance = Sugarloaf {
             state,
@@ -146,11 +160,15 @@ impl Sugarloaf<'_> {
             ctx,
             background_color: Some(wgpu::Color::BLACK),
             background_image: None,
+            background_alpha: 1.0,
             rect_brush,
             rich_text_brush,
             text_brush,
             graphics: Graphics::default(),
             filters_brush,
+            animations: Animations::default(),
+            gpu_timer,
+            blink_phase: BlinkPhase::default(),
         };
 
         Ok(instance)
@@ -204,6 +222,68 @@ impl Sugarloaf<'_> {
             .set_rich_text_font_size(rt_id, font_size, &mut self.rich_text_brush);
     }
 
+    /// Enables or disables cursor, scroll and opacity animations, when
+    /// disabled targets are applied instantly.
+    #[inline]
+    pub fn set_animations(&mut self, enabled: bool, duration: std::time::Duration) {
+        self.animations.configure(enabled, duration);
+    }
+
+    #[inline]
+    pub fn animations(&self) -> &Animations {
+        &self.animations
+    }
+
+    #[inline]
+    pub fn animations_mut(&mut self) -> &mut Animations {
+        &mut self.animations
+    }
+
+    /// Advances animations by `dt`, returns `true` if another frame
+    /// should be requested.
+    #[inline]
+    pub fn tick(&mut self, dt: std::time::Duration) -> bool {
+        self.animations.tick(dt)
+    }
+
+    /// Sets the phase of the clock shared by the cursor and blinking text.
+    #[inline]
+    pub fn set_blink_phase(&mut self, phase: BlinkPhase) {
+        self.blink_phase = phase;
+    }
+
+    #[inline]
+    pub fn blink_phase(&self) -> BlinkPhase {
+        self.blink_phase
+    }
+
+    /// Whether elements blinking at `rate` are visible in the current phase.
+    #[inline]
+    pub fn is_blink_visible(&self, rate: BlinkRate) -> bool {
+        self.blink_phase.is_visible(rate)
+    }
+
+    /// Time the GPU spent on the render pass of a recent frame, `None` if
+    /// the adapter does not support timestamp queries.
+    #[inline]
+    pub fn last_gpu_time(&self) -> Option<std::time::Duration> {
+        self.gpu_timer.last()
+    }
+
+    /// Usage of the glyph atlas.
+    #[inline]
+    pub fn atlas_stats(&self) -> AtlasStats {
+        self.rich_text_brush.atlas_stats()
+    }
+
+    /// Evicts the glyphs not used by the last rendered frame from the
+    /// atlas, returns how many were evicted. Evicted glyphs are rasterized
+    /// again the next time they are rendered.
+    #[inline]
+    pub fn atlas_trim(&mut self) -> usize {
+        self.rich_text_brush.atlas_trim()
+    }
+
     #[inline]
     pub fn update_filters(&mut self, filter_paths: &[String]) {
         self.filters_brush.update_filters(&self.ctx, filter_paths);
@@ -215,6 +295,19 @@ impl Sugarloaf<'_> {
         self
     }
 
+    /// Multiplies the alpha of the background color of the next frames,
+    /// apart from the opacity the window was created with.
+    #[inline]
+    pub fn set_background_alpha(&mut self, alpha: f32) -> &mut Self {
+        self.background_alpha = alpha.clamp(0., 1.);
+        self
+    }
+
+    #[inline]
+    pub fn background_alpha(&self) -> f32 {
+        self.background_alpha
+    }
+
     #[inline]
     pub fn set_background_image(&mut self, image: &ImageProperties) -> &mut Self {
         let handle = Handle::from_path(image.path.to_owned());
@@ -281,12 +374,7 @@ impl Sugarloaf<'_> {
     #[inline]
     pub fn resize(&mut self, width: u32, height: u32) {
         self.ctx.resize(width, height);
-        if let Some(bottom_layer) = &mut self.graphics.bottom_layer {
-            if bottom_layer.should_fit {
-                bottom_layer.data.bounds.width = self.ctx.size.width;
-                bottom_layer.data.bounds.height = self.ctx.size.height;
-            }
-        }
+        self.graphics.fit_bottom_layer(self.ctx.size);
     }
 
     #[inline]
@@ -294,12 +382,28 @@ impl Sugarloaf<'_> {
         self.ctx.scale = scale;
         self.state
             .compute_layout_rescale(scale, &mut self.rich_text_brush);
-        if let Some(bottom_layer) = &mut self.graphics.bottom_layer {
-            if bottom_layer.should_fit {
-                bottom_layer.data.bounds.width = self.ctx.size.width;
-                bottom_layer.data.bounds.height = self.ctx.size.height;
-            }
-        }
+        self.graphics.fit_bottom_layer(self.ctx.size);
+    }
+
+    /// Applies a new size and scale factor at once, as needed when the
+    /// window moves to a display with a different DPI.
+    ///
+    /// Same result as `rescale` followed by `resize`, but layouts are only
+    /// recomputed if the scale changed and the background image is fitted
+    /// to the new size once.
+    #[inline]
+    pub fn reconfigure(&mut self, width: u32, height: u32, scale: f32) {
+        self.ctx.resize(width, height);
+        rescale_if_needed(
+            &mut self.ctx.scale,
+            self.state.style.scale_factor,
+            scale,
+            |scale| {
+                self.state
+                    .compute_layout_rescale(scale, &mut self.rich_text_brush)
+            },
+        );
+        self.graphics.fit_bottom_layer(self.ctx.size);
     }
 
     #[inline]
@@ -320,103 +424,18 @@ impl Sugarloaf<'_> {
             &mut self.graphics,
         );
 
+        self.gpu_timer.poll(&self.ctx.device);
+
         match self.ctx.surface.get_current_texture() {
             Ok(frame) => {
                 let mut encoder = self.ctx.device.create_command_encoder(
                     &wgpu::CommandEncoderDescriptor { label: None },
                 );
 
-                let view = frame
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default());
-
-                if let Some(layer) = &self.graphics.bottom_layer {
-                    self.layer_brush
-                        .prepare(&mut encoder, &mut self.ctx, &[&layer.data]);
-                }
-
-                if self.graphics.has_graphics_on_top_layer() {
-                    for request in &self.graphics.top_layer {
-                        if let Some(entry) = self.graphics.get(&request.id) {
-                            self.layer_brush.prepare_with_handle(
-                                &mut encoder,
-                                &mut self.ctx,
-                                &entry.handle,
-                                &Rectangle {
-                                    width: request.width.unwrap_or(entry.width),
-                                    height: request.height.unwrap_or(entry.height),
-                                    x: request.pos_x,
-                                    y: request.pos_y,
-                                },
-                            );
-                        }
-                    }
-                }
-
-                {
-                    let load = if let Some(background_color) = self.background_color {
-                        wgpu::LoadOp::Clear(background_color)
-                    } else {
-                        wgpu::LoadOp::Load
-                    };
-
-                    let mut rpass =
-                        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                            timestamp_writes: None,
-                            occlusion_query_set: None,
-                            label: None,
-                            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                view: &view,
-                                resolve_target: None,
-                                ops: wgpu::Operations {
-                                    load,
-                                    store: wgpu::StoreOp::Store,
-                                },
-                            })],
-                            depth_stencil_attachment: None,
-                        });
-
-                    if self.graphics.bottom_layer.is_some() {
-                        self.layer_brush.render(0, &mut rpass, None);
-                    }
-
-                    if self.graphics.has_graphics_on_top_layer() {
-                        let range_request = if self.graphics.bottom_layer.is_some() {
-                            1..(self.graphics.top_layer.len() + 1)
-                        } else {
-                            0..self.graphics.top_layer.len()
-                        };
-                        for request in range_request {
-                            self.layer_brush.render(request, &mut rpass, None);
-                        }
-                    }
-
-                    self.rich_text_brush.render(&mut self.ctx, &mut rpass);
-
-                    self.quad_brush
-                        .render(&mut self.ctx, &self.state, &mut rpass);
-
-                    self.rect_brush
-                        .render(&mut rpass, &self.state, &mut self.ctx);
-
-                    self.text_brush.render(&mut self.ctx, &mut rpass);
-                }
-
-                if self.graphics.bottom_layer.is_some()
-                    || self.graphics.has_graphics_on_top_layer()
-                {
-                    self.layer_brush.end_frame();
-                    self.graphics.clear_top_layer();
-                }
-
-                self.filters_brush.render(
-                    &self.ctx,
-                    &mut encoder,
-                    &frame.texture,
-                    &frame.texture,
-                );
+                self.encode_frame(&mut encoder, &frame.texture);
 
                 self.ctx.queue.submit(Some(encoder.finish()));
+                self.gpu_timer.after_submit();
                 frame.present();
             }
             Err(error) => {
@@ -427,4 +446,210 @@ impl Sugarloaf<'_> {
         }
         self.reset();
     }
+
+    /// Renders the current content into an offscreen texture and reads it
+    /// back as RGBA pixels, e.g. for bug reports or visual tests.
+    ///
+    /// Returns `None` if the surface format can't be converted to RGBA8.
+    pub fn capture(&mut self) -> Option<GraphicData> {
+        self.state.compute_dimensions(&mut self.rich_text_brush);
+
+        self.state.compute_updates(
+            &mut self.rich_text_brush,
+            &mut self.text_brush,
+            &mut self.rect_brush,
+            &mut self.quad_brush,
+            &mut self.ctx,
+            &mut self.graphics,
+        );
+
+        let width = self.ctx.size.width as u32;
+        let height = self.ctx.size.height as u32;
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = self.ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Sugarloaf Capture Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.ctx.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let mut encoder = self
+            .ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        self.encode_frame(&mut encoder, &texture);
+
+        let pixels = capture::read_frame(
+            &self.ctx.device,
+            &self.ctx.queue,
+            encoder,
+            &texture,
+            self.ctx.alpha_mode() == wgpu::CompositeAlphaMode::PreMultiplied,
+        );
+        self.gpu_timer.after_submit();
+        self.reset();
+
+        Some(GraphicData {
+            id: GraphicId(0),
+            width: width as usize,
+            height: height as usize,
+            color_type: ColorType::Rgba,
+            pixels: pixels?,
+            is_opaque: false,
+            resize: None,
+        })
+    }
+
+    fn encode_frame(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        if let Some(layer) = &self.graphics.bottom_layer {
+            self.layer_brush
+                .prepare(encoder, &mut self.ctx, &[&layer.data]);
+        }
+
+        if self.graphics.has_graphics_on_top_layer() {
+            for request in &self.graphics.top_layer {
+                if let Some(entry) = self.graphics.get(&request.id) {
+                    self.layer_brush.prepare_with_handle(
+                        encoder,
+                        &mut self.ctx,
+                        &entry.handle,
+                        &Rectangle {
+                            width: request.width.unwrap_or(entry.width),
+                            height: request.height.unwrap_or(entry.height),
+                            x: request.pos_x,
+                            y: request.pos_y,
+                        },
+                    );
+                }
+            }
+        }
+
+        {
+            let load = if let Some(mut background_color) = self.background_color {
+                background_color.a *= self.background_alpha as f64;
+                wgpu::LoadOp::Clear(background_color)
+            } else {
+                wgpu::LoadOp::Load
+            };
+
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                timestamp_writes: self.gpu_timer.timestamp_writes(),
+                occlusion_query_set: None,
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            if self.graphics.bottom_layer.is_some() {
+                self.layer_brush.render(0, &mut rpass, None);
+            }
+
+            if self.graphics.has_graphics_on_top_layer() {
+                let range_request = if self.graphics.bottom_layer.is_some() {
+                    1..(self.graphics.top_layer.len() + 1)
+                } else {
+                    0..self.graphics.top_layer.len()
+                };
+                for request in range_request {
+                    self.layer_brush.render(request, &mut rpass, None);
+                }
+            }
+
+            self.rich_text_brush.render(&mut self.ctx, &mut rpass);
+
+            self.quad_brush
+                .render(&mut self.ctx, &self.state, &mut rpass);
+
+            self.rect_brush
+                .render(&mut rpass, &self.state, &mut self.ctx);
+
+            self.text_brush.render(&mut self.ctx, &mut rpass);
+        }
+
+        self.gpu_timer.resolve(encoder);
+
+        if self.graphics.bottom_layer.is_some()
+            || self.graphics.has_graphics_on_top_layer()
+        {
+            self.layer_brush.end_frame();
+            self.graphics.clear_top_layer();
+        }
+
+        self.filters_brush
+            .render(&self.ctx, encoder, texture, texture);
+    }
+}
+
+/// Whether `reconfigure` has to recompute layouts: the context or the
+/// layouts are not at `scale` yet.
+#[inline]
+fn needs_layout_rescale(ctx_scale: f32, layout_scale: f32, scale: f32) -> bool {
+    ctx_scale != scale || layout_scale != scale
+}
+
+/// Moves the context to `scale` and calls `compute_layout_rescale` with it,
+/// unless the context and the layouts, at `layout_scale`, are already there.
+#[inline]
+fn rescale_if_needed(
+    ctx_scale: &mut f32,
+    layout_scale: f32,
+    scale: f32,
+    compute_layout_rescale: impl FnOnce(f32),
+) {
+    if needs_layout_rescale(*ctx_scale, layout_scale, scale) {
+        *ctx_scale = scale;
+        compute_layout_rescale(scale);
+    }
+}
+
+#[test]
+fn reconfigure_only_rescales_on_scale_change() {
+    // Resize at the same scale, layouts are kept.
+    assert!(!needs_layout_rescale(2.0, 2.0, 2.0));
+    // Moved to a display with another scale factor.
+    assert!(needs_layout_rescale(2.0, 2.0, 1.0));
+    // Context already updated but layouts still at the old scale.
+    assert!(needs_layout_rescale(1.0, 2.0, 1.0));
+    assert!(needs_layout_rescale(2.0, 1.0, 1.0));
+
+    // Layouts are computed once per scale change, not on every reconfigure
+    // at the same scale.
+    let mut ctx_scale = 2.0;
+    let mut layout_scale = 2.0;
+    let mut rescales = vec![];
+    for scale in [2.0, 1.0, 1.0, 1.0, 2.0, 2.0] {
+        rescale_if_needed(&mut ctx_scale, layout_scale, scale, |scale| {
+            layout_scale = scale;
+            rescales.push(scale);
+        });
+        assert_eq!(ctx_scale, scale);
+        assert_eq!(layout_scale, scale);
+    }
+    assert_eq!(rescales, [1.0, 2.0]);
 }
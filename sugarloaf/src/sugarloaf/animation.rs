@@ -0,0 +1,297 @@
+// Copyright (c) 2023-present, Raphael Amorim.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+use std::time::Duration;
+
+pub const DEFAULT_ANIMATION_DURATION: Duration = Duration::from_millis(100);
+
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    #[inline]
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for [f32; 2] {
+    #[inline]
+    fn lerp(self, to: Self, t: f32) -> Self {
+        [self[0].lerp(to[0], t), self[1].lerp(to[1], t)]
+    }
+}
+
+/// A value moving linearly from its previous target to the current one.
+#[derive(Debug, Clone, Copy)]
+pub struct Animated<T: Lerp> {
+    from: T,
+    to: T,
+    current: T,
+    /// Time spent toward `to`, `None` once the target was reached.
+    elapsed: Option<Duration>,
+}
+
+impl<T: Lerp> Animated<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            from: value,
+            to: value,
+            current: value,
+            elapsed: None,
+        }
+    }
+
+    #[inline]
+    pub fn value(&self) -> T {
+        self.current
+    }
+
+    #[inline]
+    pub fn target(&self) -> T {
+        self.to
+    }
+
+    /// Whether the value did not reach its target yet.
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.elapsed.is_some()
+    }
+
+    /// Starts moving toward `to` from wherever the value currently is.
+    #[inline]
+    fn set_target(&mut self, to: T) {
+        self.from = self.current;
+        self.to = to;
+        self.elapsed = Some(Duration::ZERO);
+    }
+
+    #[inline]
+    fn snap(&mut self, to: T) {
+        *self = Self::new(to);
+    }
+
+    /// Advances the animation, returns `true` while it did not reach the target.
+    #[inline]
+    fn tick(&mut self, dt: Duration, duration: Duration) -> bool {
+        let Some(elapsed) = self.elapsed else {
+            return false;
+        };
+
+        let elapsed = elapsed + dt;
+        if elapsed >= duration {
+            self.snap(self.to);
+            return false;
+        }
+
+        self.elapsed = Some(elapsed);
+        let t = elapsed.as_secs_f32() / duration.as_secs_f32();
+        self.current = self.from.lerp(self.to, t);
+        true
+    }
+}
+
+/// Interpolates the cursor position, the scroll offset and the window
+/// opacity toward their targets, the renderer should read `cursor`,
+/// `scroll_offset` and `opacity` after each `tick`. When disabled every
+/// target is applied instantly.
+#[derive(Debug, Clone, Copy)]
+pub struct Animations {
+    enabled: bool,
+    duration: Duration,
+    cursor: Animated<[f32; 2]>,
+    scroll: Animated<f32>,
+    opacity: Animated<f32>,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self::new(false, DEFAULT_ANIMATION_DURATION)
+    }
+}
+
+impl Animations {
+    pub fn new(enabled: bool, duration: Duration) -> Self {
+        Self {
+            enabled,
+            duration,
+            cursor: Animated::new([0., 0.]),
+            scroll: Animated::new(0.),
+            opacity: Animated::new(1.),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn configure(&mut self, enabled: bool, duration: Duration) {
+        self.enabled = enabled;
+        self.duration = duration;
+        if !self.enabled || self.duration.is_zero() {
+            self.cursor.snap(self.cursor.target());
+            self.scroll.snap(self.scroll.target());
+            self.opacity.snap(self.opacity.target());
+        }
+    }
+
+    #[inline]
+    fn animates(&self) -> bool {
+        self.enabled && !self.duration.is_zero()
+    }
+
+    pub fn set_cursor_target(&mut self, position: [f32; 2]) {
+        if self.cursor.target() == position {
+            return;
+        }
+
+        if self.animates() {
+            self.cursor.set_target(position);
+        } else {
+            self.cursor.snap(position);
+        }
+    }
+
+    pub fn set_scroll_target(&mut self, offset: f32) {
+        if self.scroll.target() == offset {
+            return;
+        }
+
+        if self.animates() {
+            self.scroll.set_target(offset);
+        } else {
+            self.scroll.snap(offset);
+        }
+    }
+
+    /// Moves the cursor and the scroll offset without animating them, as
+    /// needed when they start to follow another terminal.
+    pub fn snap_cursor_and_scroll(&mut self, position: [f32; 2], offset: f32) {
+        self.cursor.snap(position);
+        self.scroll.snap(offset);
+    }
+
+    pub fn set_opacity_target(&mut self, opacity: f32) {
+        if self.opacity.target() == opacity {
+            return;
+        }
+
+        if self.animates() {
+            self.opacity.set_target(opacity);
+        } else {
+            self.opacity.snap(opacity);
+        }
+    }
+
+    /// Whether any animation did not reach its target yet.
+    #[inline]
+    pub fn is_animating(&self) -> bool {
+        self.cursor.is_animating()
+            || self.scroll.is_animating()
+            || self.opacity.is_animating()
+    }
+
+    #[inline]
+    pub fn is_cursor_animating(&self) -> bool {
+        self.cursor.is_animating()
+    }
+
+    /// Advances every animation by `dt`, returns `true` while any of them
+    /// still needs frames to reach its target.
+    pub fn tick(&mut self, dt: Duration) -> bool {
+        let cursor = self.cursor.tick(dt, self.duration);
+        let scroll = self.scroll.tick(dt, self.duration);
+        let opacity = self.opacity.tick(dt, self.duration);
+        cursor || scroll || opacity
+    }
+
+    #[inline]
+    pub fn cursor(&self) -> [f32; 2] {
+        self.cursor.value()
+    }
+
+    #[inline]
+    pub fn scroll_offset(&self) -> f32 {
+        self.scroll.value()
+    }
+
+    #[inline]
+    pub fn opacity(&self) -> f32 {
+        self.opacity.value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_animation_halfway() {
+        let mut animations = Animations::new(true, Duration::from_millis(100));
+        animations.set_cursor_target([0., 0.]);
+        animations.set_cursor_target([100., 40.]);
+
+        assert!(animations.tick(Duration::from_millis(50)));
+        let [x, y] = animations.cursor();
+        assert!((x - 50.).abs() < 1.);
+        assert!((y - 20.).abs() < 1.);
+
+        assert!(!animations.tick(Duration::from_millis(50)));
+        assert_eq!(animations.cursor(), [100., 40.]);
+    }
+
+    #[test]
+    fn test_retarget_starts_from_current_position() {
+        let mut animations = Animations::new(true, Duration::from_millis(100));
+        animations.set_scroll_target(10.);
+        animations.tick(Duration::from_millis(50));
+        animations.set_scroll_target(0.);
+
+        assert!((animations.scroll_offset() - 5.).abs() < 0.01);
+        animations.tick(Duration::from_millis(50));
+        assert!((animations.scroll_offset() - 2.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_snap_cursor_and_scroll() {
+        let mut animations = Animations::new(true, Duration::from_millis(100));
+        animations.set_cursor_target([10., 4.]);
+        animations.set_scroll_target(3.);
+        animations.snap_cursor_and_scroll([2., 1.], 0.);
+
+        assert!(!animations.is_animating());
+        assert_eq!(animations.cursor(), [2., 1.]);
+        assert_eq!(animations.scroll_offset(), 0.);
+    }
+
+    #[test]
+    fn test_disabled_animations_snap() {
+        let mut animations = Animations::new(false, Duration::from_millis(100));
+        animations.set_cursor_target([100., 40.]);
+        animations.set_scroll_target(3.);
+        animations.set_opacity_target(0.5);
+
+        assert_eq!(animations.cursor(), [100., 40.]);
+        assert_eq!(animations.scroll_offset(), 3.);
+        assert_eq!(animations.opacity(), 0.5);
+        assert!(!animations.tick(Duration::from_millis(16)));
+    }
+
+    #[test]
+    fn test_opacity_fade() {
+        let mut animations = Animations::new(true, Duration::from_millis(100));
+        assert_eq!(animations.opacity(), 1.);
+        animations.set_opacity_target(0.5);
+        assert!(animations.is_animating());
+
+        assert!(animations.tick(Duration::from_millis(50)));
+        assert!((animations.opacity() - 0.75).abs() < 0.01);
+        assert!(!animations.tick(Duration::from_millis(50)));
+        assert_eq!(animations.opacity(), 0.5);
+        assert!(!animations.is_animating());
+    }
+}
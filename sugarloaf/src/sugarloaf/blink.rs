@@ -0,0 +1,92 @@
+// Copyright (c) 2023-present, Raphael Amorim.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+use std::time::Duration;
+
+/// Cadence of a blinking element. The cursor and slow blinking text share
+/// the slow cadence, fast blinking text toggles twice as often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlinkRate {
+    Slow,
+    Fast,
+}
+
+/// Position of the blink clock shared by everything that blinks, counted
+/// in half blink intervals so both cadences derive from the same value
+/// and never drift apart.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BlinkPhase(pub u64);
+
+impl BlinkPhase {
+    /// Phase reached `elapsed` after the clock started, `interval` is the
+    /// time a slow element stays visible or hidden.
+    #[inline]
+    pub fn from_elapsed(elapsed: Duration, interval: Duration) -> Self {
+        let half_interval = (interval.as_millis() / 2).max(1);
+        Self((elapsed.as_millis() / half_interval) as u64)
+    }
+
+    /// Whether an element blinking at `rate` is visible during this phase.
+    #[inline]
+    pub fn is_visible(self, rate: BlinkRate) -> bool {
+        match rate {
+            BlinkRate::Fast => self.0 & 1 == 0,
+            BlinkRate::Slow => self.0 & 2 == 0,
+        }
+    }
+
+    /// Time left after `elapsed` until elements blinking at `rate` toggle.
+    #[inline]
+    pub fn next_toggle(
+        elapsed: Duration,
+        interval: Duration,
+        rate: BlinkRate,
+    ) -> Duration {
+        let half_interval = (interval.as_millis() / 2).max(1);
+        let period = match rate {
+            BlinkRate::Fast => half_interval,
+            BlinkRate::Slow => half_interval * 2,
+        };
+        Duration::from_millis((period - elapsed.as_millis() % period) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blink_cadences() {
+        let interval = Duration::from_millis(600);
+        let visibility = |millis: u64| {
+            let phase = BlinkPhase::from_elapsed(Duration::from_millis(millis), interval);
+            (
+                phase.is_visible(BlinkRate::Slow),
+                phase.is_visible(BlinkRate::Fast),
+            )
+        };
+
+        assert_eq!(visibility(0), (true, true));
+        assert_eq!(visibility(299), (true, true));
+        assert_eq!(visibility(300), (true, false));
+        assert_eq!(visibility(600), (false, true));
+        assert_eq!(visibility(900), (false, false));
+        assert_eq!(visibility(1200), (true, true));
+    }
+
+    #[test]
+    fn test_next_toggle() {
+        let interval = Duration::from_millis(600);
+        let elapsed = Duration::from_millis(250);
+        assert_eq!(
+            BlinkPhase::next_toggle(elapsed, interval, BlinkRate::Fast),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            BlinkPhase::next_toggle(elapsed, interval, BlinkRate::Slow),
+            Duration::from_millis(350)
+        );
+    }
+}
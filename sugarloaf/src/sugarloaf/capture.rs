@@ -0,0 +1,275 @@
+// Copyright (c) 2023-present, Raphael Amorim.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+/// Aligns the row size of a texture readback to what wgpu requires
+/// for `copy_texture_to_buffer`.
+#[inline]
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// Copies `texture` into a mappable buffer after the commands recorded in
+/// `encoder`, submits them and waits for the GPU to read back the frame
+/// as tightly packed RGBA8 pixels.
+pub fn read_frame(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    mut encoder: wgpu::CommandEncoder,
+    texture: &wgpu::Texture,
+    premultiplied: bool,
+) -> Option<Vec<u8>> {
+    let size = texture.size();
+    let bytes_per_row = padded_bytes_per_row(size.width);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Sugarloaf Capture Buffer"),
+        size: (bytes_per_row * size.height) as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |_| {});
+    device.poll(wgpu::Maintain::Wait);
+
+    let pixels = {
+        let data = slice.get_mapped_range();
+        frame_to_rgba(
+            &data,
+            size.width,
+            size.height,
+            bytes_per_row,
+            texture.format(),
+            premultiplied,
+        )
+    };
+    buffer.unmap();
+
+    pixels
+}
+
+/// Converts the bytes read back from a frame texture into tightly
+/// packed RGBA8 pixels.
+///
+/// Handles swizzling for BGRA surfaces and undoes premultiplied alpha,
+/// returns `None` if the texture format is not an 8-bit RGBA/BGRA one.
+pub fn frame_to_rgba(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    format: wgpu::TextureFormat,
+    premultiplied: bool,
+) -> Option<Vec<u8>> {
+    let is_bgra = match format {
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        _ => return None,
+    };
+
+    let row_len = (width * 4) as usize;
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    for row in data.chunks(bytes_per_row as usize).take(height as usize) {
+        for pixel in row[..row_len].chunks_exact(4) {
+            let (mut r, g, mut b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+            if is_bgra {
+                std::mem::swap(&mut r, &mut b);
+            }
+
+            if premultiplied && a != 0 && a != 255 {
+                let unpremultiply =
+                    |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+                pixels.extend_from_slice(&[
+                    unpremultiply(r),
+                    unpremultiply(g),
+                    unpremultiply(b),
+                    a,
+                ]);
+            } else {
+                pixels.extend_from_slice(&[r, g, b, a]);
+            }
+        }
+    }
+
+    Some(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_frame(width: u32, height: u32, pixel: [u8; 4]) -> Vec<u8> {
+        let bytes_per_row = padded_bytes_per_row(width);
+        let mut data = vec![0; (bytes_per_row * height) as usize];
+        for row in data.chunks_mut(bytes_per_row as usize) {
+            for chunk in row[..(width * 4) as usize].chunks_exact_mut(4) {
+                chunk.copy_from_slice(&pixel);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_padded_bytes_per_row() {
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+        assert_eq!(padded_bytes_per_row(1), 256);
+    }
+
+    #[test]
+    fn test_capture_solid_background_bgra() {
+        let (width, height) = (33, 17);
+        // Background #1a2b3c stored as BGRA.
+        let data = solid_frame(width, height, [0x3c, 0x2b, 0x1a, 0xff]);
+        let pixels = frame_to_rgba(
+            &data,
+            width,
+            height,
+            padded_bytes_per_row(width),
+            wgpu::TextureFormat::Bgra8Unorm,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+        let center = (((height / 2) * width + width / 2) * 4) as usize;
+        assert_eq!(&pixels[center..center + 4], &[0x1a, 0x2b, 0x3c, 0xff]);
+    }
+
+    #[test]
+    fn test_capture_unpremultiplies_alpha() {
+        let data = solid_frame(2, 2, [50, 100, 0, 128]);
+        let pixels = frame_to_rgba(
+            &data,
+            2,
+            2,
+            padded_bytes_per_row(2),
+            wgpu::TextureFormat::Rgba8Unorm,
+            true,
+        )
+        .unwrap();
+
+        let expected = [100, 199, 0, 128];
+        for (pixel, expected) in pixels.chunks_exact(4).zip(std::iter::repeat(expected)) {
+            for (channel, expected) in pixel.iter().zip(expected) {
+                assert!(channel.abs_diff(expected) <= 1);
+            }
+        }
+    }
+
+    fn headless_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::default();
+        let adapter = futures::executor::block_on(
+            instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+        )?;
+        futures::executor::block_on(
+            adapter.request_device(&wgpu::DeviceDescriptor::default(), None),
+        )
+        .ok()
+    }
+
+    #[test]
+    fn test_capture_renders_solid_background() {
+        // Needs a GPU adapter, skipped on machines without one.
+        let Some((device, queue)) = headless_device() else {
+            return;
+        };
+
+        let (width, height) = (33, 17);
+        let cases = [
+            (wgpu::TextureFormat::Bgra8Unorm, 1.0, false),
+            (wgpu::TextureFormat::Rgba8Unorm, 1.0, false),
+            (wgpu::TextureFormat::Bgra8Unorm, 0.5, true),
+        ];
+        for (format, alpha, premultiplied) in cases {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+            // Background #1a2b3c, premultiplied like the surface expects.
+            let color_alpha = if premultiplied { alpha } else { 1.0 };
+            let background = wgpu::Color {
+                r: 0x1a as f64 / 255. * color_alpha,
+                g: 0x2b as f64 / 255. * color_alpha,
+                b: 0x3c as f64 / 255. * color_alpha,
+                a: alpha,
+            };
+
+            let mut encoder = device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(background),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            let pixels =
+                read_frame(&device, &queue, encoder, &texture, premultiplied).unwrap();
+            assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+            let center = (((height / 2) * width + width / 2) * 4) as usize;
+            let expected = [0x1a, 0x2b, 0x3c, (alpha * 255.).round() as u8];
+            for (channel, expected) in pixels[center..center + 4].iter().zip(expected) {
+                assert!(
+                    channel.abs_diff(expected) <= 2,
+                    "{format:?}: {:?}",
+                    &pixels[center..center + 4]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_capture_unsupported_format() {
+        let data = solid_frame(1, 1, [0, 0, 0, 255]);
+        assert!(frame_to_rgba(
+            &data,
+            1,
+            1,
+            padded_bytes_per_row(1),
+            wgpu::TextureFormat::Rgba16Float,
+            false,
+        )
+        .is_none());
+    }
+}
@@ -41,9 +41,10 @@ impl Advanced {
         let mut found_font_features = vec![];
         if let Some(features) = font_features {
             for feature in features {
-                let setting: crate::font_introspector::Setting<u16> =
-                    (feature.as_str(), 1).into();
-                found_font_features.push(setting);
+                match crate::font::fonts::parse_font_feature(feature) {
+                    Ok(setting) => found_font_features.push((&setting).into()),
+                    Err(err) => tracing::warn!("{err}"),
+                }
             }
         }
 
@@ -5,6 +5,7 @@
 
 use crate::sugarloaf::types;
 use crate::sugarloaf::Handle;
+use crate::sugarloaf::SugarloafWindowSize;
 use image_rs::DynamicImage;
 use rustc_hash::FxHashMap;
 use std::cmp;
@@ -50,6 +51,17 @@ impl Graphics {
         self.top_layer.clear();
     }
 
+    /// Stretch the bottom layer over `size`, unless it has explicit dimensions.
+    #[inline]
+    pub fn fit_bottom_layer(&mut self, size: SugarloafWindowSize) {
+        if let Some(bottom_layer) = &mut self.bottom_layer {
+            if bottom_layer.should_fit {
+                bottom_layer.data.bounds.width = size.width;
+                bottom_layer.data.bounds.height = size.height;
+            }
+        }
+    }
+
     #[inline]
     pub fn get(&self, id: &GraphicId) -> Option<&GraphicDataEntry> {
         self.inner.get(id)
@@ -363,3 +375,55 @@ fn check_opaque_region() {
     assert!(graphic.is_filled(0, 0, 3, 3));
     assert!(!graphic.is_filled(1, 1, 4, 4));
 }
+
+#[test]
+fn fit_bottom_layer_follows_size() {
+    use crate::components::core::shapes::Rectangle;
+
+    let layer = |should_fit| BottomLayer {
+        should_fit,
+        data: types::Raster {
+            handle: Handle::from_path("background.png"),
+            bounds: Rectangle {
+                x: 0.,
+                y: 0.,
+                width: 100.,
+                height: 50.,
+            },
+        },
+    };
+    let size = SugarloafWindowSize {
+        width: 1600.,
+        height: 1200.,
+    };
+
+    let mut graphics = Graphics {
+        bottom_layer: Some(layer(true)),
+        ..Graphics::default()
+    };
+    graphics.fit_bottom_layer(size);
+    let bounds = &graphics.bottom_layer.as_ref().unwrap().data.bounds;
+    assert_eq!((bounds.width, bounds.height), (1600., 1200.));
+
+    // `rescale` then `resize` fits twice, `reconfigure` only fits the final
+    // size: both end with the same bounds.
+    let mut chained = Graphics {
+        bottom_layer: Some(layer(true)),
+        ..Graphics::default()
+    };
+    chained.fit_bottom_layer(SugarloafWindowSize {
+        width: 800.,
+        height: 600.,
+    });
+    chained.fit_bottom_layer(size);
+    assert_eq!(
+        chained.bottom_layer.as_ref().unwrap().data.bounds,
+        graphics.bottom_layer.as_ref().unwrap().data.bounds
+    );
+
+    // Images with explicit dimensions keep them.
+    graphics.bottom_layer = Some(layer(false));
+    graphics.fit_bottom_layer(size);
+    let bounds = &graphics.bottom_layer.as_ref().unwrap().data.bounds;
+    assert_eq!((bounds.width, bounds.height), (100., 50.));
+}
@@ -0,0 +1,270 @@
+// Copyright (c) 2023-present, Raphael Amorim.
+//
+// This source code is licensed under the MIT license found in the
+// LICENSE file in the root directory of this source tree.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Timestamps written at the beginning and at the end of the render pass.
+const QUERY_COUNT: u32 = 2;
+const BUFFER_SIZE: wgpu::BufferAddress =
+    QUERY_COUNT as wgpu::BufferAddress * std::mem::size_of::<u64>() as u64;
+
+/// Converts two GPU timestamps into the time spent between them, `period`
+/// is the number of nanoseconds per tick reported by the queue.
+#[inline]
+pub fn gpu_duration(start: u64, end: u64, period: f32) -> Duration {
+    let ticks = end.saturating_sub(start);
+    Duration::from_nanos((ticks as f64 * period as f64) as u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryState {
+    Idle,
+    /// Timestamps were copied to the read buffer by the current frame.
+    Resolved,
+    /// Waiting for the read buffer to be mapped.
+    Mapping,
+}
+
+impl QueryState {
+    /// State after polling the read buffer, a failed mapping goes back to
+    /// `Idle` so the next frame is measured again.
+    #[inline]
+    fn after_map(self, status: MapStatus) -> Self {
+        match (self, status) {
+            (QueryState::Mapping, MapStatus::Pending) => QueryState::Mapping,
+            (QueryState::Mapping, _) => QueryState::Idle,
+            (state, _) => state,
+        }
+    }
+}
+
+/// Result of `map_async`, shared with its callback as a `u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum MapStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+impl MapStatus {
+    #[inline]
+    fn store(self, status: &AtomicU8) {
+        status.store(self as u8, Ordering::Release);
+    }
+
+    /// Reads the status reported by the callback and resets it.
+    #[inline]
+    fn take(status: &AtomicU8) -> Self {
+        match status.swap(MapStatus::Pending as u8, Ordering::Acquire) {
+            1 => MapStatus::Ready,
+            2 => MapStatus::Failed,
+            _ => MapStatus::Pending,
+        }
+    }
+}
+
+struct Queries {
+    set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    period: f32,
+    state: QueryState,
+    map_status: Arc<AtomicU8>,
+}
+
+/// Measures the time the GPU spends on the render pass of each frame with
+/// timestamp queries. Every method is a noop and `last` stays `None` when
+/// the device does not support `TIMESTAMP_QUERY`.
+///
+/// Timestamps are read back without blocking, so a frame is only measured
+/// once the previous measurement was read.
+#[derive(Default)]
+pub struct GpuTimer {
+    queries: Option<Queries>,
+    last: Option<Duration>,
+}
+
+impl GpuTimer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return Self::default();
+        }
+
+        let set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Sugarloaf Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sugarloaf Timestamps Resolve Buffer"),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sugarloaf Timestamps Read Buffer"),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            queries: Some(Queries {
+                set,
+                resolve_buffer,
+                read_buffer,
+                period: queue.get_timestamp_period(),
+                state: QueryState::Idle,
+                map_status: Arc::new(AtomicU8::new(MapStatus::Pending as u8)),
+            }),
+            last: None,
+        }
+    }
+
+    /// GPU time of the last measured render pass.
+    #[inline]
+    pub fn last(&self) -> Option<Duration> {
+        self.last
+    }
+
+    /// Timestamps to write around the render pass, `None` if unsupported
+    /// or while the previous measurement is still being read.
+    pub fn timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let queries = self.queries.as_ref()?;
+        if queries.state != QueryState::Idle {
+            return None;
+        }
+
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &queries.set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        })
+    }
+
+    /// Copies the timestamps written by `timestamp_writes` to a buffer
+    /// readable by the CPU.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(queries) = self.queries.as_mut() else {
+            return;
+        };
+        if queries.state != QueryState::Idle {
+            return;
+        }
+
+        encoder.resolve_query_set(
+            &queries.set,
+            0..QUERY_COUNT,
+            &queries.resolve_buffer,
+            0,
+        );
+        encoder.copy_buffer_to_buffer(
+            &queries.resolve_buffer,
+            0,
+            &queries.read_buffer,
+            0,
+            BUFFER_SIZE,
+        );
+        queries.state = QueryState::Resolved;
+    }
+
+    /// Starts reading the timestamps back, must be called after the
+    /// encoder given to `resolve` was submitted.
+    pub fn after_submit(&mut self) {
+        let Some(queries) = self.queries.as_mut() else {
+            return;
+        };
+        if queries.state != QueryState::Resolved {
+            return;
+        }
+
+        let map_status = queries.map_status.clone();
+        queries
+            .read_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let status = if result.is_ok() {
+                    MapStatus::Ready
+                } else {
+                    MapStatus::Failed
+                };
+                status.store(&map_status);
+            });
+        queries.state = QueryState::Mapping;
+    }
+
+    /// Updates `last` if the GPU finished a measured frame.
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        let Some(queries) = self.queries.as_mut() else {
+            return;
+        };
+        if queries.state != QueryState::Mapping {
+            return;
+        }
+
+        device.poll(wgpu::Maintain::Poll);
+        let status = MapStatus::take(&queries.map_status);
+        queries.state = queries.state.after_map(status);
+        if status != MapStatus::Ready {
+            return;
+        }
+
+        {
+            let data = queries.read_buffer.slice(..).get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&data);
+            self.last = Some(gpu_duration(timestamps[0], timestamps[1], queries.period));
+        }
+        queries.read_buffer.unmap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpu_duration() {
+        assert_eq!(gpu_duration(100, 1100, 1.), Duration::from_nanos(1000));
+        assert_eq!(gpu_duration(0, 3, 41.666), Duration::from_nanos(124));
+        // Timestamps can be reset between the two queries on some drivers.
+        assert_eq!(gpu_duration(1100, 100, 1.), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_unsupported_timer_is_noop() {
+        let mut timer = GpuTimer::default();
+        assert!(timer.queries.is_none());
+        assert!(timer.timestamp_writes().is_none());
+
+        timer.after_submit();
+        assert_eq!(timer.last(), None);
+    }
+
+    #[test]
+    fn test_failed_mapping_resets_to_idle() {
+        let map_status = AtomicU8::new(MapStatus::Pending as u8);
+        let state = QueryState::Mapping;
+
+        // The callback did not run yet.
+        let status = MapStatus::take(&map_status);
+        assert_eq!(status, MapStatus::Pending);
+        assert_eq!(state.after_map(status), QueryState::Mapping);
+
+        MapStatus::Failed.store(&map_status);
+        let status = MapStatus::take(&map_status);
+        assert_eq!(status, MapStatus::Failed);
+        assert_eq!(state.after_map(status), QueryState::Idle);
+        // The failure is only reported once.
+        assert_eq!(MapStatus::take(&map_status), MapStatus::Pending);
+
+        MapStatus::Ready.store(&map_status);
+        let status = MapStatus::take(&map_status);
+        assert_eq!(status, MapStatus::Ready);
+        assert_eq!(state.after_map(status), QueryState::Idle);
+    }
+}
@@ -12,12 +12,20 @@ pub use self::windows::*;
 
 use std::io;
 
+/// Terminal window size, in the layout of `struct winsize` from `<termios.h>`.
+#[cfg(not(windows))]
+pub type Winsize = libc::winsize;
+
+/// Terminal window size, mirroring the layout of `libc::winsize`, which
+/// `libc` does not provide on Windows.
+#[cfg(windows)]
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Winsize {
-    ws_row: libc::c_ushort,
-    ws_col: libc::c_ushort,
-    ws_width: libc::c_ushort,
-    ws_height: libc::c_ushort,
+    pub ws_row: libc::c_ushort,
+    pub ws_col: libc::c_ushort,
+    pub ws_xpixel: libc::c_ushort,
+    pub ws_ypixel: libc::c_ushort,
 }
 
 pub trait ProcessReadWrite {
@@ -60,7 +68,59 @@ pub trait EventedPty: ProcessReadWrite {
     fn next_child_event(&mut self) -> Option<ChildEvent>;
 }
 
-#[derive(Debug, Clone)]
+/// Drives an [`EventedPty`] with its own [`corcovado::Poll`], outside of
+/// the terminal event loop.
+///
+/// The pty is registered for read readiness and child events, `poll`
+/// returns the tokens that became ready so they can be compared against
+/// [`ProcessReadWrite::read_token`] and [`EventedPty::child_event_token`].
+pub struct PtyPoller<T: EventedPty> {
+    poll: corcovado::Poll,
+    events: corcovado::Events,
+    pty: T,
+}
+
+impl<T: EventedPty> PtyPoller<T> {
+    pub fn new(mut pty: T) -> io::Result<Self> {
+        let poll = corcovado::Poll::new()?;
+        let mut tokens = (0..).map(corcovado::Token);
+        pty.register(
+            &poll,
+            &mut tokens,
+            corcovado::Ready::readable(),
+            corcovado::PollOpt::level(),
+        )?;
+
+        Ok(Self {
+            poll,
+            events: corcovado::Events::with_capacity(64),
+            pty,
+        })
+    }
+
+    #[inline]
+    pub fn pty(&mut self) -> &mut T {
+        &mut self.pty
+    }
+
+    /// Waits until the pty is readable or a child event is pending, or
+    /// until `timeout` expires. Returns the tokens of the ready sources.
+    pub fn poll(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> io::Result<Vec<corcovado::Token>> {
+        self.poll.poll(&mut self.events, timeout)?;
+        Ok(self.events.iter().map(|event| event.token()).collect())
+    }
+
+    /// Deregisters the pty and gives it back.
+    pub fn into_inner(mut self) -> io::Result<T> {
+        self.pty.deregister(&self.poll)?;
+        Ok(self.pty)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct WinsizeBuilder {
     pub rows: u16,
     pub cols: u16,
@@ -72,14 +132,14 @@ impl WinsizeBuilder {
     fn build(&self) -> Winsize {
         let ws_row = self.rows as libc::c_ushort;
         let ws_col = self.cols as libc::c_ushort;
-        let ws_width = self.width as libc::c_ushort;
-        let ws_height = self.height as libc::c_ushort;
+        let ws_xpixel = self.width as libc::c_ushort;
+        let ws_ypixel = self.height as libc::c_ushort;
 
         Winsize {
             ws_row,
             ws_col,
-            ws_width,
-            ws_height,
+            ws_xpixel,
+            ws_ypixel,
         }
     }
 }
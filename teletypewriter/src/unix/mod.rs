@@ -20,21 +20,12 @@ use std::mem::MaybeUninit;
 use std::ops::Deref;
 use std::os::fd::OwnedFd;
 use std::os::fd::{AsRawFd, FromRawFd, RawFd};
-use std::os::unix::process::CommandExt;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitStatus, Stdio};
 use std::ptr;
 use std::sync::Arc;
 
-#[cfg(all(target_os = "linux", not(target_env = "musl")))]
-const TIOCSWINSZ: libc::c_ulong = 0x5414;
-#[cfg(all(target_os = "linux", target_env = "musl"))]
-const TIOCSWINSZ: libc::c_int = 0x5414;
-#[cfg(target_os = "freebsd")]
-const TIOCSWINSZ: libc::c_ulong = 0x80087467;
-#[cfg(target_os = "macos")]
-const TIOCSWINSZ: libc::c_ulong = 2148037735;
-
 #[link(name = "util")]
 extern "C" {
     fn forkpty(
@@ -394,12 +385,40 @@ impl ShellUser {
 ///
 /// It returns two [`Pty`] along with respective process name [`String`] and process id (`libc::pid_`)
 ///
+/// UTF-8 input processing is enabled, see [`create_pty_with_spawn_utf8`].
 pub fn create_pty_with_spawn(
     shell: &str,
     args: Vec<String>,
     working_directory: &Option<String>,
     columns: u16,
     rows: u16,
+) -> Result<Pty, Error> {
+    create_pty_with_spawn_utf8(shell, args, working_directory, columns, rows, true)
+}
+
+/// Same as [`create_pty_with_spawn`], `utf8` sets whether the terminal
+/// handles input as UTF-8 (`IUTF8`).
+pub fn create_pty_with_spawn_utf8(
+    shell: &str,
+    args: Vec<String>,
+    working_directory: &Option<String>,
+    columns: u16,
+    rows: u16,
+    utf8: bool,
+) -> Result<Pty, Error> {
+    create_pty_with_spawn_env(shell, args, working_directory, columns, rows, utf8, &[])
+}
+
+/// Same as [`create_pty_with_spawn_utf8`], `env` is added to the
+/// environment of the shell.
+pub fn create_pty_with_spawn_env(
+    shell: &str,
+    args: Vec<String>,
+    working_directory: &Option<String>,
+    columns: u16,
+    rows: u16,
+    utf8: bool,
+    env: &[(String, String)],
 ) -> Result<Pty, Error> {
     #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
     let mut is_controling_terminal = true;
@@ -412,10 +431,10 @@ pub fn create_pty_with_spawn(
     let winsize = Winsize {
         ws_row: rows as libc::c_ushort,
         ws_col: columns as libc::c_ushort,
-        ws_width: 0 as libc::c_ushort,
-        ws_height: 0 as libc::c_ushort,
+        ws_xpixel: 0 as libc::c_ushort,
+        ws_ypixel: 0 as libc::c_ushort,
     };
-    let term = create_termp(true);
+    let term = create_termp(utf8);
 
     let res = unsafe {
         openpty(
@@ -475,6 +494,9 @@ pub fn create_pty_with_spawn(
             }
 
             with_args.push("--env=TERM_PROGRAM=rio".to_string());
+            for (key, value) in env {
+                with_args.push(format!("--env={key}={value}"));
+            }
 
             let output = std::process::Command::new("flatpak-spawn")
                 .args(["--host", "sh", "-c", "echo $SHELL"])
@@ -502,6 +524,7 @@ pub fn create_pty_with_spawn(
 
     builder.env("USER", user.user);
     builder.env("HOME", user.home);
+    builder.envs(env.iter().map(|(key, value)| (key, value)));
 
     unsafe {
         builder.pre_exec(move || {
@@ -581,15 +604,39 @@ pub fn create_pty_with_spawn(
 ///
 /// It returns two [`Pty`] along with respective process name [`String`] and process id (`libc::pid_`)
 ///
+/// UTF-8 input processing is enabled, see [`create_pty_with_fork_utf8`].
 pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty, Error> {
+    create_pty_with_fork_utf8(shell, columns, rows, true)
+}
+
+/// Same as [`create_pty_with_fork`], `utf8` sets whether the terminal
+/// handles input as UTF-8 (`IUTF8`).
+pub fn create_pty_with_fork_utf8(
+    shell: &str,
+    columns: u16,
+    rows: u16,
+    utf8: bool,
+) -> Result<Pty, Error> {
+    create_pty_with_fork_env(shell, columns, rows, utf8, &[])
+}
+
+/// Same as [`create_pty_with_fork_utf8`], `env` is added to the
+/// environment of the shell.
+pub fn create_pty_with_fork_env(
+    shell: &str,
+    columns: u16,
+    rows: u16,
+    utf8: bool,
+    env: &[(String, String)],
+) -> Result<Pty, Error> {
     let mut main = 0;
     let winsize = Winsize {
         ws_row: rows as libc::c_ushort,
         ws_col: columns as libc::c_ushort,
-        ws_width: 0 as libc::c_ushort,
-        ws_height: 0 as libc::c_ushort,
+        ws_xpixel: 0 as libc::c_ushort,
+        ws_ypixel: 0 as libc::c_ushort,
     };
-    let term = create_termp(true);
+    let term = create_termp(utf8);
 
     let mut shell_program = shell;
 
@@ -608,6 +655,17 @@ pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty,
 
     tracing::info!("fork {:?}", shell_program);
 
+    // Allocated before forking, the child only sets them.
+    let env: Vec<(CString, CString)> = env
+        .iter()
+        .filter_map(|(key, value)| {
+            Some((
+                CString::new(key.as_str()).ok()?,
+                CString::new(value.as_str()).ok()?,
+            ))
+        })
+        .collect();
+
     match unsafe {
         forkpty(
             &mut main as *mut _,
@@ -617,6 +675,11 @@ pub fn create_pty_with_fork(shell: &str, columns: u16, rows: u16) -> Result<Pty,
         )
     } {
         0 => {
+            for (key, value) in &env {
+                unsafe {
+                    libc::setenv(key.as_ptr(), value.as_ptr(), 1);
+                }
+            }
             default_shell_command(shell_program);
             Err(Error::new(
                 ErrorKind::Other,
@@ -712,7 +775,7 @@ impl Child {
     /// change the actual window size, and if not, will not generate a SIGWINCH.
     pub fn set_winsize(&self, winsize_builder: WinsizeBuilder) -> io::Result<()> {
         let winsize: Winsize = winsize_builder.build();
-        match unsafe { libc::ioctl(**self, TIOCSWINSZ, &winsize as *const _) } {
+        match unsafe { libc::ioctl(**self, libc::TIOCSWINSZ, &winsize as *const _) } {
             -1 => Err(io::Error::last_os_error()),
             _ => Ok(()),
         }
@@ -741,6 +804,75 @@ impl Child {
             libc::close(*self.pid);
         }
     }
+
+    /// Process id of the child.
+    #[inline]
+    pub fn pid(&self) -> libc::pid_t {
+        *self.pid
+    }
+
+    /// Sends `signal` (for example `libc::SIGHUP` or `libc::SIGTERM`) to the child.
+    ///
+    /// Closing the file descriptor alone does not guarantee that the child
+    /// terminates, this should be used when a tab is closed.
+    pub fn kill(&self, signal: libc::c_int) -> io::Result<()> {
+        match unsafe { libc::kill(*self.pid, signal) } {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Toggles UTF-8 input processing of the terminal, see [`set_utf8`].
+    #[inline]
+    pub fn set_utf8(&self, utf8: bool) -> io::Result<()> {
+        set_utf8(**self, utf8)
+    }
+
+    /// Blocks until the child exits, reaps it and returns its exit status.
+    pub fn wait(&self) -> io::Result<ExitStatus> {
+        let mut status = 0 as libc::c_int;
+        loop {
+            let res = unsafe { waitpid(*self.pid, &mut status as *mut libc::c_int, 0) };
+            if res != -1 {
+                return Ok(ExitStatus::from_raw(status));
+            }
+
+            let err = io::Error::last_os_error();
+            if err.kind() != ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Enables or disables `IUTF8` on the terminal referenced by `fd`, which
+/// makes the line discipline erase whole UTF-8 characters instead of
+/// single bytes. Legacy byte-oriented applications may need it disabled.
+/// It is a noop on FreeBSD, which does not support `IUTF8`.
+pub fn set_utf8(fd: RawFd, utf8: bool) -> io::Result<()> {
+    #[cfg(not(target_os = "freebsd"))]
+    {
+        let mut term = MaybeUninit::<libc::termios>::uninit();
+        if unsafe { libc::tcgetattr(fd, term.as_mut_ptr()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut term = unsafe { term.assume_init() };
+        if utf8 {
+            term.c_iflag |= libc::IUTF8;
+        } else {
+            term.c_iflag &= !libc::IUTF8;
+        }
+
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &term) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    #[cfg(target_os = "freebsd")]
+    let _ = (fd, utf8);
+
+    Ok(())
 }
 
 pub fn kill_pid(pid: i32) {
@@ -872,12 +1004,20 @@ pub fn tty_ptsname(fd: libc::c_int) -> Result<String, String> {
     Ok(str_buf)
 }
 
-pub fn foreground_process_name(main_fd: RawFd, shell_pid: u32) -> String {
-    let mut pid = unsafe { libc::tcgetpgrp(main_fd) };
+/// Id of the foreground process group of the terminal, or `shell_pid` when
+/// it can not be retrieved.
+pub fn foreground_process_id(main_fd: RawFd, shell_pid: u32) -> u32 {
+    let pid = unsafe { libc::tcgetpgrp(main_fd) };
     if pid < 0 {
-        pid = shell_pid as libc::pid_t;
+        return shell_pid;
     }
 
+    pid as u32
+}
+
+pub fn foreground_process_name(main_fd: RawFd, shell_pid: u32) -> String {
+    let pid = foreground_process_id(main_fd, shell_pid) as libc::pid_t;
+
     #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
     let comm_path = format!("/proc/{}/comm", pid);
     #[cfg(target_os = "freebsd")]
@@ -902,10 +1042,7 @@ pub fn foreground_process_path(
     main_fd: RawFd,
     shell_pid: u32,
 ) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let mut pid = unsafe { libc::tcgetpgrp(main_fd) };
-    if pid < 0 {
-        pid = shell_pid as libc::pid_t;
-    }
+    let pid = foreground_process_id(main_fd, shell_pid) as libc::pid_t;
 
     #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
     let link_path = format!("/proc/{}/cwd", pid);
@@ -961,3 +1098,169 @@ where
             .map(|_| ())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn kill_and_wait_child() {
+        let pty =
+            create_pty_with_spawn("sleep", vec![String::from("100")], &None, 80, 24)
+                .unwrap();
+        assert!(pty.child.pid() > 0);
+
+        let start = Instant::now();
+        pty.child.kill(libc::SIGTERM).unwrap();
+        let status = pty.child.wait().unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+
+    #[test]
+    fn winsize_maps_rows_and_columns() {
+        let builder = WinsizeBuilder {
+            rows: 24,
+            cols: 80,
+            width: 800,
+            height: 600,
+        };
+        let winsize = builder.build();
+        assert_eq!(winsize.ws_row, 24);
+        assert_eq!(winsize.ws_col, 80);
+        assert_eq!(winsize.ws_xpixel, 800);
+        assert_eq!(winsize.ws_ypixel, 600);
+
+        let pty = create_pty_with_spawn("cat", vec![], &None, 100, 30).unwrap();
+        pty.child.set_winsize(builder).unwrap();
+
+        let mut applied: Winsize = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::ioctl(*pty.child, libc::TIOCGWINSZ, &mut applied) };
+        assert_ne!(result, -1);
+        assert_eq!(applied.ws_row, 24);
+        assert_eq!(applied.ws_col, 80);
+
+        pty.child.kill(libc::SIGTERM).unwrap();
+        pty.child.wait().unwrap();
+    }
+
+    #[test]
+    fn poll_reads_echo_and_child_exit() {
+        use std::io::{Read, Write};
+
+        let pty = create_pty_with_spawn("cat", vec![], &None, 80, 24).unwrap();
+        let mut poller = crate::PtyPoller::new(pty).unwrap();
+        let read_token = poller.pty().read_token();
+        let child_event_token = poller.pty().child_event_token();
+        assert_ne!(read_token, child_event_token);
+
+        poller.pty().writer().write_all(b"hello\n").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut output = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !String::from_utf8_lossy(&output).contains("hello") {
+            assert!(Instant::now() < deadline, "no echo from the child");
+            let tokens = poller.poll(Some(Duration::from_millis(100))).unwrap();
+            if tokens.contains(&read_token) {
+                if let Ok(n) = poller.pty().reader().read(&mut buf) {
+                    output.extend_from_slice(&buf[..n]);
+                }
+            }
+        }
+
+        poller.pty().child.kill(libc::SIGTERM).unwrap();
+
+        let mut exited = false;
+        while !exited {
+            assert!(Instant::now() < deadline, "child exit was not observed");
+            let tokens = poller.poll(Some(Duration::from_millis(100))).unwrap();
+            if tokens.contains(&child_event_token) {
+                exited = poller.pty().next_child_event() == Some(ChildEvent::Exited);
+            }
+        }
+
+        poller.into_inner().unwrap();
+    }
+
+    #[cfg(not(target_os = "freebsd"))]
+    #[test]
+    fn toggle_utf8() {
+        let is_utf8 = |pty: &Pty| {
+            let mut term = MaybeUninit::<libc::termios>::uninit();
+            assert_ne!(
+                unsafe { libc::tcgetattr(*pty.child, term.as_mut_ptr()) },
+                -1
+            );
+            unsafe { term.assume_init() }.c_iflag & libc::IUTF8 != 0
+        };
+
+        let pty =
+            create_pty_with_spawn("sleep", vec![String::from("100")], &None, 80, 24)
+                .unwrap();
+        assert!(is_utf8(&pty));
+
+        pty.child.set_utf8(false).unwrap();
+        assert!(!is_utf8(&pty));
+
+        pty.child.set_utf8(true).unwrap();
+        assert!(is_utf8(&pty));
+
+        pty.child.kill(libc::SIGTERM).unwrap();
+        pty.child.wait().unwrap();
+
+        let pty = create_pty_with_spawn_utf8(
+            "sleep",
+            vec![String::from("100")],
+            &None,
+            80,
+            24,
+            false,
+        )
+        .unwrap();
+        assert!(!is_utf8(&pty));
+
+        pty.child.kill(libc::SIGTERM).unwrap();
+        pty.child.wait().unwrap();
+    }
+
+    #[test]
+    fn spawn_with_env() {
+        use std::io::Read;
+
+        let pty = create_pty_with_spawn_env(
+            "sh",
+            vec![
+                String::from("-c"),
+                String::from("echo \"profile=$RIO_PROFILE_ENV\"; sleep 100"),
+            ],
+            &None,
+            80,
+            24,
+            true,
+            &[(String::from("RIO_PROFILE_ENV"), String::from("work"))],
+        )
+        .unwrap();
+        let mut poller = crate::PtyPoller::new(pty).unwrap();
+        let read_token = poller.pty().read_token();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut output = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !String::from_utf8_lossy(&output).contains("profile=work") {
+            assert!(Instant::now() < deadline, "variable not set for the child");
+            let tokens = poller.poll(Some(Duration::from_millis(100))).unwrap();
+            if tokens.contains(&read_token) {
+                if let Ok(n) = poller.pty().reader().read(&mut buf) {
+                    output.extend_from_slice(&buf[..n]);
+                }
+            }
+        }
+
+        poller.pty().child.kill(libc::SIGTERM).unwrap();
+        poller.pty().child.wait().unwrap();
+    }
+}